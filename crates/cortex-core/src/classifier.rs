@@ -0,0 +1,254 @@
+//! Behavioural wallet classification
+//!
+//! Assigns a [`WalletClassification`] from features extracted from indexed activity.
+//! The rules are ordered from most to least specific; the first that matches wins,
+//! and confidence reflects both how clearly it matched and how much history backs it.
+
+use crate::models::WalletClassification;
+use serde::{Deserialize, Serialize};
+
+/// Portfolio size above which a wallet counts as a whale
+const WHALE_VALUE_USD: f64 = 1_000_000.0;
+/// Portfolio size above which a busy, diverse wallet looks like an exchange
+const EXCHANGE_VALUE_USD: f64 = 5_000_000.0;
+/// Trades needed before the classification is fully trusted
+const FULL_CONFIDENCE_TRADES: f64 = 50.0;
+
+/// Behavioural features of a wallet, computed from its indexed transactions
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct WalletFeatures {
+    /// Current portfolio value in USD
+    pub portfolio_value_usd: f64,
+
+    /// Number of swaps
+    pub trade_count: u64,
+
+    /// Swaps per active day (first to last transaction)
+    pub trades_per_day: f64,
+
+    /// Median time between consecutive transactions, in seconds
+    pub median_interval_secs: Option<f64>,
+
+    /// Coefficient of variation of inter-transaction gaps (low = machine-like regularity)
+    pub interval_regularity: Option<f64>,
+
+    /// Distinct tokens the wallet has traded against
+    pub counterparty_count: usize,
+
+    /// Days since the first indexed transaction
+    pub account_age_days: f64,
+
+    /// Distinct protocols used
+    pub protocol_count: usize,
+
+    /// Share of transactions that are lending or liquidity provision rather than swaps
+    pub passive_share: f64,
+
+    /// Swaps bought and sold back within a single slot (atomic arbitrage)
+    #[serde(default)]
+    pub same_slot_round_trips: u64,
+
+    /// Transactions that paid a Jito bundle tip
+    #[serde(default)]
+    pub jito_tip_count: u64,
+
+    /// Buys landing within a couple of slots of a pool's creation
+    #[serde(default)]
+    pub snipe_count: u64,
+}
+
+/// Result of classifying a wallet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClassificationResult {
+    pub classification: WalletClassification,
+
+    /// 0.0 - 1.0
+    pub confidence: f64,
+
+    /// Features the decision was based on
+    pub features: WalletFeatures,
+}
+
+/// Classify a wallet from its behavioural features
+pub fn classify_wallet(features: &WalletFeatures) -> ClassificationResult {
+    let (classification, strength) = match_rule(features);
+
+    // New wallets are confidently new; for everything else, thin history lowers confidence
+    let data_factor = if classification == WalletClassification::New {
+        1.0
+    } else {
+        0.5 + 0.5 * (features.trade_count as f64 / FULL_CONFIDENCE_TRADES).min(1.0)
+    };
+
+    ClassificationResult {
+        classification,
+        confidence: (strength * data_factor).clamp(0.0, 1.0),
+        features: features.clone(),
+    }
+}
+
+/// Pick the class and how strongly the features support it
+fn match_rule(f: &WalletFeatures) -> (WalletClassification, f64) {
+    if f.trade_count == 0 && f.passive_share == 0.0 {
+        return (WalletClassification::Unknown, 0.0);
+    }
+
+    if f.account_age_days < 7.0 || (f.trade_count < 5 && f.account_age_days < 30.0) {
+        return (WalletClassification::New, 0.9);
+    }
+
+    // Arbitrage and sniping leave footprints no human produces repeatedly
+    if f.same_slot_round_trips >= 3 || f.snipe_count >= 3 {
+        return (WalletClassification::Bot, 0.95);
+    }
+
+    let machine_like = f.trade_count >= 20
+        && (f.interval_regularity.is_some_and(|cv| cv < 0.25)
+            || f.median_interval_secs.is_some_and(|secs| secs < 10.0)
+            || f.jito_tip_count as f64 >= f.trade_count as f64 * 0.5);
+    if f.trades_per_day >= 100.0 || machine_like {
+        let strength = if f.trades_per_day >= 100.0 && machine_like { 0.95 } else { 0.8 };
+        return (WalletClassification::Bot, strength);
+    }
+
+    if f.portfolio_value_usd >= EXCHANGE_VALUE_USD && f.trades_per_day >= 20.0 && f.counterparty_count >= 30 {
+        return (WalletClassification::Exchange, 0.7);
+    }
+
+    if f.portfolio_value_usd >= WHALE_VALUE_USD {
+        // Large, diversified and mostly passive reads as managed capital
+        if f.protocol_count >= 3 && f.passive_share >= 0.5 && f.trades_per_day < 5.0 {
+            return (WalletClassification::Fund, 0.7);
+        }
+        return (WalletClassification::Whale, 0.85);
+    }
+
+    if f.trades_per_day >= 1.0 || f.trade_count >= 30 {
+        let strength = if f.trades_per_day >= 5.0 { 0.85 } else { 0.7 };
+        return (WalletClassification::Trader, strength);
+    }
+
+    (WalletClassification::Retail, 0.6)
+}
+
+/// Median gap and coefficient of variation of the gaps between sorted unix-millisecond
+/// timestamps, in seconds. `None` with fewer than three timestamps.
+pub fn interval_stats(timestamps_ms: &[i64]) -> (Option<f64>, Option<f64>) {
+    if timestamps_ms.len() < 3 {
+        return (None, None);
+    }
+
+    let mut gaps: Vec<f64> = timestamps_ms
+        .windows(2)
+        .map(|w| (w[1] - w[0]).max(0) as f64 / 1000.0)
+        .collect();
+
+    let n = gaps.len() as f64;
+    let mean = gaps.iter().sum::<f64>() / n;
+    let std_dev = (gaps.iter().map(|g| (g - mean).powi(2)).sum::<f64>() / n).sqrt();
+    let regularity = if mean > 0.0 { Some(std_dev / mean) } else { None };
+
+    gaps.sort_by(|a, b| a.total_cmp(b));
+    let mid = gaps.len() / 2;
+    let median = if gaps.len().is_multiple_of(2) {
+        (gaps[mid - 1] + gaps[mid]) / 2.0
+    } else {
+        gaps[mid]
+    };
+
+    (Some(median), regularity)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn established() -> WalletFeatures {
+        WalletFeatures {
+            portfolio_value_usd: 5_000.0,
+            trade_count: 40,
+            trades_per_day: 0.5,
+            median_interval_secs: Some(7_200.0),
+            interval_regularity: Some(1.4),
+            counterparty_count: 8,
+            account_age_days: 200.0,
+            protocol_count: 2,
+            passive_share: 0.2,
+            ..WalletFeatures::default()
+        }
+    }
+
+    #[test]
+    fn test_interval_stats() {
+        let (median, regularity) = interval_stats(&[0, 60_000, 120_000, 180_000]);
+        assert_eq!(median, Some(60.0));
+        assert_eq!(regularity, Some(0.0));
+        assert_eq!(interval_stats(&[0, 1]), (None, None));
+    }
+
+    #[test]
+    fn test_regular_timing_is_bot() {
+        let features = WalletFeatures {
+            interval_regularity: Some(0.05),
+            ..established()
+        };
+        assert_eq!(classify_wallet(&features).classification, WalletClassification::Bot);
+    }
+
+    #[test]
+    fn test_mev_patterns_are_bot() {
+        let arbitrageur = WalletFeatures {
+            same_slot_round_trips: 12,
+            ..established()
+        };
+        assert_eq!(classify_wallet(&arbitrageur).classification, WalletClassification::Bot);
+
+        let bundler = WalletFeatures {
+            jito_tip_count: 35,
+            ..established()
+        };
+        assert_eq!(classify_wallet(&bundler).classification, WalletClassification::Bot);
+    }
+
+    #[test]
+    fn test_size_and_mix() {
+        let whale = WalletFeatures {
+            portfolio_value_usd: 2_000_000.0,
+            ..established()
+        };
+        assert_eq!(classify_wallet(&whale).classification, WalletClassification::Whale);
+
+        let fund = WalletFeatures {
+            protocol_count: 4,
+            passive_share: 0.8,
+            ..whale.clone()
+        };
+        assert_eq!(classify_wallet(&fund).classification, WalletClassification::Fund);
+    }
+
+    #[test]
+    fn test_young_wallet_is_new() {
+        let features = WalletFeatures {
+            account_age_days: 2.0,
+            trade_count: 3,
+            ..established()
+        };
+        let result = classify_wallet(&features);
+        assert_eq!(result.classification, WalletClassification::New);
+        assert!(result.confidence > 0.8);
+    }
+
+    #[test]
+    fn test_thin_history_lowers_confidence() {
+        let thin = WalletFeatures {
+            trade_count: 30,
+            ..established()
+        };
+        let rich = WalletFeatures {
+            trade_count: 100,
+            ..established()
+        };
+        assert_eq!(classify_wallet(&thin).classification, WalletClassification::Trader);
+        assert!(classify_wallet(&thin).confidence < classify_wallet(&rich).confidence);
+    }
+}
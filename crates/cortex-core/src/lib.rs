@@ -7,8 +7,10 @@
 
 pub mod models;
 pub mod conviction;
+pub mod classifier;
 pub mod error;
 
 pub use models::*;
 pub use conviction::*;
+pub use classifier::*;
 pub use error::*;
@@ -55,6 +55,35 @@ pub enum WalletClassification {
     Unknown,
 }
 
+impl WalletClassification {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            WalletClassification::Whale => "whale",
+            WalletClassification::Trader => "trader",
+            WalletClassification::Bot => "bot",
+            WalletClassification::Fund => "fund",
+            WalletClassification::Exchange => "exchange",
+            WalletClassification::Retail => "retail",
+            WalletClassification::New => "new",
+            WalletClassification::Unknown => "unknown",
+        }
+    }
+
+    /// Parse the snake_case name, falling back to `Unknown`
+    pub fn from_str_lossy(s: &str) -> Self {
+        match s {
+            "whale" => WalletClassification::Whale,
+            "trader" => WalletClassification::Trader,
+            "bot" => WalletClassification::Bot,
+            "fund" => WalletClassification::Fund,
+            "exchange" => WalletClassification::Exchange,
+            "retail" => WalletClassification::Retail,
+            "new" => WalletClassification::New,
+            _ => WalletClassification::Unknown,
+        }
+    }
+}
+
 // =============================================================================
 // DeFi Domain Models
 // =============================================================================
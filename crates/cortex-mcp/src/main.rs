@@ -79,13 +79,18 @@ fn get_tools() -> Vec<Tool> {
         },
         Tool {
             name: "cortex_get_wallet_summary".to_string(),
-            description: "Get a comprehensive summary of a Solana wallet including total value, PnL (profit/loss), risk metrics, and protocol exposure. Use this to understand a wallet's overall DeFi position.".to_string(),
+            description: "Get a comprehensive summary of a Solana wallet including total value, PnL (profit/loss), risk metrics, protocol exposure, and trading performance (Sharpe, Sortino, max drawdown, win rate, profit factor). Use this to understand a wallet's overall DeFi position and trading skill.".to_string(),
             input_schema: json!({
                 "type": "object",
                 "properties": {
                     "wallet": {
                         "type": "string",
                         "description": "Solana wallet address (base58 encoded)"
+                    },
+                    "window": {
+                        "type": "string",
+                        "enum": ["24h", "7d", "30d", "all"],
+                        "description": "Time window for performance statistics (default: 30d)"
                     }
                 },
                 "required": ["wallet"]
@@ -206,8 +211,16 @@ fn get_tools() -> Vec<Tool> {
 // HTTP Client
 // ============================================================================
 
+/// Attach `CORTEX_API_KEY`, when set, as a bearer token
+fn authorized(request: ureq::Request) -> ureq::Request {
+    match std::env::var("CORTEX_API_KEY") {
+        Ok(key) if !key.is_empty() => request.set("Authorization", &format!("Bearer {}", key)),
+        _ => request,
+    }
+}
+
 fn http_get(url: &str) -> Result<Value, String> {
-    let response = ureq::get(url)
+    let response = authorized(ureq::get(url))
         .call()
         .map_err(|e| format!("HTTP request failed: {}", e))?;
 
@@ -219,7 +232,7 @@ fn http_get(url: &str) -> Result<Value, String> {
 }
 
 fn http_post(url: &str, body: Value) -> Result<Value, String> {
-    let response = ureq::post(url)
+    let response = authorized(ureq::post(url))
         .set("Content-Type", "application/json")
         .send_json(&body)
         .map_err(|e| format!("HTTP request failed: {}", e))?;
@@ -232,7 +245,7 @@ fn http_post(url: &str, body: Value) -> Result<Value, String> {
 }
 
 fn http_delete(url: &str) -> Result<Value, String> {
-    let response = ureq::delete(url)
+    let response = authorized(ureq::delete(url))
         .call()
         .map_err(|e| format!("HTTP request failed: {}", e))?;
 
@@ -260,8 +273,14 @@ fn execute_tool(name: &str, args: &Value) -> Result<Value, String> {
             let wallet = args["wallet"]
                 .as_str()
                 .ok_or("Missing wallet parameter")?;
+            let window = args["window"].as_str().unwrap_or("30d");
             let url = format!("{}/api/v1/user/{}/summary", api_url, wallet);
-            http_get(&url)
+            let mut summary = http_get(&url)?;
+            let perf_url = format!("{}/api/v1/user/{}/performance?window={}", api_url, wallet, window);
+            if let Ok(performance) = http_get(&perf_url) {
+                summary["performance"] = performance;
+            }
+            Ok(summary)
         }
 
         "cortex_get_wallet_pnl" => {
@@ -351,7 +370,12 @@ fn execute_wallet_conviction(api_url: &str, wallet_addr: &str) -> Result<Value,
         total_value_usd: summary["total_value_usd"].as_f64().unwrap_or(0.0),
         defi_positions,
         prediction_bets,
-        classification: Some(WalletClassification::Unknown),
+        classification: Some(
+            summary["classification"]["class"]
+                .as_str()
+                .map(WalletClassification::from_str_lossy)
+                .unwrap_or(WalletClassification::Unknown),
+        ),
         risk_score: summary["risk_score"].as_u64().unwrap_or(50) as u8,
         last_activity: Utc::now(),
         protocols: summary["protocols"]
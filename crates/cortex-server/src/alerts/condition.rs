@@ -0,0 +1,237 @@
+//! Alert conditions: `<metric> <operator> <number>` over a wallet summary
+
+use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+
+use rust_decimal::Decimal;
+
+use crate::db::models::WalletSummaryRow;
+use crate::types::Protocol;
+
+/// A wallet metric a condition can test
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Metric {
+    TotalValueUsd,
+    RealizedPnl24h,
+    RealizedPnl7d,
+    RealizedPnl30d,
+    UnrealizedPnl,
+    LargestPositionPct,
+    ProtocolCount,
+    PositionCount,
+    RiskScore,
+    Leverage,
+    LiquidationDistance,
+    StablecoinPct,
+    MemecoinPct,
+    Var95,
+    Var99,
+    ExpectedShortfall95,
+    ExpectedShortfall99,
+    PortfolioVolatility,
+    MevLossUsd,
+    /// Loan-to-value on one lending protocol
+    Ltv(Protocol),
+}
+
+/// Metrics named after their `WalletSummaryRow` field
+const SUMMARY_METRICS: [(&str, Metric); 19] = [
+    ("total_value_usd", Metric::TotalValueUsd),
+    ("realized_pnl_24h", Metric::RealizedPnl24h),
+    ("realized_pnl_7d", Metric::RealizedPnl7d),
+    ("realized_pnl_30d", Metric::RealizedPnl30d),
+    ("unrealized_pnl", Metric::UnrealizedPnl),
+    ("largest_position_pct", Metric::LargestPositionPct),
+    ("protocol_count", Metric::ProtocolCount),
+    ("position_count", Metric::PositionCount),
+    ("risk_score", Metric::RiskScore),
+    ("leverage", Metric::Leverage),
+    ("liquidation_distance", Metric::LiquidationDistance),
+    ("stablecoin_pct", Metric::StablecoinPct),
+    ("memecoin_pct", Metric::MemecoinPct),
+    ("var_95", Metric::Var95),
+    ("var_99", Metric::Var99),
+    ("expected_shortfall_95", Metric::ExpectedShortfall95),
+    ("expected_shortfall_99", Metric::ExpectedShortfall99),
+    ("portfolio_volatility", Metric::PortfolioVolatility),
+    ("mev_loss_usd", Metric::MevLossUsd),
+];
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Metric::Ltv(protocol) => write!(f, "{}.ltv", protocol),
+            metric => {
+                let name = SUMMARY_METRICS.iter().find(|(_, m)| m == metric).map(|(name, _)| *name);
+                write!(f, "{}", name.unwrap_or_default())
+            }
+        }
+    }
+}
+
+impl Metric {
+    /// Accepts summary field names and `<protocol>.ltv`, also written `<protocol> ltv`
+    pub fn from_str(s: &str) -> Option<Self> {
+        let name = s.trim().to_lowercase();
+        let words: Vec<&str> = name.split(|c: char| c == '.' || c.is_whitespace()).filter(|w| !w.is_empty()).collect();
+        match words.as_slice() {
+            [protocol, "ltv"] => Protocol::from_str(protocol).map(Metric::Ltv),
+            [field] => SUMMARY_METRICS.iter().find(|(n, _)| n == field).map(|(_, m)| *m),
+            _ => None,
+        }
+    }
+
+    /// The metric's value for a wallet; `None` when it doesn't apply, such as the LTV
+    /// of a protocol the wallet hasn't lent or borrowed on
+    pub fn value(&self, summary: &WalletSummaryRow) -> Option<Decimal> {
+        let parse = |s: &str| Decimal::from_str(s).ok();
+        match self {
+            Metric::TotalValueUsd => parse(&summary.total_value_usd),
+            Metric::RealizedPnl24h => parse(&summary.realized_pnl_24h),
+            Metric::RealizedPnl7d => parse(&summary.realized_pnl_7d),
+            Metric::RealizedPnl30d => parse(&summary.realized_pnl_30d),
+            Metric::UnrealizedPnl => parse(&summary.unrealized_pnl),
+            Metric::LargestPositionPct => parse(&summary.largest_position_pct),
+            Metric::ProtocolCount => Some(summary.protocol_count.into()),
+            Metric::PositionCount => Some(summary.position_count.into()),
+            Metric::RiskScore => Some(summary.risk_score.into()),
+            Metric::Leverage => parse(&summary.leverage),
+            Metric::LiquidationDistance => parse(&summary.liquidation_distance),
+            Metric::StablecoinPct => parse(&summary.stablecoin_pct),
+            Metric::MemecoinPct => parse(&summary.memecoin_pct),
+            Metric::Var95 => parse(&summary.var_95),
+            Metric::Var99 => parse(&summary.var_99),
+            Metric::ExpectedShortfall95 => parse(&summary.expected_shortfall_95),
+            Metric::ExpectedShortfall99 => parse(&summary.expected_shortfall_99),
+            Metric::PortfolioVolatility => parse(&summary.portfolio_volatility),
+            Metric::MevLossUsd => parse(&summary.mev_loss_usd),
+            Metric::Ltv(protocol) => {
+                let ltv: HashMap<String, String> = serde_json::from_str(&summary.protocol_ltv).ok()?;
+                parse(ltv.get(&protocol.to_string())?)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Comparison {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+}
+
+/// Longest first, so `>=` isn't read as `>`
+const OPERATORS: [(&str, Comparison); 6] = [
+    (">=", Comparison::Ge),
+    ("<=", Comparison::Le),
+    ("==", Comparison::Eq),
+    ("!=", Comparison::Ne),
+    (">", Comparison::Gt),
+    ("<", Comparison::Lt),
+];
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = OPERATORS.iter().find(|(_, c)| c == self).map(|(s, _)| *s).unwrap_or_default();
+        write!(f, "{}", symbol)
+    }
+}
+
+impl Comparison {
+    pub fn holds(&self, value: Decimal, threshold: Decimal) -> bool {
+        match self {
+            Comparison::Gt => value > threshold,
+            Comparison::Ge => value >= threshold,
+            Comparison::Lt => value < threshold,
+            Comparison::Le => value <= threshold,
+            Comparison::Eq => value == threshold,
+            Comparison::Ne => value != threshold,
+        }
+    }
+}
+
+/// A parsed rule condition such as `risk_score > 70`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Condition {
+    pub metric: Metric,
+    pub comparison: Comparison,
+    pub threshold: Decimal,
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {} {}", self.metric, self.comparison, self.threshold.normalize())
+    }
+}
+
+impl Condition {
+    /// Parse `<metric> <operator> <number>`, e.g. `realized_pnl_24h < -5000` or
+    /// `Kamino LTV > 0.85`. The error says which part is wrong.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        let (at, symbol, comparison) = OPERATORS
+            .iter()
+            .filter_map(|(symbol, comparison)| s.find(symbol).map(|at| (at, *symbol, *comparison)))
+            .min_by_key(|(at, symbol, _)| (*at, std::cmp::Reverse(symbol.len())))
+            .ok_or_else(|| format!("no comparison operator in \"{}\" (use >, >=, <, <=, == or !=)", s))?;
+
+        let metric_name = &s[..at];
+        let metric = Metric::from_str(metric_name).ok_or_else(|| format!("unknown metric \"{}\"", metric_name.trim()))?;
+        let threshold_text = s[at + symbol.len()..].trim();
+        let threshold = Decimal::from_str(threshold_text)
+            .or_else(|_| Decimal::from_scientific(threshold_text))
+            .map_err(|_| format!("threshold \"{}\" is not a number", threshold_text))?;
+
+        Ok(Self { metric, comparison, threshold })
+    }
+
+    /// The metric's value and whether the condition holds for it. A metric that doesn't
+    /// apply to the wallet never holds.
+    pub fn evaluate(&self, summary: &WalletSummaryRow) -> (Option<Decimal>, bool) {
+        let value = self.metric.value(summary);
+        let holds = value.is_some_and(|v| self.comparison.holds(v, self.threshold));
+        (value, holds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_parse_conditions() {
+        let condition = Condition::parse("risk_score > 70").unwrap();
+        assert_eq!(
+            condition,
+            Condition { metric: Metric::RiskScore, comparison: Comparison::Gt, threshold: dec!(70) }
+        );
+
+        let condition = Condition::parse("realized_pnl_24h<-5000").unwrap();
+        assert_eq!((condition.comparison, condition.threshold), (Comparison::Lt, dec!(-5000)));
+
+        let condition = Condition::parse("Kamino LTV >= 0.85").unwrap();
+        assert_eq!(condition.metric, Metric::Ltv(Protocol::Kamino));
+        assert_eq!(condition.comparison, Comparison::Ge);
+        assert_eq!(condition.to_string(), "kamino.ltv >= 0.85");
+        assert_eq!(Condition::parse(&condition.to_string()).unwrap(), condition);
+
+        assert!(Condition::parse("risk_score 70").unwrap_err().contains("operator"));
+        assert!(Condition::parse("vibes > 3").unwrap_err().contains("unknown metric"));
+        assert!(Condition::parse("solend.ltv > 0.5").unwrap_err().contains("unknown metric"));
+        assert!(Condition::parse("leverage > high").unwrap_err().contains("not a number"));
+    }
+
+    #[test]
+    fn test_comparisons() {
+        assert!(Comparison::Gt.holds(dec!(71), dec!(70)));
+        assert!(!Comparison::Gt.holds(dec!(70), dec!(70)));
+        assert!(Comparison::Ge.holds(dec!(70), dec!(70)));
+        assert!(Comparison::Le.holds(dec!(-5000), dec!(-5000)));
+        assert!(Comparison::Ne.holds(dec!(1), dec!(2)));
+        assert!(Comparison::Eq.holds(dec!(0.50), dec!(0.5)));
+    }
+}
@@ -0,0 +1,436 @@
+//! Declarative alert rules.
+//!
+//! A rule is a [`Condition`] such as `risk_score > 70` over one or more wallets, the
+//! current members of a wallet group, or every wallet. The engine evaluates the rules each
+//! time a wallet's summary is recomputed and tracks a state per rule and wallet: an alert
+//! fires when its condition starts to hold and resolves when it stops. Only those
+//! transitions are published, as [`WalletUpdate::Alert`], so streams and webhooks see each
+//! alert once rather than on every recomputation.
+
+mod condition;
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::{broadcast, RwLock};
+use tokio::task::JoinHandle;
+
+use crate::db::models::{AlertRuleRow, AlertStateRow, WalletSummaryRow};
+use crate::db::store::CortexStore;
+use crate::error::AppResult;
+use crate::groups::WalletGroups;
+use crate::indexer::{Indexer, WalletUpdate};
+use crate::registry::{Record, Registry};
+use crate::types::AlertState;
+
+pub use condition::Condition;
+
+/// A rule starting or stopping to hold for a wallet
+#[derive(Debug, Clone, Serialize, utoipa::ToSchema)]
+pub struct Alert {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub condition: String,
+    pub wallet: String,
+    pub state: AlertState,
+    /// The metric's value when the state changed; absent when the metric no longer applies
+    pub value: Option<Decimal>,
+    pub changed_at: DateTime<Utc>,
+}
+
+/// A stored rule with its condition parsed
+#[derive(Debug, Clone)]
+struct Rule {
+    row: AlertRuleRow,
+    condition: Condition,
+}
+
+impl Rule {
+    /// Whether the rule covers `wallet`. `group_members` are the current members of the
+    /// rule's group, `None` when it has none or the group was deleted.
+    fn applies_to(&self, wallet: &str, group_members: Option<&[String]>) -> bool {
+        if self.row.wallets.iter().any(|w| w == wallet) {
+            return true;
+        }
+        match group_members {
+            Some(members) => members.iter().any(|m| m == wallet),
+            None => self.row.wallets.is_empty() && self.row.group_id.is_empty(),
+        }
+    }
+}
+
+#[async_trait]
+impl Record for Rule {
+    fn key(&self) -> &str {
+        &self.row.id
+    }
+
+    fn created_at(&self) -> i64 {
+        self.row.created_at
+    }
+
+    fn mark_deleted(&mut self) {
+        self.row.deleted = 1;
+    }
+
+    async fn save(&self, store: &dyn CortexStore) -> AppResult<()> {
+        store.save_alert_rule(&self.row).await
+    }
+
+    /// Rules whose stored condition no longer parses are skipped
+    async fn load_all(store: &dyn CortexStore) -> AppResult<Vec<Self>> {
+        let mut rules = Vec::new();
+        for row in store.get_alert_rules().await? {
+            match Condition::parse(&row.condition) {
+                Ok(condition) => rules.push(Rule { row, condition }),
+                Err(e) => {
+                    println!("[ALERT] Skipping rule {} with invalid condition: {}", row.id, e);
+                    tracing::warn!(rule_id = %row.id, condition = %row.condition, error = %e, "Skipping alert rule with invalid condition");
+                }
+            }
+        }
+        Ok(rules)
+    }
+}
+
+#[derive(Clone)]
+pub struct AlertEngine {
+    store: Arc<dyn CortexStore>,
+    /// Where transitions are published
+    indexer: Indexer,
+    /// Resolves group-scoped rules to their current members
+    groups: WalletGroups,
+    rules: Registry<Rule>,
+    /// Latest state per rule id and wallet
+    states: Arc<RwLock<HashMap<(String, String), AlertStateRow>>>,
+}
+
+impl AlertEngine {
+    pub fn new(store: Arc<dyn CortexStore>, indexer: Indexer, groups: WalletGroups) -> Self {
+        Self {
+            rules: Registry::new(store.clone()),
+            store,
+            indexer,
+            groups,
+            states: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Load stored rules and alert states. Returns how many rules there are.
+    pub async fn load(&self) -> AppResult<usize> {
+        let count = self.rules.load().await?;
+
+        let states = self.store.get_alert_states().await?;
+        *self.states.write().await =
+            states.into_iter().map(|s| ((s.rule_id.clone(), s.wallet.clone()), s)).collect();
+
+        println!("[ALERT] Loaded {} alert rule(s)", count);
+        tracing::info!(count = %count, "Loaded alert rules");
+        Ok(count)
+    }
+
+    pub async fn create(&self, row: AlertRuleRow, condition: Condition) -> AppResult<()> {
+        self.rules.save(Rule { row: row.clone(), condition }).await?;
+        println!("[ALERT] Created rule {} ({})", row.id, row.condition);
+        tracing::info!(rule_id = %row.id, condition = %row.condition, wallets = ?row.wallets, group_id = %row.group_id, "Alert rule created");
+        Ok(())
+    }
+
+    /// Every rule, oldest first
+    pub async fn rules(&self) -> Vec<AlertRuleRow> {
+        self.rules.list().await.into_iter().map(|r| r.row).collect()
+    }
+
+    pub async fn rule(&self, id: &str) -> Option<AlertRuleRow> {
+        self.rules.get(id).await.map(|r| r.row)
+    }
+
+    /// Returns false if there was no such rule. Its alerts are dropped without resolving.
+    pub async fn delete(&self, id: &str) -> AppResult<bool> {
+        if self.rules.delete(id).await?.is_none() {
+            return Ok(false);
+        }
+        self.states.write().await.retain(|(rule_id, _), _| rule_id != id);
+        println!("[ALERT] Deleted rule {}", id);
+        tracing::info!(rule_id = %id, "Alert rule deleted");
+        Ok(true)
+    }
+
+    /// Alerts of live rules, most recently changed first, optionally only those in `state`
+    pub async fn alerts(&self, state: Option<AlertState>) -> Vec<Alert> {
+        let rules: HashMap<String, Rule> = self.rules.list().await.into_iter().map(|r| (r.row.id.clone(), r)).collect();
+        let mut alerts: Vec<Alert> = self
+            .states
+            .read()
+            .await
+            .values()
+            .filter_map(|row| {
+                let alert = alert_from_row(rules.get(&row.rule_id)?, row)?;
+                state.is_none_or(|s| alert.state == s).then_some(alert)
+            })
+            .collect();
+        alerts.sort_by(|a, b| (b.changed_at, &a.rule_id, &a.wallet).cmp(&(a.changed_at, &b.rule_id, &b.wallet)));
+        alerts
+    }
+
+    /// Evaluate rules against every recomputed summary until the indexer goes away
+    pub fn spawn(&self, mut updates: broadcast::Receiver<WalletUpdate>) -> JoinHandle<()> {
+        let engine = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match updates.recv().await {
+                    Ok(WalletUpdate::Summary(summary)) => {
+                        for alert in engine.evaluate(&summary).await {
+                            engine.indexer.publish_update(WalletUpdate::Alert(Box::new(alert)));
+                        }
+                    }
+                    Ok(_) => {}
+                    Err(RecvError::Lagged(skipped)) => {
+                        println!("[ALERT] Engine lagging, {} update(s) dropped", skipped);
+                        tracing::warn!(skipped = %skipped, "Alert engine lagging, updates dropped");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            tracing::info!("Alert engine stopped");
+        })
+    }
+
+    /// Apply every rule covering the summary's wallet, returning the alerts whose state changed.
+    /// A firing alert resolves once its wallet leaves the rule's group.
+    async fn evaluate(&self, summary: &WalletSummaryRow) -> Vec<Alert> {
+        let wallet = summary.wallet.as_str();
+        let rules = self.rules.list().await;
+
+        let mut changed = Vec::new();
+        for rule in rules {
+            let group_members = match rule.row.group_id.as_str() {
+                "" => None,
+                group_id => self.groups.members(group_id).await.ok(),
+            };
+            let covered = rule.applies_to(wallet, group_members.as_deref());
+            let key = (rule.row.id.clone(), wallet.to_string());
+            let firing = self
+                .states
+                .read()
+                .await
+                .get(&key)
+                .is_some_and(|s| AlertState::from_str(&s.state) == Some(AlertState::Firing));
+            if !covered && !firing {
+                continue;
+            }
+
+            let (value, holds) = if covered { rule.condition.evaluate(summary) } else { (None, false) };
+            if holds == firing {
+                continue;
+            }
+
+            let state = if holds { AlertState::Firing } else { AlertState::Resolved };
+            let row = AlertStateRow {
+                rule_id: rule.row.id.clone(),
+                wallet: wallet.to_string(),
+                state: state.to_string(),
+                value: value.unwrap_or_default().round_dp(4).to_string(),
+                changed_at: Utc::now().timestamp_millis(),
+            };
+            if let Err(e) = self.store.save_alert_state(&row).await {
+                tracing::warn!(rule_id = %rule.row.id, wallet = %wallet, error = %e, "Failed to store alert state");
+            }
+            self.states.write().await.insert(key, row.clone());
+
+            println!("[ALERT] {} {} for {} (value={:?})", rule.row.name, state, wallet, value);
+            tracing::info!(rule_id = %rule.row.id, wallet = %wallet, state = %state, value = ?value, "Alert state changed");
+
+            if let Some(mut alert) = alert_from_row(&rule, &row) {
+                alert.value = value;
+                changed.push(alert);
+            }
+        }
+        changed
+    }
+}
+
+fn alert_from_row(rule: &Rule, row: &AlertStateRow) -> Option<Alert> {
+    Some(Alert {
+        rule_id: rule.row.id.clone(),
+        rule_name: rule.row.name.clone(),
+        condition: rule.condition.to_string(),
+        wallet: row.wallet.clone(),
+        state: AlertState::from_str(&row.state)?,
+        value: Decimal::from_str(&row.value).ok(),
+        changed_at: DateTime::from_timestamp_millis(row.changed_at).unwrap_or_default(),
+    })
+}
+
+/// A new rule row with a random id. `condition` is stored in canonical form.
+pub fn new_rule(name: String, condition: &Condition, wallets: Vec<String>, group_id: Option<String>) -> AlertRuleRow {
+    AlertRuleRow {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        condition: condition.to_string(),
+        wallets,
+        group_id: group_id.unwrap_or_default(),
+        deleted: 0,
+        created_at: Utc::now().timestamp_millis(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::store::MemoryStore;
+
+    const WALLET: &str = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
+    const OTHER_WALLET: &str = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+
+    fn summary(wallet: &str, risk_score: u8, protocol_ltv: &str) -> WalletSummaryRow {
+        WalletSummaryRow {
+            wallet: wallet.to_string(),
+            total_value_usd: "1000".to_string(),
+            protocol_count: 1,
+            position_count: 1,
+            risk_score,
+            protocols: vec!["kamino".to_string()],
+            protocol_ltv: protocol_ltv.to_string(),
+            ..WalletSummaryRow::default()
+        }
+    }
+
+    async fn engine_with(condition: &str, wallets: Vec<String>) -> (AlertEngine, Arc<MemoryStore>) {
+        let store = Arc::new(MemoryStore::new());
+        let engine = AlertEngine::new(store.clone(), Indexer::offline(store.clone()), WalletGroups::new(store.clone()));
+        let condition = Condition::parse(condition).unwrap();
+        engine.create(new_rule("test".to_string(), &condition, wallets, None), condition).await.unwrap();
+        (engine, store)
+    }
+
+    #[tokio::test]
+    async fn test_alert_fires_once_then_resolves() {
+        let (engine, store) = engine_with("risk_score > 70", vec![WALLET.to_string()]).await;
+
+        assert!(engine.evaluate(&summary(WALLET, 50, "{}")).await.is_empty());
+
+        let fired = engine.evaluate(&summary(WALLET, 80, "{}")).await;
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].state, AlertState::Firing);
+        assert_eq!(fired[0].value, Some(Decimal::from(80)));
+
+        // Still above the threshold: no repeat
+        assert!(engine.evaluate(&summary(WALLET, 85, "{}")).await.is_empty());
+        assert_eq!(engine.alerts(Some(AlertState::Firing)).await.len(), 1);
+
+        // Rule doesn't cover other wallets
+        assert!(engine.evaluate(&summary(OTHER_WALLET, 90, "{}")).await.is_empty());
+
+        let resolved = engine.evaluate(&summary(WALLET, 60, "{}")).await;
+        assert_eq!(resolved.len(), 1);
+        assert_eq!(resolved[0].state, AlertState::Resolved);
+        assert!(engine.alerts(Some(AlertState::Firing)).await.is_empty());
+
+        let stored = store.get_alert_states().await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert_eq!(stored[0].state, "resolved");
+    }
+
+    #[tokio::test]
+    async fn test_firing_state_survives_reload() {
+        let (engine, store) = engine_with("kamino.ltv > 0.85", Vec::new()).await;
+        assert_eq!(engine.evaluate(&summary(WALLET, 10, r#"{"kamino":"0.9"}"#)).await.len(), 1);
+        // A wallet without Kamino debt has no LTV to compare
+        assert!(engine.evaluate(&summary(OTHER_WALLET, 10, "{}")).await.is_empty());
+
+        let reloaded = AlertEngine::new(store.clone(), Indexer::offline(store.clone()), WalletGroups::new(store.clone()));
+        assert_eq!(reloaded.load().await.unwrap(), 1);
+        assert!(reloaded.evaluate(&summary(WALLET, 10, r#"{"kamino":"0.95"}"#)).await.is_empty());
+
+        let resolved = reloaded.evaluate(&summary(WALLET, 10, "{}")).await;
+        assert_eq!(resolved.len(), 1);
+        assert_eq!((resolved[0].state, resolved[0].value), (AlertState::Resolved, None));
+    }
+
+    #[tokio::test]
+    async fn test_transitions_are_published() {
+        let (engine, store) = engine_with("realized_pnl_24h < -5000", Vec::new()).await;
+        let indexer = engine.indexer.clone();
+        let mut published = indexer.subscribe_updates();
+        engine.spawn(indexer.subscribe_updates());
+
+        let mut losing = summary(WALLET, 10, "{}");
+        losing.realized_pnl_24h = "-7500".to_string();
+        indexer.publish_update(WalletUpdate::Summary(Box::new(losing)));
+
+        loop {
+            match tokio::time::timeout(std::time::Duration::from_secs(2), published.recv()).await.unwrap().unwrap() {
+                WalletUpdate::Alert(alert) => {
+                    assert_eq!(alert.wallet, WALLET);
+                    assert_eq!(alert.condition, "realized_pnl_24h < -5000");
+                    assert_eq!(alert.state, AlertState::Firing);
+                    break;
+                }
+                _ => continue,
+            }
+        }
+        assert_eq!(store.get_alert_states().await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_transactions_fire_alerts() {
+        let (engine, _) = engine_with("protocol_count >= 1", vec![WALLET.to_string()]).await;
+        let indexer = engine.indexer.clone();
+        let mut published = indexer.subscribe_updates();
+        engine.spawn(indexer.subscribe_updates());
+
+        // Ingested like a live subscription; nothing hands the engine a summary directly
+        let now = chrono::Utc::now().timestamp();
+        let swap = crate::indexer::test_swap(WALLET, "sig-live-00000000000000", 1, now, (crate::tokens::USDC_MINT, 100.0), (crate::tokens::WSOL_MINT, 1.0));
+        indexer.ingest(WALLET, vec![swap]).await;
+
+        loop {
+            match tokio::time::timeout(std::time::Duration::from_secs(2), published.recv()).await.unwrap().unwrap() {
+                WalletUpdate::Alert(alert) => {
+                    assert_eq!((alert.wallet.as_str(), alert.state), (WALLET, AlertState::Firing));
+                    break;
+                }
+                _ => continue,
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn test_group_rule_follows_membership() {
+        let store = Arc::new(MemoryStore::new());
+        let groups = WalletGroups::new(store.clone());
+        let group = crate::groups::new_group("funds".to_string(), vec![WALLET.to_string()], Vec::new());
+        groups.save(group.clone()).await.unwrap();
+        let engine = AlertEngine::new(store.clone(), Indexer::offline(store.clone()), groups.clone());
+        let condition = Condition::parse("risk_score > 70").unwrap();
+        engine
+            .create(new_rule("group".to_string(), &condition, Vec::new(), Some(group.id.clone())), condition)
+            .await
+            .unwrap();
+
+        assert_eq!(engine.evaluate(&summary(WALLET, 80, "{}")).await.len(), 1);
+        // Not a member yet
+        assert!(engine.evaluate(&summary(OTHER_WALLET, 80, "{}")).await.is_empty());
+
+        // Members are looked up at evaluation time: OTHER_WALLET joins, WALLET leaves
+        let mut changed = group.clone();
+        changed.wallets = vec![OTHER_WALLET.to_string()];
+        groups.save(changed).await.unwrap();
+        assert_eq!(engine.evaluate(&summary(OTHER_WALLET, 80, "{}")).await.len(), 1);
+        let resolved = engine.evaluate(&summary(WALLET, 80, "{}")).await;
+        assert_eq!((resolved[0].state, resolved[0].value), (AlertState::Resolved, None));
+
+        // A deleted group covers nobody
+        groups.delete(&group.id).await.unwrap();
+        let resolved = engine.evaluate(&summary(OTHER_WALLET, 90, "{}")).await;
+        assert_eq!(resolved[0].state, AlertState::Resolved);
+        assert!(engine.evaluate(&summary(OTHER_WALLET, 95, "{}")).await.is_empty());
+    }
+}
@@ -1,9 +1,26 @@
+use async_graphql::SimpleObject;
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
 use chrono::{DateTime, Utc};
+use cortex_core::{WalletClassification, WalletFeatures};
+use rust_decimal::prelude::ToPrimitive;
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet};
 use std::str::FromStr;
 
-use crate::db::models::{PositionRow, PnlByProtocolRow, WalletSummaryRow};
+use crate::alerts::Alert;
+use crate::db::models::{
+    AlertRuleRow, ApiKeyRow, LeaderboardRow, PositionRow, PositionSignalRow, PnlByProtocolRow, TransactionCursor, TransactionRow, WalletGroupRow,
+    WalletMevLossRow, WalletProtocolPnlRow, WalletSummaryRow, WebhookDeliveryRow, WebhookRow,
+};
+use crate::error::AppError;
+use crate::indexer::WalletUpdate;
+use crate::metrics::cluster::Cluster;
+use crate::metrics::flows::{SmartMoneyFlow, TokenFlows, WalletFlow};
+use crate::metrics::performance::PerformanceMetrics;
+use crate::metrics::pnl::Disposal;
+use crate::metrics::signals::PositionSignal;
 
 /// Helper to parse string to Decimal, defaulting to zero on error
 fn parse_decimal(s: &str) -> Decimal {
@@ -14,17 +31,69 @@ fn parse_decimal(s: &str) -> Decimal {
 // GET /api/v1/user/{wallet}/summary
 // ============================================================================
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UserSummaryResponse {
     pub wallet: String,
     pub total_value_usd: Decimal,
     pub pnl: PnlSummary,
     pub risk: RiskSummary,
+    pub classification: ClassificationSummary,
     pub last_activity: DateTime<Utc>,
     pub protocols: Vec<String>,
+    /// Set when the summary covers the wallet's whole cluster (`?scope=entity`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity: Option<EntityInfo>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ScopeQuery {
+    #[serde(default = "default_scope")]
+    pub scope: String,
+}
+
+fn default_scope() -> String {
+    "wallet".to_string()
+}
+
+/// The cluster a response was aggregated over
+#[derive(Debug, Serialize, ToSchema)]
+pub struct EntityInfo {
+    pub cluster_id: String,
+    pub members: Vec<String>,
+}
+
+impl From<&Cluster> for EntityInfo {
+    fn from(cluster: &Cluster) -> Self {
+        Self {
+            cluster_id: cluster.id.clone(),
+            members: cluster.members.clone(),
+        }
+    }
+}
+
+/// Behavioural class assigned by `cortex_core::classify_wallet`
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClassificationSummary {
+    #[schema(value_type = String, example = "trader")]
+    pub class: WalletClassification,
+    pub confidence: Decimal,
+    #[schema(value_type = Object)]
+    pub features: WalletFeatures,
 }
 
-#[derive(Debug, Serialize)]
+impl ClassificationSummary {
+    /// Classification for a wallet that has not been indexed yet
+    pub fn unknown() -> Self {
+        Self {
+            class: WalletClassification::Unknown,
+            confidence: Decimal::ZERO,
+            features: WalletFeatures::default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
 pub struct PnlSummary {
     pub realized_24h: Decimal,
     pub realized_7d: Decimal,
@@ -32,15 +101,72 @@ pub struct PnlSummary {
     pub unrealized: Decimal,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, SimpleObject, ToSchema)]
+#[graphql(name = "Risk")]
 pub struct RiskSummary {
     pub score: u8,
     pub largest_position_pct: Decimal,
     pub protocol_count: u8,
+    pub leverage: Decimal,
+    pub liquidation_distance: Decimal,
+    pub stablecoin_pct: Decimal,
+    pub volatile_pct: Decimal,
+    pub memecoin_pct: Decimal,
+    /// USD exposure per underlying asset mint, net of debt. Wrapped and staked variants
+    /// count toward their asset, and liquidity positions are split across their pool.
+    pub asset_exposure: BTreeMap<String, Decimal>,
+    pub components: RiskComponentsSummary,
+    pub var: VarSummary,
+}
+
+/// 1-day historical value-at-risk of current exposure, losses in USD. The estimates
+/// are null while none of the exposure has price history.
+#[derive(Debug, Default, Serialize, SimpleObject, ToSchema)]
+#[graphql(name = "ValueAtRisk")]
+pub struct VarSummary {
+    pub var_95: Option<Decimal>,
+    pub var_99: Option<Decimal>,
+    pub expected_shortfall_95: Option<Decimal>,
+    pub expected_shortfall_99: Option<Decimal>,
+    pub portfolio_volatility: Option<Decimal>,
+    /// Share of exposure with price history (stablecoins count as covered), 0-1
+    pub coverage: Decimal,
+}
+
+/// Individual 0-100 scores blended into `RiskSummary::score`
+#[derive(Debug, Default, Serialize, SimpleObject, ToSchema)]
+#[graphql(name = "RiskComponents")]
+pub struct RiskComponentsSummary {
+    pub concentration: u8,
+    pub leverage: u8,
+    pub liquidation: u8,
+    pub asset: u8,
+    pub contract: u8,
+}
+
+impl RiskSummary {
+    /// Risk block for a wallet that has not been indexed yet
+    pub fn empty() -> Self {
+        Self {
+            score: 0,
+            largest_position_pct: Decimal::ZERO,
+            protocol_count: 0,
+            leverage: Decimal::ZERO,
+            liquidation_distance: Decimal::ONE,
+            stablecoin_pct: Decimal::ZERO,
+            volatile_pct: Decimal::ZERO,
+            memecoin_pct: Decimal::ZERO,
+            asset_exposure: BTreeMap::new(),
+            components: RiskComponentsSummary::default(),
+            var: VarSummary::default(),
+        }
+    }
 }
 
 impl From<WalletSummaryRow> for UserSummaryResponse {
     fn from(row: WalletSummaryRow) -> Self {
+        let var_coverage = parse_decimal(&row.var_coverage);
+        let estimate = |value: &str| (!var_coverage.is_zero()).then(|| parse_decimal(value));
         Self {
             wallet: row.wallet.clone(),
             total_value_usd: parse_decimal(&row.total_value_usd),
@@ -54,9 +180,44 @@ impl From<WalletSummaryRow> for UserSummaryResponse {
                 score: row.risk_score,
                 largest_position_pct: parse_decimal(&row.largest_position_pct),
                 protocol_count: row.protocol_count,
+                leverage: parse_decimal(&row.leverage),
+                liquidation_distance: parse_decimal(&row.liquidation_distance),
+                stablecoin_pct: parse_decimal(&row.stablecoin_pct),
+                volatile_pct: if row.position_count > 0 {
+                    Decimal::ONE - parse_decimal(&row.stablecoin_pct)
+                } else {
+                    Decimal::ZERO
+                },
+                memecoin_pct: parse_decimal(&row.memecoin_pct),
+                asset_exposure: serde_json::from_str::<BTreeMap<String, String>>(&row.asset_exposure)
+                    .unwrap_or_default()
+                    .iter()
+                    .map(|(asset, usd)| (asset.clone(), parse_decimal(usd)))
+                    .collect(),
+                components: RiskComponentsSummary {
+                    concentration: row.concentration_risk,
+                    leverage: row.leverage_risk,
+                    liquidation: row.liquidation_risk,
+                    asset: row.asset_risk,
+                    contract: row.contract_risk,
+                },
+                var: VarSummary {
+                    var_95: estimate(&row.var_95),
+                    var_99: estimate(&row.var_99),
+                    expected_shortfall_95: estimate(&row.expected_shortfall_95),
+                    expected_shortfall_99: estimate(&row.expected_shortfall_99),
+                    portfolio_volatility: estimate(&row.portfolio_volatility),
+                    coverage: var_coverage,
+                },
+            },
+            classification: ClassificationSummary {
+                class: WalletClassification::from_str_lossy(&row.classification),
+                confidence: parse_decimal(&row.classification_confidence),
+                features: serde_json::from_str(&row.classification_features).unwrap_or_default(),
             },
             last_activity: row.last_activity_datetime(),
             protocols: row.protocols,
+            entity: None,
         }
     }
 }
@@ -65,7 +226,8 @@ impl From<WalletSummaryRow> for UserSummaryResponse {
 // GET /api/v1/user/{wallet}/pnl
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PnlQuery {
     #[serde(default = "default_window")]
     pub window: String,
@@ -75,16 +237,19 @@ fn default_window() -> String {
     "7d".to_string()
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UserPnlResponse {
     pub wallet: String,
     pub window: String,
     pub total_realized: Decimal,
     pub total_unrealized: Decimal,
+    /// Estimated USD lost to sandwich attacks in the window
+    pub mev_losses: Decimal,
+    pub sandwiched_count: u64,
     pub by_protocol: Vec<ProtocolPnl>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, SimpleObject, ToSchema)]
 pub struct ProtocolPnl {
     pub protocol: String,
     pub realized: Decimal,
@@ -107,17 +272,20 @@ impl From<PnlByProtocolRow> for ProtocolPnl {
 // GET /api/v1/user/{wallet}/positions
 // ============================================================================
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct UserPositionsResponse {
     pub wallet: String,
     pub positions: Vec<Position>,
     pub total_value_usd: Decimal,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub entity: Option<EntityInfo>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, SimpleObject, ToSchema)]
 pub struct Position {
     pub protocol: String,
     #[serde(rename = "type")]
+    #[graphql(name = "type")]
     pub position_type: String,
     pub token: String,
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -127,6 +295,10 @@ pub struct Position {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub apy: Option<Decimal>,
     pub unrealized_pnl: Decimal,
+    /// Member wallet holding the position (entity scope only)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    #[graphql(skip)]
+    pub owner: Option<String>,
 }
 
 impl From<PositionRow> for Position {
@@ -141,15 +313,424 @@ impl From<PositionRow> for Position {
             usd_value: parse_decimal(&row.usd_value),
             apy: if apy.is_zero() { None } else { Some(apy) },
             unrealized_pnl: parse_decimal(&row.unrealized_pnl),
+            owner: None,
+        }
+    }
+}
+
+// ============================================================================
+// POST /api/v1/wallets/{summary,positions,pnl,portfolio}
+// ============================================================================
+
+/// Most wallets one batch request may name
+pub const MAX_BATCH_WALLETS: usize = 200;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WalletBatchRequest {
+    pub wallets: Vec<String>,
+    /// PnL window for `/pnl` and `/portfolio`; ignored by the others
+    #[serde(default = "default_window")]
+    pub window: String,
+}
+
+/// Results keyed by wallet, and the wallets that could not be served
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WalletBatchResponse<T> {
+    pub results: BTreeMap<String, T>,
+    pub errors: BTreeMap<String, BatchError>,
+}
+
+/// Why one wallet of a batch has no result, shaped like an error response
+#[derive(Debug, Serialize, ToSchema)]
+pub struct BatchError {
+    pub error: String,
+    pub code: String,
+}
+
+impl BatchError {
+    pub fn invalid_wallet(wallet: &str) -> Self {
+        Self {
+            error: AppError::InvalidWallet(wallet.to_string()).to_string(),
+            code: "INVALID_WALLET".to_string(),
+        }
+    }
+
+    pub fn not_found(wallet: &str) -> Self {
+        Self {
+            error: AppError::WalletNotFound(wallet.to_string()).to_string(),
+            code: "WALLET_NOT_FOUND".to_string(),
+        }
+    }
+}
+
+/// A set of wallets summed as one book
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PortfolioResponse {
+    /// Set when the book is a wallet group (`GET /api/v1/groups/{id}/summary`)
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<GroupInfo>,
+    /// Valid wallets in the book
+    pub wallets: Vec<String>,
+    /// How many of them have been indexed
+    pub indexed_count: usize,
+    pub total_value_usd: Decimal,
+    pub pnl: PnlSummary,
+    pub window: String,
+    /// Realized PnL over `window`, summed per protocol
+    pub by_protocol: Vec<ProtocolPnl>,
+    pub mev_losses: Decimal,
+    pub sandwiched_count: u64,
+    pub risk: PortfolioRisk,
+    pub protocols: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_activity: Option<DateTime<Utc>>,
+    /// Holdings of the same token in the same protocol and pool merged, largest first
+    pub positions: Vec<Position>,
+    pub errors: BTreeMap<String, BatchError>,
+}
+
+/// Book-level risk. Scores and mixes are member values weighted by USD value;
+/// concentration is measured on the merged positions.
+#[derive(Debug, Serialize, ToSchema)]
+pub struct PortfolioRisk {
+    pub score: u8,
+    pub largest_position_pct: Decimal,
+    pub stablecoin_pct: Decimal,
+    pub memecoin_pct: Decimal,
+}
+
+/// Rows loaded for a portfolio
+pub struct PortfolioRows {
+    pub summaries: Vec<WalletSummaryRow>,
+    pub positions: Vec<PositionRow>,
+    pub pnl: Vec<WalletProtocolPnlRow>,
+    pub mev: Vec<WalletMevLossRow>,
+}
+
+/// Mean of `(value, weight)` pairs, unweighted when every weight is zero
+fn weighted_mean(values: &[(Decimal, Decimal)]) -> Decimal {
+    if values.is_empty() {
+        return Decimal::ZERO;
+    }
+    let total_weight: Decimal = values.iter().map(|(_, w)| *w).sum();
+    if total_weight.is_zero() {
+        return values.iter().map(|(v, _)| *v).sum::<Decimal>() / Decimal::from(values.len());
+    }
+    values.iter().map(|(v, w)| v * w).sum::<Decimal>() / total_weight
+}
+
+impl PortfolioResponse {
+    pub fn new(wallets: Vec<String>, window: String, rows: PortfolioRows, errors: BTreeMap<String, BatchError>) -> Self {
+        let summaries: Vec<UserSummaryResponse> = rows.summaries.into_iter().map(Into::into).collect();
+        let sum = |f: fn(&UserSummaryResponse) -> Decimal| summaries.iter().map(f).sum::<Decimal>();
+        let weighted = |f: fn(&UserSummaryResponse) -> Decimal| {
+            weighted_mean(&summaries.iter().map(|s| (f(s), s.total_value_usd)).collect::<Vec<_>>())
+        };
+
+        let mut by_protocol: BTreeMap<String, ProtocolPnl> = BTreeMap::new();
+        for row in rows.pnl {
+            let entry = by_protocol.entry(row.protocol.clone()).or_insert_with(|| ProtocolPnl {
+                protocol: row.protocol.clone(),
+                realized: Decimal::ZERO,
+                unrealized: Decimal::ZERO,
+                trade_count: 0,
+            });
+            entry.realized += parse_decimal(&row.realized);
+            entry.unrealized += parse_decimal(&row.unrealized);
+            entry.trade_count += row.trade_count;
+        }
+        let mut by_protocol: Vec<ProtocolPnl> = by_protocol.into_values().collect();
+        by_protocol.sort_by_key(|p| Reverse(p.realized));
+
+        let mut merged: BTreeMap<(String, String, String, String), (Position, Decimal)> = BTreeMap::new();
+        for row in rows.positions {
+            let key = (row.protocol.clone(), row.position_type.clone(), row.token.clone(), row.pool.clone());
+            let position = Position::from(row);
+            let apy_weight = position.apy.unwrap_or_default() * position.usd_value;
+            match merged.get_mut(&key) {
+                Some((total, apy_total)) => {
+                    total.amount += position.amount;
+                    total.usd_value += position.usd_value;
+                    total.unrealized_pnl += position.unrealized_pnl;
+                    *apy_total += apy_weight;
+                }
+                None => {
+                    merged.insert(key, (position, apy_weight));
+                }
+            }
+        }
+        let mut positions: Vec<Position> = merged
+            .into_values()
+            .map(|(mut position, apy_total)| {
+                position.apy = (!apy_total.is_zero() && !position.usd_value.is_zero()).then(|| apy_total / position.usd_value);
+                position
+            })
+            .collect();
+        positions.sort_by_key(|p| Reverse(p.usd_value));
+
+        let position_value: Decimal = positions.iter().map(|p| p.usd_value).sum();
+        let largest_position_pct = match positions.first() {
+            Some(largest) if !position_value.is_zero() => largest.usd_value / position_value,
+            _ => Decimal::ZERO,
+        };
+        let score = weighted_mean(
+            &summaries
+                .iter()
+                .map(|s| (Decimal::from(s.risk.score), s.total_value_usd))
+                .collect::<Vec<_>>(),
+        );
+
+        let protocols: BTreeSet<String> = summaries.iter().flat_map(|s| s.protocols.iter().cloned()).collect();
+
+        Self {
+            indexed_count: summaries.len(),
+            total_value_usd: sum(|s| s.total_value_usd),
+            pnl: PnlSummary {
+                realized_24h: sum(|s| s.pnl.realized_24h),
+                realized_7d: sum(|s| s.pnl.realized_7d),
+                realized_30d: sum(|s| s.pnl.realized_30d),
+                unrealized: sum(|s| s.pnl.unrealized),
+            },
+            window,
+            by_protocol,
+            mev_losses: rows.mev.iter().map(|m| parse_decimal(&m.estimated_loss_usd)).sum(),
+            sandwiched_count: rows.mev.iter().map(|m| m.sandwiched_count).sum(),
+            risk: PortfolioRisk {
+                score: score.round().to_u8().unwrap_or(0),
+                largest_position_pct,
+                stablecoin_pct: weighted(|s| s.risk.stablecoin_pct),
+                memecoin_pct: weighted(|s| s.risk.memecoin_pct),
+            },
+            protocols: protocols.into_iter().collect(),
+            last_activity: summaries.iter().map(|s| s.last_activity).max(),
+            positions,
+            group: None,
+            wallets,
+            errors,
+        }
+    }
+}
+
+// ============================================================================
+// GET /api/v1/user/{wallet}/cluster
+// ============================================================================
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClusterResponse {
+    pub wallet: String,
+    pub cluster_id: String,
+    pub member_count: usize,
+    pub members: Vec<String>,
+    pub links: Vec<ClusterLink>,
+}
+
+/// One piece of evidence joining two members
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ClusterLink {
+    pub from: String,
+    pub to: String,
+    pub reason: String,
+    pub evidence_count: usize,
+}
+
+impl ClusterResponse {
+    pub fn new(wallet: String, cluster: Cluster) -> Self {
+        Self {
+            wallet,
+            cluster_id: cluster.id,
+            member_count: cluster.members.len(),
+            members: cluster.members,
+            links: cluster
+                .edges
+                .into_iter()
+                .map(|edge| ClusterLink {
+                    from: edge.from,
+                    to: edge.to,
+                    reason: edge.reason.to_string(),
+                    evidence_count: edge.evidence_count,
+                })
+                .collect(),
+        }
+    }
+}
+
+// ============================================================================
+// GET /api/v1/user/{wallet}/performance
+// ============================================================================
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PerformanceQuery {
+    #[serde(default = "default_performance_window")]
+    pub window: String,
+}
+
+fn default_performance_window() -> String {
+    "30d".to_string()
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UserPerformanceResponse {
+    pub wallet: String,
+    pub window: String,
+    pub trade_count: u64,
+    pub win_rate: Decimal,
+    pub avg_win: Decimal,
+    pub avg_loss: Decimal,
+    pub profit_factor: Option<Decimal>,
+    pub realized_pnl: Decimal,
+    pub sharpe_ratio: Option<f64>,
+    pub sortino_ratio: Option<f64>,
+    pub max_drawdown: Decimal,
+    pub max_drawdown_duration_days: f64,
+}
+
+impl UserPerformanceResponse {
+    pub fn new(wallet: String, window: String, metrics: PerformanceMetrics) -> Self {
+        Self {
+            wallet,
+            window,
+            trade_count: metrics.trade_count,
+            win_rate: metrics.win_rate.round_dp(4),
+            avg_win: metrics.avg_win.round_dp(2),
+            avg_loss: metrics.avg_loss.round_dp(2),
+            profit_factor: metrics.profit_factor.map(|p| p.round_dp(4)),
+            realized_pnl: metrics.realized_pnl.round_dp(2),
+            sharpe_ratio: metrics.sharpe_ratio,
+            sortino_ratio: metrics.sortino_ratio,
+            max_drawdown: metrics.max_drawdown.round_dp(2),
+            max_drawdown_duration_days: metrics.max_drawdown_duration_days,
         }
     }
 }
 
+// ============================================================================
+// GET /api/v1/user/{wallet}/tax-lots
+// ============================================================================
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TaxLotsQuery {
+    /// Tax year (UTC); defaults to the previous calendar year
+    pub year: Option<i32>,
+    #[serde(default = "default_lot_method")]
+    pub method: String,
+    #[serde(default = "default_tax_format")]
+    pub format: String,
+}
+
+fn default_lot_method() -> String {
+    "fifo".to_string()
+}
+
+fn default_tax_format() -> String {
+    "json".to_string()
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaxLotsResponse {
+    pub wallet: String,
+    pub tax_year: i32,
+    pub method: String,
+    pub disposals: Vec<TaxLotDisposal>,
+    pub totals: TaxLotTotals,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TaxLotDisposal {
+    pub token: String,
+    pub amount: Decimal,
+    pub acquired_at: DateTime<Utc>,
+    pub disposed_at: DateTime<Utc>,
+    pub proceeds: Decimal,
+    pub cost_basis: Decimal,
+    pub gain: Decimal,
+    /// `short` or `long`
+    pub holding_period: String,
+    pub signature: String,
+}
+
+#[derive(Debug, Default, Serialize, ToSchema)]
+pub struct TaxLotTotals {
+    pub proceeds: Decimal,
+    pub cost_basis: Decimal,
+    pub gain: Decimal,
+    pub short_term_gain: Decimal,
+    pub long_term_gain: Decimal,
+}
+
+impl TaxLotsResponse {
+    pub fn new(wallet: String, tax_year: i32, method: String, disposals: Vec<Disposal>) -> Self {
+        let mut totals = TaxLotTotals::default();
+        let disposals: Vec<TaxLotDisposal> = disposals
+            .into_iter()
+            .map(|d| {
+                let long_term = d.is_long_term();
+                totals.proceeds += d.proceeds;
+                totals.cost_basis += d.cost_basis;
+                totals.gain += d.gain;
+                if long_term {
+                    totals.long_term_gain += d.gain;
+                } else {
+                    totals.short_term_gain += d.gain;
+                }
+                TaxLotDisposal {
+                    token: d.token,
+                    amount: d.amount,
+                    acquired_at: DateTime::from_timestamp_millis(d.acquired_at).unwrap_or_default(),
+                    disposed_at: DateTime::from_timestamp_millis(d.disposed_at).unwrap_or_default(),
+                    proceeds: d.proceeds.round_dp(2),
+                    cost_basis: d.cost_basis.round_dp(2),
+                    gain: d.gain.round_dp(2),
+                    holding_period: if long_term { "long" } else { "short" }.to_string(),
+                    signature: d.signature,
+                }
+            })
+            .collect();
+
+        Self {
+            wallet,
+            tax_year,
+            method,
+            disposals,
+            totals: TaxLotTotals {
+                proceeds: totals.proceeds.round_dp(2),
+                cost_basis: totals.cost_basis.round_dp(2),
+                gain: totals.gain.round_dp(2),
+                short_term_gain: totals.short_term_gain.round_dp(2),
+                long_term_gain: totals.long_term_gain.round_dp(2),
+            },
+        }
+    }
+
+    /// One row per disposal, for spreadsheets and accountants
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "token,amount,acquired_date,disposed_date,proceeds_usd,cost_basis_usd,gain_usd,holding_period,signature\n",
+        );
+        for d in &self.disposals {
+            csv.push_str(&format!(
+                "{},{},{},{},{},{},{},{},{}\n",
+                d.token,
+                d.amount.normalize(),
+                d.acquired_at.format("%Y-%m-%d"),
+                d.disposed_at.format("%Y-%m-%d"),
+                d.proceeds,
+                d.cost_basis,
+                d.gain,
+                d.holding_period,
+                d.signature,
+            ));
+        }
+        csv
+    }
+}
+
 // ============================================================================
 // Health check
 // ============================================================================
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct HealthResponse {
     pub status: String,
     pub version: String,
@@ -160,12 +741,12 @@ pub struct HealthResponse {
 // Index wallet request (for triggering indexing)
 // ============================================================================
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Deserialize, ToSchema)]
 pub struct IndexWalletRequest {
     pub wallet: String,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct IndexWalletResponse {
     pub wallet: String,
     pub status: String,
@@ -176,15 +757,791 @@ pub struct IndexWalletResponse {
 // List subscriptions
 // ============================================================================
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SubscriptionsResponse {
     pub subscriptions: Vec<SubscriptionInfo>,
 }
 
-#[derive(Debug, Serialize)]
+#[derive(Debug, Serialize, ToSchema)]
 pub struct SubscriptionInfo {
     pub wallet: String,
     pub started_at: String,
     pub transactions_processed: u64,
     pub running: bool,
 }
+
+// ============================================================================
+// /api/v1/groups
+// ============================================================================
+
+/// Body of `POST /api/v1/groups` and `PUT /api/v1/groups/{id}`, which replaces the whole group
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct WalletGroupRequest {
+    pub name: String,
+    #[serde(default)]
+    pub wallets: Vec<String>,
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WalletGroupsQuery {
+    /// Only groups with this tag
+    pub tag: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WalletGroupResponse {
+    pub id: String,
+    pub name: String,
+    pub wallets: Vec<String>,
+    pub tags: Vec<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<WalletGroupRow> for WalletGroupResponse {
+    fn from(row: WalletGroupRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            wallets: row.wallets,
+            tags: row.tags,
+            created_at: DateTime::from_timestamp_millis(row.created_at).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WalletGroupsResponse {
+    pub groups: Vec<WalletGroupResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeleteWalletGroupResponse {
+    pub id: String,
+    pub status: String,
+}
+
+/// The group a portfolio was summed over
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GroupInfo {
+    pub id: String,
+    pub name: String,
+    pub tags: Vec<String>,
+}
+
+impl From<&WalletGroupRow> for GroupInfo {
+    fn from(row: &WalletGroupRow) -> Self {
+        Self {
+            id: row.id.clone(),
+            name: row.name.clone(),
+            tags: row.tags.clone(),
+        }
+    }
+}
+
+/// Outcome of starting a subscription for every member of a group
+#[derive(Debug, Serialize, ToSchema)]
+pub struct GroupIndexResponse {
+    pub group_id: String,
+    pub started: usize,
+    pub already_running: usize,
+    /// Members not started because the key's subscription quota ran out
+    pub quota_exceeded: usize,
+    /// Members whose subscription failed to start; their entries carry the error
+    pub errors: usize,
+    pub wallets: Vec<IndexWalletResponse>,
+}
+
+// ============================================================================
+// GET /api/v1/user/{wallet}/transactions
+// ============================================================================
+
+/// Most transactions returned per page
+pub const MAX_TRANSACTION_PAGE: u32 = 200;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TransactionHistoryQuery {
+    pub protocol: Option<String>,
+    pub tx_type: Option<String>,
+    /// Mint on either leg
+    pub token: Option<String>,
+    /// RFC 3339 timestamp, inclusive
+    pub from: Option<String>,
+    /// RFC 3339 timestamp, exclusive
+    pub to: Option<String>,
+    pub min_usd_value: Option<String>,
+    /// newest, oldest or largest
+    #[serde(default = "default_transaction_sort")]
+    pub sort: String,
+    #[serde(default = "default_transaction_limit")]
+    pub limit: u32,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+}
+
+fn default_transaction_sort() -> String {
+    "newest".to_string()
+}
+
+fn default_transaction_limit() -> u32 {
+    50
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TransactionHistoryResponse {
+    pub wallet: String,
+    pub transactions: Vec<TransactionEntry>,
+    /// Pass as `cursor` with the same filters and sort for the next page; absent on the last page
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, SimpleObject, ToSchema)]
+#[graphql(name = "Transaction")]
+pub struct TransactionEntry {
+    pub signature: String,
+    pub protocol: String,
+    pub tx_type: String,
+    /// What the wallet gave up, absent when nothing left the wallet
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_in: Option<TransactionLeg>,
+    /// What the wallet received, absent when nothing came back
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_out: Option<TransactionLeg>,
+    pub usd_value: Decimal,
+    pub slot: u64,
+    pub block_time: DateTime<Utc>,
+}
+
+#[derive(Debug, Serialize, SimpleObject, ToSchema)]
+pub struct TransactionLeg {
+    pub mint: String,
+    pub amount: Decimal,
+}
+
+impl TransactionLeg {
+    fn new(mint: String, amount: &str) -> Option<Self> {
+        (!mint.is_empty()).then(|| Self {
+            mint,
+            amount: parse_decimal(amount),
+        })
+    }
+}
+
+impl TransactionEntry {
+    /// Opaque pagination cursor pointing just past this transaction
+    pub fn cursor(&self) -> String {
+        format!("{}:{}:{}", self.block_time.timestamp_millis(), self.usd_value, self.signature)
+    }
+
+    /// Inverse of `cursor`
+    pub fn parse_cursor(cursor: &str) -> Option<TransactionCursor> {
+        let mut parts = cursor.splitn(3, ':');
+        let block_time = parts.next()?.parse().ok()?;
+        let usd_value = Decimal::from_str(parts.next()?).ok()?.to_string();
+        let signature = parts.next()?.to_string();
+        Some(TransactionCursor {
+            block_time,
+            usd_value,
+            signature,
+        })
+    }
+}
+
+impl From<TransactionRow> for TransactionEntry {
+    fn from(row: TransactionRow) -> Self {
+        Self {
+            token_in: TransactionLeg::new(row.token_in, &row.amount_in),
+            token_out: TransactionLeg::new(row.token_out, &row.amount_out),
+            usd_value: parse_decimal(&row.usd_value),
+            block_time: DateTime::from_timestamp_millis(row.block_time).unwrap_or_default(),
+            signature: row.signature,
+            protocol: row.protocol,
+            tx_type: row.tx_type,
+            slot: row.slot,
+        }
+    }
+}
+
+// ============================================================================
+// GET /api/v1/signals and /api/v1/signals/stream
+// ============================================================================
+
+/// Most signals returned per page
+pub const MAX_SIGNAL_PAGE: u32 = 200;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SignalFeedQuery {
+    /// Comma-separated wallets to follow; all subscribed wallets when absent
+    pub wallet: Option<String>,
+    #[serde(default = "default_signal_limit")]
+    pub limit: u32,
+    /// `next_cursor` from the previous page
+    pub cursor: Option<String>,
+}
+
+fn default_signal_limit() -> u32 {
+    50
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SignalStreamQuery {
+    pub wallet: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SignalFeedResponse {
+    pub signals: Vec<SignalEvent>,
+    /// Pass as `cursor` to fetch older signals; absent on the last page
+    pub next_cursor: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SignalEvent {
+    pub wallet: String,
+    pub signature: String,
+    pub token: String,
+    /// opened, added, reduced or closed
+    pub kind: String,
+    pub amount: Decimal,
+    pub price: Decimal,
+    pub usd_value: Decimal,
+    pub position_size: Decimal,
+    pub portfolio_weight: Decimal,
+    pub slot: u64,
+    pub block_time: DateTime<Utc>,
+}
+
+impl SignalEvent {
+    /// Opaque pagination cursor pointing just past this signal
+    pub fn cursor(&self) -> String {
+        format!("{}:{}:{}", self.block_time.timestamp_millis(), self.signature, self.token)
+    }
+
+    /// Inverse of `cursor`: `(block_time_ms, signature, token)`
+    pub fn parse_cursor(cursor: &str) -> Option<(i64, String, String)> {
+        let mut parts = cursor.splitn(3, ':');
+        let block_time = parts.next()?.parse().ok()?;
+        let signature = parts.next()?.to_string();
+        let token = parts.next()?.to_string();
+        Some((block_time, signature, token))
+    }
+}
+
+impl From<PositionSignalRow> for SignalEvent {
+    fn from(row: PositionSignalRow) -> Self {
+        Self {
+            wallet: row.wallet,
+            signature: row.signature,
+            token: row.token,
+            kind: row.kind,
+            amount: parse_decimal(&row.amount),
+            price: parse_decimal(&row.price),
+            usd_value: parse_decimal(&row.usd_value),
+            position_size: parse_decimal(&row.position_size),
+            portfolio_weight: parse_decimal(&row.portfolio_weight),
+            slot: row.slot,
+            block_time: DateTime::from_timestamp_millis(row.block_time).unwrap_or_default(),
+        }
+    }
+}
+
+impl From<PositionSignal> for SignalEvent {
+    fn from(signal: PositionSignal) -> Self {
+        Self {
+            wallet: signal.wallet,
+            signature: signal.signature,
+            token: signal.token,
+            kind: signal.kind.to_string(),
+            amount: signal.amount,
+            price: signal.price.round_dp(8),
+            usd_value: signal.usd_value,
+            position_size: signal.position_size,
+            portfolio_weight: signal.portfolio_weight,
+            slot: signal.slot,
+            block_time: DateTime::from_timestamp_millis(signal.block_time).unwrap_or_default(),
+        }
+    }
+}
+
+// ============================================================================
+// GET /api/v1/stream and /api/v1/user/{wallet}/stream (SSE and WebSocket)
+// ============================================================================
+
+/// A pushed change to a wallet. Over SSE the variant names the event and the payload
+/// is its data; over WebSocket each text message is `{"type": ..., "data": ...}`.
+#[derive(Debug, Serialize, ToSchema)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum WalletUpdateEvent {
+    Transaction(Box<TransactionUpdate>),
+    Summary(Box<UserSummaryResponse>),
+    Alert(Box<Alert>),
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TransactionUpdate {
+    pub wallet: String,
+    #[serde(flatten)]
+    pub transaction: TransactionEntry,
+}
+
+impl WalletUpdateEvent {
+    /// SSE event name
+    pub fn name(&self) -> &'static str {
+        match self {
+            WalletUpdateEvent::Transaction(_) => "transaction",
+            WalletUpdateEvent::Summary(_) => "summary",
+            WalletUpdateEvent::Alert(_) => "alert",
+        }
+    }
+}
+
+impl From<WalletUpdate> for WalletUpdateEvent {
+    fn from(update: WalletUpdate) -> Self {
+        match update {
+            WalletUpdate::Transaction(row) => WalletUpdateEvent::Transaction(Box::new(TransactionUpdate {
+                wallet: row.wallet.clone(),
+                transaction: (*row).into(),
+            })),
+            WalletUpdate::Summary(row) => WalletUpdateEvent::Summary(Box::new((*row).into())),
+            WalletUpdate::Alert(alert) => WalletUpdateEvent::Alert(alert),
+        }
+    }
+}
+
+// ============================================================================
+// /api/v1/keys
+// ============================================================================
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateApiKeyRequest {
+    pub name: String,
+    /// read, index and/or admin
+    pub scopes: Vec<String>,
+    /// Requests per minute; the server default when absent, 0 for unlimited
+    pub rate_limit_per_minute: Option<u32>,
+    /// Concurrent wallet subscriptions; the server default when absent, 0 for unlimited
+    pub max_subscriptions: Option<u32>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeyResponse {
+    pub id: String,
+    pub name: String,
+    /// First characters of the key
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    pub rate_limit_per_minute: u32,
+    pub max_subscriptions: u32,
+    pub created_at: DateTime<Utc>,
+    /// Only returned when the key is created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+}
+
+impl From<ApiKeyRow> for ApiKeyResponse {
+    fn from(row: ApiKeyRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            key_prefix: row.key_prefix,
+            scopes: row.scopes,
+            rate_limit_per_minute: row.rate_limit_per_minute,
+            max_subscriptions: row.max_subscriptions,
+            created_at: DateTime::from_timestamp_millis(row.created_at).unwrap_or_default(),
+            key: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct ApiKeysResponse {
+    pub keys: Vec<ApiKeyResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct RevokeApiKeyResponse {
+    pub id: String,
+    pub status: String,
+}
+
+// ============================================================================
+// /api/v1/alerts
+// ============================================================================
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateAlertRuleRequest {
+    pub name: String,
+    /// `<metric> <operator> <number>`, e.g. `risk_score > 70` or `kamino.ltv > 0.85`
+    pub condition: String,
+    /// Wallets the rule covers; every wallet when empty and there is no `group_id`
+    #[serde(default)]
+    pub wallets: Vec<String>,
+    /// Wallet group whose members the rule also covers, looked up each time it is evaluated
+    pub group_id: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AlertRuleResponse {
+    pub id: String,
+    pub name: String,
+    pub condition: String,
+    pub wallets: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group_id: Option<String>,
+    pub created_at: DateTime<Utc>,
+}
+
+impl From<AlertRuleRow> for AlertRuleResponse {
+    fn from(row: AlertRuleRow) -> Self {
+        Self {
+            id: row.id,
+            name: row.name,
+            condition: row.condition,
+            wallets: row.wallets,
+            group_id: Some(row.group_id).filter(|id| !id.is_empty()),
+            created_at: DateTime::from_timestamp_millis(row.created_at).unwrap_or_default(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AlertRulesResponse {
+    pub rules: Vec<AlertRuleResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeleteAlertRuleResponse {
+    pub id: String,
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct AlertsQuery {
+    /// firing or resolved; both when absent
+    pub state: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AlertsResponse {
+    pub alerts: Vec<Alert>,
+}
+
+// ============================================================================
+// /api/v1/webhooks
+// ============================================================================
+
+/// Most delivery attempts returned per request
+pub const MAX_DELIVERY_PAGE: u32 = 200;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CreateWebhookRequest {
+    pub url: String,
+    /// Follow one wallet; every wallet when absent
+    pub wallet: Option<String>,
+    /// transaction, large_trade, risk_threshold and/or alert
+    pub event_types: Vec<String>,
+    /// Smallest transaction reported as a large trade
+    pub min_usd_value: Option<Decimal>,
+    /// Risk score whose crossing is reported
+    pub risk_threshold: Option<u8>,
+    /// HMAC key for signatures; generated when absent
+    pub secret: Option<String>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookResponse {
+    pub id: String,
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub wallet: Option<String>,
+    pub event_types: Vec<String>,
+    pub min_usd_value: Decimal,
+    pub risk_threshold: u8,
+    /// False once repeated failed deliveries disabled the webhook
+    pub active: bool,
+    pub consecutive_failures: u32,
+    pub created_at: DateTime<Utc>,
+    /// Only returned when the webhook is created
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+}
+
+impl From<WebhookRow> for WebhookResponse {
+    fn from(row: WebhookRow) -> Self {
+        Self {
+            id: row.id,
+            url: row.url,
+            wallet: (!row.wallet.is_empty()).then_some(row.wallet),
+            event_types: row.event_types,
+            min_usd_value: parse_decimal(&row.min_usd_value),
+            risk_threshold: row.risk_threshold,
+            active: row.active == 1,
+            consecutive_failures: row.consecutive_failures,
+            created_at: DateTime::from_timestamp_millis(row.created_at).unwrap_or_default(),
+            secret: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhooksResponse {
+    pub webhooks: Vec<WebhookResponse>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct DeleteWebhookResponse {
+    pub id: String,
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct WebhookDeliveriesQuery {
+    #[serde(default = "default_delivery_limit")]
+    pub limit: u32,
+}
+
+fn default_delivery_limit() -> u32 {
+    50
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookDeliveriesResponse {
+    pub webhook_id: String,
+    pub deliveries: Vec<WebhookDelivery>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WebhookDelivery {
+    /// Shared by every attempt at one event
+    pub delivery_id: String,
+    pub event_type: String,
+    pub attempt: u8,
+    pub success: bool,
+    /// Absent when the endpoint didn't respond
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub status_code: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub duration_ms: u32,
+    pub attempted_at: DateTime<Utc>,
+}
+
+impl From<WebhookDeliveryRow> for WebhookDelivery {
+    fn from(row: WebhookDeliveryRow) -> Self {
+        Self {
+            delivery_id: row.delivery_id,
+            event_type: row.event_type,
+            attempt: row.attempt,
+            success: row.success == 1,
+            status_code: (row.status_code != 0).then_some(row.status_code),
+            error: (!row.error.is_empty()).then_some(row.error),
+            duration_ms: row.duration_ms,
+            attempted_at: DateTime::from_timestamp_millis(row.attempted_at).unwrap_or_default(),
+        }
+    }
+}
+
+// ============================================================================
+// GET /api/v1/leaderboard
+// ============================================================================
+
+/// Most wallets returned by the leaderboard
+pub const MAX_LEADERBOARD_SIZE: u32 = 100;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LeaderboardQuery {
+    #[serde(default = "default_window")]
+    pub window: String,
+    /// realized_pnl, roi, win_rate or sharpe
+    #[serde(default = "default_leaderboard_metric")]
+    pub metric: String,
+    /// Only count trades closed on this protocol
+    pub protocol: Option<String>,
+    /// Only rank members of this wallet group
+    pub group: Option<String>,
+    /// Minimum current portfolio value in USD
+    pub min_value: Option<String>,
+    /// Minimum closed trades in the window
+    #[serde(default = "default_min_trades")]
+    pub min_trades: u64,
+    #[serde(default = "default_leaderboard_limit")]
+    pub limit: u32,
+}
+
+fn default_leaderboard_metric() -> String {
+    "realized_pnl".to_string()
+}
+
+fn default_min_trades() -> u64 {
+    1
+}
+
+fn default_leaderboard_limit() -> u32 {
+    25
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LeaderboardResponse {
+    pub window: String,
+    pub metric: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub protocol: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    pub entries: Vec<LeaderboardEntry>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct LeaderboardEntry {
+    pub rank: u32,
+    pub wallet: String,
+    pub trade_count: u64,
+    pub win_rate: Decimal,
+    pub realized_pnl: Decimal,
+    /// Realized PnL over the cost basis of everything sold
+    pub roi: Decimal,
+    pub sharpe_ratio: Option<f64>,
+    pub total_value_usd: Decimal,
+}
+
+impl LeaderboardEntry {
+    pub fn new(rank: u32, row: LeaderboardRow) -> Self {
+        Self {
+            rank,
+            wallet: row.wallet,
+            trade_count: row.trade_count,
+            win_rate: Decimal::from_f64_retain(row.win_rate).unwrap_or_default().round_dp(4),
+            realized_pnl: parse_decimal(&row.realized_pnl),
+            roi: Decimal::from_f64_retain(row.roi).unwrap_or_default().round_dp(4),
+            sharpe_ratio: row.sharpe_ratio.filter(|s| s.is_finite()),
+            total_value_usd: parse_decimal(&row.total_value_usd),
+        }
+    }
+}
+
+// ============================================================================
+// GET /api/v1/token/{mint}/flows
+// ============================================================================
+
+/// Most wallets listed per side of the token flow breakdown
+pub const MAX_FLOW_WALLETS: usize = 50;
+
+#[derive(Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TokenFlowsQuery {
+    #[serde(default = "default_window")]
+    pub window: String,
+    /// Accumulators and distributors to list
+    #[serde(default = "default_flow_limit")]
+    pub limit: usize,
+    /// Only count swaps by members of this wallet group
+    pub group: Option<String>,
+}
+
+fn default_flow_limit() -> usize {
+    10
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TokenFlowsResponse {
+    pub mint: String,
+    pub window: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub group: Option<String>,
+    /// Latest recorded USD price, used for the `_usd` fields
+    pub price_usd: Option<Decimal>,
+    pub buy_volume: Decimal,
+    pub sell_volume: Decimal,
+    pub net_volume: Decimal,
+    pub net_volume_usd: Option<Decimal>,
+    pub unique_buyers: u64,
+    pub unique_sellers: u64,
+    pub smart_money: SmartMoneyInfo,
+    pub top_accumulators: Vec<WalletFlowEntry>,
+    pub top_distributors: Vec<WalletFlowEntry>,
+}
+
+impl TokenFlowsResponse {
+    pub fn new(mint: String, window: String, price_usd: Option<Decimal>, flows: TokenFlows) -> Self {
+        let entry = |flow: WalletFlow| WalletFlowEntry::new(flow, price_usd);
+        Self {
+            mint,
+            window,
+            group: None,
+            price_usd,
+            buy_volume: flows.buy_volume,
+            sell_volume: flows.sell_volume,
+            net_volume: flows.net_volume(),
+            net_volume_usd: price_usd.map(|p| (flows.net_volume() * p).round_dp(2)),
+            unique_buyers: flows.unique_buyers,
+            unique_sellers: flows.unique_sellers,
+            smart_money: flows.smart_money.into(),
+            top_accumulators: flows.top_accumulators.into_iter().map(entry).collect(),
+            top_distributors: flows.top_distributors.into_iter().map(entry).collect(),
+        }
+    }
+}
+
+/// Flow from wallets classified as whales or ranked as top performers
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SmartMoneyInfo {
+    pub wallet_count: u64,
+    pub buy_volume: Decimal,
+    pub sell_volume: Decimal,
+    pub net_volume: Decimal,
+    pub buy_share: Decimal,
+    pub sell_share: Decimal,
+}
+
+impl From<SmartMoneyFlow> for SmartMoneyInfo {
+    fn from(flow: SmartMoneyFlow) -> Self {
+        Self {
+            wallet_count: flow.wallet_count,
+            buy_volume: flow.buy_volume,
+            sell_volume: flow.sell_volume,
+            net_volume: flow.buy_volume - flow.sell_volume,
+            buy_share: flow.buy_share,
+            sell_share: flow.sell_share,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct WalletFlowEntry {
+    pub wallet: String,
+    pub bought: Decimal,
+    pub sold: Decimal,
+    pub net: Decimal,
+    pub net_usd: Option<Decimal>,
+    pub buy_count: u64,
+    pub sell_count: u64,
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub classification: String,
+    pub top_performer: bool,
+}
+
+impl WalletFlowEntry {
+    pub fn new(flow: WalletFlow, price_usd: Option<Decimal>) -> Self {
+        let net = flow.net();
+        Self {
+            net_usd: price_usd.map(|p| (net * p).round_dp(2)),
+            net,
+            wallet: flow.wallet,
+            bought: flow.bought,
+            sold: flow.sold,
+            buy_count: flow.buy_count,
+            sell_count: flow.sell_count,
+            classification: flow.classification,
+            top_performer: flow.top_performer,
+        }
+    }
+}
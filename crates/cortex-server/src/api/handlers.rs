@@ -1,16 +1,39 @@
 use axum::{
+    extract::ws::{Message, WebSocket, WebSocketUpgrade},
     extract::{Path, Query, State},
-    Json,
+    http::{header, StatusCode},
+    response::sse::{Event, KeepAlive, Sse},
+    response::{Html, IntoResponse, Response},
+    Extension, Json,
 };
+use async_graphql::http::GraphiQLSource;
+use futures::{Stream, StreamExt};
+use chrono::{Datelike, NaiveDate, Utc};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
+use std::collections::{BTreeMap, HashSet};
 use std::str::FromStr;
 use std::time::Instant;
+use utoipa_scalar::Scalar;
+use tokio::sync::broadcast::error::RecvError;
 
 use super::dto::*;
-use crate::db::queries;
-use crate::error::{AppError, AppResult};
-use crate::types::{validate_solana_address, TimeWindow};
+use super::openapi;
+use crate::alerts::{self, Condition};
+use crate::auth::Reservation;
+use crate::db::models::{ApiKeyRow, LeaderboardFilter, PnlByProtocolRow, TransactionFilter};
+use crate::db::store::CortexStore;
+use crate::error::{AppError, AppResult, ErrorResponse};
+use crate::graphql::{CortexSchema, Loaders};
+use crate::groups;
+use crate::indexer::parser::ParsedTransaction;
+use crate::metrics;
+use crate::telemetry;
+use crate::types::{
+    validate_solana_address, AlertState, ApiScope, LeaderboardMetric, LotMethod, Protocol, Scope, TimeWindow, TransactionSort, TransactionType,
+    WebhookEvent,
+};
+use crate::webhooks;
 use crate::AppState;
 
 /// Helper to parse string to Decimal, defaulting to zero on error
@@ -18,12 +41,21 @@ fn parse_decimal(s: &str) -> Decimal {
     Decimal::from_str(s).unwrap_or_default()
 }
 
+#[utoipa::path(
+    get,
+    path = "/health",
+    tag = "health",
+    responses(
+        (status = 200, body = HealthResponse),
+    ),
+    security(()),
+)]
 pub async fn health_check(State(state): State<AppState>) -> AppResult<Json<HealthResponse>> {
     let start = Instant::now();
     println!("[REQUEST] GET /health");
     tracing::info!("Processing health check request");
 
-    let db_status = match state.db.health_check().await {
+    let db_status = match state.store.health_check().await {
         Ok(_) => {
             tracing::debug!("Database health check passed");
             "connected"
@@ -51,13 +83,27 @@ pub async fn health_check(State(state): State<AppState>) -> AppResult<Json<Healt
     Ok(Json(response))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{wallet}/summary",
+    tag = "user",
+    params(
+        ("wallet" = String, Path, description = "Solana wallet address"),
+        ScopeQuery,
+    ),
+    responses(
+        (status = 200, body = UserSummaryResponse),
+        (status = 400, description = "Invalid wallet address", body = ErrorResponse),
+    ),
+)]
 pub async fn get_user_summary(
     State(state): State<AppState>,
     Path(wallet): Path<String>,
+    Query(query): Query<ScopeQuery>,
 ) -> AppResult<Json<UserSummaryResponse>> {
     let start = Instant::now();
-    println!("[REQUEST] GET /api/v1/user/{}/summary", wallet);
-    tracing::info!(wallet = %wallet, "Processing user summary request");
+    println!("[REQUEST] GET /api/v1/user/{}/summary?scope={}", wallet, query.scope);
+    tracing::info!(wallet = %wallet, scope = %query.scope, "Processing user summary request");
 
     // Validate wallet address
     if !validate_solana_address(&wallet) {
@@ -67,10 +113,35 @@ pub async fn get_user_summary(
     }
     tracing::debug!(wallet = %wallet, "Wallet address validated");
 
+    let scope = parse_scope(&wallet, "summary", &query.scope)?;
+    if scope == Scope::Entity {
+        println!("[DB] Resolving cluster for {}", wallet);
+        tracing::debug!(wallet = %wallet, "Resolving wallet cluster for entity summary");
+        let cluster = state.indexer.resolve_cluster(&wallet).await?;
+        let row = state.indexer.entity_summary(&cluster).await?;
+
+        let mut summary: UserSummaryResponse = row.into();
+        summary.wallet = wallet.clone();
+        summary.entity = Some(EntityInfo::from(&cluster));
+
+        let duration = start.elapsed().as_millis();
+        println!("[RESPONSE] GET /api/v1/user/{}/summary -> 200 OK ({}ms) entity={} members={} value=${}",
+            wallet, duration, cluster.id, cluster.members.len(), summary.total_value_usd);
+        tracing::info!(
+            wallet = %wallet,
+            duration_ms = %duration,
+            cluster_id = %cluster.id,
+            member_count = %cluster.members.len(),
+            total_value_usd = %summary.total_value_usd,
+            "Entity summary computed"
+        );
+        return Ok(Json(summary));
+    }
+
     // Try to fetch from database
     println!("[DB] Querying wallet summary for {}", wallet);
     tracing::debug!(wallet = %wallet, "Querying database for wallet summary");
-    if let Some(summary) = queries::get_wallet_summary(state.db.client(), &wallet).await? {
+    if let Some(summary) = state.store.get_wallet_summary(&wallet).await? {
         let duration = start.elapsed().as_millis();
         println!("[RESPONSE] GET /api/v1/user/{}/summary -> 200 OK ({}ms) value=${}",
             wallet, duration, summary.total_value_usd);
@@ -102,16 +173,27 @@ pub async fn get_user_summary(
             realized_30d: dec!(0),
             unrealized: dec!(0),
         },
-        risk: RiskSummary {
-            score: 0,
-            largest_position_pct: dec!(0),
-            protocol_count: 0,
-        },
+        risk: RiskSummary::empty(),
+        classification: ClassificationSummary::unknown(),
         last_activity: chrono::Utc::now(),
         protocols: vec![],
+        entity: None,
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{wallet}/pnl",
+    tag = "user",
+    params(
+        ("wallet" = String, Path, description = "Solana wallet address"),
+        PnlQuery,
+    ),
+    responses(
+        (status = 200, body = UserPnlResponse),
+        (status = 400, description = "Invalid wallet address", body = ErrorResponse),
+    ),
+)]
 pub async fn get_user_pnl(
     State(state): State<AppState>,
     Path(wallet): Path<String>,
@@ -141,7 +223,9 @@ pub async fn get_user_pnl(
     // Query PnL data
     println!("[DB] Querying PnL for {} (window={})", wallet, query.window);
     tracing::debug!(wallet = %wallet, "Querying database for PnL by protocol");
-    let pnl_data = queries::get_wallet_pnl_by_protocol(state.db.client(), &wallet, window).await?;
+    let pnl_data = state.store.get_wallet_pnl_by_protocol(&wallet, window).await?;
+
+    let mev = state.store.get_wallet_mev_losses(&wallet, window).await?;
 
     let total_realized: Decimal = pnl_data.iter().map(|p| parse_decimal(&p.realized)).sum();
     let total_unrealized: Decimal = pnl_data.iter().map(|p| parse_decimal(&p.unrealized)).sum();
@@ -157,6 +241,7 @@ pub async fn get_user_pnl(
         protocol_count = %protocol_count,
         total_realized = %total_realized,
         total_unrealized = %total_unrealized,
+        mev_losses = %mev.estimated_loss_usd,
         "User PnL retrieved successfully"
     );
 
@@ -165,17 +250,33 @@ pub async fn get_user_pnl(
         window: query.window,
         total_realized,
         total_unrealized,
+        mev_losses: parse_decimal(&mev.estimated_loss_usd),
+        sandwiched_count: mev.sandwiched_count,
         by_protocol: pnl_data.into_iter().map(Into::into).collect(),
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{wallet}/positions",
+    tag = "user",
+    params(
+        ("wallet" = String, Path, description = "Solana wallet address"),
+        ScopeQuery,
+    ),
+    responses(
+        (status = 200, body = UserPositionsResponse),
+        (status = 400, description = "Invalid wallet address", body = ErrorResponse),
+    ),
+)]
 pub async fn get_user_positions(
     State(state): State<AppState>,
     Path(wallet): Path<String>,
+    Query(query): Query<ScopeQuery>,
 ) -> AppResult<Json<UserPositionsResponse>> {
     let start = Instant::now();
-    println!("[REQUEST] GET /api/v1/user/{}/positions", wallet);
-    tracing::info!(wallet = %wallet, "Processing user positions request");
+    println!("[REQUEST] GET /api/v1/user/{}/positions?scope={}", wallet, query.scope);
+    tracing::info!(wallet = %wallet, scope = %query.scope, "Processing user positions request");
 
     // Validate wallet address
     if !validate_solana_address(&wallet) {
@@ -185,12 +286,31 @@ pub async fn get_user_positions(
     }
     tracing::debug!(wallet = %wallet, "Wallet address validated");
 
-    // Query positions
+    let scope = parse_scope(&wallet, "positions", &query.scope)?;
+    let cluster = match scope {
+        Scope::Entity => Some(state.indexer.resolve_cluster(&wallet).await?),
+        Scope::Wallet => None,
+    };
+
+    // Query positions, across every member for an entity
     println!("[DB] Querying positions for {}", wallet);
     tracing::debug!(wallet = %wallet, "Querying database for wallet positions");
-    let positions = queries::get_wallet_positions(state.db.client(), &wallet).await?;
-
-    let total_value: Decimal = positions.iter().map(|p| parse_decimal(&p.usd_value)).sum();
+    let mut positions: Vec<Position> = Vec::new();
+    let mut total_value = Decimal::ZERO;
+    let members = match &cluster {
+        Some(cluster) => cluster.members.clone(),
+        None => vec![wallet.clone()],
+    };
+    for member in &members {
+        for row in state.store.get_wallet_positions(member).await? {
+            total_value += parse_decimal(&row.usd_value);
+            let mut position = Position::from(row);
+            if cluster.is_some() {
+                position.owner = Some(member.clone());
+            }
+            positions.push(position);
+        }
+    }
     let position_count = positions.len();
 
     let duration = start.elapsed().as_millis();
@@ -206,14 +326,650 @@ pub async fn get_user_positions(
 
     Ok(Json(UserPositionsResponse {
         wallet,
-        positions: positions.into_iter().map(Into::into).collect(),
+        positions,
         total_value_usd: total_value,
+        entity: cluster.as_ref().map(EntityInfo::from),
+    }))
+}
+
+/// Split a batch request's wallets into valid addresses, deduplicated in order, and
+/// errors for the invalid ones. Empty or oversized batches are rejected outright.
+fn parse_batch(endpoint: &str, wallets: Vec<String>) -> AppResult<(Vec<String>, BTreeMap<String, BatchError>)> {
+    if wallets.is_empty() || wallets.len() > MAX_BATCH_WALLETS {
+        println!("[RESPONSE] POST /api/v1/wallets/{} -> 400 Bad Request (batch size {})", endpoint, wallets.len());
+        tracing::warn!(endpoint = %endpoint, wallet_count = %wallets.len(), "Batch size out of range");
+        return Err(AppError::InvalidParam(format!("wallets must list between 1 and {} addresses", MAX_BATCH_WALLETS)));
+    }
+
+    let mut seen = HashSet::new();
+    let mut valid = Vec::new();
+    let mut errors = BTreeMap::new();
+    for wallet in wallets {
+        if !seen.insert(wallet.clone()) {
+            continue;
+        }
+        if validate_solana_address(&wallet) {
+            valid.push(wallet);
+        } else {
+            errors.insert(wallet.clone(), BatchError::invalid_wallet(&wallet));
+        }
+    }
+    Ok((valid, errors))
+}
+
+/// Parse a batch request's PnL window, logging and rejecting unknown values
+fn parse_batch_window(endpoint: &str, window: &str) -> AppResult<TimeWindow> {
+    TimeWindow::from_str(window).ok_or_else(|| {
+        println!("[RESPONSE] POST /api/v1/wallets/{} -> 400 Bad Request (invalid window)", endpoint);
+        tracing::warn!(endpoint = %endpoint, window = %window, "Invalid time window parameter");
+        AppError::InvalidParam(format!("Invalid window: {}", window))
+    })
+}
+
+/// Summaries for many wallets in one query. Wallets that have not been indexed are
+/// reported under `errors` rather than given a placeholder.
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallets/summary",
+    tag = "wallets",
+    request_body = WalletBatchRequest,
+    responses(
+        (status = 200, body = WalletBatchResponse<UserSummaryResponse>),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn get_batch_summaries(
+    State(state): State<AppState>,
+    Json(request): Json<WalletBatchRequest>,
+) -> AppResult<Json<WalletBatchResponse<UserSummaryResponse>>> {
+    let start = Instant::now();
+    println!("[REQUEST] POST /api/v1/wallets/summary wallets={}", request.wallets.len());
+    tracing::info!(wallet_count = %request.wallets.len(), "Processing batch summary request");
+
+    let (wallets, mut errors) = parse_batch("summary", request.wallets)?;
+
+    println!("[DB] Querying summaries for {} wallets", wallets.len());
+    tracing::debug!(wallet_count = %wallets.len(), "Querying database for wallet summaries");
+    let results: BTreeMap<String, UserSummaryResponse> = state
+        .store
+        .get_wallet_summaries(&wallets)
+        .await?
+        .into_iter()
+        .map(|row| (row.wallet.clone(), row.into()))
+        .collect();
+    for wallet in &wallets {
+        if !results.contains_key(wallet) {
+            errors.insert(wallet.clone(), BatchError::not_found(wallet));
+        }
+    }
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] POST /api/v1/wallets/summary -> 200 OK ({}ms) results={} errors={}",
+        duration, results.len(), errors.len());
+    tracing::info!(
+        duration_ms = %duration,
+        result_count = %results.len(),
+        error_count = %errors.len(),
+        "Batch summaries retrieved successfully"
+    );
+
+    Ok(Json(WalletBatchResponse { results, errors }))
+}
+
+/// Positions for many wallets in one query
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallets/positions",
+    tag = "wallets",
+    request_body = WalletBatchRequest,
+    responses(
+        (status = 200, body = WalletBatchResponse<UserPositionsResponse>),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn get_batch_positions(
+    State(state): State<AppState>,
+    Json(request): Json<WalletBatchRequest>,
+) -> AppResult<Json<WalletBatchResponse<UserPositionsResponse>>> {
+    let start = Instant::now();
+    println!("[REQUEST] POST /api/v1/wallets/positions wallets={}", request.wallets.len());
+    tracing::info!(wallet_count = %request.wallets.len(), "Processing batch positions request");
+
+    let (wallets, errors) = parse_batch("positions", request.wallets)?;
+
+    println!("[DB] Querying positions for {} wallets", wallets.len());
+    tracing::debug!(wallet_count = %wallets.len(), "Querying database for wallet positions");
+    let rows = state.store.get_positions_for_wallets(&wallets).await?;
+
+    let mut results: BTreeMap<String, UserPositionsResponse> = wallets
+        .iter()
+        .map(|wallet| {
+            let response = UserPositionsResponse {
+                wallet: wallet.clone(),
+                positions: Vec::new(),
+                total_value_usd: Decimal::ZERO,
+                entity: None,
+            };
+            (wallet.clone(), response)
+        })
+        .collect();
+    let position_count = rows.len();
+    for row in rows {
+        if let Some(response) = results.get_mut(&row.wallet) {
+            response.total_value_usd += parse_decimal(&row.usd_value);
+            response.positions.push(row.into());
+        }
+    }
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] POST /api/v1/wallets/positions -> 200 OK ({}ms) results={} positions={} errors={}",
+        duration, results.len(), position_count, errors.len());
+    tracing::info!(
+        duration_ms = %duration,
+        result_count = %results.len(),
+        position_count = %position_count,
+        error_count = %errors.len(),
+        "Batch positions retrieved successfully"
+    );
+
+    Ok(Json(WalletBatchResponse { results, errors }))
+}
+
+/// PnL by protocol for many wallets, one query each for PnL and MEV losses
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallets/pnl",
+    tag = "wallets",
+    request_body = WalletBatchRequest,
+    responses(
+        (status = 200, body = WalletBatchResponse<UserPnlResponse>),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn get_batch_pnl(
+    State(state): State<AppState>,
+    Json(request): Json<WalletBatchRequest>,
+) -> AppResult<Json<WalletBatchResponse<UserPnlResponse>>> {
+    let start = Instant::now();
+    println!("[REQUEST] POST /api/v1/wallets/pnl wallets={} window={}", request.wallets.len(), request.window);
+    tracing::info!(wallet_count = %request.wallets.len(), window = %request.window, "Processing batch PnL request");
+
+    let window = parse_batch_window("pnl", &request.window)?;
+    let (wallets, errors) = parse_batch("pnl", request.wallets)?;
+
+    println!("[DB] Querying PnL for {} wallets (window={})", wallets.len(), request.window);
+    tracing::debug!(wallet_count = %wallets.len(), "Querying database for PnL by protocol");
+    let pnl = state.store.get_pnl_by_protocol_for_wallets(&wallets, window).await?;
+    let mev = state.store.get_mev_losses_for_wallets(&wallets, window).await?;
+
+    let mut results: BTreeMap<String, UserPnlResponse> = wallets
+        .iter()
+        .map(|wallet| {
+            let response = UserPnlResponse {
+                wallet: wallet.clone(),
+                window: request.window.clone(),
+                total_realized: Decimal::ZERO,
+                total_unrealized: Decimal::ZERO,
+                mev_losses: Decimal::ZERO,
+                sandwiched_count: 0,
+                by_protocol: Vec::new(),
+            };
+            (wallet.clone(), response)
+        })
+        .collect();
+    for row in pnl {
+        if let Some(response) = results.get_mut(&row.wallet) {
+            let protocol = ProtocolPnl::from(PnlByProtocolRow::from(row));
+            response.total_realized += protocol.realized;
+            response.total_unrealized += protocol.unrealized;
+            response.by_protocol.push(protocol);
+        }
+    }
+    for row in mev {
+        if let Some(response) = results.get_mut(&row.wallet) {
+            response.mev_losses = parse_decimal(&row.estimated_loss_usd);
+            response.sandwiched_count = row.sandwiched_count;
+        }
+    }
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] POST /api/v1/wallets/pnl -> 200 OK ({}ms) results={} errors={}",
+        duration, results.len(), errors.len());
+    tracing::info!(
+        window = %request.window,
+        duration_ms = %duration,
+        result_count = %results.len(),
+        error_count = %errors.len(),
+        "Batch PnL retrieved successfully"
+    );
+
+    Ok(Json(WalletBatchResponse { results, errors }))
+}
+
+/// A set of wallets summed as one book: values and PnL added up, positions merged
+#[utoipa::path(
+    post,
+    path = "/api/v1/wallets/portfolio",
+    tag = "wallets",
+    request_body = WalletBatchRequest,
+    responses(
+        (status = 200, body = PortfolioResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn get_portfolio(
+    State(state): State<AppState>,
+    Json(request): Json<WalletBatchRequest>,
+) -> AppResult<Json<PortfolioResponse>> {
+    let start = Instant::now();
+    println!("[REQUEST] POST /api/v1/wallets/portfolio wallets={} window={}", request.wallets.len(), request.window);
+    tracing::info!(wallet_count = %request.wallets.len(), window = %request.window, "Processing portfolio request");
+
+    let window = parse_batch_window("portfolio", &request.window)?;
+    let (wallets, errors) = parse_batch("portfolio", request.wallets)?;
+
+    println!("[DB] Querying portfolio of {} wallets (window={})", wallets.len(), request.window);
+    tracing::debug!(wallet_count = %wallets.len(), "Querying database for portfolio");
+    let rows = PortfolioRows {
+        summaries: state.store.get_wallet_summaries(&wallets).await?,
+        positions: state.store.get_positions_for_wallets(&wallets).await?,
+        pnl: state.store.get_pnl_by_protocol_for_wallets(&wallets, window).await?,
+        mev: state.store.get_mev_losses_for_wallets(&wallets, window).await?,
+    };
+    let portfolio = PortfolioResponse::new(wallets, request.window, rows, errors);
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] POST /api/v1/wallets/portfolio -> 200 OK ({}ms) wallets={} value=${} positions={}",
+        duration, portfolio.wallets.len(), portfolio.total_value_usd, portfolio.positions.len());
+    tracing::info!(
+        duration_ms = %duration,
+        wallet_count = %portfolio.wallets.len(),
+        indexed_count = %portfolio.indexed_count,
+        total_value_usd = %portfolio.total_value_usd,
+        position_count = %portfolio.positions.len(),
+        "Portfolio computed successfully"
+    );
+
+    Ok(Json(portfolio))
+}
+
+/// Addresses linked to a wallet by funding, transfer and fee-payer heuristics
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{wallet}/cluster",
+    tag = "user",
+    params(("wallet" = String, Path, description = "Solana wallet address")),
+    responses(
+        (status = 200, body = ClusterResponse),
+        (status = 400, description = "Invalid wallet address", body = ErrorResponse),
+    ),
+)]
+pub async fn get_user_cluster(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+) -> AppResult<Json<ClusterResponse>> {
+    let start = Instant::now();
+    println!("[REQUEST] GET /api/v1/user/{}/cluster", wallet);
+    tracing::info!(wallet = %wallet, "Processing wallet cluster request");
+
+    // Validate wallet address
+    if !validate_solana_address(&wallet) {
+        println!("[RESPONSE] GET /api/v1/user/{}/cluster -> 400 Bad Request (invalid wallet)", wallet);
+        tracing::warn!(wallet = %wallet, "Invalid wallet address provided");
+        return Err(AppError::InvalidWallet(wallet));
+    }
+
+    println!("[DB] Resolving cluster for {}", wallet);
+    tracing::debug!(wallet = %wallet, "Querying database for wallet links");
+    let cluster = state.indexer.resolve_cluster(&wallet).await?;
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] GET /api/v1/user/{}/cluster -> 200 OK ({}ms) cluster={} members={}",
+        wallet, duration, cluster.id, cluster.members.len());
+    tracing::info!(
+        wallet = %wallet,
+        duration_ms = %duration,
+        cluster_id = %cluster.id,
+        member_count = %cluster.members.len(),
+        link_count = %cluster.edges.len(),
+        "Wallet cluster resolved"
+    );
+
+    Ok(Json(ClusterResponse::new(wallet, cluster)))
+}
+
+/// Parse a `scope` query parameter, logging and rejecting unknown values
+fn parse_scope(wallet: &str, endpoint: &str, scope: &str) -> AppResult<Scope> {
+    Scope::from_str(scope).ok_or_else(|| {
+        println!("[RESPONSE] GET /api/v1/user/{}/{} -> 400 Bad Request (invalid scope)", wallet, endpoint);
+        tracing::warn!(wallet = %wallet, scope = %scope, "Invalid scope parameter");
+        AppError::InvalidParam(format!("Invalid scope: {}", scope))
+    })
+}
+
+/// Risk-adjusted trading statistics over a time window
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{wallet}/performance",
+    tag = "user",
+    params(
+        ("wallet" = String, Path, description = "Solana wallet address"),
+        PerformanceQuery,
+    ),
+    responses(
+        (status = 200, body = UserPerformanceResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn get_user_performance(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    Query(query): Query<PerformanceQuery>,
+) -> AppResult<Json<UserPerformanceResponse>> {
+    let start = Instant::now();
+    println!("[REQUEST] GET /api/v1/user/{}/performance?window={}", wallet, query.window);
+    tracing::info!(wallet = %wallet, window = %query.window, "Processing user performance request");
+
+    // Validate wallet address
+    if !validate_solana_address(&wallet) {
+        println!("[RESPONSE] GET /api/v1/user/{}/performance -> 400 Bad Request (invalid wallet)", wallet);
+        tracing::warn!(wallet = %wallet, "Invalid wallet address provided");
+        return Err(AppError::InvalidWallet(wallet));
+    }
+
+    let window = TimeWindow::from_str(&query.window)
+        .ok_or_else(|| {
+            println!("[RESPONSE] GET /api/v1/user/{}/performance -> 400 Bad Request (invalid window)", wallet);
+            tracing::warn!(wallet = %wallet, window = %query.window, "Invalid time window parameter");
+            AppError::InvalidParam(format!("Invalid window: {}", query.window))
+        })?;
+
+    // Load full history so positions opened before the window keep their cost basis
+    println!("[DB] Querying transactions for {}", wallet);
+    tracing::debug!(wallet = %wallet, "Querying database for wallet transactions");
+    let rows = state.store.get_wallet_transactions(&wallet, TimeWindow::All).await?;
+    let transactions: Vec<ParsedTransaction> = rows.iter().filter_map(ParsedTransaction::from_row).collect();
+
+    let performance = metrics::compute_performance(&transactions, window);
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] GET /api/v1/user/{}/performance -> 200 OK ({}ms) trades={} win_rate={}",
+        wallet, duration, performance.trade_count, performance.win_rate);
+    tracing::info!(
+        wallet = %wallet,
+        window = %query.window,
+        duration_ms = %duration,
+        trade_count = %performance.trade_count,
+        sharpe_ratio = ?performance.sharpe_ratio,
+        "User performance computed successfully"
+    );
+
+    Ok(Json(UserPerformanceResponse::new(wallet, query.window, performance)))
+}
+
+/// Per-disposal capital gains report for a tax year, as JSON or CSV
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{wallet}/tax-lots",
+    tag = "user",
+    params(
+        ("wallet" = String, Path, description = "Solana wallet address"),
+        TaxLotsQuery,
+    ),
+    responses(
+        (status = 200, description = "JSON report, or CSV with `format=csv`", content(
+            (TaxLotsResponse = "application/json"),
+            (String = "text/csv"),
+        )),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn get_user_tax_lots(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    Query(query): Query<TaxLotsQuery>,
+) -> AppResult<Response> {
+    let start = Instant::now();
+    let current_year = Utc::now().year();
+    let year = query.year.unwrap_or(current_year - 1);
+    println!("[REQUEST] GET /api/v1/user/{}/tax-lots?year={}&method={}&format={}",
+        wallet, year, query.method, query.format);
+    tracing::info!(wallet = %wallet, year = %year, method = %query.method, format = %query.format, "Processing tax lot request");
+
+    // Validate wallet address
+    if !validate_solana_address(&wallet) {
+        println!("[RESPONSE] GET /api/v1/user/{}/tax-lots -> 400 Bad Request (invalid wallet)", wallet);
+        tracing::warn!(wallet = %wallet, "Invalid wallet address provided");
+        return Err(AppError::InvalidWallet(wallet));
+    }
+
+    let method = LotMethod::from_str(&query.method)
+        .ok_or_else(|| {
+            println!("[RESPONSE] GET /api/v1/user/{}/tax-lots -> 400 Bad Request (invalid method)", wallet);
+            tracing::warn!(wallet = %wallet, method = %query.method, "Invalid lot method parameter");
+            AppError::InvalidParam(format!("Invalid method: {}", query.method))
+        })?;
+
+    let csv = match query.format.to_lowercase().as_str() {
+        "json" => false,
+        "csv" => true,
+        _ => {
+            println!("[RESPONSE] GET /api/v1/user/{}/tax-lots -> 400 Bad Request (invalid format)", wallet);
+            tracing::warn!(wallet = %wallet, format = %query.format, "Invalid report format parameter");
+            return Err(AppError::InvalidParam(format!("Invalid format: {}", query.format)));
+        }
+    };
+
+    println!("[DB] Querying transactions for {}", wallet);
+    let report = tax_lot_report(state.store.as_ref(), &wallet, year, method).await.inspect_err(|e| {
+        if let AppError::InvalidParam(_) = e {
+            println!("[RESPONSE] GET /api/v1/user/{}/tax-lots -> 400 Bad Request (invalid year)", wallet);
+            tracing::warn!(wallet = %wallet, year = %year, "Invalid tax year");
+        }
+    })?;
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] GET /api/v1/user/{}/tax-lots -> 200 OK ({}ms) disposals={} gain={}",
+        wallet, duration, report.disposals.len(), report.totals.gain);
+    tracing::info!(
+        wallet = %wallet,
+        year = %year,
+        method = %method,
+        duration_ms = %duration,
+        disposal_count = %report.disposals.len(),
+        "Tax lot report generated successfully"
+    );
+
+    if csv {
+        let filename = format!("attachment; filename=\"{}-{}-{}.csv\"", wallet, year, method);
+        Ok((
+            [
+                (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+                (header::CONTENT_DISPOSITION, filename),
+            ],
+            report.to_csv(),
+        )
+            .into_response())
+    } else {
+        Ok(Json(report).into_response())
+    }
+}
+
+/// Earliest tax year a report can be built for
+const MIN_TAX_YEAR: i32 = 1970;
+
+/// Capital gains report for `wallet`'s disposals in `year` (UTC). Also used by the
+/// `cortex tax-lots` command.
+pub async fn tax_lot_report(
+    store: &dyn CortexStore,
+    wallet: &str,
+    year: i32,
+    method: LotMethod,
+) -> AppResult<TaxLotsResponse> {
+    let invalid_year = || AppError::InvalidParam(format!("Invalid year: {}", year));
+    if !(MIN_TAX_YEAR..=Utc::now().year()).contains(&year) {
+        return Err(invalid_year());
+    }
+    let year_start = |year: i32| {
+        NaiveDate::from_ymd_opt(year, 1, 1)
+            .and_then(|date| date.and_hms_opt(0, 0, 0))
+            .map(|start| start.and_utc().timestamp_millis())
+    };
+    let (Some(year_start), Some(year_end)) = (year_start(year), year.checked_add(1).and_then(year_start)) else {
+        return Err(invalid_year());
+    };
+
+    // Lots acquired in earlier years still carry their cost basis, so load everything
+    tracing::debug!(wallet = %wallet, "Querying database for wallet transactions");
+    let rows = store.get_wallet_transactions(wallet, TimeWindow::All).await?;
+    let transactions: Vec<ParsedTransaction> = rows.iter().filter_map(ParsedTransaction::from_row).collect();
+
+    let disposals: Vec<metrics::pnl::Disposal> = metrics::pnl::disposals(&transactions, method)
+        .into_iter()
+        .filter(|d| d.disposed_at >= year_start && d.disposed_at < year_end)
+        .collect();
+
+    Ok(TaxLotsResponse::new(wallet.to_string(), year, method.to_string(), disposals))
+}
+
+/// A page of the wallet's stored transactions, filtered and sorted
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{wallet}/transactions",
+    tag = "user",
+    params(
+        ("wallet" = String, Path, description = "Solana wallet address"),
+        TransactionHistoryQuery,
+    ),
+    responses(
+        (status = 200, body = TransactionHistoryResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn get_user_transactions(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    Query(query): Query<TransactionHistoryQuery>,
+) -> AppResult<Json<TransactionHistoryResponse>> {
+    let start = Instant::now();
+    println!("[REQUEST] GET /api/v1/user/{}/transactions?sort={}&limit={}&cursor={:?}",
+        wallet, query.sort, query.limit, query.cursor);
+    tracing::info!(wallet = %wallet, query = ?query, "Processing transaction history request");
+
+    // Validate wallet address
+    if !validate_solana_address(&wallet) {
+        println!("[RESPONSE] GET /api/v1/user/{}/transactions -> 400 Bad Request (invalid wallet)", wallet);
+        tracing::warn!(wallet = %wallet, "Invalid wallet address provided");
+        return Err(AppError::InvalidWallet(wallet));
+    }
+
+    let invalid = |param: &str, value: &str| {
+        println!("[RESPONSE] GET /api/v1/user/{}/transactions -> 400 Bad Request (invalid {})", wallet, param);
+        tracing::warn!(wallet = %wallet, param = %param, value = %value, "Invalid transaction history parameter");
+        AppError::InvalidParam(format!("Invalid {}: {}", param, value))
+    };
+    let timestamp = |param: &str, value: &Option<String>| -> AppResult<Option<i64>> {
+        value
+            .as_deref()
+            .map(|v| {
+                chrono::DateTime::parse_from_rfc3339(v)
+                    .map(|t| t.timestamp_millis())
+                    .map_err(|_| invalid(param, v))
+            })
+            .transpose()
+    };
+
+    let protocol = query
+        .protocol
+        .as_deref()
+        .map(|p| Protocol::from_str(p).map(|p| p.to_string()).ok_or_else(|| invalid("protocol", p)))
+        .transpose()?;
+    let tx_type = query
+        .tx_type
+        .as_deref()
+        .map(|t| TransactionType::from_str(t).map(|t| t.to_string()).ok_or_else(|| invalid("tx_type", t)))
+        .transpose()?;
+    let from = timestamp("from", &query.from)?;
+    let to = timestamp("to", &query.to)?;
+    if let (Some(from_ms), Some(to_ms)) = (from, to) {
+        if from_ms >= to_ms {
+            return Err(invalid("time range", &format!("{:?}..{:?}", query.from, query.to)));
+        }
+    }
+    let min_usd_value = query
+        .min_usd_value
+        .as_deref()
+        .map(|v| match Decimal::from_str(v) {
+            Ok(value) if !value.is_sign_negative() => Ok(value.to_string()),
+            _ => Err(invalid("min_usd_value", v)),
+        })
+        .transpose()?;
+    let sort = TransactionSort::from_str(&query.sort).ok_or_else(|| invalid("sort", &query.sort))?;
+    if query.limit == 0 || query.limit > MAX_TRANSACTION_PAGE {
+        return Err(invalid("limit", &format!("{} (must be between 1 and {})", query.limit, MAX_TRANSACTION_PAGE)));
+    }
+    let after = query
+        .cursor
+        .as_deref()
+        .map(|c| TransactionEntry::parse_cursor(c).ok_or_else(|| invalid("cursor", c)))
+        .transpose()?;
+
+    let filter = TransactionFilter {
+        protocol,
+        tx_type,
+        token: query.token.clone().filter(|t| !t.is_empty()),
+        from,
+        to,
+        min_usd_value,
+        sort,
+        after,
+        limit: query.limit,
+    };
+
+    println!("[DB] Querying transaction page for {}", wallet);
+    tracing::debug!(wallet = %wallet, filter = ?filter, "Querying database for transaction page");
+    let rows = state.store.get_transaction_page(&wallet, &filter).await?;
+
+    let transactions: Vec<TransactionEntry> = rows.into_iter().map(Into::into).collect();
+    let next_cursor = if transactions.len() == query.limit as usize {
+        transactions.last().map(TransactionEntry::cursor)
+    } else {
+        None
+    };
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] GET /api/v1/user/{}/transactions -> 200 OK ({}ms) transactions={}",
+        wallet, duration, transactions.len());
+    tracing::info!(
+        wallet = %wallet,
+        duration_ms = %duration,
+        count = %transactions.len(),
+        has_more = %next_cursor.is_some(),
+        "Transaction history retrieved"
+    );
+
+    Ok(Json(TransactionHistoryResponse {
+        wallet,
+        transactions,
+        next_cursor,
     }))
 }
 
 /// Start continuous indexing for a wallet
+#[utoipa::path(
+    post,
+    path = "/api/v1/index",
+    tag = "index",
+    request_body = IndexWalletRequest,
+    responses(
+        (status = 200, body = IndexWalletResponse),
+        (status = 400, description = "Invalid wallet address", body = ErrorResponse),
+        (status = 403, description = "Key lacks the `index` scope or its subscription quota is used up", body = ErrorResponse),
+    ),
+)]
 pub async fn index_wallet(
     State(state): State<AppState>,
+    key: Option<Extension<ApiKeyRow>>,
     Json(request): Json<IndexWalletRequest>,
 ) -> AppResult<Json<IndexWalletResponse>> {
     let start = Instant::now();
@@ -233,7 +989,19 @@ pub async fn index_wallet(
     println!("[INDEXER] Starting continuous indexing for {}", wallet);
     tracing::info!(wallet = %wallet, "Starting continuous wallet subscription");
 
-    let was_started = state.indexer.start_subscription(&wallet).await?;
+    // A key may only have so many subscriptions running; joining a running one is free
+    let reservation = match key {
+        Some(Extension(key)) => match state.api_keys.reserve_subscription(&key, &wallet, &state.indexer).await {
+            Ok(reservation) => Some(reservation),
+            Err(e) => {
+                println!("[RESPONSE] POST /api/v1/index -> 403 Forbidden (subscription quota of key {})", key.key_prefix);
+                return Err(e);
+            }
+        },
+        None => None,
+    };
+
+    let was_started = start_reserved_subscription(&state, &wallet, reservation).await?;
 
     let duration = start.elapsed().as_millis();
 
@@ -260,7 +1028,30 @@ pub async fn index_wallet(
     }))
 }
 
+/// Start a wallet's subscription, confirming the key's reservation if it started and
+/// giving the slot back otherwise
+async fn start_reserved_subscription(state: &AppState, wallet: &str, reservation: Option<Reservation>) -> AppResult<bool> {
+    let started = state.indexer.start_subscription(wallet).await;
+    if let Some(reservation) = reservation {
+        match started {
+            Ok(true) => state.api_keys.confirm_subscription(reservation).await,
+            _ => state.api_keys.cancel_subscription(reservation).await,
+        }
+    }
+    started
+}
+
 /// Stop continuous indexing for a wallet
+#[utoipa::path(
+    delete,
+    path = "/api/v1/index/{wallet}",
+    tag = "index",
+    params(("wallet" = String, Path, description = "Solana wallet address")),
+    responses(
+        (status = 200, body = IndexWalletResponse),
+        (status = 400, description = "Invalid wallet address", body = ErrorResponse),
+    ),
+)]
 pub async fn stop_indexing(
     State(state): State<AppState>,
     Path(wallet): Path<String>,
@@ -277,6 +1068,9 @@ pub async fn stop_indexing(
     }
 
     let was_stopped = state.indexer.stop_subscription(&wallet).await;
+    if was_stopped {
+        state.api_keys.release_subscription(&wallet).await;
+    }
 
     let duration = start.elapsed().as_millis();
 
@@ -304,6 +1098,14 @@ pub async fn stop_indexing(
 }
 
 /// List all active indexing subscriptions
+#[utoipa::path(
+    get,
+    path = "/api/v1/index",
+    tag = "index",
+    responses(
+        (status = 200, body = SubscriptionsResponse),
+    ),
+)]
 pub async fn list_subscriptions(
     State(state): State<AppState>,
 ) -> AppResult<Json<SubscriptionsResponse>> {
@@ -326,3 +1128,1392 @@ pub async fn list_subscriptions(
         }).collect(),
     }))
 }
+
+/// Paginated feed of position changes by subscribed wallets, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/signals",
+    tag = "signals",
+    params(SignalFeedQuery),
+    responses(
+        (status = 200, body = SignalFeedResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn get_signals(
+    State(state): State<AppState>,
+    Query(query): Query<SignalFeedQuery>,
+) -> AppResult<Json<SignalFeedResponse>> {
+    let start = Instant::now();
+    println!("[REQUEST] GET /api/v1/signals?wallet={:?}&limit={}&cursor={:?}", query.wallet, query.limit, query.cursor);
+    tracing::info!(wallet = ?query.wallet, limit = %query.limit, cursor = ?query.cursor, "Processing signal feed request");
+
+    let wallets = parse_wallet_list("/api/v1/signals", query.wallet.as_deref())?;
+
+    if query.limit == 0 || query.limit > MAX_SIGNAL_PAGE {
+        println!("[RESPONSE] GET /api/v1/signals -> 400 Bad Request (invalid limit)");
+        tracing::warn!(limit = %query.limit, "Invalid limit parameter");
+        return Err(AppError::InvalidParam(format!("limit must be between 1 and {}", MAX_SIGNAL_PAGE)));
+    }
+
+    let cursor = match &query.cursor {
+        Some(cursor) => Some(SignalEvent::parse_cursor(cursor).ok_or_else(|| {
+            println!("[RESPONSE] GET /api/v1/signals -> 400 Bad Request (invalid cursor)");
+            tracing::warn!(cursor = %cursor, "Invalid cursor parameter");
+            AppError::InvalidParam(format!("Invalid cursor: {}", cursor))
+        })?),
+        None => None,
+    };
+
+    println!("[DB] Querying position signals");
+    tracing::debug!(wallet_count = %wallets.len(), "Querying database for position signals");
+    let rows = state.store.get_position_signals(&wallets, cursor.as_ref(), query.limit).await?;
+
+    let signals: Vec<SignalEvent> = rows.into_iter().map(Into::into).collect();
+    let next_cursor = if signals.len() == query.limit as usize {
+        signals.last().map(SignalEvent::cursor)
+    } else {
+        None
+    };
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] GET /api/v1/signals -> 200 OK ({}ms) signals={}", duration, signals.len());
+    tracing::info!(duration_ms = %duration, count = %signals.len(), "Signal feed retrieved");
+
+    Ok(Json(SignalFeedResponse { signals, next_cursor }))
+}
+
+/// Server-sent events stream of position signals as they are detected
+#[utoipa::path(
+    get,
+    path = "/api/v1/signals/stream",
+    tag = "signals",
+    params(SignalStreamQuery),
+    responses(
+        (status = 200, description = "`signal` events", body = SignalEvent, content_type = "text/event-stream"),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn stream_signals(
+    State(state): State<AppState>,
+    Query(query): Query<SignalStreamQuery>,
+) -> AppResult<Sse<impl Stream<Item = Result<Event, axum::Error>>>> {
+    println!("[REQUEST] GET /api/v1/signals/stream?wallet={:?}", query.wallet);
+    tracing::info!(wallet = ?query.wallet, "Opening signal stream");
+
+    let wallets = parse_wallet_list("/api/v1/signals/stream", query.wallet.as_deref())?;
+    let receiver = state.indexer.subscribe_signals();
+
+    let stream = futures::stream::unfold(receiver, move |mut receiver| {
+        let wallets = wallets.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(signal) if wallets.is_empty() || wallets.contains(&signal.wallet) => {
+                        let event = Event::default().event("position").json_data(SignalEvent::from(signal));
+                        return Some((event, receiver));
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped = %skipped, "Signal stream subscriber lagging, signals dropped");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    })
+    .take_until(state.shutdown.clone().cancelled_owned());
+
+    println!("[RESPONSE] GET /api/v1/signals/stream -> 200 OK (streaming)");
+    Ok(Sse::new(stream).keep_alive(KeepAlive::default()))
+}
+
+/// Push every wallet's newly indexed transactions and recomputed summaries over SSE
+#[utoipa::path(
+    get,
+    path = "/api/v1/stream",
+    tag = "streams",
+    responses(
+        (status = 200, description = "Events named after the update type", body = WalletUpdateEvent, content_type = "text/event-stream"),
+    ),
+)]
+pub async fn stream_updates(State(state): State<AppState>) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    println!("[REQUEST] GET /api/v1/stream");
+    tracing::info!("Opening update stream");
+
+    let updates = wallet_updates(&state, None);
+
+    println!("[RESPONSE] GET /api/v1/stream -> 200 OK (streaming)");
+    update_sse(updates)
+}
+
+/// Push every wallet's newly indexed transactions and recomputed summaries over WebSocket
+#[utoipa::path(
+    get,
+    path = "/api/v1/stream/ws",
+    tag = "streams",
+    responses(
+        (status = 101, description = "WebSocket of JSON text messages", body = WalletUpdateEvent),
+    ),
+)]
+pub async fn stream_updates_ws(State(state): State<AppState>, ws: WebSocketUpgrade) -> Response {
+    println!("[REQUEST] GET /api/v1/stream/ws");
+    tracing::info!("Opening update socket");
+
+    let updates = wallet_updates(&state, None);
+
+    println!("[RESPONSE] GET /api/v1/stream/ws -> 101 Switching Protocols");
+    ws.on_upgrade(move |socket| push_updates(socket, updates))
+}
+
+/// Push one wallet's newly indexed transactions and recomputed summaries over SSE
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{wallet}/stream",
+    tag = "streams",
+    params(("wallet" = String, Path, description = "Solana wallet address")),
+    responses(
+        (status = 200, description = "Events named after the update type", body = WalletUpdateEvent, content_type = "text/event-stream"),
+        (status = 400, description = "Invalid wallet address", body = ErrorResponse),
+    ),
+)]
+pub async fn stream_wallet_updates(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+) -> AppResult<Sse<impl Stream<Item = Result<Event, axum::Error>>>> {
+    println!("[REQUEST] GET /api/v1/user/{}/stream", wallet);
+    tracing::info!(wallet = %wallet, "Opening wallet update stream");
+
+    if !validate_solana_address(&wallet) {
+        println!("[RESPONSE] GET /api/v1/user/{}/stream -> 400 Bad Request (invalid wallet)", wallet);
+        tracing::warn!(wallet = %wallet, "Invalid wallet address provided");
+        return Err(AppError::InvalidWallet(wallet));
+    }
+
+    let updates = wallet_updates(&state, Some(wallet.clone()));
+
+    println!("[RESPONSE] GET /api/v1/user/{}/stream -> 200 OK (streaming)", wallet);
+    Ok(update_sse(updates))
+}
+
+/// Push one wallet's newly indexed transactions and recomputed summaries over WebSocket
+#[utoipa::path(
+    get,
+    path = "/api/v1/user/{wallet}/stream/ws",
+    tag = "streams",
+    params(("wallet" = String, Path, description = "Solana wallet address")),
+    responses(
+        (status = 101, description = "WebSocket of JSON text messages", body = WalletUpdateEvent),
+        (status = 400, description = "Invalid wallet address", body = ErrorResponse),
+    ),
+)]
+pub async fn stream_wallet_updates_ws(
+    State(state): State<AppState>,
+    Path(wallet): Path<String>,
+    ws: WebSocketUpgrade,
+) -> AppResult<Response> {
+    println!("[REQUEST] GET /api/v1/user/{}/stream/ws", wallet);
+    tracing::info!(wallet = %wallet, "Opening wallet update socket");
+
+    if !validate_solana_address(&wallet) {
+        println!("[RESPONSE] GET /api/v1/user/{}/stream/ws -> 400 Bad Request (invalid wallet)", wallet);
+        tracing::warn!(wallet = %wallet, "Invalid wallet address provided");
+        return Err(AppError::InvalidWallet(wallet));
+    }
+
+    let updates = wallet_updates(&state, Some(wallet.clone()));
+
+    println!("[RESPONSE] GET /api/v1/user/{}/stream/ws -> 101 Switching Protocols", wallet);
+    Ok(ws.on_upgrade(move |socket| push_updates(socket, updates)))
+}
+
+/// Updates from the indexer, limited to `wallet` when given. Ends when the server shuts down.
+fn wallet_updates(state: &AppState, wallet: Option<String>) -> impl Stream<Item = WalletUpdateEvent> + Send + 'static {
+    futures::stream::unfold(state.indexer.subscribe_updates(), move |mut receiver| {
+        let wallet = wallet.clone();
+        async move {
+            loop {
+                match receiver.recv().await {
+                    Ok(update) if wallet.as_deref().is_none_or(|w| w == update.wallet()) => {
+                        return Some((WalletUpdateEvent::from(update), receiver));
+                    }
+                    Ok(_) => continue,
+                    Err(RecvError::Lagged(skipped)) => {
+                        tracing::warn!(skipped = %skipped, "Update stream subscriber lagging, updates dropped");
+                    }
+                    Err(RecvError::Closed) => return None,
+                }
+            }
+        }
+    })
+    .take_until(state.shutdown.clone().cancelled_owned())
+}
+
+fn update_sse(
+    updates: impl Stream<Item = WalletUpdateEvent> + Send + 'static,
+) -> Sse<impl Stream<Item = Result<Event, axum::Error>>> {
+    let events = updates.map(|update| {
+        let event = Event::default().event(update.name());
+        match update {
+            WalletUpdateEvent::Transaction(transaction) => event.json_data(transaction),
+            WalletUpdateEvent::Summary(summary) => event.json_data(summary),
+            WalletUpdateEvent::Alert(alert) => event.json_data(alert),
+        }
+    });
+    Sse::new(events).keep_alive(KeepAlive::default())
+}
+
+/// Send each update as a JSON text message until either side hangs up
+async fn push_updates(mut socket: WebSocket, updates: impl Stream<Item = WalletUpdateEvent> + Send) {
+    let mut updates = std::pin::pin!(updates);
+    loop {
+        tokio::select! {
+            update = updates.next() => {
+                let Some(update) = update else { break };
+                let text = match serde_json::to_string(&update) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        tracing::error!(error = %e, "Failed to serialize wallet update");
+                        continue;
+                    }
+                };
+                if socket.send(Message::Text(text.into())).await.is_err() {
+                    break;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                // Pings are answered by the socket itself; clients have nothing else to say
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+    tracing::debug!("Update socket closed");
+}
+
+/// Issue an API key. The key itself is only returned here.
+#[utoipa::path(
+    post,
+    path = "/api/v1/keys",
+    tag = "keys",
+    request_body = CreateApiKeyRequest,
+    responses(
+        (status = 201, body = ApiKeyResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn create_api_key(
+    State(state): State<AppState>,
+    Json(request): Json<CreateApiKeyRequest>,
+) -> AppResult<(StatusCode, Json<ApiKeyResponse>)> {
+    let start = Instant::now();
+    println!("[REQUEST] POST /api/v1/keys name={} scopes={:?}", request.name, request.scopes);
+    tracing::info!(name = %request.name, scopes = ?request.scopes, "Processing create API key request");
+
+    let invalid = |param: &str, value: &str| {
+        println!("[RESPONSE] POST /api/v1/keys -> 400 Bad Request (invalid {})", param);
+        tracing::warn!(param = %param, value = %value, "Invalid API key parameter");
+        AppError::InvalidParam(format!("Invalid {}: {}", param, value))
+    };
+
+    if request.name.trim().is_empty() {
+        return Err(invalid("name", ""));
+    }
+    if request.scopes.is_empty() {
+        return Err(invalid("scopes", "[]"));
+    }
+    let mut scopes = Vec::new();
+    for name in &request.scopes {
+        let scope = ApiScope::from_str(name).ok_or_else(|| invalid("scope", name))?;
+        if !scopes.contains(&scope) {
+            scopes.push(scope);
+        }
+    }
+
+    let (row, key) = state
+        .api_keys
+        .create(request.name.trim().to_string(), &scopes, request.rate_limit_per_minute, request.max_subscriptions)
+        .await?;
+
+    println!("[RESPONSE] POST /api/v1/keys -> 201 Created ({}ms) id={}", start.elapsed().as_millis(), row.id);
+    tracing::info!(key_id = %row.id, duration_ms = %start.elapsed().as_millis(), "API key created");
+
+    let mut response = ApiKeyResponse::from(row);
+    response.key = Some(key);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// List stored API keys, without the keys themselves
+#[utoipa::path(
+    get,
+    path = "/api/v1/keys",
+    tag = "keys",
+    responses(
+        (status = 200, body = ApiKeysResponse),
+    ),
+)]
+pub async fn list_api_keys(State(state): State<AppState>) -> Json<ApiKeysResponse> {
+    println!("[REQUEST] GET /api/v1/keys");
+    tracing::info!("Processing list API keys request");
+
+    let keys: Vec<ApiKeyResponse> = state.api_keys.list().await.into_iter().map(Into::into).collect();
+
+    println!("[RESPONSE] GET /api/v1/keys -> 200 OK ({} keys)", keys.len());
+    Json(ApiKeysResponse { keys })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/keys/{id}",
+    tag = "keys",
+    params(("id" = String, Path, description = "API key id")),
+    responses(
+        (status = 200, body = ApiKeyResponse),
+        (status = 404, description = "No API key with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn get_api_key(State(state): State<AppState>, Path(id): Path<String>) -> AppResult<Json<ApiKeyResponse>> {
+    println!("[REQUEST] GET /api/v1/keys/{}", id);
+    tracing::info!(key_id = %id, "Processing get API key request");
+
+    let Some(key) = state.api_keys.get(&id).await else {
+        println!("[RESPONSE] GET /api/v1/keys/{} -> 404 Not Found", id);
+        return Err(AppError::ApiKeyNotFound(id));
+    };
+
+    println!("[RESPONSE] GET /api/v1/keys/{} -> 200 OK", id);
+    Ok(Json(key.into()))
+}
+
+/// Revoke an API key; requests with it are rejected from then on
+#[utoipa::path(
+    delete,
+    path = "/api/v1/keys/{id}",
+    tag = "keys",
+    params(("id" = String, Path, description = "API key id")),
+    responses(
+        (status = 200, body = RevokeApiKeyResponse),
+        (status = 404, description = "No API key with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn revoke_api_key(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> AppResult<Json<RevokeApiKeyResponse>> {
+    println!("[REQUEST] DELETE /api/v1/keys/{}", id);
+    tracing::info!(key_id = %id, "Processing revoke API key request");
+
+    if !state.api_keys.revoke(&id).await? {
+        println!("[RESPONSE] DELETE /api/v1/keys/{} -> 404 Not Found", id);
+        return Err(AppError::ApiKeyNotFound(id));
+    }
+
+    println!("[RESPONSE] DELETE /api/v1/keys/{} -> 200 OK status=revoked", id);
+    Ok(Json(RevokeApiKeyResponse {
+        id,
+        status: "revoked".to_string(),
+    }))
+}
+
+/// Define an alert rule
+#[utoipa::path(
+    post,
+    path = "/api/v1/alerts/rules",
+    tag = "alerts",
+    request_body = CreateAlertRuleRequest,
+    responses(
+        (status = 201, body = AlertRuleResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+        (status = 404, description = "No wallet group with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn create_alert_rule(
+    State(state): State<AppState>,
+    Json(request): Json<CreateAlertRuleRequest>,
+) -> AppResult<(StatusCode, Json<AlertRuleResponse>)> {
+    let start = Instant::now();
+    println!("[REQUEST] POST /api/v1/alerts/rules name={} condition={} wallets={} group={:?}", request.name, request.condition, request.wallets.len(), request.group_id);
+    tracing::info!(name = %request.name, condition = %request.condition, wallets = ?request.wallets, group_id = ?request.group_id, "Processing create alert rule request");
+
+    if request.name.trim().is_empty() {
+        println!("[RESPONSE] POST /api/v1/alerts/rules -> 400 Bad Request (empty name)");
+        return Err(AppError::InvalidParam("Invalid name: must not be empty".to_string()));
+    }
+    let condition = Condition::parse(&request.condition).map_err(|e| {
+        println!("[RESPONSE] POST /api/v1/alerts/rules -> 400 Bad Request (invalid condition)");
+        tracing::warn!(condition = %request.condition, error = %e, "Invalid alert condition");
+        AppError::InvalidParam(format!("Invalid condition: {}", e))
+    })?;
+    if let Some(wallet) = request.wallets.iter().find(|w| !validate_solana_address(w)) {
+        println!("[RESPONSE] POST /api/v1/alerts/rules -> 400 Bad Request (invalid wallet)");
+        tracing::warn!(wallet = %wallet, "Invalid wallet address provided for alert rule");
+        return Err(AppError::InvalidWallet(wallet.clone()));
+    }
+    if let Some(group_id) = &request.group_id {
+        if state.groups.group(group_id).await.is_none() {
+            println!("[RESPONSE] POST /api/v1/alerts/rules -> 404 Not Found (group)");
+            return Err(AppError::GroupNotFound(group_id.clone()));
+        }
+    }
+    let mut wallets = request.wallets;
+    wallets.sort();
+    wallets.dedup();
+
+    let rule = alerts::new_rule(request.name.trim().to_string(), &condition, wallets, request.group_id);
+    state.alerts.create(rule.clone(), condition).await?;
+
+    println!("[RESPONSE] POST /api/v1/alerts/rules -> 201 Created ({}ms) id={}", start.elapsed().as_millis(), rule.id);
+    tracing::info!(rule_id = %rule.id, duration_ms = %start.elapsed().as_millis(), "Alert rule created");
+    Ok((StatusCode::CREATED, Json(rule.into())))
+}
+
+/// List alert rules
+#[utoipa::path(
+    get,
+    path = "/api/v1/alerts/rules",
+    tag = "alerts",
+    responses(
+        (status = 200, body = AlertRulesResponse),
+    ),
+)]
+pub async fn list_alert_rules(State(state): State<AppState>) -> Json<AlertRulesResponse> {
+    println!("[REQUEST] GET /api/v1/alerts/rules");
+    tracing::info!("Processing list alert rules request");
+
+    let rules: Vec<AlertRuleResponse> = state.alerts.rules().await.into_iter().map(Into::into).collect();
+
+    println!("[RESPONSE] GET /api/v1/alerts/rules -> 200 OK ({} rules)", rules.len());
+    Json(AlertRulesResponse { rules })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/alerts/rules/{id}",
+    tag = "alerts",
+    params(("id" = String, Path, description = "Alert rule id")),
+    responses(
+        (status = 200, body = AlertRuleResponse),
+        (status = 404, description = "No alert rule with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn get_alert_rule(State(state): State<AppState>, Path(id): Path<String>) -> AppResult<Json<AlertRuleResponse>> {
+    println!("[REQUEST] GET /api/v1/alerts/rules/{}", id);
+    tracing::info!(rule_id = %id, "Processing get alert rule request");
+
+    let Some(rule) = state.alerts.rule(&id).await else {
+        println!("[RESPONSE] GET /api/v1/alerts/rules/{} -> 404 Not Found", id);
+        return Err(AppError::AlertRuleNotFound(id));
+    };
+
+    println!("[RESPONSE] GET /api/v1/alerts/rules/{} -> 200 OK", id);
+    Ok(Json(rule.into()))
+}
+
+/// Stop evaluating a rule and drop its alerts
+#[utoipa::path(
+    delete,
+    path = "/api/v1/alerts/rules/{id}",
+    tag = "alerts",
+    params(("id" = String, Path, description = "Alert rule id")),
+    responses(
+        (status = 200, body = DeleteAlertRuleResponse),
+        (status = 404, description = "No alert rule with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn delete_alert_rule(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> AppResult<Json<DeleteAlertRuleResponse>> {
+    println!("[REQUEST] DELETE /api/v1/alerts/rules/{}", id);
+    tracing::info!(rule_id = %id, "Processing delete alert rule request");
+
+    if !state.alerts.delete(&id).await? {
+        println!("[RESPONSE] DELETE /api/v1/alerts/rules/{} -> 404 Not Found", id);
+        return Err(AppError::AlertRuleNotFound(id));
+    }
+
+    println!("[RESPONSE] DELETE /api/v1/alerts/rules/{} -> 200 OK status=deleted", id);
+    Ok(Json(DeleteAlertRuleResponse {
+        id,
+        status: "deleted".to_string(),
+    }))
+}
+
+/// Current alerts, most recently changed first
+#[utoipa::path(
+    get,
+    path = "/api/v1/alerts",
+    tag = "alerts",
+    params(AlertsQuery),
+    responses(
+        (status = 200, body = AlertsResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn list_alerts(
+    State(state): State<AppState>,
+    Query(query): Query<AlertsQuery>,
+) -> AppResult<Json<AlertsResponse>> {
+    println!("[REQUEST] GET /api/v1/alerts?state={:?}", query.state);
+    tracing::info!(state = ?query.state, "Processing list alerts request");
+
+    let alert_state = match &query.state {
+        Some(s) => Some(AlertState::from_str(s).ok_or_else(|| {
+            println!("[RESPONSE] GET /api/v1/alerts -> 400 Bad Request (invalid state)");
+            tracing::warn!(state = %s, "Invalid alert state parameter");
+            AppError::InvalidParam(format!("Invalid state: {}", s))
+        })?),
+        None => None,
+    };
+
+    let alerts = state.alerts.alerts(alert_state).await;
+
+    println!("[RESPONSE] GET /api/v1/alerts -> 200 OK ({} alerts)", alerts.len());
+    Ok(Json(AlertsResponse { alerts }))
+}
+
+/// Register a webhook
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    request_body = CreateWebhookRequest,
+    responses(
+        (status = 201, body = WebhookResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn create_webhook(
+    State(state): State<AppState>,
+    Json(request): Json<CreateWebhookRequest>,
+) -> AppResult<(StatusCode, Json<WebhookResponse>)> {
+    let start = Instant::now();
+    println!("[REQUEST] POST /api/v1/webhooks url={} wallet={:?} events={:?}", request.url, request.wallet, request.event_types);
+    tracing::info!(url = %request.url, wallet = ?request.wallet, events = ?request.event_types, "Processing create webhook request");
+
+    let invalid = |param: &str, value: &str| {
+        println!("[RESPONSE] POST /api/v1/webhooks -> 400 Bad Request (invalid {})", param);
+        tracing::warn!(param = %param, value = %value, "Invalid webhook parameter");
+        AppError::InvalidParam(format!("Invalid {}: {}", param, value))
+    };
+
+    let url = reqwest::Url::parse(&request.url).map_err(|_| invalid("url", &request.url))?;
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(invalid("url", &request.url));
+    }
+    if let Some(wallet) = &request.wallet {
+        if !validate_solana_address(wallet) {
+            println!("[RESPONSE] POST /api/v1/webhooks -> 400 Bad Request (invalid wallet)");
+            tracing::warn!(wallet = %wallet, "Invalid wallet address provided for webhook");
+            return Err(AppError::InvalidWallet(wallet.clone()));
+        }
+    }
+    if request.event_types.is_empty() {
+        return Err(invalid("event_types", "[]"));
+    }
+    let mut events = Vec::new();
+    for name in &request.event_types {
+        let event = WebhookEvent::from_str(name).ok_or_else(|| invalid("event type", name))?;
+        if !events.contains(&event) {
+            events.push(event);
+        }
+    }
+    let min_usd_value = request.min_usd_value.unwrap_or(webhooks::DEFAULT_MIN_USD_VALUE);
+    if min_usd_value.is_sign_negative() {
+        return Err(invalid("min_usd_value", &min_usd_value.to_string()));
+    }
+    let risk_threshold = request.risk_threshold.unwrap_or(webhooks::DEFAULT_RISK_THRESHOLD);
+    if !(1..=100).contains(&risk_threshold) {
+        return Err(invalid("risk_threshold", &risk_threshold.to_string()));
+    }
+    let secret = match request.secret {
+        Some(secret) if secret.is_empty() => return Err(invalid("secret", "")),
+        Some(secret) => secret,
+        None => webhooks::generate_secret(),
+    };
+
+    let webhook = webhooks::new_webhook(request.url, secret, request.wallet, &events, min_usd_value, risk_threshold);
+    state.webhooks.create(webhook.clone()).await?;
+
+    println!("[RESPONSE] POST /api/v1/webhooks -> 201 Created ({}ms) id={}", start.elapsed().as_millis(), webhook.id);
+    tracing::info!(webhook_id = %webhook.id, duration_ms = %start.elapsed().as_millis(), "Webhook created");
+
+    let secret = webhook.secret.clone();
+    let mut response = WebhookResponse::from(webhook);
+    response.secret = Some(secret);
+    Ok((StatusCode::CREATED, Json(response)))
+}
+
+/// List registered webhooks
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks",
+    tag = "webhooks",
+    responses(
+        (status = 200, body = WebhooksResponse),
+    ),
+)]
+pub async fn list_webhooks(State(state): State<AppState>) -> Json<WebhooksResponse> {
+    println!("[REQUEST] GET /api/v1/webhooks");
+    tracing::info!("Processing list webhooks request");
+
+    let webhooks: Vec<WebhookResponse> = state.webhooks.list().await.into_iter().map(Into::into).collect();
+
+    println!("[RESPONSE] GET /api/v1/webhooks -> 200 OK ({} webhooks)", webhooks.len());
+    Json(WebhooksResponse { webhooks })
+}
+
+/// One webhook, including whether it has been disabled
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 200, body = WebhookResponse),
+        (status = 404, description = "No webhook with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn get_webhook(State(state): State<AppState>, Path(id): Path<String>) -> AppResult<Json<WebhookResponse>> {
+    println!("[REQUEST] GET /api/v1/webhooks/{}", id);
+    tracing::info!(webhook_id = %id, "Processing get webhook request");
+
+    let Some(webhook) = state.webhooks.get(&id).await else {
+        println!("[RESPONSE] GET /api/v1/webhooks/{} -> 404 Not Found", id);
+        return Err(AppError::WebhookNotFound(id));
+    };
+
+    println!("[RESPONSE] GET /api/v1/webhooks/{} -> 200 OK", id);
+    Ok(Json(webhook.into()))
+}
+
+/// Stop delivering to a webhook and forget it
+#[utoipa::path(
+    delete,
+    path = "/api/v1/webhooks/{id}",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 200, body = DeleteWebhookResponse),
+        (status = 404, description = "No webhook with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn delete_webhook(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> AppResult<Json<DeleteWebhookResponse>> {
+    println!("[REQUEST] DELETE /api/v1/webhooks/{}", id);
+    tracing::info!(webhook_id = %id, "Processing delete webhook request");
+
+    if !state.webhooks.delete(&id).await? {
+        println!("[RESPONSE] DELETE /api/v1/webhooks/{} -> 404 Not Found", id);
+        return Err(AppError::WebhookNotFound(id));
+    }
+
+    println!("[RESPONSE] DELETE /api/v1/webhooks/{} -> 200 OK status=deleted", id);
+    Ok(Json(DeleteWebhookResponse {
+        id,
+        status: "deleted".to_string(),
+    }))
+}
+
+/// Resume deliveries to a webhook disabled after repeated failures
+#[utoipa::path(
+    post,
+    path = "/api/v1/webhooks/{id}/enable",
+    tag = "webhooks",
+    params(("id" = String, Path, description = "Webhook id")),
+    responses(
+        (status = 200, body = WebhookResponse),
+        (status = 404, description = "No webhook with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn enable_webhook(State(state): State<AppState>, Path(id): Path<String>) -> AppResult<Json<WebhookResponse>> {
+    println!("[REQUEST] POST /api/v1/webhooks/{}/enable", id);
+    tracing::info!(webhook_id = %id, "Processing enable webhook request");
+
+    let Some(webhook) = state.webhooks.enable(&id).await? else {
+        println!("[RESPONSE] POST /api/v1/webhooks/{}/enable -> 404 Not Found", id);
+        return Err(AppError::WebhookNotFound(id));
+    };
+
+    println!("[RESPONSE] POST /api/v1/webhooks/{}/enable -> 200 OK", id);
+    Ok(Json(webhook.into()))
+}
+
+/// Recent delivery attempts for a webhook, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/webhooks/{id}/deliveries",
+    tag = "webhooks",
+    params(
+        ("id" = String, Path, description = "Webhook id"),
+        WebhookDeliveriesQuery,
+    ),
+    responses(
+        (status = 200, body = WebhookDeliveriesResponse),
+        (status = 404, description = "No webhook with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn get_webhook_deliveries(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<WebhookDeliveriesQuery>,
+) -> AppResult<Json<WebhookDeliveriesResponse>> {
+    let start = Instant::now();
+    println!("[REQUEST] GET /api/v1/webhooks/{}/deliveries?limit={}", id, query.limit);
+    tracing::info!(webhook_id = %id, limit = %query.limit, "Processing webhook deliveries request");
+
+    if !(1..=MAX_DELIVERY_PAGE).contains(&query.limit) {
+        println!("[RESPONSE] GET /api/v1/webhooks/{}/deliveries -> 400 Bad Request (invalid limit)", id);
+        tracing::warn!(limit = %query.limit, "Invalid delivery limit");
+        return Err(AppError::InvalidParam(format!("Invalid limit: {} (1-{})", query.limit, MAX_DELIVERY_PAGE)));
+    }
+    if state.webhooks.get(&id).await.is_none() {
+        println!("[RESPONSE] GET /api/v1/webhooks/{}/deliveries -> 404 Not Found", id);
+        return Err(AppError::WebhookNotFound(id));
+    }
+
+    let deliveries: Vec<WebhookDelivery> =
+        state.webhooks.deliveries(&id, query.limit).await?.into_iter().map(Into::into).collect();
+
+    println!("[RESPONSE] GET /api/v1/webhooks/{}/deliveries -> 200 OK ({}ms, {} attempts)",
+        id, start.elapsed().as_millis(), deliveries.len());
+    Ok(Json(WebhookDeliveriesResponse { webhook_id: id, deliveries }))
+}
+
+/// Rank indexed wallets by the performance of their closed trades
+#[utoipa::path(
+    get,
+    path = "/api/v1/leaderboard",
+    tag = "analytics",
+    params(LeaderboardQuery),
+    responses(
+        (status = 200, body = LeaderboardResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+        (status = 404, description = "No wallet group with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn get_leaderboard(
+    State(state): State<AppState>,
+    Query(query): Query<LeaderboardQuery>,
+) -> AppResult<Json<LeaderboardResponse>> {
+    let start = Instant::now();
+    println!("[REQUEST] GET /api/v1/leaderboard?window={}&metric={}&protocol={:?}&group={:?}&min_value={:?}&min_trades={}&limit={}",
+        query.window, query.metric, query.protocol, query.group, query.min_value, query.min_trades, query.limit);
+    tracing::info!(
+        window = %query.window,
+        metric = %query.metric,
+        protocol = ?query.protocol,
+        group = ?query.group,
+        min_value = ?query.min_value,
+        min_trades = %query.min_trades,
+        limit = %query.limit,
+        "Processing leaderboard request"
+    );
+
+    let window = TimeWindow::from_str(&query.window).ok_or_else(|| {
+        println!("[RESPONSE] GET /api/v1/leaderboard -> 400 Bad Request (invalid window)");
+        tracing::warn!(window = %query.window, "Invalid time window parameter");
+        AppError::InvalidParam(format!("Invalid window: {}", query.window))
+    })?;
+
+    let metric = LeaderboardMetric::from_str(&query.metric).ok_or_else(|| {
+        println!("[RESPONSE] GET /api/v1/leaderboard -> 400 Bad Request (invalid metric)");
+        tracing::warn!(metric = %query.metric, "Invalid leaderboard metric");
+        AppError::InvalidParam(format!("Invalid metric: {}", query.metric))
+    })?;
+
+    let protocol = match &query.protocol {
+        Some(protocol) => Some(Protocol::from_str(protocol).ok_or_else(|| {
+            println!("[RESPONSE] GET /api/v1/leaderboard -> 400 Bad Request (invalid protocol)");
+            tracing::warn!(protocol = %protocol, "Invalid protocol parameter");
+            AppError::InvalidParam(format!("Invalid protocol: {}", protocol))
+        })?),
+        None => None,
+    };
+
+    let min_value = match &query.min_value {
+        Some(value) => Decimal::from_str(value)
+            .ok()
+            .filter(|v| *v >= Decimal::ZERO)
+            .ok_or_else(|| {
+                println!("[RESPONSE] GET /api/v1/leaderboard -> 400 Bad Request (invalid min_value)");
+                tracing::warn!(min_value = %value, "Invalid min_value parameter");
+                AppError::InvalidParam(format!("Invalid min_value: {}", value))
+            })?,
+        None => Decimal::ZERO,
+    };
+
+    if query.limit == 0 || query.limit > MAX_LEADERBOARD_SIZE {
+        println!("[RESPONSE] GET /api/v1/leaderboard -> 400 Bad Request (invalid limit)");
+        tracing::warn!(limit = %query.limit, "Invalid limit parameter");
+        return Err(AppError::InvalidParam(format!("limit must be between 1 and {}", MAX_LEADERBOARD_SIZE)));
+    }
+
+    let members = group_members(&state, "GET /api/v1/leaderboard", query.group.as_deref()).await?;
+
+    println!("[DB] Ranking wallets by {}", metric);
+    tracing::debug!(metric = %metric, window = ?window, "Querying database for leaderboard");
+    let protocol_name = protocol.map(|p| p.to_string());
+    let filter = LeaderboardFilter {
+        window,
+        metric,
+        protocol: protocol_name.clone(),
+        wallets: members,
+        min_value_usd: min_value.round_dp(2).to_string(),
+        min_trades: query.min_trades,
+        limit: query.limit,
+    };
+    let rows = state.store.get_leaderboard(&filter).await?;
+
+    let entries: Vec<LeaderboardEntry> = rows
+        .into_iter()
+        .enumerate()
+        .map(|(i, row)| LeaderboardEntry::new(i as u32 + 1, row))
+        .collect();
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] GET /api/v1/leaderboard -> 200 OK ({}ms) wallets={}", duration, entries.len());
+    tracing::info!(duration_ms = %duration, count = %entries.len(), metric = %metric, "Leaderboard retrieved");
+
+    Ok(Json(LeaderboardResponse {
+        window: query.window,
+        metric: metric.to_string(),
+        protocol: protocol_name,
+        group: query.group,
+        entries,
+    }))
+}
+
+/// Net swap flow into a token across indexed wallets, with the smart-money share
+#[utoipa::path(
+    get,
+    path = "/api/v1/token/{mint}/flows",
+    tag = "analytics",
+    params(
+        ("mint" = String, Path, description = "Token mint address"),
+        TokenFlowsQuery,
+    ),
+    responses(
+        (status = 200, body = TokenFlowsResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+        (status = 404, description = "No wallet group with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn get_token_flows(
+    State(state): State<AppState>,
+    Path(mint): Path<String>,
+    Query(query): Query<TokenFlowsQuery>,
+) -> AppResult<Json<TokenFlowsResponse>> {
+    let start = Instant::now();
+    println!("[REQUEST] GET /api/v1/token/{}/flows?window={}&limit={}&group={:?}", mint, query.window, query.limit, query.group);
+    tracing::info!(mint = %mint, window = %query.window, limit = %query.limit, group = ?query.group, "Processing token flows request");
+
+    if !validate_solana_address(&mint) {
+        println!("[RESPONSE] GET /api/v1/token/{}/flows -> 400 Bad Request (invalid mint)", mint);
+        tracing::warn!(mint = %mint, "Invalid token mint provided");
+        return Err(AppError::InvalidParam(format!("Invalid mint: {}", mint)));
+    }
+
+    let window = TimeWindow::from_str(&query.window).ok_or_else(|| {
+        println!("[RESPONSE] GET /api/v1/token/{}/flows -> 400 Bad Request (invalid window)", mint);
+        tracing::warn!(mint = %mint, window = %query.window, "Invalid time window parameter");
+        AppError::InvalidParam(format!("Invalid window: {}", query.window))
+    })?;
+
+    if query.limit == 0 || query.limit > MAX_FLOW_WALLETS {
+        println!("[RESPONSE] GET /api/v1/token/{}/flows -> 400 Bad Request (invalid limit)", mint);
+        tracing::warn!(mint = %mint, limit = %query.limit, "Invalid limit parameter");
+        return Err(AppError::InvalidParam(format!("limit must be between 1 and {}", MAX_FLOW_WALLETS)));
+    }
+
+    let members = group_members(&state, &format!("GET /api/v1/token/{}/flows", mint), query.group.as_deref()).await?;
+
+    println!("[DB] Querying token flows for {}", mint);
+    tracing::debug!(mint = %mint, "Querying database for token flows");
+    let rows = state.store.get_token_flows(&mint, window, members.as_deref()).await?;
+
+    // Top performers by realized PnL over the last 30 days
+    let top_performers = LeaderboardFilter {
+        window: TimeWindow::Month,
+        metric: LeaderboardMetric::RealizedPnl,
+        protocol: None,
+        wallets: None,
+        min_value_usd: "0".to_string(),
+        min_trades: 1,
+        limit: metrics::flows::TOP_PERFORMER_COUNT,
+    };
+    let top_performers: std::collections::HashSet<String> = state
+        .store
+        .get_leaderboard(&top_performers)
+        .await?
+        .into_iter()
+        .filter(|row| parse_decimal(&row.realized_pnl) > Decimal::ZERO)
+        .map(|row| row.wallet)
+        .collect();
+
+    let price_usd = state
+        .store
+        .get_token_prices(std::slice::from_ref(&mint))
+        .await?
+        .first()
+        .map(|row| parse_decimal(&row.price_usd))
+        .filter(|price| *price > Decimal::ZERO);
+
+    let flows: Vec<metrics::flows::WalletFlow> = rows
+        .into_iter()
+        .map(|row| metrics::flows::WalletFlow {
+            top_performer: top_performers.contains(&row.wallet),
+            bought: parse_decimal(&row.bought),
+            sold: parse_decimal(&row.sold),
+            buy_count: row.buy_count,
+            sell_count: row.sell_count,
+            classification: row.classification,
+            wallet: row.wallet,
+        })
+        .collect();
+    let flows = metrics::flows::aggregate_flows(flows, query.limit);
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] GET /api/v1/token/{}/flows -> 200 OK ({}ms) buyers={} sellers={} net={}",
+        mint, duration, flows.unique_buyers, flows.unique_sellers, flows.net_volume());
+    tracing::info!(
+        mint = %mint,
+        duration_ms = %duration,
+        unique_buyers = %flows.unique_buyers,
+        unique_sellers = %flows.unique_sellers,
+        net_volume = %flows.net_volume(),
+        smart_money_buy_share = %flows.smart_money.buy_share,
+        "Token flows retrieved"
+    );
+
+    let mut response = TokenFlowsResponse::new(mint, query.window, price_usd, flows);
+    response.group = query.group;
+    Ok(Json(response))
+}
+
+/// Check a group request, returning its trimmed name, sorted unique wallets and normalized tags
+fn validate_group_request(endpoint: &str, request: WalletGroupRequest) -> AppResult<(String, Vec<String>, Vec<String>)> {
+    if request.name.trim().is_empty() {
+        println!("[RESPONSE] {} -> 400 Bad Request (empty name)", endpoint);
+        return Err(AppError::InvalidParam("Invalid name: must not be empty".to_string()));
+    }
+    if let Some(wallet) = request.wallets.iter().find(|w| !validate_solana_address(w)) {
+        println!("[RESPONSE] {} -> 400 Bad Request (invalid wallet)", endpoint);
+        tracing::warn!(wallet = %wallet, "Invalid wallet address provided for group");
+        return Err(AppError::InvalidWallet(wallet.clone()));
+    }
+    let mut wallets = request.wallets;
+    wallets.sort();
+    wallets.dedup();
+    if wallets.len() > MAX_BATCH_WALLETS {
+        println!("[RESPONSE] {} -> 400 Bad Request (too many wallets)", endpoint);
+        tracing::warn!(wallet_count = %wallets.len(), "Too many wallets for group");
+        return Err(AppError::InvalidParam(format!("wallets must list at most {} addresses", MAX_BATCH_WALLETS)));
+    }
+    Ok((request.name.trim().to_string(), wallets, groups::normalize_tags(request.tags)))
+}
+
+/// Create a named wallet group
+#[utoipa::path(
+    post,
+    path = "/api/v1/groups",
+    tag = "groups",
+    request_body = WalletGroupRequest,
+    responses(
+        (status = 201, body = WalletGroupResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+    ),
+)]
+pub async fn create_group(
+    State(state): State<AppState>,
+    Json(request): Json<WalletGroupRequest>,
+) -> AppResult<(StatusCode, Json<WalletGroupResponse>)> {
+    let start = Instant::now();
+    println!("[REQUEST] POST /api/v1/groups name={} wallets={} tags={:?}", request.name, request.wallets.len(), request.tags);
+    tracing::info!(name = %request.name, wallet_count = %request.wallets.len(), tags = ?request.tags, "Processing create group request");
+
+    let (name, wallets, tags) = validate_group_request("POST /api/v1/groups", request)?;
+    let group = groups::new_group(name, wallets, tags);
+    state.groups.save(group.clone()).await?;
+
+    println!("[RESPONSE] POST /api/v1/groups -> 201 Created ({}ms) id={}", start.elapsed().as_millis(), group.id);
+    tracing::info!(group_id = %group.id, duration_ms = %start.elapsed().as_millis(), "Wallet group created");
+    Ok((StatusCode::CREATED, Json(group.into())))
+}
+
+/// List wallet groups, optionally only those with a tag
+#[utoipa::path(
+    get,
+    path = "/api/v1/groups",
+    tag = "groups",
+    params(WalletGroupsQuery),
+    responses(
+        (status = 200, body = WalletGroupsResponse),
+    ),
+)]
+pub async fn list_groups(State(state): State<AppState>, Query(query): Query<WalletGroupsQuery>) -> Json<WalletGroupsResponse> {
+    println!("[REQUEST] GET /api/v1/groups?tag={:?}", query.tag);
+    tracing::info!(tag = ?query.tag, "Processing list groups request");
+
+    let tag = query.tag.map(|t| t.trim().to_lowercase());
+    let groups: Vec<WalletGroupResponse> = state.groups.groups(tag.as_deref()).await.into_iter().map(Into::into).collect();
+
+    println!("[RESPONSE] GET /api/v1/groups -> 200 OK groups={}", groups.len());
+    Json(WalletGroupsResponse { groups })
+}
+
+#[utoipa::path(
+    get,
+    path = "/api/v1/groups/{id}",
+    tag = "groups",
+    params(("id" = String, Path, description = "Wallet group id")),
+    responses(
+        (status = 200, body = WalletGroupResponse),
+        (status = 404, description = "No wallet group with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn get_group(State(state): State<AppState>, Path(id): Path<String>) -> AppResult<Json<WalletGroupResponse>> {
+    println!("[REQUEST] GET /api/v1/groups/{}", id);
+    tracing::info!(group_id = %id, "Processing get group request");
+
+    let Some(group) = state.groups.group(&id).await else {
+        println!("[RESPONSE] GET /api/v1/groups/{} -> 404 Not Found", id);
+        return Err(AppError::GroupNotFound(id));
+    };
+
+    println!("[RESPONSE] GET /api/v1/groups/{} -> 200 OK", id);
+    Ok(Json(group.into()))
+}
+
+/// Replace a group's name, wallets and tags
+#[utoipa::path(
+    put,
+    path = "/api/v1/groups/{id}",
+    tag = "groups",
+    params(("id" = String, Path, description = "Wallet group id")),
+    request_body = WalletGroupRequest,
+    responses(
+        (status = 200, body = WalletGroupResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+        (status = 404, description = "No wallet group with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn update_group(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Json(request): Json<WalletGroupRequest>,
+) -> AppResult<Json<WalletGroupResponse>> {
+    let start = Instant::now();
+    println!("[REQUEST] PUT /api/v1/groups/{} name={} wallets={} tags={:?}", id, request.name, request.wallets.len(), request.tags);
+    tracing::info!(group_id = %id, name = %request.name, wallet_count = %request.wallets.len(), "Processing update group request");
+
+    let Some(mut group) = state.groups.group(&id).await else {
+        println!("[RESPONSE] PUT /api/v1/groups/{} -> 404 Not Found", id);
+        return Err(AppError::GroupNotFound(id));
+    };
+    let (name, wallets, tags) = validate_group_request(&format!("PUT /api/v1/groups/{}", id), request)?;
+    group.name = name;
+    group.wallets = wallets;
+    group.tags = tags;
+    state.groups.save(group.clone()).await?;
+
+    println!("[RESPONSE] PUT /api/v1/groups/{} -> 200 OK ({}ms)", id, start.elapsed().as_millis());
+    tracing::info!(group_id = %id, duration_ms = %start.elapsed().as_millis(), "Wallet group updated");
+    Ok(Json(group.into()))
+}
+
+/// Delete a group. Subscriptions of its members keep running.
+#[utoipa::path(
+    delete,
+    path = "/api/v1/groups/{id}",
+    tag = "groups",
+    params(("id" = String, Path, description = "Wallet group id")),
+    responses(
+        (status = 200, body = DeleteWalletGroupResponse),
+        (status = 404, description = "No wallet group with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn delete_group(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+) -> AppResult<Json<DeleteWalletGroupResponse>> {
+    println!("[REQUEST] DELETE /api/v1/groups/{}", id);
+    tracing::info!(group_id = %id, "Processing delete group request");
+
+    if !state.groups.delete(&id).await? {
+        println!("[RESPONSE] DELETE /api/v1/groups/{} -> 404 Not Found", id);
+        return Err(AppError::GroupNotFound(id));
+    }
+
+    println!("[RESPONSE] DELETE /api/v1/groups/{} -> 200 OK status=deleted", id);
+    Ok(Json(DeleteWalletGroupResponse {
+        id,
+        status: "deleted".to_string(),
+    }))
+}
+
+/// Start continuous indexing for every member of a group. Members the key has no
+/// quota left for are skipped rather than failing the request.
+#[utoipa::path(
+    post,
+    path = "/api/v1/groups/{id}/index",
+    tag = "groups",
+    params(("id" = String, Path, description = "Wallet group id")),
+    responses(
+        (status = 200, body = GroupIndexResponse),
+        (status = 404, description = "No wallet group with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn index_group(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    key: Option<Extension<ApiKeyRow>>,
+) -> AppResult<Json<GroupIndexResponse>> {
+    let start = Instant::now();
+    println!("[REQUEST] POST /api/v1/groups/{}/index", id);
+    tracing::info!(group_id = %id, "Processing group indexing request");
+
+    let members = state.groups.members(&id).await.inspect_err(|_| {
+        println!("[RESPONSE] POST /api/v1/groups/{}/index -> 404 Not Found", id);
+    })?;
+
+    let key = key.map(|Extension(key)| key);
+    let mut wallets = Vec::with_capacity(members.len());
+    for wallet in members {
+        let reservation = match &key {
+            Some(key) => match state.api_keys.reserve_subscription(key, &wallet, &state.indexer).await {
+                Ok(reservation) => Some(reservation),
+                Err(_) => {
+                    tracing::warn!(group_id = %id, wallet = %wallet, key_prefix = %key.key_prefix, "Subscription quota exceeded for group member");
+                    wallets.push(IndexWalletResponse {
+                        wallet,
+                        status: "quota_exceeded".to_string(),
+                        message: "The API key's subscription quota is used up.".to_string(),
+                    });
+                    continue;
+                }
+            },
+            None => None,
+        };
+
+        let (status, message) = match start_reserved_subscription(&state, &wallet, reservation).await {
+            Ok(true) => ("started", "Continuous wallet indexing started.".to_string()),
+            Ok(false) => ("already_running", "Wallet is already being indexed continuously.".to_string()),
+            Err(e) => {
+                tracing::error!(group_id = %id, wallet = %wallet, error = %e, "Failed to start indexing group member");
+                ("error", e.to_string())
+            }
+        };
+        wallets.push(IndexWalletResponse {
+            wallet,
+            status: status.to_string(),
+            message,
+        });
+    }
+
+    let count = |status: &str| wallets.iter().filter(|w| w.status == status).count();
+    let response = GroupIndexResponse {
+        group_id: id.clone(),
+        started: count("started"),
+        already_running: count("already_running"),
+        quota_exceeded: count("quota_exceeded"),
+        errors: count("error"),
+        wallets,
+    };
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] POST /api/v1/groups/{}/index -> 200 OK ({}ms) started={} already_running={} quota_exceeded={} errors={}",
+        id, duration, response.started, response.already_running, response.quota_exceeded, response.errors);
+    tracing::info!(
+        group_id = %id,
+        duration_ms = %duration,
+        started = %response.started,
+        already_running = %response.already_running,
+        quota_exceeded = %response.quota_exceeded,
+        errors = %response.errors,
+        "Group indexing started"
+    );
+
+    Ok(Json(response))
+}
+
+/// A group's members summed as one book, like `POST /api/v1/wallets/portfolio`
+#[utoipa::path(
+    get,
+    path = "/api/v1/groups/{id}/summary",
+    tag = "groups",
+    params(
+        ("id" = String, Path, description = "Wallet group id"),
+        PnlQuery,
+    ),
+    responses(
+        (status = 200, body = PortfolioResponse),
+        (status = 400, description = "Invalid parameter", body = ErrorResponse),
+        (status = 404, description = "No wallet group with that id", body = ErrorResponse),
+    ),
+)]
+pub async fn get_group_summary(
+    State(state): State<AppState>,
+    Path(id): Path<String>,
+    Query(query): Query<PnlQuery>,
+) -> AppResult<Json<PortfolioResponse>> {
+    let start = Instant::now();
+    println!("[REQUEST] GET /api/v1/groups/{}/summary?window={}", id, query.window);
+    tracing::info!(group_id = %id, window = %query.window, "Processing group summary request");
+
+    let Some(group) = state.groups.group(&id).await else {
+        println!("[RESPONSE] GET /api/v1/groups/{}/summary -> 404 Not Found", id);
+        return Err(AppError::GroupNotFound(id));
+    };
+    let window = TimeWindow::from_str(&query.window).ok_or_else(|| {
+        println!("[RESPONSE] GET /api/v1/groups/{}/summary -> 400 Bad Request (invalid window)", id);
+        tracing::warn!(group_id = %id, window = %query.window, "Invalid time window parameter");
+        AppError::InvalidParam(format!("Invalid window: {}", query.window))
+    })?;
+
+    println!("[DB] Querying portfolio of group {} ({} wallets)", id, group.wallets.len());
+    tracing::debug!(group_id = %id, wallet_count = %group.wallets.len(), "Querying database for group portfolio");
+    let wallets = &group.wallets;
+    let rows = PortfolioRows {
+        summaries: state.store.get_wallet_summaries(wallets).await?,
+        positions: state.store.get_positions_for_wallets(wallets).await?,
+        pnl: state.store.get_pnl_by_protocol_for_wallets(wallets, window).await?,
+        mev: state.store.get_mev_losses_for_wallets(wallets, window).await?,
+    };
+    let mut portfolio = PortfolioResponse::new(group.wallets.clone(), query.window, rows, BTreeMap::new());
+    portfolio.group = Some(GroupInfo::from(&group));
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] GET /api/v1/groups/{}/summary -> 200 OK ({}ms) wallets={} value=${}",
+        id, duration, portfolio.wallets.len(), portfolio.total_value_usd);
+    tracing::info!(
+        group_id = %id,
+        duration_ms = %duration,
+        wallet_count = %portfolio.wallets.len(),
+        total_value_usd = %portfolio.total_value_usd,
+        "Group summary computed"
+    );
+
+    Ok(Json(portfolio))
+}
+
+/// Run a GraphQL query with fresh dataloaders. Errors are reported in the response
+/// body, so the status is always 200 once the request parses.
+#[utoipa::path(
+    post,
+    path = "/api/v1/graphql",
+    tag = "graphql",
+    request_body(content = Object, description = "GraphQL request with `query`, and optionally `variables` and `operationName`"),
+    responses(
+        (status = 200, description = "GraphQL response; errors are reported in its `errors` field", body = Object),
+    ),
+)]
+pub async fn graphql(
+    State(state): State<AppState>,
+    Extension(schema): Extension<CortexSchema>,
+    Json(request): Json<async_graphql::Request>,
+) -> Json<async_graphql::Response> {
+    let start = Instant::now();
+    let operation = request.operation_name.clone().unwrap_or_default();
+    println!("[REQUEST] POST /api/v1/graphql operation={}", operation);
+    tracing::info!(operation = %operation, "Processing GraphQL request");
+
+    let response = schema.execute(request.data(Loaders::new(state.store.clone()))).await;
+
+    let duration = start.elapsed().as_millis();
+    println!("[RESPONSE] POST /api/v1/graphql -> 200 OK ({}ms) errors={}", duration, response.errors.len());
+    tracing::info!(
+        operation = %operation,
+        duration_ms = %duration,
+        error_count = %response.errors.len(),
+        "GraphQL request completed"
+    );
+
+    Json(response)
+}
+
+/// GraphiQL, for trying queries in a browser
+#[utoipa::path(
+    get,
+    path = "/api/v1/graphql",
+    tag = "graphql",
+    responses(
+        (status = 200, description = "GraphiQL page", content_type = "text/html"),
+    ),
+    security(()),
+)]
+pub async fn graphiql() -> Html<String> {
+    Html(GraphiQLSource::build().endpoint("/api/v1/graphql").finish())
+}
+
+/// Prometheus metrics for the API, ClickHouse queries and the indexer
+#[utoipa::path(
+    get,
+    path = "/metrics",
+    tag = "health",
+    responses(
+        (status = 200, description = "Prometheus text exposition format", body = String, content_type = "text/plain"),
+    ),
+)]
+pub async fn get_metrics() -> impl IntoResponse {
+    ([(header::CONTENT_TYPE, telemetry::CONTENT_TYPE)], telemetry::render())
+}
+
+/// This API's OpenAPI spec
+#[utoipa::path(
+    get,
+    path = "/api/v1/openapi.json",
+    tag = "health",
+    responses(
+        (status = 200, description = "OpenAPI 3.1 document", body = Object),
+    ),
+    security(()),
+)]
+pub async fn openapi_json() -> Json<utoipa::openapi::OpenApi> {
+    Json(openapi::spec())
+}
+
+/// Scalar API reference, for browsing the spec and trying requests
+#[utoipa::path(
+    get,
+    path = "/api/v1/docs",
+    tag = "health",
+    responses(
+        (status = 200, description = "API reference page", content_type = "text/html"),
+    ),
+    security(()),
+)]
+pub async fn docs() -> Html<String> {
+    Html(Scalar::new(openapi::spec()).to_html())
+}
+
+/// Members of the group named by a `group` query parameter, if one was given
+async fn group_members(state: &AppState, endpoint: &str, group: Option<&str>) -> AppResult<Option<Vec<String>>> {
+    let Some(id) = group else {
+        return Ok(None);
+    };
+    let members = state.groups.members(id).await.inspect_err(|_| {
+        println!("[RESPONSE] {} -> 404 Not Found (unknown group {})", endpoint, id);
+    })?;
+    Ok(Some(members))
+}
+
+/// Parse an optional comma-separated list of wallets, rejecting invalid addresses
+fn parse_wallet_list(endpoint: &str, wallets: Option<&str>) -> AppResult<Vec<String>> {
+    let wallets: Vec<String> = wallets
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|w| !w.is_empty())
+        .map(String::from)
+        .collect();
+
+    if let Some(invalid) = wallets.iter().find(|w| !validate_solana_address(w)) {
+        println!("[RESPONSE] GET {} -> 400 Bad Request (invalid wallet)", endpoint);
+        tracing::warn!(wallet = %invalid, "Invalid wallet address provided");
+        return Err(AppError::InvalidWallet(invalid.clone()));
+    }
+    Ok(wallets)
+}
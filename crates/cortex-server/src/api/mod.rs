@@ -1,23 +1,103 @@
 pub mod dto;
 pub mod handlers;
+pub mod openapi;
+#[cfg(test)]
+mod tests;
 
 use axum::{
-    routing::{delete, get, post},
-    Router,
+    middleware,
+    routing::{delete, get, post, put},
+    Extension, Router,
 };
 
+use crate::auth;
+use crate::graphql;
+use crate::telemetry;
+use crate::types::ApiScope;
 use crate::AppState;
 
-pub fn create_router() -> Router<AppState> {
-    Router::new()
+/// Every route, each group behind an API key with the scope it needs
+pub fn create_router(state: AppState) -> Router {
+    let require = |scope: ApiScope| middleware::from_fn_with_state((state.clone(), scope), auth::require_api_key);
+
+    let public = Router::new()
         // Health check
         .route("/health", get(handlers::health_check))
+        // GraphiQL page; its queries still need a key
+        .route("/api/v1/graphql", get(handlers::graphiql))
+        // OpenAPI spec and a browsable reference
+        .route("/api/v1/openapi.json", get(handlers::openapi_json))
+        .route("/api/v1/docs", get(handlers::docs));
+
+    let read = Router::new()
         // User endpoints
         .route("/api/v1/user/{wallet}/summary", get(handlers::get_user_summary))
         .route("/api/v1/user/{wallet}/pnl", get(handlers::get_user_pnl))
         .route("/api/v1/user/{wallet}/positions", get(handlers::get_user_positions))
-        // Indexing subscription endpoints
+        .route("/api/v1/user/{wallet}/performance", get(handlers::get_user_performance))
+        .route("/api/v1/user/{wallet}/tax-lots", get(handlers::get_user_tax_lots))
+        .route("/api/v1/user/{wallet}/transactions", get(handlers::get_user_transactions))
+        .route("/api/v1/user/{wallet}/cluster", get(handlers::get_user_cluster))
+        // Many wallets per request
+        .route("/api/v1/wallets/summary", post(handlers::get_batch_summaries))
+        .route("/api/v1/wallets/positions", post(handlers::get_batch_positions))
+        .route("/api/v1/wallets/pnl", post(handlers::get_batch_pnl))
+        .route("/api/v1/wallets/portfolio", post(handlers::get_portfolio))
+        // Push of newly indexed transactions and recomputed summaries
+        .route("/api/v1/stream", get(handlers::stream_updates))
+        .route("/api/v1/stream/ws", get(handlers::stream_updates_ws))
+        .route("/api/v1/user/{wallet}/stream", get(handlers::stream_wallet_updates))
+        .route("/api/v1/user/{wallet}/stream/ws", get(handlers::stream_wallet_updates_ws))
+        // Copy-trading signals
+        .route("/api/v1/signals", get(handlers::get_signals))
+        .route("/api/v1/signals/stream", get(handlers::stream_signals))
+        // Smart-money leaderboard and token flows
+        .route("/api/v1/leaderboard", get(handlers::get_leaderboard))
+        .route("/api/v1/token/{mint}/flows", get(handlers::get_token_flows))
+        // Alerts and the rules behind them
+        .route("/api/v1/alerts", get(handlers::list_alerts))
+        .route("/api/v1/alerts/rules", get(handlers::list_alert_rules))
+        .route("/api/v1/alerts/rules/{id}", get(handlers::get_alert_rule))
+        // Indexing subscriptions
         .route("/api/v1/index", get(handlers::list_subscriptions))
+        // Prometheus metrics
+        .route("/metrics", get(handlers::get_metrics))
+        // Wallet groups and their combined book
+        .route("/api/v1/groups", get(handlers::list_groups))
+        .route("/api/v1/groups/{id}", get(handlers::get_group))
+        .route("/api/v1/groups/{id}/summary", get(handlers::get_group_summary))
+        // GraphQL over wallets and prediction markets
+        .route("/api/v1/graphql", post(handlers::graphql).layer(Extension(graphql::schema())))
+        .route_layer(require(ApiScope::Read));
+
+    let index = Router::new()
+        // Starting and stopping indexing subscriptions
         .route("/api/v1/index", post(handlers::index_wallet))
         .route("/api/v1/index/{wallet}", delete(handlers::stop_indexing))
+        .route("/api/v1/groups/{id}/index", post(handlers::index_group))
+        .route_layer(require(ApiScope::Index));
+
+    let admin = Router::new()
+        // API keys
+        .route("/api/v1/keys", get(handlers::list_api_keys).post(handlers::create_api_key))
+        .route("/api/v1/keys/{id}", get(handlers::get_api_key).delete(handlers::revoke_api_key))
+        // Wallet group changes
+        .route("/api/v1/groups", post(handlers::create_group))
+        .route("/api/v1/groups/{id}", put(handlers::update_group).delete(handlers::delete_group))
+        // Alert rule changes
+        .route("/api/v1/alerts/rules", post(handlers::create_alert_rule))
+        .route("/api/v1/alerts/rules/{id}", delete(handlers::delete_alert_rule))
+        // Outbound webhooks
+        .route("/api/v1/webhooks", get(handlers::list_webhooks).post(handlers::create_webhook))
+        .route("/api/v1/webhooks/{id}", get(handlers::get_webhook).delete(handlers::delete_webhook))
+        .route("/api/v1/webhooks/{id}/enable", post(handlers::enable_webhook))
+        .route("/api/v1/webhooks/{id}/deliveries", get(handlers::get_webhook_deliveries))
+        .route_layer(require(ApiScope::Admin));
+
+    public
+        .merge(read)
+        .merge(index)
+        .merge(admin)
+        .layer(middleware::from_fn(telemetry::track_requests))
+        .with_state(state)
 }
@@ -0,0 +1,140 @@
+//! OpenAPI description of the REST API, generated from the handlers and DTOs.
+//!
+//! Served at `/api/v1/openapi.json` and browsable at `/api/v1/docs`. `openapi.json` at
+//! the repository root is a committed copy for tooling that can't reach a running server;
+//! a test fails when it no longer matches the served spec.
+
+use std::sync::LazyLock;
+
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, HttpAuthScheme, HttpBuilder, SecurityRequirement, SecurityScheme};
+use utoipa::openapi::path::Operation;
+use utoipa::openapi::{Content, Ref, ResponseBuilder};
+use utoipa::{Modify, OpenApi};
+
+use super::handlers;
+use crate::auth::{API_KEY_HEADER, API_KEY_PARAM};
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Solder Cortex API",
+        description = "DeFi Agent Memory Layer: pre-indexed Solana DeFi activity served as JSON for AI agents.\n\n\
+                       Every endpoint except `/health` and the docs needs an API key, sent as \
+                       `Authorization: Bearer <key>` or as `X-API-Key`; stream routes also take `?api_key=`.",
+    ),
+    servers((url = "http://localhost:3000", description = "Local development server")),
+    paths(
+        handlers::health_check,
+        handlers::get_metrics,
+        handlers::openapi_json,
+        handlers::docs,
+        handlers::get_user_summary,
+        handlers::get_user_pnl,
+        handlers::get_user_positions,
+        handlers::get_user_performance,
+        handlers::get_user_tax_lots,
+        handlers::get_user_transactions,
+        handlers::get_user_cluster,
+        handlers::get_batch_summaries,
+        handlers::get_batch_positions,
+        handlers::get_batch_pnl,
+        handlers::get_portfolio,
+        handlers::stream_updates,
+        handlers::stream_updates_ws,
+        handlers::stream_wallet_updates,
+        handlers::stream_wallet_updates_ws,
+        handlers::get_signals,
+        handlers::stream_signals,
+        handlers::get_leaderboard,
+        handlers::get_token_flows,
+        handlers::list_alerts,
+        handlers::list_alert_rules,
+        handlers::get_alert_rule,
+        handlers::create_alert_rule,
+        handlers::delete_alert_rule,
+        handlers::list_subscriptions,
+        handlers::index_wallet,
+        handlers::stop_indexing,
+        handlers::list_groups,
+        handlers::get_group,
+        handlers::get_group_summary,
+        handlers::create_group,
+        handlers::update_group,
+        handlers::delete_group,
+        handlers::index_group,
+        handlers::list_api_keys,
+        handlers::get_api_key,
+        handlers::create_api_key,
+        handlers::revoke_api_key,
+        handlers::list_webhooks,
+        handlers::get_webhook,
+        handlers::create_webhook,
+        handlers::delete_webhook,
+        handlers::enable_webhook,
+        handlers::get_webhook_deliveries,
+        handlers::graphql,
+        handlers::graphiql,
+    ),
+    modifiers(&ApiKeyAuth),
+    security(("bearer" = []), ("api_key_header" = []), ("api_key_query" = [])),
+    tags(
+        (name = "health", description = "Service health, metrics and API docs"),
+        (name = "user", description = "One wallet's summary, PnL, positions and history"),
+        (name = "wallets", description = "Many wallets per request"),
+        (name = "streams", description = "Push of newly indexed transactions, summaries and alerts"),
+        (name = "signals", description = "Copy-trading signals"),
+        (name = "analytics", description = "Smart-money leaderboard and token flows"),
+        (name = "alerts", description = "Alert rules and the alerts they raise"),
+        (name = "index", description = "Continuous indexing subscriptions (`index` scope to change)"),
+        (name = "groups", description = "Named wallet groups (`admin` scope to change)"),
+        (name = "keys", description = "API keys (`admin` scope)"),
+        (name = "webhooks", description = "Outbound webhooks (`admin` scope)"),
+        (name = "graphql", description = "GraphQL over wallets and prediction markets"),
+    ),
+)]
+struct ApiDoc;
+
+/// Registers the ways to send an API key and the errors every keyed route can return
+struct ApiKeyAuth;
+
+impl Modify for ApiKeyAuth {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "bearer",
+                SecurityScheme::Http(HttpBuilder::new().scheme(HttpAuthScheme::Bearer).build()),
+            );
+            components.add_security_scheme("api_key_header", SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))));
+            components.add_security_scheme("api_key_query", SecurityScheme::ApiKey(ApiKey::Query(ApiKeyValue::new(API_KEY_PARAM))));
+        }
+
+        let public = |op: &Operation| op.security.as_ref().is_some_and(|s| s.iter().all(|r| *r == SecurityRequirement::default()));
+        for item in openapi.paths.paths.values_mut() {
+            for op in [&mut item.get, &mut item.post, &mut item.put, &mut item.delete].into_iter().flatten() {
+                if public(op) {
+                    continue;
+                }
+                for (status, description) in [
+                    ("401", "Missing, unknown or revoked API key"),
+                    ("403", "API key lacks the scope this route needs"),
+                    ("429", "Rate limit exceeded; see `Retry-After`"),
+                ] {
+                    op.responses.responses.entry(status.to_string()).or_insert_with(|| {
+                        ResponseBuilder::new()
+                            .description(description)
+                            .content("application/json", Content::new(Some(Ref::from_schema_name("ErrorResponse"))))
+                            .build()
+                            .into()
+                    });
+                }
+            }
+        }
+    }
+}
+
+static SPEC: LazyLock<utoipa::openapi::OpenApi> = LazyLock::new(ApiDoc::openapi);
+
+/// The spec served at `/api/v1/openapi.json`
+pub fn spec() -> utoipa::openapi::OpenApi {
+    SPEC.clone()
+}
@@ -0,0 +1,995 @@
+//! Router tests against an in-memory store
+
+use std::str::FromStr;
+use std::sync::Arc;
+
+use axum::body::{to_bytes, Body};
+use axum::http::{Method, Request, StatusCode};
+use axum::Router;
+use futures::StreamExt;
+use rust_decimal::Decimal;
+use serde_json::{json, Value};
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
+
+use crate::alerts::AlertEngine;
+use crate::auth::ApiKeys;
+use crate::config::{AppConfig, AuthConfig, DatabaseConfig, HeliusConfig, LysLabsConfig, ServerConfig, WebhookConfig};
+use crate::db::models::{ClosedTradeRow, MarketRow, PositionRow, PositionSignalRow, PredictionPositionRow, TransactionRow, WalletSummaryRow};
+use crate::db::store::{CortexStore, MemoryStore};
+use crate::db::Database;
+use crate::groups::WalletGroups;
+use crate::indexer::{Indexer, WalletUpdate};
+use crate::webhooks::WebhookDispatcher;
+use crate::AppState;
+
+const WALLET: &str = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
+
+fn app(store: Arc<MemoryStore>) -> Router {
+    app_with_indexer(store.clone(), Indexer::offline(store))
+}
+
+fn app_with_indexer(store: Arc<MemoryStore>, indexer: Indexer) -> Router {
+    let auth = AuthConfig {
+        enabled: false,
+        ..AuthConfig::default()
+    };
+    build_app(store, indexer, auth, CancellationToken::new())
+}
+
+/// An app requiring API keys, with `admin` as the configured admin key
+fn app_with_auth(store: Arc<MemoryStore>) -> Router {
+    let auth = AuthConfig {
+        enabled: true,
+        admin_key: Some("admin".to_string()),
+        rate_limit_per_minute: 600,
+        max_subscriptions: 1,
+    };
+    build_app(store.clone(), Indexer::offline(store), auth, CancellationToken::new())
+}
+
+fn build_app(store: Arc<MemoryStore>, indexer: Indexer, auth: AuthConfig, shutdown: CancellationToken) -> Router {
+    let config = AppConfig {
+        server: ServerConfig {
+            host: "127.0.0.1".to_string(),
+            port: 0,
+        },
+        database: DatabaseConfig {
+            url: "http://127.0.0.1:1".to_string(),
+            database: "cortex".to_string(),
+            user: None,
+            password: None,
+            insert_batch_rows: 1,
+            insert_flush_ms: 1,
+        },
+        lyslabs: LysLabsConfig {
+            api_key: String::new(),
+            ws_url: "ws://127.0.0.1:1".to_string(),
+        },
+        helius: HeliusConfig { api_key: String::new() },
+        webhooks: WebhookConfig::default(),
+        auth,
+    };
+    let groups = WalletGroups::new(store.clone());
+    let state = AppState {
+        db: Database::unreachable(),
+        store: store.clone(),
+        indexer: indexer.clone(),
+        webhooks: WebhookDispatcher::new(store.clone(), &config.webhooks),
+        alerts: AlertEngine::new(store.clone(), indexer, groups.clone()),
+        api_keys: ApiKeys::new(store, &config.auth),
+        groups,
+        config: Arc::new(config),
+        shutdown,
+    };
+    super::create_router(state)
+}
+
+async fn request(app: &Router, method: Method, uri: &str, body: Option<Value>) -> (StatusCode, Value) {
+    request_with_key(app, method, uri, None, body).await
+}
+
+async fn request_with_key(app: &Router, method: Method, uri: &str, key: Option<&str>, body: Option<Value>) -> (StatusCode, Value) {
+    let mut builder = Request::builder().method(method).uri(uri);
+    if let Some(key) = key {
+        builder = builder.header("authorization", format!("Bearer {}", key));
+    }
+    let request = match body {
+        Some(body) => builder
+            .header("content-type", "application/json")
+            .body(Body::from(body.to_string())),
+        None => builder.body(Body::empty()),
+    }
+    .unwrap();
+
+    let response = app.clone().oneshot(request).await.unwrap();
+    let status = response.status();
+    let bytes = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, serde_json::from_slice(&bytes).unwrap_or(Value::Null))
+}
+
+async fn get(app: &Router, uri: &str) -> (StatusCode, Value) {
+    request(app, Method::GET, uri, None).await
+}
+
+fn decimal(value: &Value) -> Decimal {
+    Decimal::from_str(value.as_str().unwrap()).unwrap()
+}
+
+fn transaction(signature: &str, protocol: &str, tx_type: &str, usd_value: &str, age_hours: i64) -> TransactionRow {
+    TransactionRow {
+        signature: signature.to_string(),
+        wallet: WALLET.to_string(),
+        protocol: protocol.to_string(),
+        tx_type: tx_type.to_string(),
+        token_in: crate::tokens::USDC_MINT.to_string(),
+        token_out: crate::tokens::WSOL_MINT.to_string(),
+        amount_in: usd_value.to_string(),
+        amount_out: "1".to_string(),
+        usd_value: usd_value.to_string(),
+        block_time: (chrono::Utc::now() - chrono::Duration::hours(age_hours)).timestamp_millis(),
+        slot: 1,
+    }
+}
+
+fn summary(total_value_usd: &str) -> WalletSummaryRow {
+    WalletSummaryRow {
+        wallet: WALLET.to_string(),
+        total_value_usd: total_value_usd.to_string(),
+        position_count: 1,
+        risk_score: 40,
+        classification: "trader".to_string(),
+        classification_confidence: "0.8".to_string(),
+        ..WalletSummaryRow::default()
+    }
+}
+
+#[tokio::test]
+async fn test_health_reports_store() {
+    let app = app(Arc::new(MemoryStore::new()));
+    let (status, body) = get(&app, "/health").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["database"], "connected");
+}
+
+#[tokio::test]
+async fn test_summary_and_pnl_from_store() {
+    let store = Arc::new(MemoryStore::new());
+    let app = app(store.clone());
+
+    // Unknown wallets get an empty placeholder
+    let (status, body) = get(&app, &format!("/api/v1/user/{}/summary", WALLET)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(decimal(&body["total_value_usd"]), Decimal::ZERO);
+
+    let asset_exposure = json!({ crate::tokens::WSOL_MINT: "1800.5", crate::tokens::USDC_MINT: "699.5" }).to_string();
+    store.upsert_wallet_summary(&WalletSummaryRow { asset_exposure, ..summary("2500") }).await.unwrap();
+    for row in [
+        transaction("sig-1", "kamino", "deposit", "1000", 72),
+        transaction("sig-2", "kamino", "withdraw", "1040", 2),
+        transaction("sig-3", "jupiter", "swap", "300", 1),
+    ] {
+        store.write_transaction(row).await.unwrap();
+    }
+
+    let (status, body) = get(&app, &format!("/api/v1/user/{}/summary", WALLET)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(decimal(&body["total_value_usd"]), Decimal::from(2500));
+    // Realized windows and protocols come from the live activity
+    assert_eq!(decimal(&body["pnl"]["realized_24h"]), Decimal::from(1340));
+    assert_eq!(body["protocols"], json!(["jupiter", "kamino"]));
+    assert_eq!(decimal(&body["risk"]["asset_exposure"][crate::tokens::WSOL_MINT]), Decimal::from_str("1800.5").unwrap());
+    // No exposure has price history, so VaR is unknown rather than zero
+    assert!(body["risk"]["var"]["var_95"].is_null());
+    assert_eq!(decimal(&body["risk"]["var"]["coverage"]), Decimal::ZERO);
+
+    let (status, body) = get(&app, &format!("/api/v1/user/{}/pnl?window=24h", WALLET)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(decimal(&body["total_realized"]), Decimal::from(1340));
+
+    let (status, body) = get(&app, &format!("/api/v1/user/{}/pnl?window=7d", WALLET)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(decimal(&body["total_realized"]), Decimal::from(340));
+    assert_eq!(body["by_protocol"][0]["protocol"], "jupiter");
+    assert_eq!(body["by_protocol"][1]["trade_count"], 2);
+
+    let (status, _) = get(&app, &format!("/api/v1/user/{}/pnl?window=1y", WALLET)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_positions_and_transactions_from_store() {
+    let store = Arc::new(MemoryStore::new());
+    let app = app(store.clone());
+
+    for (token, usd_value) in [("SOL", "150"), ("USDC", "900")] {
+        store
+            .upsert_position(&PositionRow {
+                wallet: WALLET.to_string(),
+                protocol: "kamino".to_string(),
+                position_type: "lending".to_string(),
+                token: token.to_string(),
+                pool: String::new(),
+                amount: "1".to_string(),
+                entry_price: "0".to_string(),
+                current_price: "0".to_string(),
+                usd_value: usd_value.to_string(),
+                unrealized_pnl: "0".to_string(),
+                apy: "0".to_string(),
+            })
+            .await
+            .unwrap();
+    }
+
+    let (status, body) = get(&app, &format!("/api/v1/user/{}/positions", WALLET)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(decimal(&body["total_value_usd"]), Decimal::from(1050));
+    assert_eq!(body["positions"][0]["token"], "USDC");
+
+    store.write_transaction(transaction("sig-1", "jupiter", "swap", "100", 5)).await.unwrap();
+    let (status, body) = get(&app, &format!("/api/v1/user/{}/performance?window=all", WALLET)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["wallet"], WALLET);
+
+    let (status, body) = get(&app, "/api/v1/user/not-a-wallet/positions").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert_eq!(body["code"], "INVALID_WALLET");
+}
+
+#[tokio::test]
+async fn test_subscription_lifecycle() {
+    let store = Arc::new(MemoryStore::new());
+    let app = app(store.clone());
+
+    let (status, body) = request(&app, Method::POST, "/api/v1/index", Some(json!({ "wallet": WALLET }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "started");
+    assert_eq!(store.subscribed_wallets().await.unwrap(), vec![WALLET]);
+
+    let (_, body) = request(&app, Method::POST, "/api/v1/index", Some(json!({ "wallet": WALLET }))).await;
+    assert_eq!(body["status"], "already_running");
+
+    let (status, body) = get(&app, "/api/v1/index").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["subscriptions"][0]["wallet"], WALLET);
+
+    let (status, body) = request(&app, Method::DELETE, &format!("/api/v1/index/{}", WALLET), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["status"], "stopped");
+    assert!(store.subscribed_wallets().await.unwrap().is_empty());
+
+    let (_, body) = request(&app, Method::DELETE, &format!("/api/v1/index/{}", WALLET), None).await;
+    assert_eq!(body["status"], "not_running");
+}
+
+#[tokio::test]
+async fn test_transaction_history_pages_and_filters() {
+    let store = Arc::new(MemoryStore::new());
+    let app = app(store.clone());
+
+    for (signature, protocol, tx_type, usd_value, age_hours) in [
+        ("sig-a", "jupiter", "swap", "50", 5),
+        ("sig-b", "jupiter", "swap", "700", 4),
+        ("sig-c", "kamino", "deposit", "1000", 3),
+        ("sig-d", "jupiter", "swap", "20", 2),
+        ("sig-e", "raydium", "swap", "300", 1),
+    ] {
+        store.write_transaction(transaction(signature, protocol, tx_type, usd_value, age_hours)).await.unwrap();
+    }
+    let uri = |params: &str| format!("/api/v1/user/{}/transactions?{}", WALLET, params);
+    let signatures = |body: &Value| -> Vec<String> {
+        body["transactions"].as_array().unwrap().iter().map(|t| t["signature"].as_str().unwrap().to_string()).collect()
+    };
+
+    // Newest first, two pages of two and a last page of one
+    let (status, body) = get(&app, &uri("limit=2")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(signatures(&body), vec!["sig-e", "sig-d"]);
+    assert_eq!(body["transactions"][0]["token_in"]["mint"], crate::tokens::USDC_MINT);
+    assert_eq!(decimal(&body["transactions"][0]["token_out"]["amount"]), Decimal::ONE);
+
+    let mut seen = signatures(&body);
+    let mut cursor = body["next_cursor"].as_str().unwrap().to_string();
+    loop {
+        let (_, page) = get(&app, &uri(&format!("limit=2&cursor={}", cursor))).await;
+        seen.extend(signatures(&page));
+        match page["next_cursor"].as_str() {
+            Some(next) => cursor = next.to_string(),
+            None => break,
+        }
+    }
+    assert_eq!(seen, vec!["sig-e", "sig-d", "sig-c", "sig-b", "sig-a"]);
+
+    let (_, body) = get(&app, &uri("protocol=jupiter&sort=oldest")).await;
+    assert_eq!(signatures(&body), vec!["sig-a", "sig-b", "sig-d"]);
+    assert!(body["next_cursor"].is_null());
+
+    let (_, body) = get(&app, &uri("tx_type=swap&min_usd_value=100&sort=largest")).await;
+    assert_eq!(signatures(&body), vec!["sig-b", "sig-e"]);
+
+    let (_, first) = get(&app, &uri("sort=largest&limit=1")).await;
+    let cursor = first["next_cursor"].as_str().unwrap();
+    let (_, second) = get(&app, &uri(&format!("sort=largest&limit=1&cursor={}", cursor))).await;
+    assert_eq!(signatures(&first), vec!["sig-c"]);
+    assert_eq!(signatures(&second), vec!["sig-b"]);
+
+    let (status, _) = get(&app, &uri("sort=sideways")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, &uri("from=yesterday")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = get(&app, &uri("limit=0")).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_tax_lots_reject_out_of_range_years() {
+    let app = app(Arc::new(MemoryStore::new()));
+    let uri = |year: &str| format!("/api/v1/user/{}/tax-lots?year={}", WALLET, year);
+
+    let (status, body) = get(&app, &uri("2024")).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["tax_year"], 2024);
+
+    let next_year = chrono::Datelike::year(&chrono::Utc::now()) + 1;
+    for year in ["1969", "2147483647", &next_year.to_string()] {
+        let (status, _) = get(&app, &uri(year)).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "year {}", year);
+    }
+}
+
+#[tokio::test]
+async fn test_wallet_stream_pushes_updates() {
+    let store = Arc::new(MemoryStore::new());
+    let indexer = Indexer::offline(store.clone());
+    let app = app_with_indexer(store, indexer.clone());
+
+    let request = Request::builder().uri(format!("/api/v1/user/{}/stream", WALLET)).body(Body::empty()).unwrap();
+    let response = app.clone().oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(response.headers()["content-type"], "text/event-stream");
+    let mut body = response.into_body().into_data_stream();
+    let mut next_event = async || {
+        let frame = tokio::time::timeout(std::time::Duration::from_secs(5), body.next()).await.unwrap();
+        String::from_utf8(frame.unwrap().unwrap().to_vec()).unwrap()
+    };
+
+    // Other wallets' updates are filtered out
+    let mut other = transaction("sig-other", "jupiter", "swap", "5", 0);
+    other.wallet = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM".to_string();
+    indexer.publish_update(WalletUpdate::Transaction(Box::new(other)));
+    indexer.publish_update(WalletUpdate::Transaction(Box::new(transaction("sig-mine", "jupiter", "swap", "250", 0))));
+    indexer.publish_update(WalletUpdate::Summary(Box::new(summary("1200"))));
+
+    let event = next_event().await;
+    assert!(event.starts_with("event: transaction\n"), "{event}");
+    let data: Value = serde_json::from_str(event.lines().nth(1).unwrap().strip_prefix("data: ").unwrap()).unwrap();
+    assert_eq!(data["wallet"], WALLET);
+    assert_eq!(data["signature"], "sig-mine");
+    assert_eq!(decimal(&data["usd_value"]), Decimal::from(250));
+
+    let event = next_event().await;
+    assert!(event.starts_with("event: summary\n"), "{event}");
+    let data: Value = serde_json::from_str(event.lines().nth(1).unwrap().strip_prefix("data: ").unwrap()).unwrap();
+    assert_eq!(decimal(&data["total_value_usd"]), Decimal::from(1200));
+
+    let (status, _) = get(&app, "/api/v1/user/not-a-wallet/stream").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_streams_end_on_shutdown() {
+    let store = Arc::new(MemoryStore::new());
+    let shutdown = CancellationToken::new();
+    let auth = AuthConfig {
+        enabled: false,
+        ..AuthConfig::default()
+    };
+    let app = build_app(store.clone(), Indexer::offline(store), auth, shutdown.clone());
+
+    let mut bodies = Vec::new();
+    for uri in ["/api/v1/stream".to_string(), format!("/api/v1/user/{}/stream", WALLET), "/api/v1/signals/stream".to_string()] {
+        let response = app.clone().oneshot(Request::builder().uri(uri).body(Body::empty()).unwrap()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        bodies.push(response.into_body());
+    }
+
+    // Open streams would otherwise keep graceful shutdown waiting forever
+    shutdown.cancel();
+    for body in bodies {
+        let drained = tokio::time::timeout(std::time::Duration::from_secs(5), to_bytes(body, usize::MAX)).await;
+        assert!(drained.expect("stream did not end").is_ok());
+    }
+}
+
+#[tokio::test]
+async fn test_webhook_lifecycle() {
+    let app = app(Arc::new(MemoryStore::new()));
+
+    let (status, created) = request(
+        &app,
+        Method::POST,
+        "/api/v1/webhooks",
+        Some(json!({
+            "url": "https://hooks.example.com/cortex",
+            "wallet": WALLET,
+            "event_types": ["large_trade", "risk_threshold"],
+            "min_usd_value": 5000
+        })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["id"].as_str().unwrap().to_string();
+    assert!(created["secret"].as_str().unwrap().starts_with("whsec_"));
+    assert_eq!(created["event_types"], json!(["large_trade", "risk_threshold"]));
+    assert_eq!(decimal(&created["min_usd_value"]), Decimal::from(5000));
+    assert_eq!(created["risk_threshold"], 70);
+    assert_eq!(created["active"], true);
+
+    // The secret is only shown once
+    let (status, listed) = get(&app, "/api/v1/webhooks").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed["webhooks"][0]["id"], id.as_str());
+    assert!(listed["webhooks"][0]["secret"].is_null());
+
+    let (status, deliveries) = get(&app, &format!("/api/v1/webhooks/{}/deliveries", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(deliveries["deliveries"], json!([]));
+
+    let (status, enabled) = request(&app, Method::POST, &format!("/api/v1/webhooks/{}/enable", id), None).await;
+    assert_eq!((status, enabled["active"].clone()), (StatusCode::OK, json!(true)));
+
+    let invalid = [
+        json!({"url": "ftp://hooks.example.com", "event_types": ["transaction"]}),
+        json!({"url": "https://hooks.example.com", "event_types": []}),
+        json!({"url": "https://hooks.example.com", "event_types": ["everything"]}),
+        json!({"url": "https://hooks.example.com", "event_types": ["transaction"], "wallet": "nope"}),
+        json!({"url": "https://hooks.example.com", "event_types": ["risk_threshold"], "risk_threshold": 0}),
+    ];
+    for body in invalid {
+        let (status, _) = request(&app, Method::POST, "/api/v1/webhooks", Some(body.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    }
+
+    let (status, _) = request(&app, Method::DELETE, &format!("/api/v1/webhooks/{}", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = get(&app, &format!("/api/v1/webhooks/{}", id)).await;
+    assert_eq!((status, body["code"].clone()), (StatusCode::NOT_FOUND, json!("WEBHOOK_NOT_FOUND")));
+    let (status, _) = request(&app, Method::DELETE, &format!("/api/v1/webhooks/{}", id), None).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn test_alert_rule_lifecycle() {
+    let app = app(Arc::new(MemoryStore::new()));
+
+    let (status, created) = request(
+        &app,
+        Method::POST,
+        "/api/v1/alerts/rules",
+        Some(json!({"name": "Kamino near liquidation", "condition": "Kamino LTV > 0.85", "wallets": [WALLET]})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["id"].as_str().unwrap().to_string();
+    // Stored in canonical form
+    assert_eq!(created["condition"], "kamino.ltv > 0.85");
+    assert_eq!(created["wallets"], json!([WALLET]));
+
+    let (status, listed) = get(&app, "/api/v1/alerts/rules").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(listed["rules"][0]["id"], id.as_str());
+
+    let (status, alerts) = get(&app, "/api/v1/alerts?state=firing").await;
+    assert_eq!((status, alerts["alerts"].clone()), (StatusCode::OK, json!([])));
+    let (status, _) = get(&app, "/api/v1/alerts?state=loud").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let invalid = [
+        json!({"name": "", "condition": "risk_score > 70"}),
+        json!({"name": "x", "condition": "risk_score is high"}),
+        json!({"name": "x", "condition": "vibes > 3"}),
+        json!({"name": "x", "condition": "risk_score > 70", "wallets": ["nope"]}),
+    ];
+    for body in invalid {
+        let (status, _) = request(&app, Method::POST, "/api/v1/alerts/rules", Some(body.clone())).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "{body}");
+    }
+
+    // Scoped to a group, which has to exist
+    let rule = json!({"name": "Funds at risk", "condition": "risk_score > 70", "group_id": "no-such-group"});
+    let (status, body) = request(&app, Method::POST, "/api/v1/alerts/rules", Some(rule)).await;
+    assert_eq!((status, body["code"].clone()), (StatusCode::NOT_FOUND, json!("GROUP_NOT_FOUND")));
+    let (_, group) = request(&app, Method::POST, "/api/v1/groups", Some(json!({"name": "funds", "wallets": [WALLET]}))).await;
+    let rule = json!({"name": "Funds at risk", "condition": "risk_score > 70", "group_id": group["id"]});
+    let (status, created) = request(&app, Method::POST, "/api/v1/alerts/rules", Some(rule)).await;
+    assert_eq!(status, StatusCode::CREATED);
+    assert_eq!((created["group_id"].clone(), created["wallets"].clone()), (group["id"].clone(), json!([])));
+
+    let (status, _) = request(&app, Method::DELETE, &format!("/api/v1/alerts/rules/{}", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = get(&app, &format!("/api/v1/alerts/rules/{}", id)).await;
+    assert_eq!((status, body["code"].clone()), (StatusCode::NOT_FOUND, json!("ALERT_RULE_NOT_FOUND")));
+}
+
+#[tokio::test]
+async fn test_api_keys_scopes_and_quotas() {
+    let app = app_with_auth(Arc::new(MemoryStore::new()));
+    let alerts = "/api/v1/alerts";
+
+    // Health stays open; everything else needs a key
+    let (status, _) = get(&app, "/health").await;
+    assert_ne!(status, StatusCode::UNAUTHORIZED);
+    let (status, body) = get(&app, alerts).await;
+    assert_eq!((status, body["code"].clone()), (StatusCode::UNAUTHORIZED, json!("UNAUTHORIZED")));
+    let (status, _) = request_with_key(&app, Method::GET, alerts, Some("ck_guess"), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let (status, created) = request_with_key(
+        &app,
+        Method::POST,
+        "/api/v1/keys",
+        Some("admin"),
+        Some(json!({"name": "dashboard", "scopes": ["read", "index"]})),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let key = created["key"].as_str().unwrap().to_string();
+    let id = created["id"].as_str().unwrap().to_string();
+    assert_eq!(created["max_subscriptions"], 1);
+
+    // The key reads but can't administer
+    let (status, _) = request_with_key(&app, Method::GET, alerts, Some(&key), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = request_with_key(&app, Method::GET, "/api/v1/webhooks", Some(&key), None).await;
+    assert_eq!((status, body["code"].clone()), (StatusCode::FORBIDDEN, json!("FORBIDDEN")));
+    let (status, listed) = request_with_key(&app, Method::GET, "/api/v1/keys", Some("admin"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(listed["keys"][0]["key"].is_null());
+
+    // Accepted as a query parameter only on stream routes, for EventSource clients
+    let (status, _) = get(&app, &format!("{}?state=firing&api_key={}", alerts, key)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+    let (status, _) = get(&app, &format!("/api/v1/user/not-a-wallet/stream?api_key={}", key)).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    // One subscription allowed; re-requesting a running one doesn't count twice
+    let index = |wallet: &str| json!({ "wallet": wallet });
+    let (status, _) = request_with_key(&app, Method::POST, "/api/v1/index", Some(&key), Some(index(WALLET))).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, body) = request_with_key(&app, Method::POST, "/api/v1/index", Some(&key), Some(index(WALLET))).await;
+    assert_eq!((status, body["status"].clone()), (StatusCode::OK, json!("already_running")));
+    let other = "9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM";
+    let (status, body) = request_with_key(&app, Method::POST, "/api/v1/index", Some(&key), Some(index(other))).await;
+    assert_eq!((status, body["code"].clone()), (StatusCode::FORBIDDEN, json!("QUOTA_EXCEEDED")));
+
+    // Stopping frees the slot
+    let (status, _) = request_with_key(&app, Method::DELETE, &format!("/api/v1/index/{}", WALLET), Some(&key), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = request_with_key(&app, Method::POST, "/api/v1/index", Some(&key), Some(index(other))).await;
+    assert_eq!(status, StatusCode::OK);
+    request_with_key(&app, Method::DELETE, &format!("/api/v1/index/{}", other), Some(&key), None).await;
+
+    let (status, _) = request_with_key(&app, Method::DELETE, &format!("/api/v1/keys/{}", id), Some("admin"), None).await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = request_with_key(&app, Method::GET, alerts, Some(&key), None).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+}
+
+#[tokio::test]
+async fn test_rate_limited_key_gets_retry_after() {
+    let app = app_with_auth(Arc::new(MemoryStore::new()));
+    let (_, created) = request_with_key(
+        &app,
+        Method::POST,
+        "/api/v1/keys",
+        Some("admin"),
+        Some(json!({"name": "tight", "scopes": ["read"], "rate_limit_per_minute": 1})),
+    )
+    .await;
+    let key = created["key"].as_str().unwrap();
+
+    let send = || {
+        let request = Request::builder()
+            .uri("/api/v1/alerts")
+            .header("x-api-key", key)
+            .body(Body::empty())
+            .unwrap();
+        app.clone().oneshot(request)
+    };
+    assert_eq!(send().await.unwrap().status(), StatusCode::OK);
+    let limited = send().await.unwrap();
+    assert_eq!(limited.status(), StatusCode::TOO_MANY_REQUESTS);
+    let retry_after: u64 = limited.headers()["retry-after"].to_str().unwrap().parse().unwrap();
+    assert!((1..=60).contains(&retry_after));
+}
+
+#[tokio::test]
+async fn test_graphql_wallet_with_predictions() {
+    let store = Arc::new(MemoryStore::new());
+    let app = app(store.clone());
+
+    store.upsert_wallet_summary(&summary("2500")).await.unwrap();
+    store.write_transaction(transaction("sig-1", "jupiter", "swap", "300", 1)).await.unwrap();
+    store
+        .add_market(MarketRow {
+            slug: "sol-above-200".to_string(),
+            platform: "polymarket".to_string(),
+            title: "Will Solana reach $200 by June?".to_string(),
+            description: String::new(),
+            category: "crypto".to_string(),
+            status: "active".to_string(),
+            outcome_labels: vec!["Yes".to_string(), "No".to_string()],
+            resolved_outcome: String::new(),
+            end_date: 0,
+        })
+        .await;
+    store
+        .add_prediction_position(PredictionPositionRow {
+            trader: WALLET.to_string(),
+            slug: "sol-above-200".to_string(),
+            platform: "polymarket".to_string(),
+            title: "Will Solana reach $200 by June?".to_string(),
+            category: "crypto".to_string(),
+            status: "active".to_string(),
+            outcome: "Yes".to_string(),
+            shares: "1000".to_string(),
+            cost_usd: "400".to_string(),
+            avg_price: "0.4".to_string(),
+            current_price: "0.55".to_string(),
+            first_trade_at: 1_700_000_000_000,
+            end_date: 0,
+        })
+        .await;
+
+    let query = r#"query Wallet($address: String!) {
+        wallet(address: $address) {
+            indexed totalValueUsd classification
+            pnl(window: "24h") { totalRealized byProtocol { protocol } }
+            transactions(first: 5) { signature }
+            predictions { outcome valueUsd market { title outcomes } }
+        }
+    }"#;
+    let (status, body) = request(
+        &app,
+        Method::POST,
+        "/api/v1/graphql",
+        Some(json!({ "query": query, "variables": { "address": WALLET } })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body.get("errors").is_none(), "{}", body);
+    let wallet = &body["data"]["wallet"];
+    assert_eq!(wallet["indexed"], true);
+    assert_eq!(decimal(&wallet["totalValueUsd"]), Decimal::from(2500));
+    assert_eq!(wallet["classification"], "trader");
+    assert_eq!(decimal(&wallet["pnl"]["totalRealized"]), Decimal::from(300));
+    assert_eq!(wallet["transactions"][0]["signature"], "sig-1");
+    assert_eq!(decimal(&wallet["predictions"][0]["valueUsd"]), Decimal::from(550));
+    assert_eq!(wallet["predictions"][0]["market"]["outcomes"], json!(["Yes", "No"]));
+
+    // Bad addresses are field errors, not a failed request
+    let (status, body) = request(
+        &app,
+        Method::POST,
+        "/api/v1/graphql",
+        Some(json!({ "query": "{ wallets(addresses: [\"not-a-wallet\"]) { address } }" })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(body["errors"][0]["message"].as_str().unwrap().contains("not-a-wallet"));
+}
+
+#[tokio::test]
+async fn test_batch_wallets_and_portfolio() {
+    const OTHER: &str = "95n9a8yd6aZzKGMtbWSjqbijZ1u99z1GQF79HkbCvtwN";
+    let store = Arc::new(MemoryStore::new());
+    let app = app(store.clone());
+
+    store.upsert_wallet_summary(&summary("2500")).await.unwrap();
+    store
+        .upsert_wallet_summary(&WalletSummaryRow { wallet: OTHER.to_string(), risk_score: 80, ..summary("500") })
+        .await
+        .unwrap();
+    for (wallet, usd_value) in [(WALLET, "900"), (OTHER, "300")] {
+        store
+            .upsert_position(&PositionRow {
+                wallet: wallet.to_string(),
+                protocol: "kamino".to_string(),
+                position_type: "lending".to_string(),
+                token: "USDC".to_string(),
+                pool: String::new(),
+                amount: usd_value.to_string(),
+                entry_price: "1".to_string(),
+                current_price: "1".to_string(),
+                usd_value: usd_value.to_string(),
+                unrealized_pnl: "0".to_string(),
+                apy: "0.08".to_string(),
+            })
+            .await
+            .unwrap();
+    }
+    store.write_transaction(transaction("sig-1", "jupiter", "swap", "300", 1)).await.unwrap();
+
+    let wallets = json!([WALLET, OTHER, WALLET, "not-a-wallet"]);
+    let (status, body) = request(&app, Method::POST, "/api/v1/wallets/summary", Some(json!({ "wallets": wallets }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(decimal(&body["results"][WALLET]["total_value_usd"]), Decimal::from(2500));
+    assert_eq!(body["results"][OTHER]["risk"]["score"], 80);
+    assert_eq!(body["errors"]["not-a-wallet"]["code"], "INVALID_WALLET");
+
+    let (status, body) = request(&app, Method::POST, "/api/v1/wallets/positions", Some(json!({ "wallets": [WALLET, OTHER] }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(decimal(&body["results"][OTHER]["total_value_usd"]), Decimal::from(300));
+
+    let (status, body) = request(&app, Method::POST, "/api/v1/wallets/pnl", Some(json!({ "wallets": [WALLET, OTHER], "window": "24h" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(decimal(&body["results"][WALLET]["total_realized"]), Decimal::from(300));
+    assert_eq!(body["results"][OTHER]["by_protocol"], json!([]));
+
+    let (status, body) = request(&app, Method::POST, "/api/v1/wallets/portfolio", Some(json!({ "wallets": wallets, "window": "24h" }))).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["wallets"], json!([WALLET, OTHER]));
+    assert_eq!(decimal(&body["total_value_usd"]), Decimal::from(3000));
+    // Scores are weighted by value: (40 * 2500 + 80 * 500) / 3000
+    assert_eq!(body["risk"]["score"], 47);
+    assert_eq!(body["positions"].as_array().unwrap().len(), 1);
+    assert_eq!(decimal(&body["positions"][0]["usd_value"]), Decimal::from(1200));
+    assert_eq!(decimal(&body["positions"][0]["apy"]), Decimal::from_str("0.08").unwrap());
+    assert_eq!(decimal(&body["by_protocol"][0]["realized"]), Decimal::from(300));
+    assert_eq!(body["errors"]["not-a-wallet"]["code"], "INVALID_WALLET");
+
+    // Unindexed wallets are errors for summaries, and batches must be 1..=200 wallets
+    const UNKNOWN: &str = "So11111111111111111111111111111111111111112";
+    let (_, body) = request(&app, Method::POST, "/api/v1/wallets/summary", Some(json!({ "wallets": [UNKNOWN] }))).await;
+    assert_eq!(body["errors"][UNKNOWN]["code"], "WALLET_NOT_FOUND");
+    let (status, _) = request(&app, Method::POST, "/api/v1/wallets/summary", Some(json!({ "wallets": [] }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    let (status, _) = request(&app, Method::POST, "/api/v1/wallets/pnl", Some(json!({ "wallets": [WALLET], "window": "1y" }))).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_signals_leaderboard_and_token_flows() {
+    const OTHER: &str = "95n9a8yd6aZzKGMtbWSjqbijZ1u99z1GQF79HkbCvtwN";
+    let store = Arc::new(MemoryStore::new());
+    let app = app(store.clone());
+    let hours_ago = |hours: i64| (chrono::Utc::now() - chrono::Duration::hours(hours)).timestamp_millis();
+
+    // Signals page newest first, with a cursor to the rest
+    for (wallet, signature, age_hours) in [(WALLET, "sig-a", 3), (WALLET, "sig-b", 2), (WALLET, "sig-c", 1), (OTHER, "sig-d", 0)] {
+        let signal = PositionSignalRow {
+            wallet: wallet.to_string(),
+            signature: signature.to_string(),
+            token: crate::tokens::WSOL_MINT.to_string(),
+            kind: "opened".to_string(),
+            amount: "1".to_string(),
+            price: "150".to_string(),
+            usd_value: "150".to_string(),
+            position_size: "1".to_string(),
+            portfolio_weight: "0.1".to_string(),
+            slot: 1,
+            block_time: hours_ago(age_hours),
+        };
+        store.insert_position_signal(&signal).await.unwrap();
+    }
+    let (status, body) = get(&app, &format!("/api/v1/signals?wallet={}&limit=2", WALLET)).await;
+    assert_eq!(status, StatusCode::OK);
+    let signatures: Vec<&str> = body["signals"].as_array().unwrap().iter().map(|s| s["signature"].as_str().unwrap()).collect();
+    assert_eq!(signatures, vec!["sig-c", "sig-b"]);
+    let cursor = body["next_cursor"].as_str().unwrap().to_string();
+    let (status, body) = get(&app, &format!("/api/v1/signals?wallet={}&limit=2&cursor={}", WALLET, cursor)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["signals"][0]["signature"], "sig-a");
+    assert!(body["next_cursor"].is_null());
+
+    // Ranked by realized PnL of closed trades, joined to current value
+    store.upsert_wallet_summary(&summary("1200")).await.unwrap();
+    store
+        .upsert_wallet_summary(&WalletSummaryRow { wallet: OTHER.to_string(), ..summary("50") })
+        .await
+        .unwrap();
+    let trade = |wallet: &str, signature: &str, cost_basis: &str, pnl: &str| ClosedTradeRow {
+        wallet: wallet.to_string(),
+        signature: signature.to_string(),
+        protocol: "jupiter".to_string(),
+        token: crate::tokens::WSOL_MINT.to_string(),
+        cost_basis: cost_basis.to_string(),
+        pnl: pnl.to_string(),
+        block_time: hours_ago(1),
+    };
+    store
+        .insert_closed_trades(&[trade(WALLET, "t-1", "1000", "100"), trade(WALLET, "t-2", "200", "-20"), trade(OTHER, "t-3", "1000", "300")])
+        .await
+        .unwrap();
+    let (status, body) = get(&app, "/api/v1/leaderboard?window=7d").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["entries"][0]["wallet"], OTHER);
+    assert_eq!(body["entries"][1]["rank"], 2);
+    assert_eq!(body["entries"][1]["trade_count"], 2);
+    assert_eq!(decimal(&body["entries"][1]["realized_pnl"]), Decimal::from(80));
+    assert_eq!(decimal(&body["entries"][1]["win_rate"]), Decimal::from_str("0.5").unwrap());
+    let (status, body) = get(&app, "/api/v1/leaderboard?window=7d&min_value=100").await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["entries"].as_array().unwrap().len(), 1);
+    assert_eq!(body["entries"][0]["wallet"], WALLET);
+
+    // One wallet buys the token, the other sells it; both are top performers
+    store.write_transaction(transaction("sig-buy", "jupiter", "swap", "150", 1)).await.unwrap();
+    let sell = TransactionRow {
+        wallet: OTHER.to_string(),
+        token_in: crate::tokens::WSOL_MINT.to_string(),
+        token_out: crate::tokens::USDC_MINT.to_string(),
+        amount_in: "3".to_string(),
+        amount_out: "450".to_string(),
+        ..transaction("sig-sell", "jupiter", "swap", "450", 1)
+    };
+    store.write_transaction(sell).await.unwrap();
+    let (status, body) = get(&app, &format!("/api/v1/token/{}/flows?window=24h", crate::tokens::WSOL_MINT)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(decimal(&body["buy_volume"]), Decimal::from(1));
+    assert_eq!(decimal(&body["sell_volume"]), Decimal::from(3));
+    assert_eq!(decimal(&body["net_volume"]), Decimal::from(-2));
+    assert_eq!((body["unique_buyers"].clone(), body["unique_sellers"].clone()), (json!(1), json!(1)));
+    assert_eq!(body["smart_money"]["wallet_count"], 2);
+    assert!(body["price_usd"].is_null());
+    let (status, _) = get(&app, "/api/v1/token/not-a-mint/flows").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
+
+#[tokio::test]
+async fn test_wallet_group_lifecycle() {
+    const OTHER: &str = "95n9a8yd6aZzKGMtbWSjqbijZ1u99z1GQF79HkbCvtwN";
+    let store = Arc::new(MemoryStore::new());
+    let app = app(store.clone());
+
+    store.upsert_wallet_summary(&summary("2500")).await.unwrap();
+
+    let (status, created) = request(
+        &app,
+        Method::POST,
+        "/api/v1/groups",
+        Some(json!({ "name": " Funds ", "wallets": [WALLET, WALLET], "tags": ["Fund", "fund", "tier-1"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::CREATED);
+    let id = created["id"].as_str().unwrap().to_string();
+    assert_eq!(created["name"], "Funds");
+    assert_eq!(created["wallets"], json!([WALLET]));
+    assert_eq!(created["tags"], json!(["fund", "tier-1"]));
+
+    let (status, body) = request(&app, Method::POST, "/api/v1/groups", Some(json!({ "name": "bad", "wallets": ["nope"] }))).await;
+    assert_eq!((status, body["code"].clone()), (StatusCode::BAD_REQUEST, json!("INVALID_WALLET")));
+
+    let (status, body) = request(
+        &app,
+        Method::PUT,
+        &format!("/api/v1/groups/{}", id),
+        Some(json!({ "name": "Funds", "wallets": [WALLET, OTHER], "tags": ["fund"] })),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["wallets"].as_array().unwrap().len(), 2);
+
+    let (_, body) = get(&app, "/api/v1/groups?tag=FUND").await;
+    assert_eq!(body["groups"][0]["id"], id.as_str());
+    let (_, body) = get(&app, "/api/v1/groups?tag=treasury").await;
+    assert_eq!(body["groups"], json!([]));
+
+    // Every member is subscribed at once
+    let (status, body) = request(&app, Method::POST, &format!("/api/v1/groups/{}/index", id), None).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!((body["started"].clone(), body["errors"].clone()), (json!(2), json!(0)));
+    assert_eq!(store.subscribed_wallets().await.unwrap().len(), 2);
+    let (_, body) = request(&app, Method::POST, &format!("/api/v1/groups/{}/index", id), None).await;
+    assert_eq!(body["already_running"], 2);
+
+    let (status, body) = get(&app, &format!("/api/v1/groups/{}/summary?window=24h", id)).await;
+    assert_eq!(status, StatusCode::OK);
+    assert_eq!(body["group"]["name"], "Funds");
+    assert_eq!(body["indexed_count"], 1);
+    assert_eq!(decimal(&body["total_value_usd"]), Decimal::from(2500));
+
+    // Unknown groups are 404 wherever they can be named
+    let (status, body) = get(&app, "/api/v1/leaderboard?group=missing").await;
+    assert_eq!((status, body["code"].clone()), (StatusCode::NOT_FOUND, json!("GROUP_NOT_FOUND")));
+    let (status, _) = get(&app, &format!("/api/v1/token/{}/flows?group=missing", crate::tokens::USDC_MINT)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, body) = request(&app, Method::DELETE, &format!("/api/v1/groups/{}", id), None).await;
+    assert_eq!((status, body["status"].clone()), (StatusCode::OK, json!("deleted")));
+    let (status, _) = get(&app, &format!("/api/v1/groups/{}", id)).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+    // Members keep indexing
+    assert_eq!(store.subscribed_wallets().await.unwrap().len(), 2);
+}
+
+/// Set to regenerate `openapi.json` from the served spec instead of failing on a mismatch
+const UPDATE_OPENAPI_ENV: &str = "UPDATE_OPENAPI";
+
+#[tokio::test]
+async fn test_served_openapi_matches_committed_spec() {
+    let app = app_with_auth(Arc::new(MemoryStore::new()));
+    // Served without a key
+    let (status, served) = get(&app, "/api/v1/openapi.json").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(served["paths"]["/api/v1/groups/{id}/summary"]["get"].is_object());
+    let (status, _) = get(&app, "/api/v1/docs").await;
+    assert_eq!(status, StatusCode::OK);
+
+    let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("../../openapi.json");
+    if std::env::var_os(UPDATE_OPENAPI_ENV).is_some() {
+        std::fs::write(&path, serde_json::to_string_pretty(&served).unwrap() + "\n").unwrap();
+        return;
+    }
+    let committed: Value = serde_json::from_str(&std::fs::read_to_string(&path).unwrap_or_default()).unwrap_or(Value::Null);
+    assert!(
+        served == committed,
+        "openapi.json is out of date with the handlers and DTOs; regenerate it with `{}=1 cargo test -p cortex-server openapi`",
+        UPDATE_OPENAPI_ENV
+    );
+}
+
+/// `paths(...)` in the spec is listed by hand, so check it against the router's routes
+#[test]
+fn test_every_route_is_documented() {
+    let mut routed = std::collections::BTreeSet::new();
+    for line in include_str!("mod.rs").lines() {
+        let Some((path, methods)) = line.trim().strip_prefix(".route(\"").and_then(|rest| rest.split_once('"')) else {
+            continue;
+        };
+        for method in ["get", "post", "put", "delete"] {
+            if methods.contains(&format!("{}(handlers::", method)) {
+                routed.insert((path.to_string(), method.to_string()));
+            }
+        }
+    }
+    assert!(routed.len() > 40, "found only {} routes in api/mod.rs", routed.len());
+
+    let spec = serde_json::to_value(super::openapi::spec()).unwrap();
+    let documented: std::collections::BTreeSet<(String, String)> = spec["paths"]
+        .as_object()
+        .unwrap()
+        .iter()
+        .flat_map(|(path, item)| {
+            item.as_object()
+                .unwrap()
+                .keys()
+                .filter(|method| ["get", "post", "put", "delete"].contains(&method.as_str()))
+                .map(move |method| (path.clone(), method.clone()))
+        })
+        .collect();
+
+    let undocumented: Vec<_> = routed.difference(&documented).collect();
+    assert!(undocumented.is_empty(), "routes missing from the OpenAPI paths: {:?}", undocumented);
+    let unrouted: Vec<_> = documented.difference(&routed).collect();
+    assert!(unrouted.is_empty(), "documented routes the router doesn't serve: {:?}", unrouted);
+}
+
+#[tokio::test]
+async fn test_metrics_time_requests_by_route() {
+    let app = app_with_auth(Arc::new(MemoryStore::new()));
+    let (status, _) = get(&app, "/health").await;
+    assert_eq!(status, StatusCode::OK);
+    let (status, _) = get(&app, &format!("/api/v1/user/{}/summary", WALLET)).await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    // Needs the read scope like the rest of the API
+    let (status, _) = get(&app, "/metrics").await;
+    assert_eq!(status, StatusCode::UNAUTHORIZED);
+
+    let request = Request::builder()
+        .uri("/metrics")
+        .header("authorization", "Bearer admin")
+        .body(Body::empty())
+        .unwrap();
+    let response = app.oneshot(request).await.unwrap();
+    assert_eq!(response.status(), StatusCode::OK);
+    assert!(response.headers()["content-type"].to_str().unwrap().starts_with("text/plain"));
+    let text = String::from_utf8(to_bytes(response.into_body(), usize::MAX).await.unwrap().to_vec()).unwrap();
+
+    assert!(text.contains(r#"cortex_http_request_duration_seconds_count{method="GET",route="/health",status="200"}"#));
+    // Labelled by route template rather than by wallet
+    assert!(text.contains(r#"route="/api/v1/user/{wallet}/summary",status="401""#));
+    assert!(!text.contains(WALLET));
+    assert!(text.contains("cortex_indexer_messages_received_total"));
+}
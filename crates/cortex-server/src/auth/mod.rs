@@ -0,0 +1,501 @@
+//! API keys for the REST API.
+//!
+//! Keys carry [`ApiScope`]s, a per-minute rate limit and a quota of concurrent wallet
+//! subscriptions. Only a key's SHA-256 is stored. [`require_api_key`] is layered over each
+//! group of routes with the scope the group needs; it rejects requests without a valid key
+//! (401), with a key lacking the scope (403) or over the key's rate (429), and otherwise
+//! hands the key to the handler as a request extension.
+
+mod rate_limit;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use async_trait::async_trait;
+use axum::extract::{Request, State};
+use axum::http::{header, Uri};
+use axum::middleware::Next;
+use axum::response::Response;
+use chrono::Utc;
+use sha2::{Digest, Sha256};
+use tokio::sync::RwLock;
+
+use self::rate_limit::TokenBucket;
+use crate::config::AuthConfig;
+use crate::db::models::{ApiKeyRow, KeySubscriptionRow};
+use crate::db::store::CortexStore;
+use crate::error::{AppError, AppResult};
+use crate::indexer::Indexer;
+use crate::registry::{Record, Registry};
+use crate::types::ApiScope;
+use crate::AppState;
+
+/// Header checked for a key besides `Authorization: Bearer`
+pub const API_KEY_HEADER: &str = "x-api-key";
+
+/// Query parameter checked last, for clients that can't set headers (EventSource, browser WebSockets).
+/// Only accepted on the stream routes, and redacted from traced URIs by [`redacted_uri`].
+pub const API_KEY_PARAM: &str = "api_key";
+
+/// Id of the key configured as `auth.admin_key`
+const CONFIG_ADMIN_KEY_ID: &str = "config";
+
+/// Characters of a key kept to tell keys apart
+const KEY_PREFIX_LEN: usize = 10;
+
+#[derive(Clone)]
+pub struct ApiKeys {
+    store: Arc<dyn CortexStore>,
+    config: AuthConfig,
+    /// Live keys by hash
+    keys: Registry<ApiKeyRow>,
+    /// Rate limit state per key id
+    buckets: Arc<Mutex<HashMap<String, TokenBucket>>>,
+    /// Wallets each key id has subscribed
+    subscriptions: Arc<RwLock<HashMap<String, OwnedSubscriptions>>>,
+}
+
+/// Subscriptions started with one key
+#[derive(Default)]
+struct OwnedSubscriptions {
+    /// Every wallet the key started, running or since stopped
+    wallets: BTreeSet<String>,
+    /// Reservations per wallet whose subscription is still starting
+    pending: BTreeMap<String, usize>,
+}
+
+impl OwnedSubscriptions {
+    fn end_reservation(&mut self, wallet: &str) {
+        if let Some(count) = self.pending.get_mut(wallet) {
+            *count -= 1;
+            if *count == 0 {
+                self.pending.remove(wallet);
+            }
+        }
+    }
+}
+
+/// A slot of a key's subscription quota, taken by [`ApiKeys::reserve_subscription`]
+pub struct Reservation {
+    key_id: String,
+    wallet: String,
+    /// Whether the key didn't own the wallet before
+    newly_owned: bool,
+}
+
+impl ApiKeys {
+    pub fn new(store: Arc<dyn CortexStore>, config: &AuthConfig) -> Self {
+        Self {
+            keys: Registry::new(store.clone()),
+            store,
+            config: config.clone(),
+            buckets: Arc::new(Mutex::new(HashMap::new())),
+            subscriptions: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.config.enabled
+    }
+
+    /// Load stored keys and the subscriptions they started. Returns how many keys there are.
+    pub async fn load(&self) -> AppResult<usize> {
+        let count = self.keys.load().await?;
+
+        let mut subscriptions: HashMap<String, OwnedSubscriptions> = HashMap::new();
+        for row in self.store.get_key_subscriptions().await? {
+            subscriptions.entry(row.key_id).or_default().wallets.insert(row.wallet);
+        }
+        *self.subscriptions.write().await = subscriptions;
+
+        println!("[AUTH] Loaded {} API key(s)", count);
+        tracing::info!(count = %count, "Loaded API keys");
+        if self.config.enabled && count == 0 && self.config.admin_key.is_none() {
+            println!("[AUTH] WARNING: No API keys and no admin key configured; every request will be rejected");
+            tracing::warn!("Authentication enabled without any API keys or admin key");
+        }
+        Ok(count)
+    }
+
+    /// Store a new key. Returns its row and the key itself, which isn't kept.
+    pub async fn create(
+        &self,
+        name: String,
+        scopes: &[ApiScope],
+        rate_limit_per_minute: Option<u32>,
+        max_subscriptions: Option<u32>,
+    ) -> AppResult<(ApiKeyRow, String)> {
+        let key = generate_key();
+        let row = ApiKeyRow {
+            id: uuid::Uuid::new_v4().to_string(),
+            name,
+            key_hash: hash_key(&key),
+            key_prefix: key.chars().take(KEY_PREFIX_LEN).collect(),
+            scopes: scopes.iter().map(|s| s.to_string()).collect(),
+            rate_limit_per_minute: rate_limit_per_minute.unwrap_or(self.config.rate_limit_per_minute),
+            max_subscriptions: max_subscriptions.unwrap_or(self.config.max_subscriptions),
+            revoked: 0,
+            created_at: Utc::now().timestamp_millis(),
+        };
+        self.keys.save(row.clone()).await?;
+        println!("[AUTH] Created API key {} ({}) scopes={:?}", row.id, row.key_prefix, row.scopes);
+        tracing::info!(key_id = %row.id, key_prefix = %row.key_prefix, scopes = ?row.scopes, "API key created");
+        Ok((row, key))
+    }
+
+    /// Every stored key, oldest first
+    pub async fn list(&self) -> Vec<ApiKeyRow> {
+        self.keys.list().await
+    }
+
+    pub async fn get(&self, id: &str) -> Option<ApiKeyRow> {
+        self.keys.find(|k| k.id == id).await
+    }
+
+    /// Returns false if there was no such key. Subscriptions it started keep running.
+    pub async fn revoke(&self, id: &str) -> AppResult<bool> {
+        let Some(row) = self.get(id).await else {
+            return Ok(false);
+        };
+        if self.keys.delete(&row.key_hash).await?.is_none() {
+            return Ok(false);
+        }
+        self.buckets.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+        println!("[AUTH] Revoked API key {}", id);
+        tracing::info!(key_id = %id, "API key revoked");
+        Ok(true)
+    }
+
+    /// The key's row, if `key` is live or is the configured admin key
+    pub async fn authenticate(&self, key: &str) -> Option<ApiKeyRow> {
+        let hash = hash_key(key);
+        if let Some(admin_key) = self.config.admin_key.as_deref().filter(|k| !k.is_empty()) {
+            if hash == hash_key(admin_key) {
+                return Some(ApiKeyRow {
+                    id: CONFIG_ADMIN_KEY_ID.to_string(),
+                    name: "admin (config)".to_string(),
+                    key_hash: hash,
+                    key_prefix: key.chars().take(KEY_PREFIX_LEN).collect(),
+                    scopes: vec![ApiScope::Admin.to_string()],
+                    rate_limit_per_minute: 0,
+                    max_subscriptions: 0,
+                    revoked: 0,
+                    created_at: 0,
+                });
+            }
+        }
+        self.keys.get(&hash).await
+    }
+
+    /// Spend one of the key's requests, or fail with how long to wait
+    fn check_rate(&self, key: &ApiKeyRow) -> AppResult<()> {
+        if key.rate_limit_per_minute == 0 {
+            return Ok(());
+        }
+        let now = Instant::now();
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets
+            .entry(key.id.clone())
+            .or_insert_with(|| TokenBucket::per_minute(key.rate_limit_per_minute, now))
+            .take(now)
+            .map_err(|wait| AppError::RateLimited(wait.as_secs_f64().ceil().max(1.0) as u64))
+    }
+
+    /// Reserve a slot of the key's subscription quota for `wallet`, failing if the key
+    /// already has as many subscriptions running or starting as it may. Joining a running
+    /// subscription is free. The check and the reservation happen under one lock, so
+    /// concurrent requests can't both take the last slot; the reservation must be
+    /// confirmed once the subscription starts, or cancelled.
+    pub async fn reserve_subscription(&self, key: &ApiKeyRow, wallet: &str, indexer: &Indexer) -> AppResult<Reservation> {
+        let mut subscriptions = self.subscriptions.write().await;
+        let owned = subscriptions.entry(key.id.clone()).or_default();
+
+        if key.max_subscriptions > 0 && !owned.pending.contains_key(wallet) && !indexer.is_subscribed(wallet).await {
+            let mut running = owned.pending.len();
+            for owned_wallet in owned.wallets.iter().filter(|w| !owned.pending.contains_key(*w)) {
+                if indexer.is_subscribed(owned_wallet).await {
+                    running += 1;
+                }
+            }
+            if running >= key.max_subscriptions as usize {
+                return Err(AppError::QuotaExceeded(format!(
+                    "key {} already has {} of {} wallet subscriptions running",
+                    key.key_prefix, running, key.max_subscriptions
+                )));
+            }
+        }
+
+        let newly_owned = owned.wallets.insert(wallet.to_string());
+        *owned.pending.entry(wallet.to_string()).or_default() += 1;
+        Ok(Reservation {
+            key_id: key.id.clone(),
+            wallet: wallet.to_string(),
+            newly_owned,
+        })
+    }
+
+    /// Count a reserved subscription that started against the key
+    pub async fn confirm_subscription(&self, reservation: Reservation) {
+        if let Some(owned) = self.subscriptions.write().await.get_mut(&reservation.key_id) {
+            owned.end_reservation(&reservation.wallet);
+        }
+        let row = KeySubscriptionRow {
+            key_id: reservation.key_id,
+            wallet: reservation.wallet,
+            active: 1,
+        };
+        if let Err(e) = self.store.set_key_subscription(&row).await {
+            tracing::warn!(key_id = %row.key_id, wallet = %row.wallet, error = %e, "Failed to store key subscription");
+        }
+    }
+
+    /// Give back a reserved slot whose subscription didn't start
+    pub async fn cancel_subscription(&self, reservation: Reservation) {
+        if let Some(owned) = self.subscriptions.write().await.get_mut(&reservation.key_id) {
+            owned.end_reservation(&reservation.wallet);
+            if reservation.newly_owned {
+                owned.wallets.remove(&reservation.wallet);
+            }
+        }
+    }
+
+    /// Stop counting a stopped subscription against whichever key started it
+    pub async fn release_subscription(&self, wallet: &str) {
+        let owners: Vec<String> = {
+            let mut subscriptions = self.subscriptions.write().await;
+            subscriptions
+                .iter_mut()
+                .filter_map(|(id, owned)| owned.wallets.remove(wallet).then(|| id.clone()))
+                .collect()
+        };
+        for key_id in owners {
+            let row = KeySubscriptionRow {
+                key_id,
+                wallet: wallet.to_string(),
+                active: 0,
+            };
+            if let Err(e) = self.store.set_key_subscription(&row).await {
+                tracing::warn!(key_id = %row.key_id, wallet = %wallet, error = %e, "Failed to store key subscription");
+            }
+        }
+    }
+}
+
+/// Keys are found by hash; a revoked key counts as deleted
+#[async_trait]
+impl Record for ApiKeyRow {
+    fn key(&self) -> &str {
+        &self.key_hash
+    }
+
+    fn created_at(&self) -> i64 {
+        self.created_at
+    }
+
+    fn mark_deleted(&mut self) {
+        self.revoked = 1;
+    }
+
+    async fn save(&self, store: &dyn CortexStore) -> AppResult<()> {
+        store.save_api_key(self).await
+    }
+
+    async fn load_all(store: &dyn CortexStore) -> AppResult<Vec<Self>> {
+        store.get_api_keys().await
+    }
+}
+
+/// Whether a key with `scopes` may use routes needing `required`
+pub fn allows(scopes: &[String], required: ApiScope) -> bool {
+    scopes
+        .iter()
+        .filter_map(|s| ApiScope::from_str(s))
+        .any(|scope| scope == required || scope == ApiScope::Admin)
+}
+
+/// Middleware requiring a key with `scope`, layered with `from_fn_with_state((state, scope), ..)`
+pub async fn require_api_key(
+    State((state, scope)): State<(AppState, ApiScope)>,
+    mut request: Request,
+    next: Next,
+) -> Result<Response, AppError> {
+    let api_keys = &state.api_keys;
+    if !api_keys.enabled() {
+        return Ok(next.run(request).await);
+    }
+    let path = request.uri().path().to_string();
+
+    let Some(presented) = presented_key(&request) else {
+        println!("[AUTH] {} {} -> 401 Unauthorized (no API key)", request.method(), path);
+        return Err(AppError::Unauthorized("missing API key".to_string()));
+    };
+    let Some(key) = api_keys.authenticate(&presented).await else {
+        println!("[AUTH] {} {} -> 401 Unauthorized (unknown API key)", request.method(), path);
+        return Err(AppError::Unauthorized("invalid or revoked API key".to_string()));
+    };
+    if !allows(&key.scopes, scope) {
+        println!("[AUTH] {} {} -> 403 Forbidden (key {} lacks {})", request.method(), path, key.key_prefix, scope);
+        return Err(AppError::Forbidden(format!("key {} lacks the {} scope", key.key_prefix, scope)));
+    }
+    if let Err(e) = api_keys.check_rate(&key) {
+        println!("[AUTH] {} {} -> 429 Too Many Requests (key {})", request.method(), path, key.key_prefix);
+        return Err(e);
+    }
+
+    tracing::debug!(key_id = %key.id, scope = %scope, path = %path, "Request authenticated");
+    request.extensions_mut().insert(key);
+    Ok(next.run(request).await)
+}
+
+/// The key from `Authorization: Bearer`, `X-API-Key` or, on stream routes, `?api_key=`, in that order
+fn presented_key(request: &Request) -> Option<String> {
+    let headers = request.headers();
+    let bearer = headers
+        .get(header::AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "));
+    let header_key = headers.get(API_KEY_HEADER).and_then(|v| v.to_str().ok());
+    let param = if is_stream_path(request.uri().path()) {
+        request.uri().query().and_then(|query| {
+            query
+                .split('&')
+                .filter_map(|pair| pair.split_once('='))
+                .find(|(name, _)| *name == API_KEY_PARAM)
+                .map(|(_, value)| value)
+        })
+    } else {
+        None
+    };
+
+    bearer.or(header_key).or(param).map(str::trim).filter(|k| !k.is_empty()).map(String::from)
+}
+
+/// Whether a path is an SSE or WebSocket stream, whose browser clients can't set headers
+fn is_stream_path(path: &str) -> bool {
+    path.ends_with("/stream") || path.ends_with("/stream/ws")
+}
+
+/// The URI with any `api_key` query value replaced, for logging
+pub fn redacted_uri(uri: &Uri) -> String {
+    let Some(query) = uri.query() else {
+        return uri.to_string();
+    };
+    let query: Vec<&str> = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((API_KEY_PARAM, _)) => "api_key=[REDACTED]",
+            _ => pair,
+        })
+        .collect();
+    format!("{}?{}", uri.path(), query.join("&"))
+}
+
+/// Hex SHA-256 of a key
+fn hash_key(key: &str) -> String {
+    Sha256::digest(key.as_bytes()).iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// A new random key
+fn generate_key() -> String {
+    format!("ck_{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::store::MemoryStore;
+
+    fn config() -> AuthConfig {
+        AuthConfig {
+            enabled: true,
+            admin_key: Some("bootstrap-admin".to_string()),
+            rate_limit_per_minute: 600,
+            max_subscriptions: 1,
+        }
+    }
+
+    #[test]
+    fn test_admin_scope_allows_everything() {
+        let scopes = |s: &[&str]| s.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        assert!(allows(&scopes(&["read"]), ApiScope::Read));
+        assert!(!allows(&scopes(&["read"]), ApiScope::Index));
+        assert!(!allows(&scopes(&["index"]), ApiScope::Admin));
+        assert!(allows(&scopes(&["admin"]), ApiScope::Index));
+        assert!(!allows(&[], ApiScope::Read));
+    }
+
+    #[test]
+    fn test_query_keys_are_redacted() {
+        let uri: Uri = "/api/v1/stream?wallet=abc&api_key=ck_secret".parse().unwrap();
+        assert_eq!(redacted_uri(&uri), "/api/v1/stream?wallet=abc&api_key=[REDACTED]");
+        let uri: Uri = "/api/v1/alerts?state=firing".parse().unwrap();
+        assert_eq!(redacted_uri(&uri), "/api/v1/alerts?state=firing");
+        assert!(is_stream_path("/api/v1/user/abc/stream/ws"));
+        assert!(!is_stream_path("/api/v1/alerts"));
+    }
+
+    #[tokio::test]
+    async fn test_keys_are_stored_hashed_and_revocable() {
+        let store = Arc::new(MemoryStore::new());
+        let keys = ApiKeys::new(store.clone(), &config());
+        let (row, key) = keys.create("ci".to_string(), &[ApiScope::Read], None, None).await.unwrap();
+
+        assert!(key.starts_with("ck_"));
+        assert!(key.starts_with(&row.key_prefix));
+        let stored = store.get_api_keys().await.unwrap();
+        assert_eq!(stored[0].key_hash, hash_key(&key));
+        assert!(!stored[0].key_hash.contains(&key));
+        assert_eq!(stored[0].rate_limit_per_minute, 600);
+
+        // A fresh registry finds it from the store
+        let reloaded = ApiKeys::new(store.clone(), &config());
+        assert_eq!(reloaded.load().await.unwrap(), 1);
+        assert_eq!(reloaded.authenticate(&key).await.unwrap().id, row.id);
+        assert!(reloaded.authenticate("ck_guess").await.is_none());
+
+        let admin = reloaded.authenticate("bootstrap-admin").await.unwrap();
+        assert!(allows(&admin.scopes, ApiScope::Admin));
+
+        assert!(reloaded.revoke(&row.id).await.unwrap());
+        assert!(reloaded.authenticate(&key).await.is_none());
+        assert!(store.get_api_keys().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rate_limit_per_key() {
+        let keys = ApiKeys::new(Arc::new(MemoryStore::new()), &config());
+        let (limited, _) = keys.create("limited".to_string(), &[ApiScope::Read], Some(2), None).await.unwrap();
+        let (other, _) = keys.create("other".to_string(), &[ApiScope::Read], Some(2), None).await.unwrap();
+
+        assert!(keys.check_rate(&limited).is_ok());
+        assert!(keys.check_rate(&limited).is_ok());
+        match keys.check_rate(&limited) {
+            Err(AppError::RateLimited(retry_after)) => assert!((1..=30).contains(&retry_after)),
+            other => panic!("expected rate limiting, got {other:?}"),
+        }
+        assert!(keys.check_rate(&other).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_reserved_subscriptions_count_against_quota() {
+        let store = Arc::new(MemoryStore::new());
+        let indexer = Indexer::offline(store.clone());
+        let keys = ApiKeys::new(store, &config());
+        let (key, _) = keys.create("one".to_string(), &[ApiScope::Index], None, None).await.unwrap();
+
+        // A subscription that is still starting holds the only slot
+        let first = keys.reserve_subscription(&key, "wallet-a", &indexer).await.unwrap();
+        let refused = keys.reserve_subscription(&key, "wallet-b", &indexer).await;
+        assert!(matches!(refused, Err(AppError::QuotaExceeded(_))));
+        // The same wallet joins the reservation
+        let joined = keys.reserve_subscription(&key, "wallet-a", &indexer).await.unwrap();
+        keys.cancel_subscription(joined).await;
+        assert!(keys.reserve_subscription(&key, "wallet-b", &indexer).await.is_err());
+
+        // Cancelling gives the slot back
+        keys.cancel_subscription(first).await;
+        let second = keys.reserve_subscription(&key, "wallet-b", &indexer).await.unwrap();
+        keys.confirm_subscription(second).await;
+    }
+}
@@ -0,0 +1,64 @@
+//! Per-key token buckets
+
+use std::time::{Duration, Instant};
+
+/// Holds up to a minute's worth of requests and refills continuously, so a key can burst
+/// to its per-minute limit and then sustain that rate
+#[derive(Debug)]
+pub(super) struct TokenBucket {
+    capacity: f64,
+    tokens: f64,
+    per_second: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    pub fn per_minute(limit: u32, now: Instant) -> Self {
+        let capacity = f64::from(limit.max(1));
+        Self {
+            capacity,
+            tokens: capacity,
+            per_second: capacity / 60.0,
+            updated: now,
+        }
+    }
+
+    /// Take one token, or say how long until one is available
+    pub fn take(&mut self, now: Instant) -> Result<(), Duration> {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.per_second).min(self.capacity);
+        self.updated = now;
+
+        if self.tokens >= 1.0 {
+            self.tokens -= 1.0;
+            Ok(())
+        } else {
+            Err(Duration::from_secs_f64((1.0 - self.tokens) / self.per_second))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bucket_bursts_then_refills() {
+        let start = Instant::now();
+        let mut bucket = TokenBucket::per_minute(3, start);
+        for _ in 0..3 {
+            assert!(bucket.take(start).is_ok());
+        }
+        let wait = bucket.take(start).unwrap_err();
+        assert_eq!(wait.as_secs_f64().round(), 20.0);
+
+        // One token back every 20 seconds, never more than the limit
+        assert!(bucket.take(start + Duration::from_secs(21)).is_ok());
+        assert!(bucket.take(start + Duration::from_secs(21)).is_err());
+        let later = start + Duration::from_secs(3600);
+        for _ in 0..3 {
+            assert!(bucket.take(later).is_ok());
+        }
+        assert!(bucket.take(later).is_err());
+    }
+}
@@ -7,6 +7,10 @@ pub struct AppConfig {
     pub database: DatabaseConfig,
     pub lyslabs: LysLabsConfig,
     pub helius: HeliusConfig,
+    #[serde(default)]
+    pub webhooks: WebhookConfig,
+    #[serde(default)]
+    pub auth: AuthConfig,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -23,6 +27,20 @@ pub struct DatabaseConfig {
     pub user: Option<String>,
     #[serde(default)]
     pub password: Option<String>,
+    /// Transactions buffered before a batch insert is committed
+    #[serde(default = "default_insert_batch_rows")]
+    pub insert_batch_rows: u64,
+    /// Longest a buffered transaction waits before being committed
+    #[serde(default = "default_insert_flush_ms")]
+    pub insert_flush_ms: u64,
+}
+
+fn default_insert_batch_rows() -> u64 {
+    1000
+}
+
+fn default_insert_flush_ms() -> u64 {
+    1000
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -36,6 +54,96 @@ pub struct HeliusConfig {
     pub api_key: String,
 }
 
+#[derive(Debug, Deserialize, Clone)]
+pub struct WebhookConfig {
+    /// Attempts per event before the delivery counts as failed
+    #[serde(default = "default_webhook_max_attempts")]
+    pub max_attempts: u32,
+    /// Wait before the first retry; doubles after every further failure
+    #[serde(default = "default_webhook_retry_base_ms")]
+    pub retry_base_ms: u64,
+    /// Failed deliveries in a row after which a webhook is disabled
+    #[serde(default = "default_webhook_disable_after")]
+    pub disable_after: u32,
+    /// Longest an endpoint may take to respond
+    #[serde(default = "default_webhook_timeout_ms")]
+    pub timeout_ms: u64,
+    /// Events waiting per webhook; further events are dropped until it catches up
+    #[serde(default = "default_webhook_queue_size")]
+    pub queue_size: usize,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: default_webhook_max_attempts(),
+            retry_base_ms: default_webhook_retry_base_ms(),
+            disable_after: default_webhook_disable_after(),
+            timeout_ms: default_webhook_timeout_ms(),
+            queue_size: default_webhook_queue_size(),
+        }
+    }
+}
+
+fn default_webhook_max_attempts() -> u32 {
+    5
+}
+
+fn default_webhook_retry_base_ms() -> u64 {
+    1000
+}
+
+fn default_webhook_disable_after() -> u32 {
+    5
+}
+
+fn default_webhook_timeout_ms() -> u64 {
+    10_000
+}
+
+fn default_webhook_queue_size() -> usize {
+    1000
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AuthConfig {
+    /// Require an API key on every route but `/health`
+    #[serde(default = "default_auth_enabled")]
+    pub enabled: bool,
+    /// Key with the admin scope and no limits, for creating the first stored keys
+    #[serde(default)]
+    pub admin_key: Option<String>,
+    /// Requests per minute for keys created without a limit; 0 for unlimited
+    #[serde(default = "default_auth_rate_limit_per_minute")]
+    pub rate_limit_per_minute: u32,
+    /// Concurrent wallet subscriptions for keys created without a quota; 0 for unlimited
+    #[serde(default = "default_auth_max_subscriptions")]
+    pub max_subscriptions: u32,
+}
+
+impl Default for AuthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: default_auth_enabled(),
+            admin_key: None,
+            rate_limit_per_minute: default_auth_rate_limit_per_minute(),
+            max_subscriptions: default_auth_max_subscriptions(),
+        }
+    }
+}
+
+fn default_auth_enabled() -> bool {
+    true
+}
+
+fn default_auth_rate_limit_per_minute() -> u32 {
+    600
+}
+
+fn default_auth_max_subscriptions() -> u32 {
+    10
+}
+
 impl AppConfig {
     pub fn load() -> Result<Self, ConfigError> {
         let config = Config::builder()
@@ -0,0 +1,333 @@
+//! Versioned schema migrations.
+//!
+//! The SQL files under `migrations/` are compiled into the binary and applied in
+//! version order. Each applied migration is recorded in `schema_migrations` with a
+//! checksum of its SQL, so an edited migration or a database migrated by a newer
+//! build is detected instead of silently diverging.
+//!
+//! The files name their tables `cortex.<table>`, so they can also be run by hand with
+//! `clickhouse-client`. When applied here, `cortex` is replaced with the configured
+//! database; checksums are taken over the files as shipped.
+
+use std::fmt::Write;
+use std::time::Instant;
+
+use clickhouse::{Client, Row};
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+
+use crate::error::{AppError, AppResult};
+
+/// Database the migration files are written against
+const FILE_DATABASE: &str = "cortex";
+
+pub struct Migration {
+    pub version: u32,
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+impl Migration {
+    /// Hex SHA-256 of the migration's SQL
+    pub fn checksum(&self) -> String {
+        Sha256::digest(self.sql.as_bytes())
+            .iter()
+            .fold(String::with_capacity(64), |mut hex, byte| {
+                let _ = write!(hex, "{:02x}", byte);
+                hex
+            })
+    }
+
+    /// The migration's statements, run against `database`
+    pub fn statements(&self, database: &str) -> Vec<String> {
+        split_statements(self.sql)
+            .iter()
+            .map(|statement| with_database(statement, database))
+            .collect()
+    }
+}
+
+macro_rules! migration {
+    ($version:literal, $name:literal) => {
+        Migration {
+            version: $version,
+            name: $name,
+            sql: include_str!(concat!("../../../../migrations/", $name, ".sql")),
+        }
+    };
+}
+
+/// Every migration shipped with this build, in the order they must be applied
+pub static MIGRATIONS: &[Migration] = &[
+    migration!(1, "001_init"),
+    migration!(2, "002_prediction_markets"),
+    migration!(3, "003_wallet_risk_components"),
+    migration!(4, "004_token_price_history"),
+    migration!(5, "005_wallet_classification"),
+    migration!(6, "006_mev_flags"),
+    migration!(7, "007_wallet_links"),
+    migration!(8, "008_position_signals"),
+    migration!(9, "009_closed_trades"),
+    migration!(10, "010_wallet_protocol_daily"),
+    migration!(11, "011_wallet_subscriptions"),
+    migration!(12, "012_webhooks"),
+    migration!(13, "013_alerts"),
+    migration!(14, "014_api_keys"),
+    migration!(15, "015_wallet_groups"),
+    migration!(16, "016_alert_rule_groups"),
+    migration!(17, "017_var_coverage"),
+    migration!(18, "018_wallet_asset_exposure"),
+];
+
+/// A migration recorded in `schema_migrations`
+#[derive(Debug, Clone, Row, Deserialize)]
+pub struct AppliedMigration {
+    pub version: u32,
+    pub name: String,
+    pub checksum: String,
+}
+
+/// Work out which migrations still need applying, in order.
+///
+/// Fails when the database has a migration this build doesn't know (it was migrated by
+/// a newer release) or when an applied migration's SQL has since changed.
+pub fn pending<'a>(known: &'a [Migration], applied: &[AppliedMigration]) -> AppResult<Vec<&'a Migration>> {
+    for record in applied {
+        let Some(migration) = known.iter().find(|m| m.version == record.version) else {
+            return Err(AppError::Config(format!(
+                "database schema is at migration {} ({}), which this build doesn't know; upgrade cortex",
+                record.version, record.name
+            )));
+        };
+        if migration.checksum() != record.checksum {
+            return Err(AppError::Config(format!(
+                "migration {} ({}) was changed after it was applied (checksum {} in database, {} in build)",
+                migration.version, migration.name, record.checksum, migration.checksum()
+            )));
+        }
+    }
+
+    Ok(known
+        .iter()
+        .filter(|m| !applied.iter().any(|a| a.version == m.version))
+        .collect())
+}
+
+/// Split a migration file into single statements, dropping `--` comments.
+pub fn split_statements(sql: &str) -> Vec<String> {
+    let mut statements = Vec::new();
+    let mut current = String::new();
+    let mut in_string = false;
+    let mut chars = sql.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => {
+                in_string = !in_string;
+                current.push(c);
+            }
+            '-' if !in_string && chars.peek() == Some(&'-') => {
+                // Skip to end of line, keeping the newline as whitespace
+                for next in chars.by_ref() {
+                    if next == '\n' {
+                        current.push('\n');
+                        break;
+                    }
+                }
+            }
+            ';' if !in_string => {
+                let statement = current.trim();
+                if !statement.is_empty() {
+                    statements.push(statement.to_string());
+                }
+                current.clear();
+            }
+            _ => current.push(c),
+        }
+    }
+    let statement = current.trim();
+    if !statement.is_empty() {
+        statements.push(statement.to_string());
+    }
+
+    statements
+}
+
+/// Replace the `cortex` database name in a statement with `database`, leaving string
+/// literals and longer identifiers such as `cortex_x` alone.
+pub fn with_database(statement: &str, database: &str) -> String {
+    let is_identifier = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    let mut qualified = String::with_capacity(statement.len());
+    let mut in_string = false;
+    let mut rest = statement;
+
+    while let Some(c) = rest.chars().next() {
+        if c == '\'' {
+            in_string = !in_string;
+        }
+        let at_name = !in_string
+            && rest.starts_with(FILE_DATABASE)
+            && !qualified.ends_with(is_identifier)
+            && !rest[FILE_DATABASE.len()..].starts_with(is_identifier);
+        if at_name {
+            qualified.push_str(database);
+            rest = &rest[FILE_DATABASE.len()..];
+        } else {
+            qualified.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+
+    qualified
+}
+
+/// Create the migrations table if needed and read what has been applied.
+///
+/// `server` must not be bound to a database, since the database itself may not exist
+/// yet.
+pub async fn applied_migrations(server: &Client, database: &str) -> AppResult<Vec<AppliedMigration>> {
+    server
+        .query(&format!("CREATE DATABASE IF NOT EXISTS {}", database))
+        .execute()
+        .await?;
+    server
+        .query(&format!(
+            r#"
+            CREATE TABLE IF NOT EXISTS {}.schema_migrations (
+                version UInt32,
+                name String,
+                checksum String,
+                applied_at DateTime64(3) DEFAULT now64(3)
+            ) ENGINE = MergeTree()
+            ORDER BY version
+            "#,
+            database
+        ))
+        .execute()
+        .await?;
+
+    let applied: Vec<AppliedMigration> = server
+        .query(&format!(
+            "SELECT version, name, checksum FROM {}.schema_migrations ORDER BY version",
+            database
+        ))
+        .fetch_all()
+        .await?;
+
+    Ok(applied)
+}
+
+/// Apply every pending migration in order and return the ones applied.
+///
+/// Refuses to touch the schema when `pending` reports it incompatible.
+pub async fn migrate(server: &Client, database: &str) -> AppResult<Vec<&'static Migration>> {
+    let applied = applied_migrations(server, database).await?;
+    let pending = pending(MIGRATIONS, &applied)?;
+
+    if pending.is_empty() {
+        println!("[MIGRATE] Schema up to date ({} migrations applied)", applied.len());
+        tracing::info!(applied = %applied.len(), "Schema up to date");
+        return Ok(pending);
+    }
+
+    for migration in &pending {
+        let start = Instant::now();
+        println!("[MIGRATE] Applying {}...", migration.name);
+        tracing::info!(version = %migration.version, name = %migration.name, "Applying migration");
+
+        for statement in migration.statements(database) {
+            tracing::debug!(version = %migration.version, statement = %statement, "Executing migration statement");
+            server.query(&statement).execute().await?;
+        }
+        server
+            .query(&format!(
+                "INSERT INTO {}.schema_migrations (version, name, checksum) VALUES (?, ?, ?)",
+                database
+            ))
+            .bind(migration.version)
+            .bind(migration.name)
+            .bind(migration.checksum())
+            .execute()
+            .await?;
+
+        println!("[MIGRATE] Applied {} ({}ms)", migration.name, start.elapsed().as_millis());
+        tracing::info!(
+            version = %migration.version,
+            name = %migration.name,
+            duration_ms = %start.elapsed().as_millis(),
+            "Migration applied"
+        );
+    }
+
+    Ok(pending)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(migration: &Migration) -> AppliedMigration {
+        AppliedMigration {
+            version: migration.version,
+            name: migration.name.to_string(),
+            checksum: migration.checksum(),
+        }
+    }
+
+    #[test]
+    fn test_migrations_are_ordered() {
+        for (i, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version as usize, i + 1);
+            assert!(migration.name.starts_with(&format!("{:03}_", migration.version)));
+        }
+    }
+
+    #[test]
+    fn test_pending_and_incompatible_schemas() {
+        let applied: Vec<AppliedMigration> = MIGRATIONS[..3].iter().map(record).collect();
+        let pending = pending(MIGRATIONS, &applied).unwrap();
+        assert_eq!(pending.len(), MIGRATIONS.len() - 3);
+        assert_eq!(pending[0].version, 4);
+
+        let mut edited = applied.clone();
+        edited[1].checksum = "0".repeat(64);
+        assert!(super::pending(MIGRATIONS, &edited).is_err());
+
+        let mut newer = applied;
+        newer.push(AppliedMigration {
+            version: 999,
+            name: "999_future".to_string(),
+            checksum: String::new(),
+        });
+        assert!(super::pending(MIGRATIONS, &newer).is_err());
+    }
+
+    #[test]
+    fn test_split_statements() {
+        let sql = "-- Header; with a semicolon\nCREATE TABLE t (\n    a String DEFAULT 'x;y', -- trailing\n    b UInt8\n);\n\nALTER TABLE t ADD COLUMN c UInt8;\n";
+        let statements = split_statements(sql);
+        assert_eq!(statements.len(), 2);
+        assert!(statements[0].contains("'x;y'"));
+        assert!(!statements[0].contains("trailing"));
+        assert_eq!(statements[1], "ALTER TABLE t ADD COLUMN c UInt8");
+
+        // Every shipped migration splits into at least one statement
+        assert!(MIGRATIONS.iter().all(|m| !split_statements(m.sql).is_empty()));
+    }
+
+    #[test]
+    fn test_statements_use_configured_database() {
+        let statement = "INSERT INTO cortex.t SELECT cortex_id, 'cortex.t' FROM cortex.transactions";
+        assert_eq!(
+            with_database(statement, "analytics"),
+            "INSERT INTO analytics.t SELECT cortex_id, 'cortex.t' FROM analytics.transactions"
+        );
+        assert_eq!(with_database("CREATE DATABASE IF NOT EXISTS cortex", "analytics"), "CREATE DATABASE IF NOT EXISTS analytics");
+
+        for migration in MIGRATIONS {
+            for statement in migration.statements("analytics") {
+                assert!(!statement.contains("cortex."), "{} still names cortex: {}", migration.name, statement);
+            }
+        }
+    }
+}
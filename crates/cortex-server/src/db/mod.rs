@@ -1,40 +1,75 @@
+pub mod migrations;
 pub mod models;
 pub mod queries;
+pub mod store;
+pub mod writer;
 
 use clickhouse::Client;
 
 use crate::config::DatabaseConfig;
+use crate::error::AppResult;
 
 #[derive(Clone)]
 pub struct Database {
     client: Client,
+    /// Same server without a default database, for creating it during migrations
+    server: Client,
+    database: String,
 }
 
 impl Database {
     pub fn new(config: &DatabaseConfig) -> Self {
-        let mut client = Client::default()
-            .with_url(&config.url)
-            .with_database(&config.database);
+        let mut server = Client::default().with_url(&config.url);
 
         // Add user/password if provided
         if let Some(ref user) = config.user {
-            client = client.with_user(user);
+            server = server.with_user(user);
         }
         if let Some(ref password) = config.password {
-            client = client.with_password(password);
+            server = server.with_password(password);
         }
+        let client = server.clone().with_database(&config.database);
 
         println!("[DB] Connecting to {} database '{}'", config.url, config.database);
 
-        Self { client }
+        Self {
+            client,
+            server,
+            database: config.database.clone(),
+        }
     }
 
     pub fn client(&self) -> &Client {
         &self.client
     }
 
+    /// Apply pending schema migrations, failing if the schema is incompatible
+    pub async fn migrate(&self) -> AppResult<Vec<&'static migrations::Migration>> {
+        migrations::migrate(&self.server, &self.database).await
+    }
+
+    /// Migrations recorded as applied
+    pub async fn applied_migrations(&self) -> AppResult<Vec<migrations::AppliedMigration>> {
+        migrations::applied_migrations(&self.server, &self.database).await
+    }
+
     pub async fn health_check(&self) -> Result<(), clickhouse::error::Error> {
         self.client.query("SELECT 1").execute().await?;
         Ok(())
     }
 }
+
+#[cfg(test)]
+impl Database {
+    /// A database whose server can't be reached, for tests that run without ClickHouse
+    pub fn unreachable() -> Self {
+        Self::new(&DatabaseConfig {
+            url: "http://127.0.0.1:1".to_string(),
+            database: "cortex".to_string(),
+            user: None,
+            password: None,
+            insert_batch_rows: 1,
+            insert_flush_ms: 1,
+        })
+    }
+}
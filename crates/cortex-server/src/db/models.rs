@@ -2,6 +2,8 @@ use chrono::{DateTime, Utc};
 use clickhouse::Row;
 use serde::{Deserialize, Serialize};
 
+use crate::types::{LeaderboardMetric, TimeWindow, TransactionSort};
+
 /// Decimal values are stored as strings for precise representation in ClickHouse
 #[derive(Debug, Clone, Row, Serialize, Deserialize)]
 pub struct TransactionRow {
@@ -47,6 +49,81 @@ pub struct WalletSummaryRow {
     pub risk_score: u8,
     pub last_activity: i64,
     pub protocols: Vec<String>,
+    pub leverage: String,
+    pub liquidation_distance: String,
+    pub stablecoin_pct: String,
+    pub memecoin_pct: String,
+    pub concentration_risk: u8,
+    pub leverage_risk: u8,
+    pub liquidation_risk: u8,
+    pub asset_risk: u8,
+    pub contract_risk: u8,
+    pub var_95: String,
+    pub var_99: String,
+    pub expected_shortfall_95: String,
+    pub expected_shortfall_99: String,
+    pub portfolio_volatility: String,
+    /// Share of exposure backed by price history; VaR is unknown when zero
+    pub var_coverage: String,
+    pub classification: String,
+    pub classification_confidence: String,
+    /// JSON-encoded `cortex_core::WalletFeatures`
+    pub classification_features: String,
+    pub mev_loss_usd: String,
+    pub sandwiched_count: u32,
+    pub round_trip_count: u32,
+    pub jito_tip_count: u32,
+    pub snipe_count: u32,
+    /// JSON map of lending protocol to loan-to-value, e.g. `{"kamino":"0.62"}`
+    pub protocol_ltv: String,
+    /// JSON map of underlying asset mint to USD exposure net of debt
+    pub asset_exposure: String,
+}
+
+/// A wallet with nothing indexed yet: zero values, no protocols, unclassified
+impl Default for WalletSummaryRow {
+    fn default() -> Self {
+        let zero = || "0".to_string();
+        Self {
+            wallet: String::new(),
+            total_value_usd: zero(),
+            realized_pnl_24h: zero(),
+            realized_pnl_7d: zero(),
+            realized_pnl_30d: zero(),
+            unrealized_pnl: zero(),
+            largest_position_pct: zero(),
+            protocol_count: 0,
+            position_count: 0,
+            risk_score: 0,
+            last_activity: 0,
+            protocols: Vec::new(),
+            leverage: zero(),
+            liquidation_distance: zero(),
+            stablecoin_pct: zero(),
+            memecoin_pct: zero(),
+            concentration_risk: 0,
+            leverage_risk: 0,
+            liquidation_risk: 0,
+            asset_risk: 0,
+            contract_risk: 0,
+            var_95: zero(),
+            var_99: zero(),
+            expected_shortfall_95: zero(),
+            expected_shortfall_99: zero(),
+            portfolio_volatility: zero(),
+            var_coverage: zero(),
+            classification: "unknown".to_string(),
+            classification_confidence: zero(),
+            classification_features: "{}".to_string(),
+            mev_loss_usd: zero(),
+            sandwiched_count: 0,
+            round_trip_count: 0,
+            jito_tip_count: 0,
+            snipe_count: 0,
+            protocol_ltv: "{}".to_string(),
+            asset_exposure: "{}".to_string(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Row, Serialize, Deserialize)]
@@ -57,14 +134,403 @@ pub struct PnlByProtocolRow {
     pub trade_count: u64,
 }
 
+/// Realized PnL of one of several wallets on one protocol
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct WalletProtocolPnlRow {
+    pub wallet: String,
+    pub protocol: String,
+    pub realized: String,
+    pub unrealized: String,
+    pub trade_count: u64,
+}
+
+impl From<WalletProtocolPnlRow> for PnlByProtocolRow {
+    fn from(row: WalletProtocolPnlRow) -> Self {
+        Self {
+            protocol: row.protocol,
+            realized: row.realized,
+            unrealized: row.unrealized,
+            trade_count: row.trade_count,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Row, Serialize, Deserialize)]
 pub struct TokenPriceRow {
     pub token: String,
     pub price_usd: String,
 }
 
+/// Last recorded price of a token on a given day
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct TokenDailyCloseRow {
+    pub token: String,
+    pub day: String,
+    pub price_usd: String,
+}
+
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct TransactionFlagRow {
+    pub signature: String,
+    pub wallet: String,
+    pub flag: String,
+    pub counterparty: String,
+    pub estimated_loss_usd: String,
+    pub slot: u64,
+    pub block_time: i64,
+}
+
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct TokenLaunchRow {
+    pub mint: String,
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct MevLossRow {
+    pub estimated_loss_usd: String,
+    pub sandwiched_count: u64,
+}
+
+/// Sandwich losses for one wallet of a batch
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct WalletMevLossRow {
+    pub wallet: String,
+    pub estimated_loss_usd: String,
+    pub sandwiched_count: u64,
+}
+
+/// Evidence that two wallets may be controlled by the same entity
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct WalletLinkRow {
+    pub source: String,
+    pub target: String,
+    pub link_type: String,
+    pub mint: String,
+    pub amount: String,
+    pub signature: String,
+    pub block_time: i64,
+}
+
+/// Distinct wallets a source has sent funds to or paid fees for
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct LinkFanOutRow {
+    pub source: String,
+    pub targets: u64,
+}
+
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct PositionSignalRow {
+    pub wallet: String,
+    pub signature: String,
+    pub token: String,
+    pub kind: String,
+    pub amount: String,
+    pub price: String,
+    pub usd_value: String,
+    pub position_size: String,
+    pub portfolio_weight: String,
+    pub slot: u64,
+    pub block_time: i64,
+}
+
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct ClosedTradeRow {
+    pub wallet: String,
+    pub signature: String,
+    pub protocol: String,
+    pub token: String,
+    pub cost_basis: String,
+    pub pnl: String,
+    pub block_time: i64,
+}
+
+/// A webhook subscription; the latest row per id wins
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct WebhookRow {
+    pub id: String,
+    pub url: String,
+    pub secret: String,
+    /// Empty to receive every wallet's events
+    pub wallet: String,
+    pub event_types: Vec<String>,
+    pub min_usd_value: String,
+    pub risk_threshold: u8,
+    pub active: u8,
+    /// Deliveries in a row that exhausted their retries
+    pub consecutive_failures: u32,
+    pub deleted: u8,
+    pub created_at: i64,
+}
+
+/// One attempt at delivering a webhook event
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct WebhookDeliveryRow {
+    pub webhook_id: String,
+    pub delivery_id: String,
+    pub event_type: String,
+    pub attempt: u8,
+    /// 0 when no response was received
+    pub status_code: u16,
+    pub error: String,
+    pub success: u8,
+    pub duration_ms: u32,
+    pub attempted_at: i64,
+}
+
+/// An API key; only its hash is kept. The latest row per id wins.
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct ApiKeyRow {
+    pub id: String,
+    pub name: String,
+    /// Hex SHA-256 of the key
+    pub key_hash: String,
+    /// First characters of the key, to tell keys apart
+    pub key_prefix: String,
+    pub scopes: Vec<String>,
+    /// 0 for unlimited
+    pub rate_limit_per_minute: u32,
+    /// Wallet subscriptions the key may have running at once; 0 for unlimited
+    pub max_subscriptions: u32,
+    pub revoked: u8,
+    pub created_at: i64,
+}
+
+/// A wallet subscription started with an API key
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct KeySubscriptionRow {
+    pub key_id: String,
+    pub wallet: String,
+    pub active: u8,
+}
+
+/// A named group of wallets; the latest row per id wins
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct WalletGroupRow {
+    pub id: String,
+    pub name: String,
+    pub wallets: Vec<String>,
+    pub tags: Vec<String>,
+    pub deleted: u8,
+    pub created_at: i64,
+}
+
+/// A declarative alert rule; the latest row per id wins
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct AlertRuleRow {
+    pub id: String,
+    pub name: String,
+    /// e.g. `risk_score > 70`, parsed by `alerts::Condition`
+    pub condition: String,
+    /// Empty to evaluate every wallet, unless `group_id` is set
+    pub wallets: Vec<String>,
+    /// Wallet group whose current members the rule also covers; empty for none
+    pub group_id: String,
+    pub deleted: u8,
+    pub created_at: i64,
+}
+
+/// Latest state of one rule for one wallet
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct AlertStateRow {
+    pub rule_id: String,
+    pub wallet: String,
+    pub state: String,
+    /// Metric value at the transition
+    pub value: String,
+    pub changed_at: i64,
+}
+
+/// One wallet's aggregated closed-trade performance, as ranked on the leaderboard
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct LeaderboardRow {
+    pub wallet: String,
+    pub trade_count: u64,
+    pub win_count: u64,
+    pub realized_pnl: String,
+    pub cost_basis: String,
+    pub roi: f64,
+    pub win_rate: f64,
+    /// Annualized Sharpe ratio on daily returns; `None` with fewer than two days
+    pub sharpe_ratio: Option<f64>,
+    pub total_value_usd: String,
+}
+
+/// One wallet's swaps into and out of a token
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct TokenFlowRow {
+    pub wallet: String,
+    pub bought: String,
+    pub sold: String,
+    pub buy_count: u64,
+    pub sell_count: u64,
+    pub classification: String,
+}
+
+/// Rollup-backed activity of one or more wallets
+#[derive(Debug, Clone, Default, Row, Serialize, Deserialize)]
+pub struct WalletActivityRow {
+    pub realized_pnl_24h: String,
+    pub realized_pnl_7d: String,
+    pub realized_pnl_30d: String,
+    pub protocols: Vec<String>,
+    pub last_activity: i64,
+    pub tx_count: u64,
+}
+
+/// Rollup-backed activity of one of several wallets
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct WalletActivityByWalletRow {
+    pub wallet: String,
+    pub realized_pnl_24h: String,
+    pub realized_pnl_7d: String,
+    pub realized_pnl_30d: String,
+    pub protocols: Vec<String>,
+    pub last_activity: i64,
+    pub tx_count: u64,
+}
+
+impl From<WalletActivityByWalletRow> for WalletActivityRow {
+    fn from(row: WalletActivityByWalletRow) -> Self {
+        Self {
+            realized_pnl_24h: row.realized_pnl_24h,
+            realized_pnl_7d: row.realized_pnl_7d,
+            realized_pnl_30d: row.realized_pnl_30d,
+            protocols: row.protocols,
+            last_activity: row.last_activity,
+            tx_count: row.tx_count,
+        }
+    }
+}
+
 impl WalletSummaryRow {
+    /// Replace the stored activity fields with live values from the daily rollup.
+    /// Leaves the row untouched when the rollup has nothing for the wallet yet.
+    pub fn apply_activity(&mut self, activity: &WalletActivityRow) {
+        if activity.tx_count == 0 {
+            return;
+        }
+        self.realized_pnl_24h = activity.realized_pnl_24h.clone();
+        self.realized_pnl_7d = activity.realized_pnl_7d.clone();
+        self.realized_pnl_30d = activity.realized_pnl_30d.clone();
+        self.protocol_count = activity.protocols.len().min(u8::MAX as usize) as u8;
+        self.protocols = activity.protocols.clone();
+        self.last_activity = activity.last_activity;
+    }
+
     pub fn last_activity_datetime(&self) -> DateTime<Utc> {
         DateTime::from_timestamp_millis(self.last_activity).unwrap_or_default()
     }
 }
+
+/// Where a transaction history page ends; the next page starts just past it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TransactionCursor {
+    pub block_time: i64,
+    pub usd_value: String,
+    pub signature: String,
+}
+
+/// Filters, order and position of a page of a wallet's transactions
+#[derive(Debug, Clone)]
+pub struct TransactionFilter {
+    pub protocol: Option<String>,
+    pub tx_type: Option<String>,
+    /// Matches either leg
+    pub token: Option<String>,
+    /// Unix milliseconds, inclusive
+    pub from: Option<i64>,
+    /// Unix milliseconds, exclusive
+    pub to: Option<i64>,
+    pub min_usd_value: Option<String>,
+    pub sort: TransactionSort,
+    pub after: Option<TransactionCursor>,
+    pub limit: u32,
+}
+
+/// Trades counted, ranking and length of a leaderboard
+#[derive(Debug, Clone)]
+pub struct LeaderboardFilter {
+    pub window: TimeWindow,
+    pub metric: LeaderboardMetric,
+    pub protocol: Option<String>,
+    /// Only rank these wallets
+    pub wallets: Option<Vec<String>>,
+    /// Minimum current portfolio value in USD
+    pub min_value_usd: String,
+    pub min_trades: u64,
+    pub limit: u32,
+}
+
+/// A prediction market from the `markets` table
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct MarketRow {
+    pub slug: String,
+    pub platform: String,
+    pub title: String,
+    pub description: String,
+    pub category: String,
+    /// active, closed or resolved
+    pub status: String,
+    pub outcome_labels: Vec<String>,
+    /// Empty until resolved
+    pub resolved_outcome: String,
+    pub end_date: i64,
+}
+
+/// Open, high, low and close price of a market over one interval, with its volume
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct MarketCandleRow {
+    pub interval_start: i64,
+    pub open: String,
+    pub high: String,
+    pub low: String,
+    pub close: String,
+    pub volume_usd: String,
+    pub trade_count: u32,
+}
+
+/// Recent trading volume and latest order book depth of a market
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct MarketVolumeRow {
+    pub slug: String,
+    pub volume_24h: String,
+    pub volume_7d: String,
+    pub trades_24h: u32,
+    pub unique_traders_24h: u32,
+    pub avg_trade_size: String,
+    pub bid_depth_usd: String,
+    pub ask_depth_usd: String,
+    pub spread: String,
+}
+
+/// An outcome price unusually far from its trailing one-hour mean
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct MarketAnomalyRow {
+    pub outcome_token: String,
+    pub timestamp: i64,
+    pub price: String,
+    pub mean_price: String,
+    pub std_dev: String,
+    pub z_score: f64,
+}
+
+/// A trader's net holding of one market outcome, built from their trades as taker
+#[derive(Debug, Clone, Row, Serialize, Deserialize)]
+pub struct PredictionPositionRow {
+    pub trader: String,
+    pub slug: String,
+    pub platform: String,
+    pub title: String,
+    pub category: String,
+    pub status: String,
+    /// Outcome label, or the outcome token when the market has no label for it
+    pub outcome: String,
+    pub shares: String,
+    /// USD paid for buys less USD received for sells
+    pub cost_usd: String,
+    pub avg_price: String,
+    pub current_price: String,
+    pub first_trade_at: i64,
+    pub end_date: i64,
+}
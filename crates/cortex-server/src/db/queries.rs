@@ -1,35 +1,73 @@
 use chrono::{Duration, Utc};
 use clickhouse::Client;
+use std::collections::HashMap;
 use std::time::Instant;
 
-use super::models::{PnlByProtocolRow, PositionRow, WalletSummaryRow};
+use super::models::{
+    AlertRuleRow, AlertStateRow, ApiKeyRow, KeySubscriptionRow, ClosedTradeRow, LeaderboardFilter, LeaderboardRow, LinkFanOutRow, MarketAnomalyRow, MarketCandleRow, MarketRow, MarketVolumeRow, MevLossRow,
+    PredictionPositionRow, TokenFlowRow, WalletActivityByWalletRow, WalletActivityRow, WalletGroupRow, WalletMevLossRow, PnlByProtocolRow, PositionRow, PositionSignalRow, TokenDailyCloseRow, TokenLaunchRow, TokenPriceRow,
+    TransactionFilter, TransactionFlagRow, TransactionRow, WalletLinkRow, WalletProtocolPnlRow, WalletSummaryRow, WebhookDeliveryRow, WebhookRow,
+};
 use crate::error::AppResult;
-use crate::types::TimeWindow;
+use crate::telemetry;
+use crate::types::{CandleInterval, LeaderboardMetric, TimeWindow, TransactionSort};
+
+/// Columns of `WalletSummaryRow`, in order
+const SUMMARY_COLUMNS: &str = r#"
+        wallet,
+        total_value_usd,
+        realized_pnl_24h,
+        realized_pnl_7d,
+        realized_pnl_30d,
+        unrealized_pnl,
+        largest_position_pct,
+        protocol_count,
+        position_count,
+        risk_score,
+        last_activity,
+        protocols,
+        leverage,
+        liquidation_distance,
+        stablecoin_pct,
+        memecoin_pct,
+        concentration_risk,
+        leverage_risk,
+        liquidation_risk,
+        asset_risk,
+        contract_risk,
+        var_95,
+        var_99,
+        expected_shortfall_95,
+        expected_shortfall_99,
+        portfolio_volatility,
+        var_coverage,
+        classification,
+        classification_confidence,
+        classification_features,
+        mev_loss_usd,
+        sandwiched_count,
+        round_trip_count,
+        jito_tip_count,
+        snipe_count,
+        protocol_ltv,
+        asset_exposure
+"#;
 
 pub async fn get_wallet_summary(client: &Client, wallet: &str) -> AppResult<Option<WalletSummaryRow>> {
     let start = Instant::now();
     tracing::debug!(wallet = %wallet, query = "get_wallet_summary", "Executing database query");
 
-    let query = r#"
-        SELECT
-            wallet,
-            total_value_usd,
-            realized_pnl_24h,
-            realized_pnl_7d,
-            realized_pnl_30d,
-            unrealized_pnl,
-            largest_position_pct,
-            protocol_count,
-            position_count,
-            risk_score,
-            last_activity,
-            protocols
+    let query = format!(
+        r#"
+        SELECT {}
         FROM wallet_summaries FINAL
         WHERE wallet = ?
-    "#;
+        "#,
+        SUMMARY_COLUMNS
+    );
 
     let result: Vec<WalletSummaryRow> = client
-        .query(query)
+        .query(&query)
         .bind(wallet)
         .fetch_all()
         .await?;
@@ -42,8 +80,63 @@ pub async fn get_wallet_summary(client: &Client, wallet: &str) -> AppResult<Opti
         found = %found,
         "Database query completed"
     );
+    telemetry::observe_query("get_wallet_summary", start.elapsed());
+
+    let Some(mut summary) = result.into_iter().next() else {
+        return Ok(None);
+    };
+    let activity = get_wallet_activity(client, std::slice::from_ref(&summary.wallet)).await?;
+    summary.apply_activity(&activity);
+
+    Ok(Some(summary))
+}
+
+/// Stored summaries of several wallets with their live activity applied, in no
+/// particular order. Wallets that haven't been indexed are left out.
+pub async fn get_wallet_summaries(client: &Client, wallets: &[String]) -> AppResult<Vec<WalletSummaryRow>> {
+    let start = Instant::now();
+    tracing::debug!(wallet_count = %wallets.len(), query = "get_wallet_summaries", "Executing database query");
+
+    if wallets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = format!(
+        r#"
+        SELECT {}
+        FROM wallet_summaries FINAL
+        WHERE has(?, wallet)
+        "#,
+        SUMMARY_COLUMNS
+    );
+
+    let mut summaries: Vec<WalletSummaryRow> = client
+        .query(&query)
+        .bind(wallets)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        query = "get_wallet_summaries",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %summaries.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_wallet_summaries", start.elapsed());
+
+    let found: Vec<String> = summaries.iter().map(|s| s.wallet.clone()).collect();
+    let mut activity: HashMap<String, WalletActivityRow> = get_activity_by_wallet(client, &found)
+        .await?
+        .into_iter()
+        .map(|row| (row.wallet.clone(), row.into()))
+        .collect();
+    for summary in &mut summaries {
+        if let Some(activity) = activity.remove(&summary.wallet) {
+            summary.apply_activity(&activity);
+        }
+    }
 
-    Ok(result.into_iter().next())
+    Ok(summaries)
 }
 
 pub async fn get_wallet_positions(client: &Client, wallet: &str) -> AppResult<Vec<PositionRow>> {
@@ -81,6 +174,51 @@ pub async fn get_wallet_positions(client: &Client, wallet: &str) -> AppResult<Ve
         row_count = %positions.len(),
         "Database query completed"
     );
+    telemetry::observe_query("get_wallet_positions", start.elapsed());
+
+    Ok(positions)
+}
+
+/// Open positions of several wallets, each wallet's largest first
+pub async fn get_positions_for_wallets(client: &Client, wallets: &[String]) -> AppResult<Vec<PositionRow>> {
+    let start = Instant::now();
+    tracing::debug!(wallet_count = %wallets.len(), query = "get_positions_for_wallets", "Executing database query");
+
+    if wallets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = r#"
+        SELECT
+            wallet,
+            protocol,
+            position_type,
+            token,
+            pool,
+            amount,
+            entry_price,
+            current_price,
+            usd_value,
+            unrealized_pnl,
+            apy
+        FROM positions FINAL
+        WHERE has(?, wallet)
+        ORDER BY wallet, usd_value DESC
+    "#;
+
+    let positions: Vec<PositionRow> = client
+        .query(query)
+        .bind(wallets)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        query = "get_positions_for_wallets",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %positions.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_positions_for_wallets", start.elapsed());
 
     Ok(positions)
 }
@@ -98,49 +236,46 @@ pub async fn get_wallet_pnl_by_protocol(
         "Executing database query"
     );
 
-    let time_filter = match window.to_days() {
-        Some(days) => {
-            let cutoff = Utc::now() - Duration::days(days);
-            tracing::debug!(cutoff = %cutoff, days = %days, "Applying time filter");
-            format!("AND block_time >= toDateTime64('{}', 3)", cutoff.format("%Y-%m-%d %H:%M:%S"))
-        }
-        None => {
-            tracing::debug!("No time filter applied (all time)");
-            String::new()
-        }
-    };
+    // Whole days from the rollup, the partial first day from raw transactions
+    let (day_filter, partial_day_filter) = rollup_window_filters(window);
 
     let query = format!(
         r#"
         SELECT
             protocol,
-            sum(
-                CASE
-                    WHEN tx_type IN ('swap', 'remove_liquidity', 'withdraw')
-                    THEN usd_value
-                    ELSE 0
-                END
-            ) - sum(
-                CASE
-                    WHEN tx_type IN ('add_liquidity', 'deposit', 'borrow')
-                    THEN usd_value
-                    ELSE 0
-                END
-            ) as realized,
-            0 as unrealized,
-            count(*) as trade_count
-        FROM transactions
-        WHERE wallet = ?
-        {}
+            toString(sum(realized)) AS realized,
+            '0' AS unrealized,
+            sum(trade_count) AS trade_count
+        FROM (
+            SELECT
+                protocol,
+                sum(realized_usd) - sum(deployed_usd) AS realized,
+                sum(tx_count) AS trade_count
+            FROM wallet_protocol_daily
+            WHERE wallet = ?
+            {}
+            GROUP BY protocol
+            UNION ALL
+            SELECT
+                protocol,
+                sumIf(usd_value, tx_type IN ('swap', 'withdraw', 'remove_liquidity'))
+                    - sumIf(usd_value, tx_type IN ('deposit', 'borrow', 'add_liquidity')) AS realized,
+                count() AS trade_count
+            FROM transactions
+            WHERE wallet = ?
+            {}
+            GROUP BY protocol
+        )
         GROUP BY protocol
-        ORDER BY realized DESC
+        ORDER BY sum(realized) DESC
         "#,
-        time_filter
+        day_filter, partial_day_filter
     );
 
     let results: Vec<PnlByProtocolRow> = client
         .query(&query)
         .bind(wallet)
+        .bind(wallet)
         .fetch_all()
         .await?;
 
@@ -151,124 +286,2268 @@ pub async fn get_wallet_pnl_by_protocol(
         protocol_count = %results.len(),
         "Database query completed"
     );
+    telemetry::observe_query("get_wallet_pnl_by_protocol", start.elapsed());
 
     Ok(results)
 }
 
-pub async fn upsert_wallet_summary(client: &Client, summary: &WalletSummaryRow) -> AppResult<()> {
+/// Realized PnL per wallet and protocol within a window, for several wallets at once
+pub async fn get_pnl_by_protocol_for_wallets(
+    client: &Client,
+    wallets: &[String],
+    window: TimeWindow,
+) -> AppResult<Vec<WalletProtocolPnlRow>> {
     let start = Instant::now();
     tracing::debug!(
-        wallet = %summary.wallet,
-        query = "upsert_wallet_summary",
-        "Executing database insert"
+        wallet_count = %wallets.len(),
+        window = ?window,
+        query = "get_pnl_by_protocol_for_wallets",
+        "Executing database query"
     );
 
-    let query = r#"
-        INSERT INTO wallet_summaries (
-            wallet, total_value_usd, realized_pnl_24h, realized_pnl_7d, realized_pnl_30d,
-            unrealized_pnl, largest_position_pct, protocol_count, position_count,
-            risk_score, last_activity, protocols, updated_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, now64(3))
-    "#;
+    if wallets.is_empty() {
+        return Ok(Vec::new());
+    }
 
-    client
-        .query(query)
-        .bind(&summary.wallet)
-        .bind(&summary.total_value_usd)
-        .bind(&summary.realized_pnl_24h)
-        .bind(&summary.realized_pnl_7d)
-        .bind(&summary.realized_pnl_30d)
-        .bind(&summary.unrealized_pnl)
-        .bind(&summary.largest_position_pct)
-        .bind(summary.protocol_count)
-        .bind(summary.position_count)
-        .bind(summary.risk_score)
-        .bind(summary.last_activity)
-        .bind(&summary.protocols)
-        .execute()
+    let (day_filter, partial_day_filter) = rollup_window_filters(window);
+
+    let query = format!(
+        r#"
+        SELECT
+            wallet,
+            protocol,
+            toString(sum(realized)) AS realized,
+            '0' AS unrealized,
+            sum(trade_count) AS trade_count
+        FROM (
+            SELECT
+                wallet,
+                protocol,
+                sum(realized_usd) - sum(deployed_usd) AS realized,
+                sum(tx_count) AS trade_count
+            FROM wallet_protocol_daily
+            WHERE has(?, wallet)
+            {}
+            GROUP BY wallet, protocol
+            UNION ALL
+            SELECT
+                wallet,
+                protocol,
+                sumIf(usd_value, tx_type IN ('swap', 'withdraw', 'remove_liquidity'))
+                    - sumIf(usd_value, tx_type IN ('deposit', 'borrow', 'add_liquidity')) AS realized,
+                count() AS trade_count
+            FROM transactions
+            WHERE has(?, wallet)
+            {}
+            GROUP BY wallet, protocol
+        )
+        GROUP BY wallet, protocol
+        ORDER BY wallet, sum(realized) DESC
+        "#,
+        day_filter, partial_day_filter
+    );
+
+    let results: Vec<WalletProtocolPnlRow> = client
+        .query(&query)
+        .bind(wallets)
+        .bind(wallets)
+        .fetch_all()
         .await?;
 
     tracing::debug!(
-        wallet = %summary.wallet,
-        query = "upsert_wallet_summary",
+        query = "get_pnl_by_protocol_for_wallets",
         duration_ms = %start.elapsed().as_millis(),
-        "Database insert completed"
+        row_count = %results.len(),
+        "Database query completed"
     );
+    telemetry::observe_query("get_pnl_by_protocol_for_wallets", start.elapsed());
 
-    Ok(())
+    Ok(results)
 }
 
-pub async fn upsert_position(client: &Client, position: &PositionRow) -> AppResult<()> {
+/// Live activity of one or more wallets from the daily rollup: realized PnL windows,
+/// protocols used and last activity. Windows are exact; the partial first day of each
+/// is read from raw transactions.
+pub async fn get_wallet_activity(client: &Client, wallets: &[String]) -> AppResult<WalletActivityRow> {
     let start = Instant::now();
+    tracing::debug!(wallet_count = %wallets.len(), query = "get_wallet_activity", "Executing database query");
+
+    let day = cutoff_literal(1);
+    let week = cutoff_literal(7);
+    let month = cutoff_literal(30);
+    let query = format!(
+        r#"
+        SELECT
+            toString(d.realized_24h + p.realized_24h) AS realized_pnl_24h,
+            toString(d.realized_7d + p.realized_7d) AS realized_pnl_7d,
+            toString(d.realized_30d + p.realized_30d) AS realized_pnl_30d,
+            d.protocols AS protocols,
+            toUnixTimestamp64Milli(d.last_activity) AS last_activity,
+            d.tx_count AS tx_count
+        FROM (
+            SELECT
+                sumIf(realized_usd, day > toDate({day})) AS realized_24h,
+                sumIf(realized_usd, day > toDate({week})) AS realized_7d,
+                sumIf(realized_usd, day > toDate({month})) AS realized_30d,
+                arraySort(groupUniqArray(toString(protocol))) AS protocols,
+                max(last_activity) AS last_activity,
+                sum(tx_count) AS tx_count
+            FROM wallet_protocol_daily
+            WHERE has(?, wallet)
+        ) AS d
+        CROSS JOIN (
+            SELECT
+                sumIf(usd_value, block_time >= {day} AND toDate(block_time) = toDate({day})) AS realized_24h,
+                sumIf(usd_value, block_time >= {week} AND toDate(block_time) = toDate({week})) AS realized_7d,
+                sumIf(usd_value, block_time >= {month} AND toDate(block_time) = toDate({month})) AS realized_30d
+            FROM transactions
+            WHERE has(?, wallet)
+            AND tx_type IN ('swap', 'withdraw', 'remove_liquidity')
+            AND block_time >= {month}
+            AND toDate(block_time) IN (toDate({day}), toDate({week}), toDate({month}))
+        ) AS p
+        "#,
+        day = day,
+        week = week,
+        month = month
+    );
+
+    let activity: Option<WalletActivityRow> = client
+        .query(&query)
+        .bind(wallets)
+        .bind(wallets)
+        .fetch_optional()
+        .await?;
+    let activity = activity.unwrap_or_default();
+
     tracing::debug!(
-        wallet = %position.wallet,
-        protocol = %position.protocol,
-        token = %position.token,
-        query = "upsert_position",
-        "Executing database insert"
+        query = "get_wallet_activity",
+        duration_ms = %start.elapsed().as_millis(),
+        tx_count = %activity.tx_count,
+        "Database query completed"
     );
+    telemetry::observe_query("get_wallet_activity", start.elapsed());
 
-    let query = r#"
-        INSERT INTO positions (
-            wallet, protocol, position_type, token, pool, amount,
-            entry_price, current_price, usd_value, unrealized_pnl, apy, updated_at
-        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, now64(3))
-    "#;
+    Ok(activity)
+}
 
-    client
-        .query(query)
-        .bind(&position.wallet)
-        .bind(&position.protocol)
-        .bind(&position.position_type)
-        .bind(&position.token)
-        .bind(&position.pool)
-        .bind(&position.amount)
-        .bind(&position.entry_price)
-        .bind(&position.current_price)
-        .bind(&position.usd_value)
-        .bind(&position.unrealized_pnl)
-        .bind(&position.apy)
-        .execute()
+/// Live activity of each of several wallets, as `get_wallet_activity` computes it for
+/// one. Wallets without rollup rows are left out.
+pub async fn get_activity_by_wallet(client: &Client, wallets: &[String]) -> AppResult<Vec<WalletActivityByWalletRow>> {
+    let start = Instant::now();
+    tracing::debug!(wallet_count = %wallets.len(), query = "get_activity_by_wallet", "Executing database query");
+
+    if wallets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let day = cutoff_literal(1);
+    let week = cutoff_literal(7);
+    let month = cutoff_literal(30);
+    let query = format!(
+        r#"
+        SELECT
+            d.wallet AS wallet,
+            toString(d.realized_24h + p.realized_24h) AS realized_pnl_24h,
+            toString(d.realized_7d + p.realized_7d) AS realized_pnl_7d,
+            toString(d.realized_30d + p.realized_30d) AS realized_pnl_30d,
+            d.protocols AS protocols,
+            toUnixTimestamp64Milli(d.last_activity) AS last_activity,
+            d.tx_count AS tx_count
+        FROM (
+            SELECT
+                wallet,
+                sumIf(realized_usd, day > toDate({day})) AS realized_24h,
+                sumIf(realized_usd, day > toDate({week})) AS realized_7d,
+                sumIf(realized_usd, day > toDate({month})) AS realized_30d,
+                arraySort(groupUniqArray(toString(protocol))) AS protocols,
+                max(last_activity) AS last_activity,
+                sum(tx_count) AS tx_count
+            FROM wallet_protocol_daily
+            WHERE has(?, wallet)
+            GROUP BY wallet
+        ) AS d
+        LEFT JOIN (
+            SELECT
+                wallet,
+                sumIf(usd_value, block_time >= {day} AND toDate(block_time) = toDate({day})) AS realized_24h,
+                sumIf(usd_value, block_time >= {week} AND toDate(block_time) = toDate({week})) AS realized_7d,
+                sumIf(usd_value, block_time >= {month} AND toDate(block_time) = toDate({month})) AS realized_30d
+            FROM transactions
+            WHERE has(?, wallet)
+            AND tx_type IN ('swap', 'withdraw', 'remove_liquidity')
+            AND block_time >= {month}
+            AND toDate(block_time) IN (toDate({day}), toDate({week}), toDate({month}))
+            GROUP BY wallet
+        ) AS p ON d.wallet = p.wallet
+        "#,
+        day = day,
+        week = week,
+        month = month
+    );
+
+    let rows: Vec<WalletActivityByWalletRow> = client
+        .query(&query)
+        .bind(wallets)
+        .bind(wallets)
+        .fetch_all()
         .await?;
 
     tracing::debug!(
-        wallet = %position.wallet,
-        query = "upsert_position",
+        query = "get_activity_by_wallet",
         duration_ms = %start.elapsed().as_millis(),
-        "Database insert completed"
+        row_count = %rows.len(),
+        "Database query completed"
     );
+    telemetry::observe_query("get_activity_by_wallet", start.elapsed());
 
-    Ok(())
+    Ok(rows)
 }
 
-pub async fn wallet_exists(client: &Client, wallet: &str) -> AppResult<bool> {
+/// Fetch a wallet's transactions within a time window, oldest first.
+pub async fn get_wallet_transactions(
+    client: &Client,
+    wallet: &str,
+    window: TimeWindow,
+) -> AppResult<Vec<TransactionRow>> {
     let start = Instant::now();
-    tracing::debug!(wallet = %wallet, query = "wallet_exists", "Executing database query");
-
-    let query = r#"
-        SELECT count(*) as cnt FROM wallet_summaries FINAL WHERE wallet = ?
-    "#;
+    tracing::debug!(
+        wallet = %wallet,
+        window = ?window,
+        query = "get_wallet_transactions",
+        "Executing database query"
+    );
 
-    #[derive(clickhouse::Row, serde::Deserialize)]
-    struct CountResult {
-        cnt: u64,
-    }
+    let query = format!(
+        r#"
+        SELECT
+            signature,
+            wallet,
+            protocol,
+            tx_type,
+            token_in,
+            token_out,
+            toString(amount_in) AS amount_in,
+            toString(amount_out) AS amount_out,
+            toString(usd_value) AS usd_value,
+            toUnixTimestamp64Milli(block_time) AS block_time,
+            slot
+        FROM transactions
+        WHERE wallet = ?
+        {}
+        ORDER BY block_time ASC, signature ASC
+        "#,
+        block_time_filter(window)
+    );
 
-    let result: Vec<CountResult> = client
-        .query(query)
+    let transactions: Vec<TransactionRow> = client
+        .query(&query)
         .bind(wallet)
         .fetch_all()
         .await?;
 
-    let exists = result.first().map(|r| r.cnt > 0).unwrap_or(false);
-
     tracing::debug!(
         wallet = %wallet,
-        query = "wallet_exists",
+        query = "get_wallet_transactions",
         duration_ms = %start.elapsed().as_millis(),
-        exists = %exists,
+        row_count = %transactions.len(),
         "Database query completed"
     );
+    telemetry::observe_query("get_wallet_transactions", start.elapsed());
 
-    Ok(exists)
+    Ok(transactions)
+}
+
+/// One page of a wallet's transactions matching `filter`, in the filter's order.
+pub async fn get_transaction_page(
+    client: &Client,
+    wallet: &str,
+    filter: &TransactionFilter,
+) -> AppResult<Vec<TransactionRow>> {
+    let start = Instant::now();
+    tracing::debug!(
+        wallet = %wallet,
+        filter = ?filter,
+        query = "get_transaction_page",
+        "Executing database query"
+    );
+
+    let mut conditions = String::new();
+    if filter.protocol.is_some() {
+        conditions.push_str(" AND protocol = ?");
+    }
+    if filter.tx_type.is_some() {
+        conditions.push_str(" AND tx_type = ?");
+    }
+    if filter.token.is_some() {
+        conditions.push_str(" AND (token_in = ? OR token_out = ?)");
+    }
+    if filter.from.is_some() {
+        conditions.push_str(" AND toUnixTimestamp64Milli(block_time) >= ?");
+    }
+    if filter.to.is_some() {
+        conditions.push_str(" AND toUnixTimestamp64Milli(block_time) < ?");
+    }
+    if filter.min_usd_value.is_some() {
+        conditions.push_str(" AND usd_value >= toDecimal64(?, 2)");
+    }
+    let (cursor_filter, order) = match filter.sort {
+        TransactionSort::Newest => (
+            "AND (toUnixTimestamp64Milli(block_time), signature) < (?, ?)",
+            "block_time DESC, signature DESC",
+        ),
+        TransactionSort::Oldest => (
+            "AND (toUnixTimestamp64Milli(block_time), signature) > (?, ?)",
+            "block_time ASC, signature ASC",
+        ),
+        TransactionSort::Largest => (
+            "AND (usd_value, toUnixTimestamp64Milli(block_time), signature) < (toDecimal64(?, 2), ?, ?)",
+            "usd_value DESC, block_time DESC, signature DESC",
+        ),
+    };
+    if filter.after.is_some() {
+        conditions.push(' ');
+        conditions.push_str(cursor_filter);
+    }
+
+    let query = format!(
+        r#"
+        SELECT
+            signature,
+            wallet,
+            protocol,
+            tx_type,
+            token_in,
+            token_out,
+            toString(amount_in) AS amount_in,
+            toString(amount_out) AS amount_out,
+            toString(usd_value) AS usd_value,
+            toUnixTimestamp64Milli(block_time) AS block_time,
+            slot
+        FROM transactions
+        WHERE wallet = ?
+        {}
+        ORDER BY {}
+        LIMIT ?
+        "#,
+        conditions, order
+    );
+
+    let mut q = client.query(&query).bind(wallet);
+    if let Some(protocol) = &filter.protocol {
+        q = q.bind(protocol);
+    }
+    if let Some(tx_type) = &filter.tx_type {
+        q = q.bind(tx_type);
+    }
+    if let Some(token) = &filter.token {
+        q = q.bind(token).bind(token);
+    }
+    if let Some(from) = filter.from {
+        q = q.bind(from);
+    }
+    if let Some(to) = filter.to {
+        q = q.bind(to);
+    }
+    if let Some(min_usd_value) = &filter.min_usd_value {
+        q = q.bind(min_usd_value);
+    }
+    if let Some(cursor) = &filter.after {
+        if filter.sort == TransactionSort::Largest {
+            q = q.bind(&cursor.usd_value);
+        }
+        q = q.bind(cursor.block_time).bind(&cursor.signature);
+    }
+    let transactions: Vec<TransactionRow> = q.bind(filter.limit).fetch_all().await?;
+
+    tracing::debug!(
+        wallet = %wallet,
+        query = "get_transaction_page",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %transactions.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_transaction_page", start.elapsed());
+
+    Ok(transactions)
+}
+
+/// Fetch the last recorded price per day for each token over the past `days` days.
+pub async fn get_token_daily_closes(
+    client: &Client,
+    tokens: &[String],
+    days: u32,
+) -> AppResult<Vec<TokenDailyCloseRow>> {
+    let start = Instant::now();
+    tracing::debug!(
+        token_count = %tokens.len(),
+        days = %days,
+        query = "get_token_daily_closes",
+        "Executing database query"
+    );
+
+    if tokens.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = r#"
+        SELECT
+            token,
+            toString(toDate(timestamp)) AS day,
+            toString(argMax(price_usd, timestamp)) AS price_usd
+        FROM token_price_history
+        WHERE has(?, token)
+          AND timestamp >= now64(3) - toIntervalDay(?)
+        GROUP BY token, toDate(timestamp)
+        ORDER BY token, day
+    "#;
+
+    let closes: Vec<TokenDailyCloseRow> = client
+        .query(query)
+        .bind(tokens)
+        .bind(days)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        query = "get_token_daily_closes",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %closes.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_token_daily_closes", start.elapsed());
+
+    Ok(closes)
+}
+
+pub async fn upsert_wallet_summary(client: &Client, summary: &WalletSummaryRow) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(
+        wallet = %summary.wallet,
+        query = "upsert_wallet_summary",
+        "Executing database insert"
+    );
+
+    let query = r#"
+        INSERT INTO wallet_summaries (
+            wallet, total_value_usd, realized_pnl_24h, realized_pnl_7d, realized_pnl_30d,
+            unrealized_pnl, largest_position_pct, protocol_count, position_count,
+            risk_score, last_activity, protocols, leverage, liquidation_distance,
+            stablecoin_pct, memecoin_pct, concentration_risk, leverage_risk,
+            liquidation_risk, asset_risk, contract_risk, var_95, var_99,
+            expected_shortfall_95, expected_shortfall_99, portfolio_volatility, var_coverage, classification,
+            classification_confidence, classification_features, mev_loss_usd,
+            sandwiched_count, round_trip_count, jito_tip_count, snipe_count, protocol_ltv, asset_exposure, updated_at
+        ) VALUES (
+            ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?,
+            ?, ?, ?, ?, ?, ?, ?, ?, now64(3)
+        )
+    "#;
+
+    client
+        .query(query)
+        .bind(&summary.wallet)
+        .bind(&summary.total_value_usd)
+        .bind(&summary.realized_pnl_24h)
+        .bind(&summary.realized_pnl_7d)
+        .bind(&summary.realized_pnl_30d)
+        .bind(&summary.unrealized_pnl)
+        .bind(&summary.largest_position_pct)
+        .bind(summary.protocol_count)
+        .bind(summary.position_count)
+        .bind(summary.risk_score)
+        .bind(summary.last_activity)
+        .bind(&summary.protocols)
+        .bind(&summary.leverage)
+        .bind(&summary.liquidation_distance)
+        .bind(&summary.stablecoin_pct)
+        .bind(&summary.memecoin_pct)
+        .bind(summary.concentration_risk)
+        .bind(summary.leverage_risk)
+        .bind(summary.liquidation_risk)
+        .bind(summary.asset_risk)
+        .bind(summary.contract_risk)
+        .bind(&summary.var_95)
+        .bind(&summary.var_99)
+        .bind(&summary.expected_shortfall_95)
+        .bind(&summary.expected_shortfall_99)
+        .bind(&summary.portfolio_volatility)
+        .bind(&summary.var_coverage)
+        .bind(&summary.classification)
+        .bind(&summary.classification_confidence)
+        .bind(&summary.classification_features)
+        .bind(&summary.mev_loss_usd)
+        .bind(summary.sandwiched_count)
+        .bind(summary.round_trip_count)
+        .bind(summary.jito_tip_count)
+        .bind(summary.snipe_count)
+        .bind(&summary.protocol_ltv)
+        .bind(&summary.asset_exposure)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        wallet = %summary.wallet,
+        query = "upsert_wallet_summary",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("upsert_wallet_summary", start.elapsed());
+
+    Ok(())
+}
+
+pub async fn upsert_position(client: &Client, position: &PositionRow) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(
+        wallet = %position.wallet,
+        protocol = %position.protocol,
+        token = %position.token,
+        query = "upsert_position",
+        "Executing database insert"
+    );
+
+    let query = r#"
+        INSERT INTO positions (
+            wallet, protocol, position_type, token, pool, amount,
+            entry_price, current_price, usd_value, unrealized_pnl, apy, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, now64(3))
+    "#;
+
+    client
+        .query(query)
+        .bind(&position.wallet)
+        .bind(&position.protocol)
+        .bind(&position.position_type)
+        .bind(&position.token)
+        .bind(&position.pool)
+        .bind(&position.amount)
+        .bind(&position.entry_price)
+        .bind(&position.current_price)
+        .bind(&position.usd_value)
+        .bind(&position.unrealized_pnl)
+        .bind(&position.apy)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        wallet = %position.wallet,
+        query = "upsert_position",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("upsert_position", start.elapsed());
+
+    Ok(())
+}
+
+/// Swaps by other wallets in the given slots, for sandwich detection.
+pub async fn get_slot_swaps(client: &Client, wallet: &str, slots: &[u64]) -> AppResult<Vec<TransactionRow>> {
+    let start = Instant::now();
+    tracing::debug!(
+        wallet = %wallet,
+        slot_count = %slots.len(),
+        query = "get_slot_swaps",
+        "Executing database query"
+    );
+
+    if slots.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = r#"
+        SELECT
+            signature,
+            wallet,
+            protocol,
+            tx_type,
+            token_in,
+            token_out,
+            toString(amount_in) AS amount_in,
+            toString(amount_out) AS amount_out,
+            toString(usd_value) AS usd_value,
+            toUnixTimestamp64Milli(block_time) AS block_time,
+            slot
+        FROM transactions
+        WHERE has(?, slot)
+          AND wallet != ?
+          AND tx_type = 'swap'
+    "#;
+
+    let swaps: Vec<TransactionRow> = client
+        .query(query)
+        .bind(slots)
+        .bind(wallet)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        wallet = %wallet,
+        query = "get_slot_swaps",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %swaps.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_slot_swaps", start.elapsed());
+
+    Ok(swaps)
+}
+
+/// Earliest recorded creation slot for each of the given mints.
+pub async fn get_token_launches(client: &Client, mints: &[String]) -> AppResult<Vec<TokenLaunchRow>> {
+    let start = Instant::now();
+    tracing::debug!(mint_count = %mints.len(), query = "get_token_launches", "Executing database query");
+
+    if mints.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = r#"
+        SELECT mint, min(slot) AS slot
+        FROM token_launches
+        WHERE has(?, mint)
+        GROUP BY mint
+    "#;
+
+    let launches: Vec<TokenLaunchRow> = client
+        .query(query)
+        .bind(mints)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        query = "get_token_launches",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %launches.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_token_launches", start.elapsed());
+
+    Ok(launches)
+}
+
+pub async fn record_token_launch(client: &Client, mint: &str, pool: &str, slot: u64, block_time: i64) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(mint = %mint, slot = %slot, query = "record_token_launch", "Executing database insert");
+
+    let query = r#"
+        INSERT INTO token_launches (mint, pool, slot, block_time)
+        VALUES (?, ?, ?, fromUnixTimestamp64Milli(?))
+    "#;
+
+    client
+        .query(query)
+        .bind(mint)
+        .bind(pool)
+        .bind(slot)
+        .bind(block_time)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        mint = %mint,
+        query = "record_token_launch",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("record_token_launch", start.elapsed());
+
+    Ok(())
+}
+
+pub async fn insert_transaction_flag(client: &Client, flag: &TransactionFlagRow) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(
+        wallet = %flag.wallet,
+        signature = %flag.signature,
+        flag = %flag.flag,
+        query = "insert_transaction_flag",
+        "Executing database insert"
+    );
+
+    let query = r#"
+        INSERT INTO transaction_flags (
+            signature, wallet, flag, counterparty, estimated_loss_usd, slot, block_time
+        ) VALUES (?, ?, ?, ?, ?, ?, fromUnixTimestamp64Milli(?))
+    "#;
+
+    client
+        .query(query)
+        .bind(&flag.signature)
+        .bind(&flag.wallet)
+        .bind(&flag.flag)
+        .bind(&flag.counterparty)
+        .bind(&flag.estimated_loss_usd)
+        .bind(flag.slot)
+        .bind(flag.block_time)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        wallet = %flag.wallet,
+        query = "insert_transaction_flag",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("insert_transaction_flag", start.elapsed());
+
+    Ok(())
+}
+
+/// Store flags in a single insert.
+pub async fn insert_transaction_flags(client: &Client, flags: &[TransactionFlagRow]) -> AppResult<()> {
+    if flags.is_empty() {
+        return Ok(());
+    }
+    let start = Instant::now();
+    tracing::debug!(flag_count = %flags.len(), query = "insert_transaction_flags", "Executing database insert");
+
+    let values = vec!["(?, ?, ?, ?, ?, ?, fromUnixTimestamp64Milli(?))"; flags.len()].join(", ");
+    let query = format!(
+        r#"
+        INSERT INTO transaction_flags (
+            signature, wallet, flag, counterparty, estimated_loss_usd, slot, block_time
+        ) VALUES {}
+        "#,
+        values
+    );
+
+    let mut insert = client.query(&query);
+    for flag in flags {
+        insert = insert
+            .bind(&flag.signature)
+            .bind(&flag.wallet)
+            .bind(&flag.flag)
+            .bind(&flag.counterparty)
+            .bind(&flag.estimated_loss_usd)
+            .bind(flag.slot)
+            .bind(flag.block_time);
+    }
+    insert.execute().await?;
+
+    tracing::debug!(
+        flag_count = %flags.len(),
+        query = "insert_transaction_flags",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("insert_transaction_flags", start.elapsed());
+
+    Ok(())
+}
+
+/// Flags already stored for any of a wallet's `signatures`.
+pub async fn get_transaction_flags(client: &Client, wallet: &str, signatures: &[String]) -> AppResult<Vec<TransactionFlagRow>> {
+    let start = Instant::now();
+    tracing::debug!(
+        wallet = %wallet,
+        signature_count = %signatures.len(),
+        query = "get_transaction_flags",
+        "Executing database query"
+    );
+
+    let query = r#"
+        SELECT
+            signature,
+            wallet,
+            flag,
+            counterparty,
+            toString(estimated_loss_usd) AS estimated_loss_usd,
+            slot,
+            toUnixTimestamp64Milli(block_time) AS block_time
+        FROM transaction_flags FINAL
+        WHERE wallet = ?
+        AND has(?, signature)
+    "#;
+
+    let flags: Vec<TransactionFlagRow> = client
+        .query(query)
+        .bind(wallet)
+        .bind(signatures)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        wallet = %wallet,
+        query = "get_transaction_flags",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %flags.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_transaction_flags", start.elapsed());
+
+    Ok(flags)
+}
+
+/// Number of distinct transactions carrying `flag` for a wallet.
+pub async fn count_wallet_flags(client: &Client, wallet: &str, flag: &str) -> AppResult<u64> {
+    let start = Instant::now();
+    tracing::debug!(wallet = %wallet, flag = %flag, query = "count_wallet_flags", "Executing database query");
+
+    let query = r#"
+        SELECT uniqExact(signature) FROM transaction_flags WHERE wallet = ? AND flag = ?
+    "#;
+
+    let count: u64 = client
+        .query(query)
+        .bind(wallet)
+        .bind(flag)
+        .fetch_one()
+        .await?;
+
+    tracing::debug!(
+        wallet = %wallet,
+        query = "count_wallet_flags",
+        duration_ms = %start.elapsed().as_millis(),
+        count = %count,
+        "Database query completed"
+    );
+    telemetry::observe_query("count_wallet_flags", start.elapsed());
+
+    Ok(count)
+}
+
+/// Estimated USD lost to sandwich attacks within a time window.
+pub async fn get_wallet_mev_losses(client: &Client, wallet: &str, window: TimeWindow) -> AppResult<MevLossRow> {
+    let start = Instant::now();
+    tracing::debug!(
+        wallet = %wallet,
+        window = ?window,
+        query = "get_wallet_mev_losses",
+        "Executing database query"
+    );
+
+    let query = format!(
+        r#"
+        SELECT
+            toString(sum(estimated_loss_usd)) AS estimated_loss_usd,
+            count() AS sandwiched_count
+        FROM transaction_flags FINAL
+        WHERE wallet = ?
+          AND flag = 'sandwiched'
+        {}
+        "#,
+        block_time_filter(window)
+    );
+
+    let losses: MevLossRow = client
+        .query(&query)
+        .bind(wallet)
+        .fetch_one()
+        .await?;
+
+    tracing::debug!(
+        wallet = %wallet,
+        query = "get_wallet_mev_losses",
+        duration_ms = %start.elapsed().as_millis(),
+        sandwiched_count = %losses.sandwiched_count,
+        "Database query completed"
+    );
+    telemetry::observe_query("get_wallet_mev_losses", start.elapsed());
+
+    Ok(losses)
+}
+
+/// Sandwich losses for many wallets in one query. Wallets without any are left out.
+pub async fn get_mev_losses_for_wallets(client: &Client, wallets: &[String], window: TimeWindow) -> AppResult<Vec<WalletMevLossRow>> {
+    let start = Instant::now();
+    tracing::debug!(
+        wallet_count = %wallets.len(),
+        window = ?window,
+        query = "get_mev_losses_for_wallets",
+        "Executing database query"
+    );
+
+    if wallets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = format!(
+        r#"
+        SELECT
+            wallet,
+            toString(sum(estimated_loss_usd)) AS estimated_loss_usd,
+            count() AS sandwiched_count
+        FROM transaction_flags FINAL
+        WHERE has(?, wallet)
+          AND flag = 'sandwiched'
+        {}
+        GROUP BY wallet
+        "#,
+        block_time_filter(window)
+    );
+
+    let losses: Vec<WalletMevLossRow> = client
+        .query(&query)
+        .bind(wallets)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        query = "get_mev_losses_for_wallets",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %losses.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_mev_losses_for_wallets", start.elapsed());
+
+    Ok(losses)
+}
+
+pub async fn insert_wallet_link(client: &Client, link: &WalletLinkRow) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(
+        source = %link.source,
+        target = %link.target,
+        link_type = %link.link_type,
+        query = "insert_wallet_link",
+        "Executing database insert"
+    );
+
+    let query = r#"
+        INSERT INTO wallet_links (
+            source, target, link_type, mint, amount, signature, block_time
+        ) VALUES (?, ?, ?, ?, ?, ?, fromUnixTimestamp64Milli(?))
+    "#;
+
+    client
+        .query(query)
+        .bind(&link.source)
+        .bind(&link.target)
+        .bind(&link.link_type)
+        .bind(&link.mint)
+        .bind(&link.amount)
+        .bind(&link.signature)
+        .bind(link.block_time)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        signature = %link.signature,
+        query = "insert_wallet_link",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("insert_wallet_link", start.elapsed());
+
+    Ok(())
+}
+
+/// All links where any of the given wallets is the source or the target.
+pub async fn get_wallet_links(client: &Client, wallets: &[String]) -> AppResult<Vec<WalletLinkRow>> {
+    let start = Instant::now();
+    tracing::debug!(wallet_count = %wallets.len(), query = "get_wallet_links", "Executing database query");
+
+    if wallets.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = r#"
+        SELECT
+            source,
+            target,
+            link_type,
+            mint,
+            toString(amount) AS amount,
+            signature,
+            toUnixTimestamp64Milli(block_time) AS block_time
+        FROM wallet_links FINAL
+        WHERE has(?, source) OR has(?, target)
+        ORDER BY block_time ASC
+    "#;
+
+    let links: Vec<WalletLinkRow> = client
+        .query(query)
+        .bind(wallets)
+        .bind(wallets)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        query = "get_wallet_links",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %links.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_wallet_links", start.elapsed());
+
+    Ok(links)
+}
+
+/// Number of distinct wallets each of the given sources has linked to, used to tell
+/// exchanges and relayers apart from personal funding wallets.
+pub async fn get_link_fan_out(client: &Client, sources: &[String]) -> AppResult<Vec<LinkFanOutRow>> {
+    let start = Instant::now();
+    tracing::debug!(source_count = %sources.len(), query = "get_link_fan_out", "Executing database query");
+
+    if sources.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = r#"
+        SELECT source, uniqExact(target) AS targets
+        FROM wallet_links
+        WHERE has(?, source)
+        GROUP BY source
+    "#;
+
+    let fan_out: Vec<LinkFanOutRow> = client
+        .query(query)
+        .bind(sources)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        query = "get_link_fan_out",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %fan_out.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_link_fan_out", start.elapsed());
+
+    Ok(fan_out)
+}
+
+/// Latest known USD price for each of the given tokens.
+pub async fn get_token_prices(client: &Client, tokens: &[String]) -> AppResult<Vec<TokenPriceRow>> {
+    let start = Instant::now();
+    tracing::debug!(token_count = %tokens.len(), query = "get_token_prices", "Executing database query");
+
+    if tokens.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = r#"
+        SELECT token, toString(price_usd) AS price_usd
+        FROM token_prices FINAL
+        WHERE has(?, token)
+    "#;
+
+    let prices: Vec<TokenPriceRow> = client
+        .query(query)
+        .bind(tokens)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        query = "get_token_prices",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %prices.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_token_prices", start.elapsed());
+
+    Ok(prices)
+}
+
+/// Record a token's USD price as of `at` (ms). Each price is also copied into
+/// `token_price_history`.
+pub async fn record_token_price(client: &Client, token: &str, price_usd: &str, at: i64) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(token = %token, price_usd = %price_usd, query = "record_token_price", "Executing database insert");
+
+    let query = r#"
+        INSERT INTO token_prices (token, price_usd, updated_at)
+        VALUES (?, ?, fromUnixTimestamp64Milli(?))
+    "#;
+
+    client
+        .query(query)
+        .bind(token)
+        .bind(price_usd)
+        .bind(at)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        token = %token,
+        query = "record_token_price",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("record_token_price", start.elapsed());
+
+    Ok(())
+}
+
+pub async fn insert_position_signal(client: &Client, signal: &PositionSignalRow) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(
+        wallet = %signal.wallet,
+        signature = %signal.signature,
+        kind = %signal.kind,
+        query = "insert_position_signal",
+        "Executing database insert"
+    );
+
+    let query = r#"
+        INSERT INTO position_signals (
+            wallet, signature, token, kind, amount, price, usd_value,
+            position_size, portfolio_weight, slot, block_time
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, fromUnixTimestamp64Milli(?))
+    "#;
+
+    client
+        .query(query)
+        .bind(&signal.wallet)
+        .bind(&signal.signature)
+        .bind(&signal.token)
+        .bind(&signal.kind)
+        .bind(&signal.amount)
+        .bind(&signal.price)
+        .bind(&signal.usd_value)
+        .bind(&signal.position_size)
+        .bind(&signal.portfolio_weight)
+        .bind(signal.slot)
+        .bind(signal.block_time)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        wallet = %signal.wallet,
+        query = "insert_position_signal",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("insert_position_signal", start.elapsed());
+
+    Ok(())
+}
+
+/// Position signals newest first, optionally for a set of wallets (all when empty),
+/// starting strictly after the `before` cursor `(block_time, signature, token)`.
+pub async fn get_position_signals(
+    client: &Client,
+    wallets: &[String],
+    before: Option<&(i64, String, String)>,
+    limit: u32,
+) -> AppResult<Vec<PositionSignalRow>> {
+    let start = Instant::now();
+    tracing::debug!(
+        wallet_count = %wallets.len(),
+        before = ?before,
+        limit = %limit,
+        query = "get_position_signals",
+        "Executing database query"
+    );
+
+    let wallet_filter = if wallets.is_empty() { "" } else { "AND has(?, wallet)" };
+    let cursor_filter = if before.is_some() {
+        "AND (toUnixTimestamp64Milli(block_time), signature, token) < (?, ?, ?)"
+    } else {
+        ""
+    };
+    let query = format!(
+        r#"
+        SELECT
+            wallet,
+            signature,
+            token,
+            kind,
+            toString(amount) AS amount,
+            toString(price) AS price,
+            toString(usd_value) AS usd_value,
+            toString(position_size) AS position_size,
+            toString(portfolio_weight) AS portfolio_weight,
+            slot,
+            toUnixTimestamp64Milli(block_time) AS block_time
+        FROM position_signals FINAL
+        WHERE 1 = 1
+        {}
+        {}
+        ORDER BY block_time DESC, signature DESC, token DESC
+        LIMIT ?
+        "#,
+        wallet_filter, cursor_filter
+    );
+
+    let mut q = client.query(&query);
+    if !wallets.is_empty() {
+        q = q.bind(wallets);
+    }
+    if let Some((block_time, signature, token)) = before {
+        q = q.bind(*block_time).bind(signature).bind(token);
+    }
+    let signals: Vec<PositionSignalRow> = q.bind(limit).fetch_all().await?;
+
+    tracing::debug!(
+        query = "get_position_signals",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %signals.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_position_signals", start.elapsed());
+
+    Ok(signals)
+}
+
+/// Store closed trades in a single insert.
+pub async fn insert_closed_trades(client: &Client, trades: &[ClosedTradeRow]) -> AppResult<()> {
+    if trades.is_empty() {
+        return Ok(());
+    }
+    let start = Instant::now();
+    tracing::debug!(trade_count = %trades.len(), query = "insert_closed_trades", "Executing database insert");
+
+    let values = vec!["(?, ?, ?, ?, ?, ?, fromUnixTimestamp64Milli(?))"; trades.len()].join(", ");
+    let query = format!(
+        r#"
+        INSERT INTO closed_trades (
+            wallet, signature, protocol, token, cost_basis, pnl, block_time
+        ) VALUES {}
+        "#,
+        values
+    );
+
+    let mut insert = client.query(&query);
+    for trade in trades {
+        insert = insert
+            .bind(&trade.wallet)
+            .bind(&trade.signature)
+            .bind(&trade.protocol)
+            .bind(&trade.token)
+            .bind(&trade.cost_basis)
+            .bind(&trade.pnl)
+            .bind(trade.block_time);
+    }
+    insert.execute().await?;
+
+    tracing::debug!(
+        trade_count = %trades.len(),
+        query = "insert_closed_trades",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("insert_closed_trades", start.elapsed());
+
+    Ok(())
+}
+
+/// Rank indexed wallets by the performance of trades closed within the filter's window.
+///
+/// Sharpe is computed the same way as the per-wallet performance endpoint: daily
+/// returns (PnL over cost basis sold that day) from the first to the last trading day,
+/// idle days counted as zero, annualized over 365 days.
+pub async fn get_leaderboard(client: &Client, filter: &LeaderboardFilter) -> AppResult<Vec<LeaderboardRow>> {
+    let start = Instant::now();
+    tracing::debug!(
+        window = ?filter.window,
+        metric = %filter.metric,
+        protocol = ?filter.protocol,
+        wallet_count = ?filter.wallets.as_ref().map(|w| w.len()),
+        min_value_usd = %filter.min_value_usd,
+        min_trades = %filter.min_trades,
+        limit = %filter.limit,
+        query = "get_leaderboard",
+        "Executing database query"
+    );
+
+    let time_filter = block_time_filter(filter.window);
+    let protocol_filter = if filter.protocol.is_some() { "AND protocol = ?" } else { "" };
+    let wallet_filter = if filter.wallets.is_some() { "AND has(?, wallet)" } else { "" };
+    let order_by = match filter.metric {
+        LeaderboardMetric::RealizedPnl => "t.pnl_sum DESC",
+        LeaderboardMetric::Roi => "roi DESC, t.pnl_sum DESC",
+        LeaderboardMetric::WinRate => "win_rate DESC, t.trades DESC",
+        LeaderboardMetric::Sharpe => "sharpe_ratio DESC NULLS LAST, t.pnl_sum DESC",
+    };
+    let query = format!(
+        r#"
+        SELECT
+            t.wallet AS wallet,
+            t.trades AS trade_count,
+            t.wins AS win_count,
+            toString(t.pnl_sum) AS realized_pnl,
+            toString(t.cost_sum) AS cost_basis,
+            if(t.cost_sum > 0, toFloat64(t.pnl_sum) / toFloat64(t.cost_sum), 0) AS roi,
+            t.wins / t.trades AS win_rate,
+            if(t.days > 1 AND t.var_ret > 0, t.mean_ret / sqrt(t.var_ret) * sqrt(365), NULL) AS sharpe_ratio,
+            toString(s.total_value_usd) AS total_value_usd
+        FROM (
+            SELECT
+                wallet,
+                sum(day_trades) AS trades,
+                sum(day_wins) AS wins,
+                sum(day_pnl) AS pnl_sum,
+                sum(day_cost) AS cost_sum,
+                dateDiff('day', min(day), max(day)) + 1 AS days,
+                sum(day_ret) / days AS mean_ret,
+                (sum(day_ret * day_ret) - days * mean_ret * mean_ret) / greatest(days - 1, 1) AS var_ret
+            FROM (
+                SELECT
+                    wallet,
+                    toDate(block_time) AS day,
+                    count() AS day_trades,
+                    countIf(pnl > 0) AS day_wins,
+                    sum(pnl) AS day_pnl,
+                    sum(cost_basis) AS day_cost,
+                    if(day_cost > 0, toFloat64(day_pnl) / toFloat64(day_cost), 0) AS day_ret
+                FROM closed_trades FINAL
+                WHERE 1 = 1
+                {}
+                {}
+                {}
+                GROUP BY wallet, day
+            )
+            GROUP BY wallet
+            HAVING trades >= ?
+        ) AS t
+        INNER JOIN (
+            SELECT wallet, total_value_usd FROM wallet_summaries FINAL
+        ) AS s ON s.wallet = t.wallet
+        WHERE s.total_value_usd >= toDecimal64(?, 2)
+        ORDER BY {}, t.wallet
+        LIMIT ?
+        "#,
+        time_filter, protocol_filter, wallet_filter, order_by
+    );
+
+    let mut q = client.query(&query);
+    if let Some(protocol) = &filter.protocol {
+        q = q.bind(protocol);
+    }
+    if let Some(wallets) = &filter.wallets {
+        q = q.bind(wallets);
+    }
+    let rows: Vec<LeaderboardRow> = q
+        .bind(filter.min_trades)
+        .bind(&filter.min_value_usd)
+        .bind(filter.limit)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        query = "get_leaderboard",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_leaderboard", start.elapsed());
+
+    Ok(rows)
+}
+
+/// Per-wallet swap volume into and out of `mint` within `window`, optionally only by `wallets`
+pub async fn get_token_flows(
+    client: &Client,
+    mint: &str,
+    window: TimeWindow,
+    wallets: Option<&[String]>,
+) -> AppResult<Vec<TokenFlowRow>> {
+    let start = Instant::now();
+    tracing::debug!(
+        mint = %mint,
+        window = ?window,
+        wallet_count = ?wallets.map(|w| w.len()),
+        query = "get_token_flows",
+        "Executing database query"
+    );
+
+    let time_filter = block_time_filter(window);
+    let wallet_filter = if wallets.is_some() { "AND has(?, wallet)" } else { "" };
+    let query = format!(
+        r#"
+        SELECT
+            f.wallet AS wallet,
+            toString(f.bought) AS bought,
+            toString(f.sold) AS sold,
+            f.buy_count AS buy_count,
+            f.sell_count AS sell_count,
+            s.classification AS classification
+        FROM (
+            SELECT
+                wallet,
+                sumIf(amount_out, token_out = ?) AS bought,
+                sumIf(amount_in, token_in = ?) AS sold,
+                countIf(token_out = ?) AS buy_count,
+                countIf(token_in = ?) AS sell_count
+            FROM transactions
+            WHERE tx_type = 'swap'
+            AND (token_in = ? OR token_out = ?)
+            {}
+            {}
+            GROUP BY wallet
+        ) AS f
+        LEFT JOIN (
+            SELECT wallet, classification FROM wallet_summaries FINAL
+        ) AS s ON s.wallet = f.wallet
+        "#,
+        wallet_filter, time_filter
+    );
+
+    let mut q = client
+        .query(&query)
+        .bind(mint)
+        .bind(mint)
+        .bind(mint)
+        .bind(mint)
+        .bind(mint)
+        .bind(mint);
+    if let Some(wallets) = wallets {
+        q = q.bind(wallets);
+    }
+    let flows: Vec<TokenFlowRow> = q.fetch_all().await?;
+
+    tracing::debug!(
+        mint = %mint,
+        query = "get_token_flows",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %flows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_token_flows", start.elapsed());
+
+    Ok(flows)
+}
+
+/// Record a wallet's subscription as started (`active`) or stopped.
+pub async fn set_wallet_subscription(client: &Client, wallet: &str, active: bool) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(wallet = %wallet, active = %active, query = "set_wallet_subscription", "Executing database insert");
+
+    client
+        .query("INSERT INTO wallet_subscriptions (wallet, active, updated_at) VALUES (?, ?, now64(3))")
+        .bind(wallet)
+        .bind(u8::from(active))
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        wallet = %wallet,
+        query = "set_wallet_subscription",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("set_wallet_subscription", start.elapsed());
+
+    Ok(())
+}
+
+/// Wallets whose latest subscription record is active.
+pub async fn get_subscribed_wallets(client: &Client) -> AppResult<Vec<String>> {
+    let start = Instant::now();
+    tracing::debug!(query = "get_subscribed_wallets", "Executing database query");
+
+    let wallets: Vec<String> = client
+        .query("SELECT wallet FROM wallet_subscriptions FINAL WHERE active = 1 ORDER BY wallet")
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        query = "get_subscribed_wallets",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %wallets.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_subscribed_wallets", start.elapsed());
+
+    Ok(wallets)
+}
+
+/// Write the current state of a webhook, replacing earlier rows for its id.
+pub async fn save_webhook(client: &Client, webhook: &WebhookRow) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(webhook_id = %webhook.id, query = "save_webhook", "Executing database insert");
+
+    let query = r#"
+        INSERT INTO webhooks (
+            id, url, secret, wallet, event_types, min_usd_value, risk_threshold,
+            active, consecutive_failures, deleted, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, fromUnixTimestamp64Milli(?), now64(3))
+    "#;
+
+    client
+        .query(query)
+        .bind(&webhook.id)
+        .bind(&webhook.url)
+        .bind(&webhook.secret)
+        .bind(&webhook.wallet)
+        .bind(&webhook.event_types)
+        .bind(&webhook.min_usd_value)
+        .bind(webhook.risk_threshold)
+        .bind(webhook.active)
+        .bind(webhook.consecutive_failures)
+        .bind(webhook.deleted)
+        .bind(webhook.created_at)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        webhook_id = %webhook.id,
+        query = "save_webhook",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("save_webhook", start.elapsed());
+
+    Ok(())
+}
+
+/// Every webhook that hasn't been deleted, oldest first.
+pub async fn get_webhooks(client: &Client) -> AppResult<Vec<WebhookRow>> {
+    let start = Instant::now();
+    tracing::debug!(query = "get_webhooks", "Executing database query");
+
+    let query = r#"
+        SELECT
+            id,
+            url,
+            secret,
+            wallet,
+            event_types,
+            toString(min_usd_value) AS min_usd_value,
+            risk_threshold,
+            active,
+            consecutive_failures,
+            deleted,
+            toUnixTimestamp64Milli(created_at) AS created_at
+        FROM webhooks FINAL
+        WHERE deleted = 0
+        ORDER BY created_at, id
+    "#;
+
+    let rows: Vec<WebhookRow> = client.query(query).fetch_all().await?;
+
+    tracing::debug!(
+        query = "get_webhooks",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_webhooks", start.elapsed());
+
+    Ok(rows)
+}
+
+pub async fn insert_webhook_delivery(client: &Client, delivery: &WebhookDeliveryRow) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(
+        webhook_id = %delivery.webhook_id,
+        delivery_id = %delivery.delivery_id,
+        attempt = %delivery.attempt,
+        query = "insert_webhook_delivery",
+        "Executing database insert"
+    );
+
+    let query = r#"
+        INSERT INTO webhook_deliveries (
+            webhook_id, delivery_id, event_type, attempt, status_code,
+            error, success, duration_ms, attempted_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, fromUnixTimestamp64Milli(?))
+    "#;
+
+    client
+        .query(query)
+        .bind(&delivery.webhook_id)
+        .bind(&delivery.delivery_id)
+        .bind(&delivery.event_type)
+        .bind(delivery.attempt)
+        .bind(delivery.status_code)
+        .bind(&delivery.error)
+        .bind(delivery.success)
+        .bind(delivery.duration_ms)
+        .bind(delivery.attempted_at)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        webhook_id = %delivery.webhook_id,
+        query = "insert_webhook_delivery",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("insert_webhook_delivery", start.elapsed());
+
+    Ok(())
+}
+
+/// A webhook's most recent delivery attempts, newest first.
+pub async fn get_webhook_deliveries(client: &Client, webhook_id: &str, limit: u32) -> AppResult<Vec<WebhookDeliveryRow>> {
+    let start = Instant::now();
+    tracing::debug!(webhook_id = %webhook_id, limit = %limit, query = "get_webhook_deliveries", "Executing database query");
+
+    let query = r#"
+        SELECT
+            webhook_id,
+            delivery_id,
+            event_type,
+            attempt,
+            status_code,
+            error,
+            success,
+            duration_ms,
+            toUnixTimestamp64Milli(attempted_at) AS attempted_at
+        FROM webhook_deliveries
+        WHERE webhook_id = ?
+        ORDER BY attempted_at DESC, attempt DESC
+        LIMIT ?
+    "#;
+
+    let rows: Vec<WebhookDeliveryRow> = client
+        .query(query)
+        .bind(webhook_id)
+        .bind(limit)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        webhook_id = %webhook_id,
+        query = "get_webhook_deliveries",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_webhook_deliveries", start.elapsed());
+
+    Ok(rows)
+}
+
+/// Write the current state of an API key, replacing earlier rows for its id.
+pub async fn save_api_key(client: &Client, key: &ApiKeyRow) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(key_id = %key.id, query = "save_api_key", "Executing database insert");
+
+    let query = r#"
+        INSERT INTO api_keys (
+            id, name, key_hash, key_prefix, scopes, rate_limit_per_minute,
+            max_subscriptions, revoked, created_at, updated_at
+        ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, fromUnixTimestamp64Milli(?), now64(3))
+    "#;
+
+    client
+        .query(query)
+        .bind(&key.id)
+        .bind(&key.name)
+        .bind(&key.key_hash)
+        .bind(&key.key_prefix)
+        .bind(&key.scopes)
+        .bind(key.rate_limit_per_minute)
+        .bind(key.max_subscriptions)
+        .bind(key.revoked)
+        .bind(key.created_at)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        key_id = %key.id,
+        query = "save_api_key",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("save_api_key", start.elapsed());
+
+    Ok(())
+}
+
+/// Every API key that hasn't been revoked, oldest first.
+pub async fn get_api_keys(client: &Client) -> AppResult<Vec<ApiKeyRow>> {
+    let start = Instant::now();
+    tracing::debug!(query = "get_api_keys", "Executing database query");
+
+    let query = r#"
+        SELECT
+            id,
+            name,
+            key_hash,
+            key_prefix,
+            scopes,
+            rate_limit_per_minute,
+            max_subscriptions,
+            revoked,
+            toUnixTimestamp64Milli(created_at) AS created_at
+        FROM api_keys FINAL
+        WHERE revoked = 0
+        ORDER BY created_at, id
+    "#;
+
+    let rows: Vec<ApiKeyRow> = client.query(query).fetch_all().await?;
+
+    tracing::debug!(
+        query = "get_api_keys",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_api_keys", start.elapsed());
+
+    Ok(rows)
+}
+
+/// Record that a key started (`active`) or no longer holds a wallet subscription.
+pub async fn set_key_subscription(client: &Client, subscription: &KeySubscriptionRow) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(
+        key_id = %subscription.key_id,
+        wallet = %subscription.wallet,
+        active = %subscription.active,
+        query = "set_key_subscription",
+        "Executing database insert"
+    );
+
+    client
+        .query("INSERT INTO api_key_subscriptions (key_id, wallet, active, updated_at) VALUES (?, ?, ?, now64(3))")
+        .bind(&subscription.key_id)
+        .bind(&subscription.wallet)
+        .bind(subscription.active)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        key_id = %subscription.key_id,
+        query = "set_key_subscription",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("set_key_subscription", start.elapsed());
+
+    Ok(())
+}
+
+/// Wallet subscriptions whose latest record for their key is active.
+pub async fn get_key_subscriptions(client: &Client) -> AppResult<Vec<KeySubscriptionRow>> {
+    let start = Instant::now();
+    tracing::debug!(query = "get_key_subscriptions", "Executing database query");
+
+    let query = r#"
+        SELECT key_id, wallet, active
+        FROM api_key_subscriptions FINAL
+        WHERE active = 1
+        ORDER BY key_id, wallet
+    "#;
+
+    let rows: Vec<KeySubscriptionRow> = client.query(query).fetch_all().await?;
+
+    tracing::debug!(
+        query = "get_key_subscriptions",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_key_subscriptions", start.elapsed());
+
+    Ok(rows)
+}
+
+/// Write the current state of an alert rule, replacing earlier rows for its id.
+pub async fn save_alert_rule(client: &Client, rule: &AlertRuleRow) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(rule_id = %rule.id, query = "save_alert_rule", "Executing database insert");
+
+    let query = r#"
+        INSERT INTO alert_rules (id, name, condition, wallets, group_id, deleted, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, ?, fromUnixTimestamp64Milli(?), now64(3))
+    "#;
+
+    client
+        .query(query)
+        .bind(&rule.id)
+        .bind(&rule.name)
+        .bind(&rule.condition)
+        .bind(&rule.wallets)
+        .bind(&rule.group_id)
+        .bind(rule.deleted)
+        .bind(rule.created_at)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        rule_id = %rule.id,
+        query = "save_alert_rule",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("save_alert_rule", start.elapsed());
+
+    Ok(())
+}
+
+/// Every alert rule that hasn't been deleted, oldest first.
+pub async fn get_alert_rules(client: &Client) -> AppResult<Vec<AlertRuleRow>> {
+    let start = Instant::now();
+    tracing::debug!(query = "get_alert_rules", "Executing database query");
+
+    let query = r#"
+        SELECT
+            id,
+            name,
+            condition,
+            wallets,
+            group_id,
+            deleted,
+            toUnixTimestamp64Milli(created_at) AS created_at
+        FROM alert_rules FINAL
+        WHERE deleted = 0
+        ORDER BY created_at, id
+    "#;
+
+    let rows: Vec<AlertRuleRow> = client.query(query).fetch_all().await?;
+
+    tracing::debug!(
+        query = "get_alert_rules",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_alert_rules", start.elapsed());
+
+    Ok(rows)
+}
+
+pub async fn save_alert_state(client: &Client, state: &AlertStateRow) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(
+        rule_id = %state.rule_id,
+        wallet = %state.wallet,
+        state = %state.state,
+        query = "save_alert_state",
+        "Executing database insert"
+    );
+
+    let query = r#"
+        INSERT INTO alert_states (rule_id, wallet, state, value, changed_at)
+        VALUES (?, ?, ?, ?, fromUnixTimestamp64Milli(?))
+    "#;
+
+    client
+        .query(query)
+        .bind(&state.rule_id)
+        .bind(&state.wallet)
+        .bind(&state.state)
+        .bind(&state.value)
+        .bind(state.changed_at)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        rule_id = %state.rule_id,
+        query = "save_alert_state",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("save_alert_state", start.elapsed());
+
+    Ok(())
+}
+
+/// Latest state of every rule for every wallet it has been evaluated against.
+pub async fn get_alert_states(client: &Client) -> AppResult<Vec<AlertStateRow>> {
+    let start = Instant::now();
+    tracing::debug!(query = "get_alert_states", "Executing database query");
+
+    let query = r#"
+        SELECT
+            rule_id,
+            wallet,
+            state,
+            toString(value) AS value,
+            toUnixTimestamp64Milli(changed_at) AS changed_at
+        FROM alert_states FINAL
+        ORDER BY changed_at DESC
+    "#;
+
+    let rows: Vec<AlertStateRow> = client.query(query).fetch_all().await?;
+
+    tracing::debug!(
+        query = "get_alert_states",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_alert_states", start.elapsed());
+
+    Ok(rows)
+}
+
+pub async fn save_wallet_group(client: &Client, group: &WalletGroupRow) -> AppResult<()> {
+    let start = Instant::now();
+    tracing::debug!(group_id = %group.id, query = "save_wallet_group", "Executing database insert");
+
+    let query = r#"
+        INSERT INTO wallet_groups (id, name, wallets, tags, deleted, created_at, updated_at)
+        VALUES (?, ?, ?, ?, ?, fromUnixTimestamp64Milli(?), now64(3))
+    "#;
+
+    client
+        .query(query)
+        .bind(&group.id)
+        .bind(&group.name)
+        .bind(&group.wallets)
+        .bind(&group.tags)
+        .bind(group.deleted)
+        .bind(group.created_at)
+        .execute()
+        .await?;
+
+    tracing::debug!(
+        group_id = %group.id,
+        query = "save_wallet_group",
+        duration_ms = %start.elapsed().as_millis(),
+        "Database insert completed"
+    );
+    telemetry::observe_query("save_wallet_group", start.elapsed());
+
+    Ok(())
+}
+
+/// Every wallet group that hasn't been deleted, oldest first.
+pub async fn get_wallet_groups(client: &Client) -> AppResult<Vec<WalletGroupRow>> {
+    let start = Instant::now();
+    tracing::debug!(query = "get_wallet_groups", "Executing database query");
+
+    let query = r#"
+        SELECT
+            id,
+            name,
+            wallets,
+            tags,
+            deleted,
+            toUnixTimestamp64Milli(created_at) AS created_at
+        FROM wallet_groups FINAL
+        WHERE deleted = 0
+        ORDER BY created_at, id
+    "#;
+
+    let rows: Vec<WalletGroupRow> = client.query(query).fetch_all().await?;
+
+    tracing::debug!(
+        query = "get_wallet_groups",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_wallet_groups", start.elapsed());
+
+    Ok(rows)
+}
+
+/// Prediction markets by slug. Unknown slugs are left out.
+pub async fn get_markets(client: &Client, slugs: &[String]) -> AppResult<Vec<MarketRow>> {
+    let start = Instant::now();
+    tracing::debug!(market_count = %slugs.len(), query = "get_markets", "Executing database query");
+
+    if slugs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = r#"
+        SELECT
+            slug,
+            platform,
+            title,
+            description,
+            category,
+            status,
+            outcome_labels,
+            resolved_outcome,
+            toUnixTimestamp64Milli(end_date) AS end_date
+        FROM markets FINAL
+        WHERE has(?, slug)
+    "#;
+
+    let rows: Vec<MarketRow> = client.query(query).bind(slugs).fetch_all().await?;
+
+    tracing::debug!(
+        query = "get_markets",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_markets", start.elapsed());
+
+    Ok(rows)
+}
+
+/// Price candles of a market over the interval's lookback, oldest first
+pub async fn get_market_candles(client: &Client, slug: &str, interval: CandleInterval) -> AppResult<Vec<MarketCandleRow>> {
+    let start = Instant::now();
+    tracing::debug!(slug = %slug, interval = %interval, query = "get_market_candles", "Executing database query");
+
+    let query = format!(
+        r#"
+        SELECT
+            toUnixTimestamp64Milli(toDateTime64(toStartOfInterval(timestamp, INTERVAL {minutes} MINUTE), 3)) AS interval_start,
+            toString(argMin(price, timestamp)) AS open,
+            toString(max(price)) AS high,
+            toString(min(price)) AS low,
+            toString(argMax(price, timestamp)) AS close,
+            toString(sum(usd_value)) AS volume_usd,
+            toUInt32(count()) AS trade_count
+        FROM market_trades
+        WHERE slug = ?
+        AND timestamp >= now64(3) - INTERVAL {lookback} MINUTE
+        GROUP BY interval_start
+        ORDER BY interval_start ASC
+        "#,
+        minutes = interval.to_minutes(),
+        lookback = interval.lookback_minutes()
+    );
+
+    let rows: Vec<MarketCandleRow> = client.query(&query).bind(slug).fetch_all().await?;
+
+    tracing::debug!(
+        slug = %slug,
+        query = "get_market_candles",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_market_candles", start.elapsed());
+
+    Ok(rows)
+}
+
+/// Trading volume and latest order book depth of several markets. Markets without
+/// trades are left out.
+pub async fn get_market_volumes(client: &Client, slugs: &[String]) -> AppResult<Vec<MarketVolumeRow>> {
+    let start = Instant::now();
+    tracing::debug!(market_count = %slugs.len(), query = "get_market_volumes", "Executing database query");
+
+    if slugs.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = r#"
+        SELECT
+            t.slug AS slug,
+            toString(t.volume_24h) AS volume_24h,
+            toString(t.volume_7d) AS volume_7d,
+            toUInt32(t.trades_24h) AS trades_24h,
+            toUInt32(t.traders_24h) AS unique_traders_24h,
+            toString(t.avg_trade) AS avg_trade_size,
+            toString(o.bid_depth_usd) AS bid_depth_usd,
+            toString(o.ask_depth_usd) AS ask_depth_usd,
+            toString(o.spread) AS spread
+        FROM (
+            SELECT
+                slug,
+                sumIf(usd_value, timestamp >= now64(3) - INTERVAL 24 HOUR) AS volume_24h,
+                sumIf(usd_value, timestamp >= now64(3) - INTERVAL 7 DAY) AS volume_7d,
+                countIf(timestamp >= now64(3) - INTERVAL 24 HOUR) AS trades_24h,
+                uniqExactIf(taker_address, timestamp >= now64(3) - INTERVAL 24 HOUR) AS traders_24h,
+                if(trades_24h > 0, volume_24h / trades_24h, 0) AS avg_trade
+            FROM market_trades
+            WHERE has(?, slug)
+            AND timestamp >= now64(3) - INTERVAL 7 DAY
+            GROUP BY slug
+        ) AS t
+        LEFT JOIN (
+            SELECT
+                slug,
+                argMax(bid_depth_usd, timestamp) AS bid_depth_usd,
+                argMax(ask_depth_usd, timestamp) AS ask_depth_usd,
+                argMax(spread, timestamp) AS spread
+            FROM market_orderbook
+            WHERE has(?, slug)
+            GROUP BY slug
+        ) AS o ON t.slug = o.slug
+    "#;
+
+    let rows: Vec<MarketVolumeRow> = client
+        .query(query)
+        .bind(slugs)
+        .bind(slugs)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        query = "get_market_volumes",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_market_volumes", start.elapsed());
+
+    Ok(rows)
+}
+
+/// Outcome prices in the last 24 hours more than `min_z_score` standard deviations from
+/// their trailing one-hour mean, newest first
+pub async fn get_market_anomalies(client: &Client, slug: &str, min_z_score: f64) -> AppResult<Vec<MarketAnomalyRow>> {
+    let start = Instant::now();
+    tracing::debug!(slug = %slug, min_z_score = %min_z_score, query = "get_market_anomalies", "Executing database query");
+
+    let query = r#"
+        SELECT
+            outcome_token,
+            toUnixTimestamp64Milli(timestamp) AS timestamp,
+            toString(price) AS price,
+            toString(mean_price) AS mean_price,
+            toString(std_dev) AS std_dev,
+            (toFloat64(price) - mean_price) / std_dev AS z_score
+        FROM (
+            SELECT
+                outcome_token,
+                timestamp,
+                price,
+                avg(toFloat64(price)) OVER w AS mean_price,
+                stddevPop(toFloat64(price)) OVER w AS std_dev
+            FROM market_prices
+            WHERE slug = ?
+            AND timestamp >= now64(3) - INTERVAL 24 HOUR
+            WINDOW w AS (PARTITION BY outcome_token ORDER BY toDateTime(timestamp) RANGE BETWEEN 3600 PRECEDING AND CURRENT ROW)
+        )
+        WHERE std_dev > 0
+        AND abs((toFloat64(price) - mean_price) / std_dev) > ?
+        ORDER BY timestamp DESC
+    "#;
+
+    let rows: Vec<MarketAnomalyRow> = client
+        .query(query)
+        .bind(slug)
+        .bind(min_z_score)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        slug = %slug,
+        query = "get_market_anomalies",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_market_anomalies", start.elapsed());
+
+    Ok(rows)
+}
+
+/// Open prediction market holdings of several traders, from the trades they took.
+/// Outcomes a trader has sold out of are left out.
+pub async fn get_prediction_positions(client: &Client, traders: &[String]) -> AppResult<Vec<PredictionPositionRow>> {
+    let start = Instant::now();
+    tracing::debug!(trader_count = %traders.len(), query = "get_prediction_positions", "Executing database query");
+
+    if traders.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let query = r#"
+        SELECT
+            h.trader AS trader,
+            h.slug AS slug,
+            m.platform AS platform,
+            m.title AS title,
+            m.category AS category,
+            m.status AS status,
+            if(indexOf(m.outcome_tokens, h.outcome_token) > 0,
+               m.outcome_labels[indexOf(m.outcome_tokens, h.outcome_token)],
+               h.outcome_token) AS outcome,
+            toString(h.shares) AS shares,
+            toString(h.cost_usd) AS cost_usd,
+            toString(if(h.bought > 0, h.bought_usd / h.bought, 0)) AS avg_price,
+            toString(p.price) AS current_price,
+            toUnixTimestamp64Milli(h.first_trade_at) AS first_trade_at,
+            toUnixTimestamp64Milli(m.end_date) AS end_date
+        FROM (
+            SELECT
+                taker_address AS trader,
+                slug,
+                outcome_token,
+                sumIf(quantity, side = 'buy') - sumIf(quantity, side = 'sell') AS shares,
+                sumIf(usd_value, side = 'buy') - sumIf(usd_value, side = 'sell') AS cost_usd,
+                sumIf(quantity, side = 'buy') AS bought,
+                sumIf(usd_value, side = 'buy') AS bought_usd,
+                min(timestamp) AS first_trade_at
+            FROM market_trades
+            WHERE has(?, taker_address)
+            GROUP BY trader, slug, outcome_token
+            HAVING shares > 0
+        ) AS h
+        INNER JOIN (SELECT * FROM markets FINAL) AS m ON h.slug = m.slug
+        LEFT JOIN (
+            SELECT slug, outcome_token, argMax(price, timestamp) AS price
+            FROM market_prices
+            WHERE slug IN (SELECT slug FROM market_trades WHERE has(?, taker_address))
+            GROUP BY slug, outcome_token
+        ) AS p ON h.slug = p.slug AND h.outcome_token = p.outcome_token
+        ORDER BY trader, h.cost_usd DESC
+    "#;
+
+    let rows: Vec<PredictionPositionRow> = client
+        .query(query)
+        .bind(traders)
+        .bind(traders)
+        .fetch_all()
+        .await?;
+
+    tracing::debug!(
+        query = "get_prediction_positions",
+        duration_ms = %start.elapsed().as_millis(),
+        row_count = %rows.len(),
+        "Database query completed"
+    );
+    telemetry::observe_query("get_prediction_positions", start.elapsed());
+
+    Ok(rows)
+}
+
+pub async fn wallet_exists(client: &Client, wallet: &str) -> AppResult<bool> {
+    let start = Instant::now();
+    tracing::debug!(wallet = %wallet, query = "wallet_exists", "Executing database query");
+
+    let query = r#"
+        SELECT count(*) as cnt FROM wallet_summaries FINAL WHERE wallet = ?
+    "#;
+
+    #[derive(clickhouse::Row, serde::Deserialize)]
+    struct CountResult {
+        cnt: u64,
+    }
+
+    let result: Vec<CountResult> = client
+        .query(query)
+        .bind(wallet)
+        .fetch_all()
+        .await?;
+
+    let exists = result.first().map(|r| r.cnt > 0).unwrap_or(false);
+
+    tracing::debug!(
+        wallet = %wallet,
+        query = "wallet_exists",
+        duration_ms = %start.elapsed().as_millis(),
+        exists = %exists,
+        "Database query completed"
+    );
+    telemetry::observe_query("wallet_exists", start.elapsed());
+
+    Ok(exists)
+}
+
+/// SQL literal for the moment `days` days ago
+fn cutoff_literal(days: i64) -> String {
+    let cutoff = Utc::now() - Duration::days(days);
+    format!("toDateTime64('{}', 3)", cutoff.format("%Y-%m-%d %H:%M:%S"))
+}
+
+/// Split a window into the whole days served by `wallet_protocol_daily` and the
+/// partial first day, which is read from raw transactions. Returns
+/// `(rollup filter, transactions filter)`.
+fn rollup_window_filters(window: TimeWindow) -> (String, String) {
+    match window.to_days() {
+        Some(days) => {
+            let cutoff = cutoff_literal(days);
+            tracing::debug!(cutoff = %cutoff, days = %days, "Applying rollup time filter");
+            (
+                format!("AND day > toDate({})", cutoff),
+                format!("AND block_time >= {0} AND toDate(block_time) = toDate({0})", cutoff),
+            )
+        }
+        // The rollup covers all of history
+        None => (String::new(), "AND 0".to_string()),
+    }
+}
+
+/// Build an `AND block_time >= ...` clause for a time window (empty for all time).
+fn block_time_filter(window: TimeWindow) -> String {
+    match window.to_days() {
+        Some(days) => {
+            let cutoff = cutoff_literal(days);
+            tracing::debug!(cutoff = %cutoff, days = %days, "Applying time filter");
+            format!("AND block_time >= {}", cutoff)
+        }
+        None => {
+            tracing::debug!("No time filter applied (all time)");
+            String::new()
+        }
+    }
 }
@@ -0,0 +1,394 @@
+//! Storage behind the API and indexer.
+//!
+//! Handlers and the indexer read and write wallet summaries, positions, transactions,
+//! PnL, MEV flags, wallet links, prices, signals, closed trades, leaderboards, token flows,
+//! subscriptions, wallet groups, webhooks, alerts, API keys and prediction markets through
+//! [`CortexStore`] rather than calling `queries` with a ClickHouse client, so they can run
+//! against [`MemoryStore`] in tests.
+
+#[cfg(test)]
+mod memory;
+
+use async_trait::async_trait;
+use rust_decimal::Decimal;
+
+use super::models::{
+    AlertRuleRow, AlertStateRow, ApiKeyRow, ClosedTradeRow, KeySubscriptionRow, LeaderboardFilter, LeaderboardRow, LinkFanOutRow, MarketAnomalyRow, MarketCandleRow, MarketRow, MarketVolumeRow, MevLossRow,
+    PnlByProtocolRow, PositionRow, PositionSignalRow, PredictionPositionRow, TokenDailyCloseRow, TokenFlowRow, TokenLaunchRow, TokenPriceRow, TransactionFilter, TransactionFlagRow,
+    TransactionRow, WalletActivityRow, WalletGroupRow, WalletLinkRow, WalletMevLossRow, WalletProtocolPnlRow, WalletSummaryRow, WebhookDeliveryRow, WebhookRow,
+};
+use super::writer::{Committed, TransactionWriter};
+use super::{queries, Database};
+use crate::error::AppResult;
+use crate::types::{CandleInterval, TimeWindow};
+
+#[cfg(test)]
+pub use memory::MemoryStore;
+
+#[async_trait]
+pub trait CortexStore: Send + Sync {
+    async fn health_check(&self) -> AppResult<()>;
+
+    /// Stored summary of a wallet with its live activity applied
+    async fn get_wallet_summary(&self, wallet: &str) -> AppResult<Option<WalletSummaryRow>>;
+    /// Summaries of several wallets in one query, in no particular order. Wallets that
+    /// haven't been indexed are left out.
+    async fn get_wallet_summaries(&self, wallets: &[String]) -> AppResult<Vec<WalletSummaryRow>>;
+    async fn upsert_wallet_summary(&self, summary: &WalletSummaryRow) -> AppResult<()>;
+    /// Realized PnL windows, protocols and last activity across one or more wallets
+    async fn get_wallet_activity(&self, wallets: &[String]) -> AppResult<WalletActivityRow>;
+
+    /// Open positions, largest first
+    async fn get_wallet_positions(&self, wallet: &str) -> AppResult<Vec<PositionRow>>;
+    /// Open positions of several wallets in one query, each wallet's largest first
+    async fn get_positions_for_wallets(&self, wallets: &[String]) -> AppResult<Vec<PositionRow>>;
+    async fn upsert_position(&self, position: &PositionRow) -> AppResult<()>;
+
+    /// Transactions within a window, oldest first
+    async fn get_wallet_transactions(&self, wallet: &str, window: TimeWindow) -> AppResult<Vec<TransactionRow>>;
+    /// One page of a wallet's transactions matching `filter`, in the filter's order
+    async fn get_transaction_page(&self, wallet: &str, filter: &TransactionFilter) -> AppResult<Vec<TransactionRow>>;
+    /// Queue a transaction for storage. It may not be readable until the returned receipt
+    /// resolves or the next flush.
+    async fn write_transaction(&self, row: TransactionRow) -> AppResult<Committed>;
+    /// Store every queued transaction
+    async fn flush_transactions(&self) -> AppResult<()>;
+
+    /// Realized PnL per protocol within a window, most profitable first
+    async fn get_wallet_pnl_by_protocol(&self, wallet: &str, window: TimeWindow) -> AppResult<Vec<PnlByProtocolRow>>;
+    /// Realized PnL per wallet and protocol for several wallets in one query, each
+    /// wallet's most profitable first
+    async fn get_pnl_by_protocol_for_wallets(&self, wallets: &[String], window: TimeWindow) -> AppResult<Vec<WalletProtocolPnlRow>>;
+    async fn get_wallet_mev_losses(&self, wallet: &str, window: TimeWindow) -> AppResult<MevLossRow>;
+    /// Sandwich losses for several wallets in one query, leaving out wallets without any
+    async fn get_mev_losses_for_wallets(&self, wallets: &[String], window: TimeWindow) -> AppResult<Vec<WalletMevLossRow>>;
+    async fn insert_transaction_flag(&self, flag: &TransactionFlagRow) -> AppResult<()>;
+    /// Store flags in one batch
+    async fn insert_transaction_flags(&self, flags: &[TransactionFlagRow]) -> AppResult<()>;
+    /// Flags already stored for any of a wallet's `signatures`
+    async fn get_transaction_flags(&self, wallet: &str, signatures: &[String]) -> AppResult<Vec<TransactionFlagRow>>;
+    /// Number of distinct transactions carrying `flag` for a wallet
+    async fn count_wallet_flags(&self, wallet: &str, flag: &str) -> AppResult<u64>;
+    /// Swaps by other wallets in the given slots, for sandwich detection
+    async fn get_slot_swaps(&self, wallet: &str, slots: &[u64]) -> AppResult<Vec<TransactionRow>>;
+    async fn record_token_launch(&self, mint: &str, pool: &str, slot: u64, block_time: i64) -> AppResult<()>;
+    /// Earliest recorded creation slot of each mint; mints never seen launching are left out
+    async fn get_token_launches(&self, mints: &[String]) -> AppResult<Vec<TokenLaunchRow>>;
+
+    /// Record a token's USD price as of `at` (ms), which also extends its price history
+    async fn record_token_price(&self, token: &str, price_usd: Decimal, at: i64) -> AppResult<()>;
+    /// Latest known USD price of each token; tokens without one are left out
+    async fn get_token_prices(&self, tokens: &[String]) -> AppResult<Vec<TokenPriceRow>>;
+    /// Last price of each token per day over the past `days` days, by token then day
+    async fn get_token_daily_closes(&self, tokens: &[String], days: u32) -> AppResult<Vec<TokenDailyCloseRow>>;
+
+    async fn insert_position_signal(&self, signal: &PositionSignalRow) -> AppResult<()>;
+    /// Position signals newest first, of `wallets` (all when empty), strictly after the
+    /// `before` cursor `(block_time, signature, token)`
+    async fn get_position_signals(&self, wallets: &[String], before: Option<&(i64, String, String)>, limit: u32) -> AppResult<Vec<PositionSignalRow>>;
+    /// Store closed trades in one batch
+    async fn insert_closed_trades(&self, trades: &[ClosedTradeRow]) -> AppResult<()>;
+    /// Indexed wallets ranked by the trades they closed within the filter's window
+    async fn get_leaderboard(&self, filter: &LeaderboardFilter) -> AppResult<Vec<LeaderboardRow>>;
+    /// Swap volume into and out of `mint` per wallet within a window, optionally only by `wallets`
+    async fn get_token_flows(&self, mint: &str, window: TimeWindow, wallets: Option<&[String]>) -> AppResult<Vec<TokenFlowRow>>;
+
+    async fn insert_wallet_link(&self, link: &WalletLinkRow) -> AppResult<()>;
+    /// Links where any of the wallets is the source or the target, oldest first
+    async fn get_wallet_links(&self, wallets: &[String]) -> AppResult<Vec<WalletLinkRow>>;
+    /// Number of distinct wallets each source has linked to
+    async fn get_link_fan_out(&self, sources: &[String]) -> AppResult<Vec<LinkFanOutRow>>;
+
+    /// Remember that a wallet is under continuous indexing
+    async fn save_subscription(&self, wallet: &str) -> AppResult<()>;
+    async fn remove_subscription(&self, wallet: &str) -> AppResult<()>;
+    async fn subscribed_wallets(&self) -> AppResult<Vec<String>>;
+
+    /// Store the current state of a webhook, including deletion
+    async fn save_webhook(&self, webhook: &WebhookRow) -> AppResult<()>;
+    /// Every webhook that hasn't been deleted, oldest first
+    async fn get_webhooks(&self) -> AppResult<Vec<WebhookRow>>;
+    async fn record_webhook_delivery(&self, delivery: &WebhookDeliveryRow) -> AppResult<()>;
+    /// Most recent delivery attempts for a webhook, newest first
+    async fn get_webhook_deliveries(&self, webhook_id: &str, limit: u32) -> AppResult<Vec<WebhookDeliveryRow>>;
+
+    /// Store the current state of an alert rule, including deletion
+    async fn save_alert_rule(&self, rule: &AlertRuleRow) -> AppResult<()>;
+    /// Every alert rule that hasn't been deleted, oldest first
+    async fn get_alert_rules(&self) -> AppResult<Vec<AlertRuleRow>>;
+    /// Record a rule starting or stopping to hold for a wallet
+    async fn save_alert_state(&self, state: &AlertStateRow) -> AppResult<()>;
+    /// Latest state per rule and wallet, most recently changed first
+    async fn get_alert_states(&self) -> AppResult<Vec<AlertStateRow>>;
+
+    /// Store the current state of an API key, including revocation
+    async fn save_api_key(&self, key: &ApiKeyRow) -> AppResult<()>;
+    /// Every API key that hasn't been revoked, oldest first
+    async fn get_api_keys(&self) -> AppResult<Vec<ApiKeyRow>>;
+    /// Record a key starting or giving up a wallet subscription
+    async fn set_key_subscription(&self, subscription: &KeySubscriptionRow) -> AppResult<()>;
+    /// Every active wallet subscription started with a key
+    async fn get_key_subscriptions(&self) -> AppResult<Vec<KeySubscriptionRow>>;
+
+    /// Save a wallet group, replacing any earlier version with the same id
+    async fn save_wallet_group(&self, group: &WalletGroupRow) -> AppResult<()>;
+    /// Every group that hasn't been deleted
+    async fn get_wallet_groups(&self) -> AppResult<Vec<WalletGroupRow>>;
+
+    /// Prediction markets by slug; unknown slugs are left out
+    async fn get_markets(&self, slugs: &[String]) -> AppResult<Vec<MarketRow>>;
+    /// Price candles of a market, oldest first
+    async fn get_market_candles(&self, slug: &str, interval: CandleInterval) -> AppResult<Vec<MarketCandleRow>>;
+    /// Recent volume and order book depth of several markets; markets without trades are left out
+    async fn get_market_volumes(&self, slugs: &[String]) -> AppResult<Vec<MarketVolumeRow>>;
+    /// Prices in the last day at least `min_z_score` deviations from their trailing mean, newest first
+    async fn get_market_anomalies(&self, slug: &str, min_z_score: f64) -> AppResult<Vec<MarketAnomalyRow>>;
+    /// Open prediction market holdings of several trader addresses
+    async fn get_prediction_positions(&self, traders: &[String]) -> AppResult<Vec<PredictionPositionRow>>;
+
+    /// Store anything still buffered before the process exits
+    async fn close(&self) {}
+}
+
+/// The production store: ClickHouse, with transactions inserted in batches
+pub struct ClickHouseStore {
+    db: Database,
+    writer: TransactionWriter,
+}
+
+impl ClickHouseStore {
+    pub fn new(db: Database, writer: TransactionWriter) -> Self {
+        Self { db, writer }
+    }
+}
+
+#[async_trait]
+impl CortexStore for ClickHouseStore {
+    async fn health_check(&self) -> AppResult<()> {
+        Ok(self.db.health_check().await?)
+    }
+
+    async fn get_wallet_summary(&self, wallet: &str) -> AppResult<Option<WalletSummaryRow>> {
+        queries::get_wallet_summary(self.db.client(), wallet).await
+    }
+
+    async fn get_wallet_summaries(&self, wallets: &[String]) -> AppResult<Vec<WalletSummaryRow>> {
+        queries::get_wallet_summaries(self.db.client(), wallets).await
+    }
+
+    async fn upsert_wallet_summary(&self, summary: &WalletSummaryRow) -> AppResult<()> {
+        queries::upsert_wallet_summary(self.db.client(), summary).await
+    }
+
+    async fn get_wallet_activity(&self, wallets: &[String]) -> AppResult<WalletActivityRow> {
+        queries::get_wallet_activity(self.db.client(), wallets).await
+    }
+
+    async fn get_wallet_positions(&self, wallet: &str) -> AppResult<Vec<PositionRow>> {
+        queries::get_wallet_positions(self.db.client(), wallet).await
+    }
+
+    async fn get_positions_for_wallets(&self, wallets: &[String]) -> AppResult<Vec<PositionRow>> {
+        queries::get_positions_for_wallets(self.db.client(), wallets).await
+    }
+
+    async fn upsert_position(&self, position: &PositionRow) -> AppResult<()> {
+        queries::upsert_position(self.db.client(), position).await
+    }
+
+    async fn get_wallet_transactions(&self, wallet: &str, window: TimeWindow) -> AppResult<Vec<TransactionRow>> {
+        queries::get_wallet_transactions(self.db.client(), wallet, window).await
+    }
+
+    async fn get_transaction_page(&self, wallet: &str, filter: &TransactionFilter) -> AppResult<Vec<TransactionRow>> {
+        queries::get_transaction_page(self.db.client(), wallet, filter).await
+    }
+
+    async fn write_transaction(&self, row: TransactionRow) -> AppResult<Committed> {
+        self.writer.write(row).await
+    }
+
+    async fn flush_transactions(&self) -> AppResult<()> {
+        self.writer.flush().await
+    }
+
+    async fn get_wallet_pnl_by_protocol(&self, wallet: &str, window: TimeWindow) -> AppResult<Vec<PnlByProtocolRow>> {
+        queries::get_wallet_pnl_by_protocol(self.db.client(), wallet, window).await
+    }
+
+    async fn get_pnl_by_protocol_for_wallets(&self, wallets: &[String], window: TimeWindow) -> AppResult<Vec<WalletProtocolPnlRow>> {
+        queries::get_pnl_by_protocol_for_wallets(self.db.client(), wallets, window).await
+    }
+
+    async fn get_wallet_mev_losses(&self, wallet: &str, window: TimeWindow) -> AppResult<MevLossRow> {
+        queries::get_wallet_mev_losses(self.db.client(), wallet, window).await
+    }
+
+    async fn get_mev_losses_for_wallets(&self, wallets: &[String], window: TimeWindow) -> AppResult<Vec<WalletMevLossRow>> {
+        queries::get_mev_losses_for_wallets(self.db.client(), wallets, window).await
+    }
+
+    async fn insert_transaction_flag(&self, flag: &TransactionFlagRow) -> AppResult<()> {
+        queries::insert_transaction_flag(self.db.client(), flag).await
+    }
+
+    async fn insert_transaction_flags(&self, flags: &[TransactionFlagRow]) -> AppResult<()> {
+        queries::insert_transaction_flags(self.db.client(), flags).await
+    }
+
+    async fn get_transaction_flags(&self, wallet: &str, signatures: &[String]) -> AppResult<Vec<TransactionFlagRow>> {
+        queries::get_transaction_flags(self.db.client(), wallet, signatures).await
+    }
+
+    async fn count_wallet_flags(&self, wallet: &str, flag: &str) -> AppResult<u64> {
+        queries::count_wallet_flags(self.db.client(), wallet, flag).await
+    }
+
+    async fn get_slot_swaps(&self, wallet: &str, slots: &[u64]) -> AppResult<Vec<TransactionRow>> {
+        queries::get_slot_swaps(self.db.client(), wallet, slots).await
+    }
+
+    async fn record_token_launch(&self, mint: &str, pool: &str, slot: u64, block_time: i64) -> AppResult<()> {
+        queries::record_token_launch(self.db.client(), mint, pool, slot, block_time).await
+    }
+
+    async fn get_token_launches(&self, mints: &[String]) -> AppResult<Vec<TokenLaunchRow>> {
+        queries::get_token_launches(self.db.client(), mints).await
+    }
+
+    async fn record_token_price(&self, token: &str, price_usd: Decimal, at: i64) -> AppResult<()> {
+        queries::record_token_price(self.db.client(), token, &price_usd.to_string(), at).await
+    }
+
+    async fn get_token_prices(&self, tokens: &[String]) -> AppResult<Vec<TokenPriceRow>> {
+        queries::get_token_prices(self.db.client(), tokens).await
+    }
+
+    async fn get_token_daily_closes(&self, tokens: &[String], days: u32) -> AppResult<Vec<TokenDailyCloseRow>> {
+        queries::get_token_daily_closes(self.db.client(), tokens, days).await
+    }
+
+    async fn insert_position_signal(&self, signal: &PositionSignalRow) -> AppResult<()> {
+        queries::insert_position_signal(self.db.client(), signal).await
+    }
+
+    async fn get_position_signals(&self, wallets: &[String], before: Option<&(i64, String, String)>, limit: u32) -> AppResult<Vec<PositionSignalRow>> {
+        queries::get_position_signals(self.db.client(), wallets, before, limit).await
+    }
+
+    async fn insert_closed_trades(&self, trades: &[ClosedTradeRow]) -> AppResult<()> {
+        queries::insert_closed_trades(self.db.client(), trades).await
+    }
+
+    async fn get_leaderboard(&self, filter: &LeaderboardFilter) -> AppResult<Vec<LeaderboardRow>> {
+        queries::get_leaderboard(self.db.client(), filter).await
+    }
+
+    async fn get_token_flows(&self, mint: &str, window: TimeWindow, wallets: Option<&[String]>) -> AppResult<Vec<TokenFlowRow>> {
+        queries::get_token_flows(self.db.client(), mint, window, wallets).await
+    }
+
+    async fn insert_wallet_link(&self, link: &WalletLinkRow) -> AppResult<()> {
+        queries::insert_wallet_link(self.db.client(), link).await
+    }
+
+    async fn get_wallet_links(&self, wallets: &[String]) -> AppResult<Vec<WalletLinkRow>> {
+        queries::get_wallet_links(self.db.client(), wallets).await
+    }
+
+    async fn get_link_fan_out(&self, sources: &[String]) -> AppResult<Vec<LinkFanOutRow>> {
+        queries::get_link_fan_out(self.db.client(), sources).await
+    }
+
+    async fn save_subscription(&self, wallet: &str) -> AppResult<()> {
+        queries::set_wallet_subscription(self.db.client(), wallet, true).await
+    }
+
+    async fn remove_subscription(&self, wallet: &str) -> AppResult<()> {
+        queries::set_wallet_subscription(self.db.client(), wallet, false).await
+    }
+
+    async fn subscribed_wallets(&self) -> AppResult<Vec<String>> {
+        queries::get_subscribed_wallets(self.db.client()).await
+    }
+
+    async fn save_webhook(&self, webhook: &WebhookRow) -> AppResult<()> {
+        queries::save_webhook(self.db.client(), webhook).await
+    }
+
+    async fn get_webhooks(&self) -> AppResult<Vec<WebhookRow>> {
+        queries::get_webhooks(self.db.client()).await
+    }
+
+    async fn record_webhook_delivery(&self, delivery: &WebhookDeliveryRow) -> AppResult<()> {
+        queries::insert_webhook_delivery(self.db.client(), delivery).await
+    }
+
+    async fn get_webhook_deliveries(&self, webhook_id: &str, limit: u32) -> AppResult<Vec<WebhookDeliveryRow>> {
+        queries::get_webhook_deliveries(self.db.client(), webhook_id, limit).await
+    }
+
+    async fn save_alert_rule(&self, rule: &AlertRuleRow) -> AppResult<()> {
+        queries::save_alert_rule(self.db.client(), rule).await
+    }
+
+    async fn get_alert_rules(&self) -> AppResult<Vec<AlertRuleRow>> {
+        queries::get_alert_rules(self.db.client()).await
+    }
+
+    async fn save_alert_state(&self, state: &AlertStateRow) -> AppResult<()> {
+        queries::save_alert_state(self.db.client(), state).await
+    }
+
+    async fn get_alert_states(&self) -> AppResult<Vec<AlertStateRow>> {
+        queries::get_alert_states(self.db.client()).await
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRow) -> AppResult<()> {
+        queries::save_api_key(self.db.client(), key).await
+    }
+
+    async fn get_api_keys(&self) -> AppResult<Vec<ApiKeyRow>> {
+        queries::get_api_keys(self.db.client()).await
+    }
+
+    async fn set_key_subscription(&self, subscription: &KeySubscriptionRow) -> AppResult<()> {
+        queries::set_key_subscription(self.db.client(), subscription).await
+    }
+
+    async fn get_key_subscriptions(&self) -> AppResult<Vec<KeySubscriptionRow>> {
+        queries::get_key_subscriptions(self.db.client()).await
+    }
+
+    async fn save_wallet_group(&self, group: &WalletGroupRow) -> AppResult<()> {
+        queries::save_wallet_group(self.db.client(), group).await
+    }
+
+    async fn get_wallet_groups(&self) -> AppResult<Vec<WalletGroupRow>> {
+        queries::get_wallet_groups(self.db.client()).await
+    }
+
+    async fn get_markets(&self, slugs: &[String]) -> AppResult<Vec<MarketRow>> {
+        queries::get_markets(self.db.client(), slugs).await
+    }
+
+    async fn get_market_candles(&self, slug: &str, interval: CandleInterval) -> AppResult<Vec<MarketCandleRow>> {
+        queries::get_market_candles(self.db.client(), slug, interval).await
+    }
+
+    async fn get_market_volumes(&self, slugs: &[String]) -> AppResult<Vec<MarketVolumeRow>> {
+        queries::get_market_volumes(self.db.client(), slugs).await
+    }
+
+    async fn get_market_anomalies(&self, slug: &str, min_z_score: f64) -> AppResult<Vec<MarketAnomalyRow>> {
+        queries::get_market_anomalies(self.db.client(), slug, min_z_score).await
+    }
+
+    async fn get_prediction_positions(&self, traders: &[String]) -> AppResult<Vec<PredictionPositionRow>> {
+        queries::get_prediction_positions(self.db.client(), traders).await
+    }
+
+    async fn close(&self) {
+        self.writer.shutdown().await;
+    }
+}
@@ -0,0 +1,794 @@
+//! In-memory [`CortexStore`], mirroring the ClickHouse queries closely enough for tests
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use async_trait::async_trait;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use tokio::sync::RwLock;
+
+use super::CortexStore;
+use crate::db::models::{
+    AlertRuleRow, AlertStateRow, ApiKeyRow, ClosedTradeRow, KeySubscriptionRow, LeaderboardFilter, LeaderboardRow, LinkFanOutRow, MarketAnomalyRow, MarketCandleRow, MarketRow, MarketVolumeRow, MevLossRow,
+    PnlByProtocolRow, PositionRow, PositionSignalRow, PredictionPositionRow, TokenDailyCloseRow, TokenFlowRow, TokenLaunchRow, TokenPriceRow, TransactionCursor, TransactionFilter,
+    TransactionFlagRow, TransactionRow, WalletActivityRow, WalletGroupRow, WalletLinkRow, WalletMevLossRow, WalletProtocolPnlRow, WalletSummaryRow, WebhookDeliveryRow,
+    WebhookRow,
+};
+use crate::db::writer::Committed;
+use crate::error::AppResult;
+use crate::types::{CandleInterval, LeaderboardMetric, TimeWindow, TransactionSort};
+
+/// Transaction types whose value comes back to the wallet
+const REALIZING_TX_TYPES: [&str; 3] = ["swap", "withdraw", "remove_liquidity"];
+/// Transaction types whose value the wallet puts to work
+const DEPLOYING_TX_TYPES: [&str; 3] = ["deposit", "borrow", "add_liquidity"];
+const MILLIS_PER_DAY: i64 = 86_400_000;
+
+/// A store held entirely in memory. Writes are visible immediately. Prediction markets
+/// and holdings are added directly; there are no market trades, so candles, volumes and
+/// anomalies are always empty.
+#[derive(Default)]
+pub struct MemoryStore {
+    summaries: RwLock<HashMap<String, WalletSummaryRow>>,
+    /// Keyed like the `positions` table: wallet, protocol, position type, token
+    positions: RwLock<HashMap<(String, String, String, String), PositionRow>>,
+    transactions: RwLock<Vec<TransactionRow>>,
+    subscriptions: RwLock<BTreeSet<String>>,
+    webhooks: RwLock<HashMap<String, WebhookRow>>,
+    webhook_deliveries: RwLock<Vec<WebhookDeliveryRow>>,
+    alert_rules: RwLock<HashMap<String, AlertRuleRow>>,
+    /// Keyed like the `alert_states` table: rule id, wallet
+    alert_states: RwLock<HashMap<(String, String), AlertStateRow>>,
+    api_keys: RwLock<HashMap<String, ApiKeyRow>>,
+    /// Wallets each key has subscribed, like the `api_key_subscriptions` table
+    key_subscriptions: RwLock<BTreeSet<(String, String)>>,
+    wallet_groups: RwLock<HashMap<String, WalletGroupRow>>,
+    /// Keyed like the `transaction_flags` table: wallet, signature, flag
+    transaction_flags: RwLock<HashMap<(String, String, String), TransactionFlagRow>>,
+    /// Earliest launch slot per mint
+    token_launches: RwLock<HashMap<String, u64>>,
+    /// Every recorded price, like `token_price_history`: token, USD price, time (ms)
+    token_prices: RwLock<Vec<(String, Decimal, i64)>>,
+    /// Keyed like the `wallet_links` table: source, target, link type, signature
+    wallet_links: RwLock<HashMap<(String, String, String, String), WalletLinkRow>>,
+    /// Keyed like the `position_signals` table: wallet, block time, signature, token
+    position_signals: RwLock<HashMap<(String, i64, String, String), PositionSignalRow>>,
+    /// Keyed like the `closed_trades` table: wallet, signature
+    closed_trades: RwLock<HashMap<(String, String), ClosedTradeRow>>,
+    markets: RwLock<HashMap<String, MarketRow>>,
+    prediction_positions: RwLock<Vec<PredictionPositionRow>>,
+}
+
+impl MemoryStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Closed trades stored for a wallet, oldest first
+    pub async fn closed_trades(&self, wallet: &str) -> Vec<ClosedTradeRow> {
+        let mut trades: Vec<ClosedTradeRow> =
+            self.closed_trades.read().await.values().filter(|t| t.wallet == wallet).cloned().collect();
+        trades.sort_by(|a, b| (a.block_time, &a.signature).cmp(&(b.block_time, &b.signature)));
+        trades
+    }
+
+    /// Flags stored for a wallet, by signature
+    pub async fn transaction_flags(&self, wallet: &str) -> Vec<TransactionFlagRow> {
+        let mut flags: Vec<TransactionFlagRow> =
+            self.transaction_flags.read().await.values().filter(|f| f.wallet == wallet).cloned().collect();
+        flags.sort_by(|a, b| (&a.signature, &a.flag).cmp(&(&b.signature, &b.flag)));
+        flags
+    }
+
+    /// Sandwich losses of one wallet within a window
+    async fn sandwich_losses(&self, wallet: &str, window: TimeWindow) -> (Decimal, u64) {
+        let cutoff = window.cutoff_millis();
+        self.transaction_flags
+            .read()
+            .await
+            .values()
+            .filter(|f| f.wallet == wallet && f.flag == "sandwiched" && cutoff.is_none_or(|c| f.block_time >= c))
+            .fold((Decimal::ZERO, 0), |(loss, count), f| (loss + parse_decimal(&f.estimated_loss_usd), count + 1))
+    }
+
+    pub async fn add_market(&self, market: MarketRow) {
+        self.markets.write().await.insert(market.slug.clone(), market);
+    }
+
+    pub async fn add_prediction_position(&self, position: PredictionPositionRow) {
+        self.prediction_positions.write().await.push(position);
+    }
+}
+
+fn parse_decimal(s: &str) -> Decimal {
+    s.parse().unwrap_or_default()
+}
+
+fn in_window(tx: &TransactionRow, window: TimeWindow) -> bool {
+    window.cutoff_millis().is_none_or(|cutoff| tx.block_time >= cutoff)
+}
+
+fn matches(tx: &TransactionRow, filter: &TransactionFilter) -> bool {
+    filter.protocol.as_ref().is_none_or(|p| &tx.protocol == p)
+        && filter.tx_type.as_ref().is_none_or(|t| &tx.tx_type == t)
+        && filter.token.as_ref().is_none_or(|t| &tx.token_in == t || &tx.token_out == t)
+        && filter.from.is_none_or(|from| tx.block_time >= from)
+        && filter.to.is_none_or(|to| tx.block_time < to)
+        && filter.min_usd_value.as_ref().is_none_or(|min| parse_decimal(&tx.usd_value) >= parse_decimal(min))
+}
+
+/// Sort key of a transaction under `ORDER BY usd_value, block_time, signature`
+fn value_key(usd_value: &str, block_time: i64, signature: &str) -> (Decimal, i64, String) {
+    (parse_decimal(usd_value), block_time, signature.to_string())
+}
+
+/// Whether `tx` comes after `cursor` in `sort` order
+fn is_after(tx: &TransactionRow, cursor: &TransactionCursor, sort: TransactionSort) -> bool {
+    let time_key = (tx.block_time, tx.signature.as_str());
+    let cursor_time_key = (cursor.block_time, cursor.signature.as_str());
+    match sort {
+        TransactionSort::Newest => time_key < cursor_time_key,
+        TransactionSort::Oldest => time_key > cursor_time_key,
+        TransactionSort::Largest => {
+            value_key(&tx.usd_value, tx.block_time, &tx.signature)
+                < value_key(&cursor.usd_value, cursor.block_time, &cursor.signature)
+        }
+    }
+}
+
+#[async_trait]
+impl CortexStore for MemoryStore {
+    async fn health_check(&self) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn get_wallet_summary(&self, wallet: &str) -> AppResult<Option<WalletSummaryRow>> {
+        let Some(mut summary) = self.summaries.read().await.get(wallet).cloned() else {
+            return Ok(None);
+        };
+        let activity = self.get_wallet_activity(std::slice::from_ref(&summary.wallet)).await?;
+        summary.apply_activity(&activity);
+        Ok(Some(summary))
+    }
+
+    async fn get_wallet_summaries(&self, wallets: &[String]) -> AppResult<Vec<WalletSummaryRow>> {
+        let mut summaries = Vec::new();
+        for wallet in wallets {
+            summaries.extend(self.get_wallet_summary(wallet).await?);
+        }
+        Ok(summaries)
+    }
+
+    async fn upsert_wallet_summary(&self, summary: &WalletSummaryRow) -> AppResult<()> {
+        self.summaries.write().await.insert(summary.wallet.clone(), summary.clone());
+        Ok(())
+    }
+
+    async fn get_wallet_activity(&self, wallets: &[String]) -> AppResult<WalletActivityRow> {
+        let transactions = self.transactions.read().await;
+        let mut activity = WalletActivityRow::default();
+        let mut realized = [Decimal::ZERO; 3];
+        let mut protocols = BTreeSet::new();
+
+        for tx in transactions.iter().filter(|tx| wallets.contains(&tx.wallet)) {
+            activity.tx_count += 1;
+            activity.last_activity = activity.last_activity.max(tx.block_time);
+            protocols.insert(tx.protocol.clone());
+            if REALIZING_TX_TYPES.contains(&tx.tx_type.as_str()) {
+                for (total, window) in realized.iter_mut().zip([TimeWindow::Day, TimeWindow::Week, TimeWindow::Month]) {
+                    if in_window(tx, window) {
+                        *total += parse_decimal(&tx.usd_value);
+                    }
+                }
+            }
+        }
+
+        activity.realized_pnl_24h = realized[0].to_string();
+        activity.realized_pnl_7d = realized[1].to_string();
+        activity.realized_pnl_30d = realized[2].to_string();
+        activity.protocols = protocols.into_iter().collect();
+        Ok(activity)
+    }
+
+    async fn get_wallet_positions(&self, wallet: &str) -> AppResult<Vec<PositionRow>> {
+        let mut positions: Vec<PositionRow> = self
+            .positions
+            .read()
+            .await
+            .values()
+            .filter(|p| p.wallet == wallet)
+            .cloned()
+            .collect();
+        positions.sort_by_key(|p| Reverse(parse_decimal(&p.usd_value)));
+        Ok(positions)
+    }
+
+    async fn get_positions_for_wallets(&self, wallets: &[String]) -> AppResult<Vec<PositionRow>> {
+        let mut positions = Vec::new();
+        for wallet in wallets {
+            positions.extend(self.get_wallet_positions(wallet).await?);
+        }
+        Ok(positions)
+    }
+
+    async fn upsert_position(&self, position: &PositionRow) -> AppResult<()> {
+        let key = (
+            position.wallet.clone(),
+            position.protocol.clone(),
+            position.position_type.clone(),
+            position.token.clone(),
+        );
+        self.positions.write().await.insert(key, position.clone());
+        Ok(())
+    }
+
+    async fn get_wallet_transactions(&self, wallet: &str, window: TimeWindow) -> AppResult<Vec<TransactionRow>> {
+        let mut transactions: Vec<TransactionRow> = self
+            .transactions
+            .read()
+            .await
+            .iter()
+            .filter(|tx| tx.wallet == wallet && in_window(tx, window))
+            .cloned()
+            .collect();
+        transactions.sort_by(|a, b| a.block_time.cmp(&b.block_time).then_with(|| a.signature.cmp(&b.signature)));
+        Ok(transactions)
+    }
+
+    async fn get_transaction_page(&self, wallet: &str, filter: &TransactionFilter) -> AppResult<Vec<TransactionRow>> {
+        let mut page: Vec<TransactionRow> = self
+            .transactions
+            .read()
+            .await
+            .iter()
+            .filter(|tx| tx.wallet == wallet && matches(tx, filter))
+            .filter(|tx| filter.after.as_ref().is_none_or(|cursor| is_after(tx, cursor, filter.sort)))
+            .cloned()
+            .collect();
+        match filter.sort {
+            TransactionSort::Newest => page.sort_by(|a, b| (b.block_time, &b.signature).cmp(&(a.block_time, &a.signature))),
+            TransactionSort::Oldest => page.sort_by(|a, b| (a.block_time, &a.signature).cmp(&(b.block_time, &b.signature))),
+            TransactionSort::Largest => {
+                page.sort_by_key(|tx| Reverse(value_key(&tx.usd_value, tx.block_time, &tx.signature)))
+            }
+        }
+        page.truncate(filter.limit as usize);
+        Ok(page)
+    }
+
+    async fn write_transaction(&self, row: TransactionRow) -> AppResult<Committed> {
+        self.transactions.write().await.push(row);
+        Ok(Committed::done())
+    }
+
+    async fn flush_transactions(&self) -> AppResult<()> {
+        Ok(())
+    }
+
+    async fn get_wallet_pnl_by_protocol(&self, wallet: &str, window: TimeWindow) -> AppResult<Vec<PnlByProtocolRow>> {
+        let mut by_protocol: BTreeMap<String, (Decimal, u64)> = BTreeMap::new();
+        for tx in self.get_wallet_transactions(wallet, window).await? {
+            let (realized, count) = by_protocol.entry(tx.protocol.clone()).or_default();
+            *count += 1;
+            if REALIZING_TX_TYPES.contains(&tx.tx_type.as_str()) {
+                *realized += parse_decimal(&tx.usd_value);
+            } else if DEPLOYING_TX_TYPES.contains(&tx.tx_type.as_str()) {
+                *realized -= parse_decimal(&tx.usd_value);
+            }
+        }
+
+        let mut rows: Vec<(Decimal, PnlByProtocolRow)> = by_protocol
+            .into_iter()
+            .map(|(protocol, (realized, trade_count))| {
+                let row = PnlByProtocolRow {
+                    protocol,
+                    realized: realized.to_string(),
+                    unrealized: "0".to_string(),
+                    trade_count,
+                };
+                (realized, row)
+            })
+            .collect();
+        rows.sort_by_key(|(realized, _)| Reverse(*realized));
+        Ok(rows.into_iter().map(|(_, row)| row).collect())
+    }
+
+    async fn get_pnl_by_protocol_for_wallets(&self, wallets: &[String], window: TimeWindow) -> AppResult<Vec<WalletProtocolPnlRow>> {
+        let mut rows = Vec::new();
+        for wallet in wallets {
+            for row in self.get_wallet_pnl_by_protocol(wallet, window).await? {
+                rows.push(WalletProtocolPnlRow {
+                    wallet: wallet.clone(),
+                    protocol: row.protocol,
+                    realized: row.realized,
+                    unrealized: row.unrealized,
+                    trade_count: row.trade_count,
+                });
+            }
+        }
+        Ok(rows)
+    }
+
+    async fn get_wallet_mev_losses(&self, wallet: &str, window: TimeWindow) -> AppResult<MevLossRow> {
+        let (loss, count) = self.sandwich_losses(wallet, window).await;
+        Ok(MevLossRow {
+            estimated_loss_usd: loss.to_string(),
+            sandwiched_count: count,
+        })
+    }
+
+    async fn get_mev_losses_for_wallets(&self, wallets: &[String], window: TimeWindow) -> AppResult<Vec<WalletMevLossRow>> {
+        let mut losses = Vec::new();
+        for wallet in wallets {
+            let (loss, count) = self.sandwich_losses(wallet, window).await;
+            if count > 0 {
+                losses.push(WalletMevLossRow {
+                    wallet: wallet.clone(),
+                    estimated_loss_usd: loss.to_string(),
+                    sandwiched_count: count,
+                });
+            }
+        }
+        Ok(losses)
+    }
+
+    async fn insert_transaction_flag(&self, flag: &TransactionFlagRow) -> AppResult<()> {
+        self.transaction_flags
+            .write()
+            .await
+            .insert((flag.wallet.clone(), flag.signature.clone(), flag.flag.clone()), flag.clone());
+        Ok(())
+    }
+
+    async fn insert_transaction_flags(&self, flags: &[TransactionFlagRow]) -> AppResult<()> {
+        let mut stored = self.transaction_flags.write().await;
+        for flag in flags {
+            stored.insert((flag.wallet.clone(), flag.signature.clone(), flag.flag.clone()), flag.clone());
+        }
+        Ok(())
+    }
+
+    async fn get_transaction_flags(&self, wallet: &str, signatures: &[String]) -> AppResult<Vec<TransactionFlagRow>> {
+        Ok(self
+            .transaction_flags
+            .read()
+            .await
+            .values()
+            .filter(|f| f.wallet == wallet && signatures.contains(&f.signature))
+            .cloned()
+            .collect())
+    }
+
+    async fn count_wallet_flags(&self, wallet: &str, flag: &str) -> AppResult<u64> {
+        let flags = self.transaction_flags.read().await;
+        let signatures: BTreeSet<&str> =
+            flags.values().filter(|f| f.wallet == wallet && f.flag == flag).map(|f| f.signature.as_str()).collect();
+        Ok(signatures.len() as u64)
+    }
+
+    async fn get_slot_swaps(&self, wallet: &str, slots: &[u64]) -> AppResult<Vec<TransactionRow>> {
+        Ok(self
+            .transactions
+            .read()
+            .await
+            .iter()
+            .filter(|tx| slots.contains(&tx.slot) && tx.wallet != wallet && tx.tx_type == "swap")
+            .cloned()
+            .collect())
+    }
+
+    async fn record_token_launch(&self, mint: &str, _pool: &str, slot: u64, _block_time: i64) -> AppResult<()> {
+        let mut launches = self.token_launches.write().await;
+        let earliest = launches.entry(mint.to_string()).or_insert(slot);
+        *earliest = (*earliest).min(slot);
+        Ok(())
+    }
+
+    async fn get_token_launches(&self, mints: &[String]) -> AppResult<Vec<TokenLaunchRow>> {
+        let launches = self.token_launches.read().await;
+        Ok(mints
+            .iter()
+            .filter_map(|mint| launches.get(mint).map(|slot| TokenLaunchRow { mint: mint.clone(), slot: *slot }))
+            .collect())
+    }
+
+    async fn record_token_price(&self, token: &str, price_usd: Decimal, at: i64) -> AppResult<()> {
+        self.token_prices.write().await.push((token.to_string(), price_usd, at));
+        Ok(())
+    }
+
+    async fn get_token_prices(&self, tokens: &[String]) -> AppResult<Vec<TokenPriceRow>> {
+        // The latest by time wins, like the table's replacing version
+        let mut latest: BTreeMap<&str, (i64, Decimal)> = BTreeMap::new();
+        let prices = self.token_prices.read().await;
+        for (token, price, at) in prices.iter().filter(|(token, _, _)| tokens.contains(token)) {
+            if latest.get(token.as_str()).is_none_or(|(seen, _)| at >= seen) {
+                latest.insert(token, (*at, *price));
+            }
+        }
+        Ok(latest
+            .into_iter()
+            .map(|(token, (_, price))| TokenPriceRow { token: token.to_string(), price_usd: price.to_string() })
+            .collect())
+    }
+
+    async fn get_token_daily_closes(&self, tokens: &[String], days: u32) -> AppResult<Vec<TokenDailyCloseRow>> {
+        let cutoff = chrono::Utc::now().timestamp_millis() - i64::from(days) * MILLIS_PER_DAY;
+        let mut closes: BTreeMap<(&str, String), (i64, Decimal)> = BTreeMap::new();
+        let prices = self.token_prices.read().await;
+        for (token, price, at) in prices.iter().filter(|(token, _, at)| tokens.contains(token) && *at >= cutoff) {
+            let day = chrono::DateTime::from_timestamp_millis(*at).unwrap_or_default().format("%Y-%m-%d").to_string();
+            let close = closes.entry((token, day)).or_insert((*at, *price));
+            if *at >= close.0 {
+                *close = (*at, *price);
+            }
+        }
+        Ok(closes
+            .into_iter()
+            .map(|((token, day), (_, price))| TokenDailyCloseRow { token: token.to_string(), day, price_usd: price.to_string() })
+            .collect())
+    }
+
+    async fn insert_position_signal(&self, signal: &PositionSignalRow) -> AppResult<()> {
+        self.position_signals.write().await.insert(
+            (signal.wallet.clone(), signal.block_time, signal.signature.clone(), signal.token.clone()),
+            signal.clone(),
+        );
+        Ok(())
+    }
+
+    async fn get_position_signals(&self, wallets: &[String], before: Option<&(i64, String, String)>, limit: u32) -> AppResult<Vec<PositionSignalRow>> {
+        let mut signals: Vec<PositionSignalRow> = self
+            .position_signals
+            .read()
+            .await
+            .values()
+            .filter(|s| wallets.is_empty() || wallets.contains(&s.wallet))
+            .filter(|s| before.is_none_or(|(time, signature, token)| (s.block_time, &s.signature, &s.token) < (*time, signature, token)))
+            .cloned()
+            .collect();
+        signals.sort_by(|a, b| (b.block_time, &b.signature, &b.token).cmp(&(a.block_time, &a.signature, &a.token)));
+        signals.truncate(limit as usize);
+        Ok(signals)
+    }
+
+    async fn insert_closed_trades(&self, trades: &[ClosedTradeRow]) -> AppResult<()> {
+        let mut stored = self.closed_trades.write().await;
+        for trade in trades {
+            stored.insert((trade.wallet.clone(), trade.signature.clone()), trade.clone());
+        }
+        Ok(())
+    }
+
+    async fn get_leaderboard(&self, filter: &LeaderboardFilter) -> AppResult<Vec<LeaderboardRow>> {
+        let cutoff = filter.window.cutoff_millis();
+        // Trades, wins, PnL and cost basis per wallet and day
+        let mut days: BTreeMap<String, BTreeMap<i64, (u64, u64, Decimal, Decimal)>> = BTreeMap::new();
+        for trade in self.closed_trades.read().await.values() {
+            if cutoff.is_some_and(|c| trade.block_time < c)
+                || filter.protocol.as_ref().is_some_and(|p| &trade.protocol != p)
+                || filter.wallets.as_ref().is_some_and(|w| !w.contains(&trade.wallet))
+            {
+                continue;
+            }
+            let pnl = parse_decimal(&trade.pnl);
+            let day = days.entry(trade.wallet.clone()).or_default().entry(trade.block_time.div_euclid(MILLIS_PER_DAY)).or_default();
+            day.0 += 1;
+            day.1 += u64::from(pnl > Decimal::ZERO);
+            day.2 += pnl;
+            day.3 += parse_decimal(&trade.cost_basis);
+        }
+
+        let summaries = self.summaries.read().await;
+        let min_value = parse_decimal(&filter.min_value_usd);
+        let mut rows = Vec::new();
+        for (wallet, by_day) in days {
+            let Some(summary) = summaries.get(&wallet) else { continue };
+            let total_value = parse_decimal(&summary.total_value_usd);
+            let (trades, wins, pnl, cost) = by_day
+                .values()
+                .fold((0, 0, Decimal::ZERO, Decimal::ZERO), |acc, d| (acc.0 + d.0, acc.1 + d.1, acc.2 + d.2, acc.3 + d.3));
+            if trades < filter.min_trades || total_value < min_value {
+                continue;
+            }
+            let ratio = |pnl: Decimal, cost: Decimal| if cost > Decimal::ZERO { (pnl / cost).to_f64().unwrap_or(0.0) } else { 0.0 };
+            let returns: Vec<f64> = by_day.values().map(|d| ratio(d.2, d.3)).collect();
+            let span = (by_day.keys().last().unwrap() - by_day.keys().next().unwrap() + 1) as f64;
+            let mean = returns.iter().sum::<f64>() / span;
+            let variance = (returns.iter().map(|r| r * r).sum::<f64>() - span * mean * mean) / (span - 1.0).max(1.0);
+            rows.push(LeaderboardRow {
+                wallet,
+                trade_count: trades,
+                win_count: wins,
+                realized_pnl: pnl.to_string(),
+                cost_basis: cost.to_string(),
+                roi: ratio(pnl, cost),
+                win_rate: wins as f64 / trades as f64,
+                sharpe_ratio: (span > 1.0 && variance > 0.0).then(|| mean / variance.sqrt() * 365f64.sqrt()),
+                total_value_usd: summary.total_value_usd.clone(),
+            });
+        }
+
+        let by_pnl = |row: &LeaderboardRow| parse_decimal(&row.realized_pnl);
+        rows.sort_by(|a, b| {
+            let order = match filter.metric {
+                LeaderboardMetric::RealizedPnl => by_pnl(b).cmp(&by_pnl(a)),
+                LeaderboardMetric::Roi => b.roi.total_cmp(&a.roi).then_with(|| by_pnl(b).cmp(&by_pnl(a))),
+                LeaderboardMetric::WinRate => b.win_rate.total_cmp(&a.win_rate).then_with(|| b.trade_count.cmp(&a.trade_count)),
+                LeaderboardMetric::Sharpe => match (a.sharpe_ratio, b.sharpe_ratio) {
+                    (Some(a), Some(b)) => b.total_cmp(&a),
+                    (a, b) => b.is_some().cmp(&a.is_some()),
+                }
+                .then_with(|| by_pnl(b).cmp(&by_pnl(a))),
+            };
+            order.then_with(|| a.wallet.cmp(&b.wallet))
+        });
+        rows.truncate(filter.limit as usize);
+        Ok(rows)
+    }
+
+    async fn get_token_flows(&self, mint: &str, window: TimeWindow, wallets: Option<&[String]>) -> AppResult<Vec<TokenFlowRow>> {
+        let mut flows: BTreeMap<String, (Decimal, Decimal, u64, u64)> = BTreeMap::new();
+        for tx in self.transactions.read().await.iter() {
+            if tx.tx_type != "swap"
+                || (tx.token_in != mint && tx.token_out != mint)
+                || !in_window(tx, window)
+                || wallets.is_some_and(|w| !w.contains(&tx.wallet))
+            {
+                continue;
+            }
+            let flow = flows.entry(tx.wallet.clone()).or_default();
+            if tx.token_out == mint {
+                flow.0 += parse_decimal(&tx.amount_out);
+                flow.2 += 1;
+            }
+            if tx.token_in == mint {
+                flow.1 += parse_decimal(&tx.amount_in);
+                flow.3 += 1;
+            }
+        }
+
+        let summaries = self.summaries.read().await;
+        Ok(flows
+            .into_iter()
+            .map(|(wallet, (bought, sold, buy_count, sell_count))| TokenFlowRow {
+                classification: summaries.get(&wallet).map(|s| s.classification.clone()).unwrap_or_default(),
+                wallet,
+                bought: bought.to_string(),
+                sold: sold.to_string(),
+                buy_count,
+                sell_count,
+            })
+            .collect())
+    }
+
+    async fn insert_wallet_link(&self, link: &WalletLinkRow) -> AppResult<()> {
+        self.wallet_links.write().await.insert(
+            (link.source.clone(), link.target.clone(), link.link_type.clone(), link.signature.clone()),
+            link.clone(),
+        );
+        Ok(())
+    }
+
+    async fn get_wallet_links(&self, wallets: &[String]) -> AppResult<Vec<WalletLinkRow>> {
+        let mut links: Vec<WalletLinkRow> = self
+            .wallet_links
+            .read()
+            .await
+            .values()
+            .filter(|link| wallets.contains(&link.source) || wallets.contains(&link.target))
+            .cloned()
+            .collect();
+        links.sort_by(|a, b| (a.block_time, &a.signature).cmp(&(b.block_time, &b.signature)));
+        Ok(links)
+    }
+
+    async fn get_link_fan_out(&self, sources: &[String]) -> AppResult<Vec<LinkFanOutRow>> {
+        let links = self.wallet_links.read().await;
+        let mut targets: BTreeMap<&str, BTreeSet<&str>> = BTreeMap::new();
+        for link in links.values().filter(|link| sources.contains(&link.source)) {
+            targets.entry(&link.source).or_default().insert(&link.target);
+        }
+        Ok(targets
+            .into_iter()
+            .map(|(source, targets)| LinkFanOutRow {
+                source: source.to_string(),
+                targets: targets.len() as u64,
+            })
+            .collect())
+    }
+
+    async fn save_subscription(&self, wallet: &str) -> AppResult<()> {
+        self.subscriptions.write().await.insert(wallet.to_string());
+        Ok(())
+    }
+
+    async fn remove_subscription(&self, wallet: &str) -> AppResult<()> {
+        self.subscriptions.write().await.remove(wallet);
+        Ok(())
+    }
+
+    async fn subscribed_wallets(&self) -> AppResult<Vec<String>> {
+        Ok(self.subscriptions.read().await.iter().cloned().collect())
+    }
+
+    async fn save_webhook(&self, webhook: &WebhookRow) -> AppResult<()> {
+        self.webhooks.write().await.insert(webhook.id.clone(), webhook.clone());
+        Ok(())
+    }
+
+    async fn get_webhooks(&self) -> AppResult<Vec<WebhookRow>> {
+        let mut webhooks: Vec<WebhookRow> =
+            self.webhooks.read().await.values().filter(|w| w.deleted == 0).cloned().collect();
+        webhooks.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        Ok(webhooks)
+    }
+
+    async fn record_webhook_delivery(&self, delivery: &WebhookDeliveryRow) -> AppResult<()> {
+        self.webhook_deliveries.write().await.push(delivery.clone());
+        Ok(())
+    }
+
+    async fn get_webhook_deliveries(&self, webhook_id: &str, limit: u32) -> AppResult<Vec<WebhookDeliveryRow>> {
+        Ok(self
+            .webhook_deliveries
+            .read()
+            .await
+            .iter()
+            .rev()
+            .filter(|d| d.webhook_id == webhook_id)
+            .take(limit as usize)
+            .cloned()
+            .collect())
+    }
+
+    async fn save_alert_rule(&self, rule: &AlertRuleRow) -> AppResult<()> {
+        self.alert_rules.write().await.insert(rule.id.clone(), rule.clone());
+        Ok(())
+    }
+
+    async fn get_alert_rules(&self) -> AppResult<Vec<AlertRuleRow>> {
+        let mut rules: Vec<AlertRuleRow> =
+            self.alert_rules.read().await.values().filter(|r| r.deleted == 0).cloned().collect();
+        rules.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        Ok(rules)
+    }
+
+    async fn save_alert_state(&self, state: &AlertStateRow) -> AppResult<()> {
+        let key = (state.rule_id.clone(), state.wallet.clone());
+        self.alert_states.write().await.insert(key, state.clone());
+        Ok(())
+    }
+
+    async fn get_alert_states(&self) -> AppResult<Vec<AlertStateRow>> {
+        let mut states: Vec<AlertStateRow> = self.alert_states.read().await.values().cloned().collect();
+        states.sort_by_key(|s| Reverse(s.changed_at));
+        Ok(states)
+    }
+
+    async fn save_api_key(&self, key: &ApiKeyRow) -> AppResult<()> {
+        self.api_keys.write().await.insert(key.id.clone(), key.clone());
+        Ok(())
+    }
+
+    async fn get_api_keys(&self) -> AppResult<Vec<ApiKeyRow>> {
+        let mut keys: Vec<ApiKeyRow> = self.api_keys.read().await.values().filter(|k| k.revoked == 0).cloned().collect();
+        keys.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        Ok(keys)
+    }
+
+    async fn set_key_subscription(&self, subscription: &KeySubscriptionRow) -> AppResult<()> {
+        let key = (subscription.key_id.clone(), subscription.wallet.clone());
+        let mut subscriptions = self.key_subscriptions.write().await;
+        if subscription.active == 1 {
+            subscriptions.insert(key);
+        } else {
+            subscriptions.remove(&key);
+        }
+        Ok(())
+    }
+
+    async fn get_key_subscriptions(&self) -> AppResult<Vec<KeySubscriptionRow>> {
+        Ok(self
+            .key_subscriptions
+            .read()
+            .await
+            .iter()
+            .map(|(key_id, wallet)| KeySubscriptionRow {
+                key_id: key_id.clone(),
+                wallet: wallet.clone(),
+                active: 1,
+            })
+            .collect())
+    }
+
+    async fn save_wallet_group(&self, group: &WalletGroupRow) -> AppResult<()> {
+        self.wallet_groups.write().await.insert(group.id.clone(), group.clone());
+        Ok(())
+    }
+
+    async fn get_wallet_groups(&self) -> AppResult<Vec<WalletGroupRow>> {
+        let mut groups: Vec<WalletGroupRow> =
+            self.wallet_groups.read().await.values().filter(|g| g.deleted == 0).cloned().collect();
+        groups.sort_by(|a, b| (a.created_at, &a.id).cmp(&(b.created_at, &b.id)));
+        Ok(groups)
+    }
+
+    async fn get_markets(&self, slugs: &[String]) -> AppResult<Vec<MarketRow>> {
+        let markets = self.markets.read().await;
+        Ok(slugs.iter().filter_map(|slug| markets.get(slug).cloned()).collect())
+    }
+
+    async fn get_market_candles(&self, _slug: &str, _interval: CandleInterval) -> AppResult<Vec<MarketCandleRow>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_market_volumes(&self, _slugs: &[String]) -> AppResult<Vec<MarketVolumeRow>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_market_anomalies(&self, _slug: &str, _min_z_score: f64) -> AppResult<Vec<MarketAnomalyRow>> {
+        Ok(Vec::new())
+    }
+
+    async fn get_prediction_positions(&self, traders: &[String]) -> AppResult<Vec<PredictionPositionRow>> {
+        Ok(self
+            .prediction_positions
+            .read()
+            .await
+            .iter()
+            .filter(|p| traders.contains(&p.trader))
+            .cloned()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tx(signature: &str, protocol: &str, tx_type: &str, usd_value: &str, age_days: i64) -> TransactionRow {
+        TransactionRow {
+            signature: signature.to_string(),
+            wallet: "wallet".to_string(),
+            protocol: protocol.to_string(),
+            tx_type: tx_type.to_string(),
+            token_in: String::new(),
+            token_out: String::new(),
+            amount_in: "0".to_string(),
+            amount_out: "0".to_string(),
+            usd_value: usd_value.to_string(),
+            block_time: (chrono::Utc::now() - chrono::Duration::days(age_days)).timestamp_millis(),
+            slot: 0,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_pnl_and_activity() {
+        let store = MemoryStore::new();
+        for row in [
+            tx("a", "kamino", "deposit", "1000", 20),
+            tx("b", "kamino", "withdraw", "1100", 3),
+            tx("c", "jupiter", "swap", "50", 0),
+            tx("d", "jupiter", "swap", "10", 60),
+        ] {
+            store.write_transaction(row).await.unwrap();
+        }
+
+        let pnl = store.get_wallet_pnl_by_protocol("wallet", TimeWindow::Month).await.unwrap();
+        let pnl: Vec<(&str, &str, u64)> = pnl.iter().map(|p| (p.protocol.as_str(), p.realized.as_str(), p.trade_count)).collect();
+        assert_eq!(pnl, vec![("kamino", "100", 2), ("jupiter", "50", 1)]);
+
+        let activity = store.get_wallet_activity(&["wallet".to_string()]).await.unwrap();
+        assert_eq!(activity.realized_pnl_24h, "50");
+        assert_eq!(activity.realized_pnl_7d, "1150");
+        assert_eq!(activity.realized_pnl_30d, "1150");
+        assert_eq!(activity.protocols, vec!["jupiter", "kamino"]);
+        assert_eq!(activity.tx_count, 4);
+
+        let history = store.get_wallet_transactions("wallet", TimeWindow::All).await.unwrap();
+        let order: Vec<&str> = history.iter().map(|t| t.signature.as_str()).collect();
+        assert_eq!(order, vec!["d", "a", "b", "c"]);
+    }
+}
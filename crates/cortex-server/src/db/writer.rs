@@ -0,0 +1,371 @@
+//! Buffered transaction writer.
+//!
+//! Rows are queued on a bounded channel and written by a single background task
+//! through the `clickhouse` inserter, which commits a batch once it reaches the
+//! configured row count or age. A full queue makes `write` wait, so a fast stream
+//! slows down instead of piling rows up in memory.
+//!
+//! A batch that fails to commit is kept and retried on the next tick, and each `write`
+//! hands back a [`Committed`] receipt that resolves once its row is stored.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use clickhouse::inserter::Inserter;
+use clickhouse::{Client, Row};
+use rust_decimal::Decimal;
+use serde::Serialize;
+use tokio::sync::{mpsc, oneshot, Mutex};
+use tokio::task::JoinHandle;
+
+use super::models::TransactionRow;
+use crate::config::DatabaseConfig;
+use crate::error::{AppError, AppResult};
+use crate::telemetry;
+
+/// `transactions` as written in RowBinary: decimals as scaled integers, times in ms
+#[derive(Debug, Row, Serialize)]
+struct TransactionInsert {
+    signature: String,
+    wallet: String,
+    protocol: String,
+    tx_type: String,
+    token_in: String,
+    token_out: String,
+    /// Decimal128(18)
+    amount_in: i128,
+    /// Decimal128(18)
+    amount_out: i128,
+    /// Decimal64(2)
+    usd_value: i64,
+    block_time: i64,
+    slot: u64,
+}
+
+impl TransactionInsert {
+    fn from_row(row: TransactionRow) -> Option<Self> {
+        Some(Self {
+            amount_in: to_fixed(&row.amount_in, 18)?,
+            amount_out: to_fixed(&row.amount_out, 18)?,
+            usd_value: i64::try_from(to_fixed(&row.usd_value, 2)?).ok()?,
+            signature: row.signature,
+            wallet: row.wallet,
+            protocol: row.protocol,
+            tx_type: row.tx_type,
+            token_in: row.token_in,
+            token_out: row.token_out,
+            block_time: row.block_time,
+            slot: row.slot,
+        })
+    }
+}
+
+/// A decimal string as the scaled integer ClickHouse stores for `Decimal(_, scale)`.
+/// `None` when unparseable or out of range.
+fn to_fixed(value: &str, scale: u32) -> Option<i128> {
+    let decimal = Decimal::from_str(value).ok()?.round_dp(scale);
+    decimal
+        .mantissa()
+        .checked_mul(10i128.checked_pow(scale - decimal.scale())?)
+}
+
+/// A row waiting for its batch to commit, and whoever queued it
+struct Queued {
+    row: TransactionInsert,
+    committed: oneshot::Sender<AppResult<()>>,
+}
+
+enum Control {
+    Flush(oneshot::Sender<AppResult<()>>),
+    Shutdown,
+}
+
+/// Resolves once a queued transaction has been committed, or with the error that made
+/// the writer give up on it at shutdown
+pub struct Committed(oneshot::Receiver<AppResult<()>>);
+
+impl Committed {
+    /// Receipt for a row that is already stored
+    pub fn done() -> Self {
+        let (sender, receiver) = oneshot::channel();
+        let _ = sender.send(Ok(()));
+        Self(receiver)
+    }
+}
+
+impl Future for Committed {
+    type Output = AppResult<()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut self.0).poll(cx).map(|result| {
+            result.unwrap_or_else(|_| Err(AppError::Internal("Transaction writer stopped before committing".to_string())))
+        })
+    }
+}
+
+/// Handle to the background transaction writer. Cheap to clone.
+#[derive(Clone)]
+pub struct TransactionWriter {
+    rows: mpsc::Sender<Queued>,
+    control: mpsc::Sender<Control>,
+    task: Arc<Mutex<Option<JoinHandle<()>>>>,
+}
+
+impl TransactionWriter {
+    /// Start the writer task for `transactions`, batching per `config`.
+    pub fn spawn(client: &Client, config: &DatabaseConfig) -> AppResult<Self> {
+        let flush_interval = Duration::from_millis(config.insert_flush_ms.max(1));
+        let batch_rows = config.insert_batch_rows.max(1) as usize;
+        let inserter = client
+            .inserter::<TransactionInsert>("transactions")?
+            .with_max_rows(config.insert_batch_rows)
+            .with_period(Some(flush_interval));
+
+        let (rows, row_receiver) = mpsc::channel(batch_rows);
+        let (control, control_receiver) = mpsc::channel(1);
+        let batch = Batch {
+            inserter,
+            rows: Vec::new(),
+            failed: false,
+        };
+        let task = tokio::spawn(run(batch, row_receiver, control_receiver, flush_interval, batch_rows * MAX_BACKLOG_BATCHES));
+
+        println!("[DB] Transaction writer started (batch {} rows / {}ms)",
+            config.insert_batch_rows, flush_interval.as_millis());
+        tracing::info!(
+            batch_rows = %config.insert_batch_rows,
+            flush_ms = %flush_interval.as_millis(),
+            "Transaction writer started"
+        );
+
+        Ok(Self {
+            rows,
+            control,
+            task: Arc::new(Mutex::new(Some(task))),
+        })
+    }
+
+    /// Queue a transaction, waiting while the buffer is full. The returned receipt
+    /// resolves once the row is committed.
+    pub async fn write(&self, row: TransactionRow) -> AppResult<Committed> {
+        let signature = row.signature.clone();
+        let row = TransactionInsert::from_row(row)
+            .ok_or_else(|| AppError::Internal(format!("Transaction {} has out-of-range amounts", signature)))?;
+        let (committed, receipt) = oneshot::channel();
+        self.rows
+            .send(Queued { row, committed })
+            .await
+            .map_err(|_| AppError::Internal("Transaction writer has shut down".to_string()))?;
+        Ok(Committed(receipt))
+    }
+
+    /// Commit everything queued so far and wait until it is stored.
+    pub async fn flush(&self) -> AppResult<()> {
+        let (done, result) = oneshot::channel();
+        self.control
+            .send(Control::Flush(done))
+            .await
+            .map_err(|_| AppError::Internal("Transaction writer has shut down".to_string()))?;
+        result
+            .await
+            .map_err(|_| AppError::Internal("Transaction writer stopped before flushing".to_string()))?
+    }
+
+    /// Write out the remaining buffer and stop the task. Later writes fail.
+    pub async fn shutdown(&self) {
+        let Some(task) = self.task.lock().await.take() else {
+            return;
+        };
+        if self.control.send(Control::Shutdown).await.is_ok() {
+            if let Err(e) = task.await {
+                tracing::error!(error = %e, "Transaction writer task failed");
+            }
+        }
+    }
+}
+
+/// Rows held back while ClickHouse keeps failing, in batches; once reached, `write` waits
+/// until a retry gets through
+const MAX_BACKLOG_BATCHES: usize = 10;
+
+/// Rows written since the last successful commit. The inserter drops its rows when a
+/// commit fails, so they are kept here and written again on the next tick.
+struct Batch {
+    inserter: Inserter<TransactionInsert>,
+    rows: Vec<Queued>,
+    /// The last commit failed and the inserter no longer holds `rows`
+    failed: bool,
+}
+
+impl Batch {
+    fn push(&mut self, queued: Queued) {
+        // Rows wait for the retry rather than starting a batch of their own
+        if !self.failed {
+            if let Err(e) = self.inserter.write(&queued.row) {
+                tracing::error!(signature = %queued.row.signature, error = %e, "Failed to buffer transaction");
+                let _ = queued.committed.send(Err(e.into()));
+                return;
+            }
+        }
+        self.rows.push(queued);
+        telemetry::insert_backlog(self.rows.len());
+    }
+
+    /// Commit the batch if it is due (or unconditionally with `force`), retrying a
+    /// failed one
+    async fn commit(&mut self, force: bool) -> AppResult<()> {
+        let retry = self.failed;
+        if retry {
+            for queued in &self.rows {
+                self.inserter.write(&queued.row)?;
+            }
+            self.failed = false;
+        }
+
+        let result = if force || retry {
+            self.inserter.force_commit().await
+        } else {
+            self.inserter.commit().await
+        };
+
+        match result {
+            Ok(stats) => {
+                if stats.rows > 0 {
+                    telemetry::transactions_inserted(stats.rows);
+                    tracing::debug!(rows = %stats.rows, bytes = %stats.bytes, "Transaction batch committed");
+                    for queued in self.rows.drain(..) {
+                        let _ = queued.committed.send(Ok(()));
+                    }
+                    telemetry::insert_backlog(0);
+                }
+                Ok(())
+            }
+            Err(e) => {
+                self.failed = true;
+                telemetry::insert_failed();
+                println!("[DB] Failed to commit {} transactions, retrying: {}", self.rows.len(), e);
+                tracing::error!(rows = %self.rows.len(), error = %e, "Failed to commit transaction batch, retrying on the next tick");
+                Err(e.into())
+            }
+        }
+    }
+}
+
+async fn run(
+    mut batch: Batch,
+    mut rows: mpsc::Receiver<Queued>,
+    mut control: mpsc::Receiver<Control>,
+    flush_interval: Duration,
+    max_backlog: usize,
+) {
+    let mut ticker = tokio::time::interval(flush_interval);
+
+    loop {
+        tokio::select! {
+            // Stop taking rows while a failing ClickHouse has too many held back
+            queued = rows.recv(), if batch.rows.len() < max_backlog => match queued {
+                Some(queued) => {
+                    batch.push(queued);
+                    if !batch.failed {
+                        let _ = batch.commit(false).await;
+                    }
+                }
+                None => break,
+            },
+            command = control.recv() => match command {
+                Some(Control::Flush(done)) => {
+                    // Rows queued before the flush arrive on their own channel
+                    while let Ok(queued) = rows.try_recv() {
+                        batch.push(queued);
+                    }
+                    let _ = done.send(batch.commit(true).await);
+                }
+                Some(Control::Shutdown) | None => break,
+            },
+            // Commits once the period has elapsed, so a quiet stream still gets stored,
+            // and retries a failed batch
+            _ = ticker.tick() => {
+                let _ = batch.commit(false).await;
+            }
+        }
+    }
+
+    // Rows already queued are committed along with the buffer
+    rows.close();
+    while let Some(queued) = rows.recv().await {
+        batch.push(queued);
+    }
+    let pending = batch.rows.len();
+    match batch.commit(true).await {
+        Ok(()) => {
+            println!("[DB] Transaction writer stopped, flushed {} rows", pending);
+            tracing::info!(rows = %pending, "Transaction writer flushed on shutdown");
+        }
+        Err(e) => {
+            println!("[DB] Transaction writer failed to flush {} rows on shutdown: {}", pending, e);
+            tracing::error!(rows = %pending, error = %e, "Transaction writer failed to flush on shutdown");
+            for queued in batch.rows.drain(..) {
+                let _ = queued.committed.send(Err(AppError::Internal(format!("Transaction writer failed to flush: {}", e))));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_to_fixed() {
+        assert_eq!(to_fixed("1.5", 2), Some(150));
+        assert_eq!(to_fixed("0.000000000000000001", 18), Some(1));
+        assert_eq!(to_fixed("123.456", 2), Some(12346));
+        assert_eq!(to_fixed("-42", 2), Some(-4200));
+        assert_eq!(to_fixed("not a number", 2), None);
+        // 10^28 at 18 places overflows i128
+        assert_eq!(to_fixed("9999999999999999999999999999", 18), None);
+    }
+
+    #[tokio::test]
+    async fn test_failed_batch_is_kept_and_reported() {
+        let db = crate::db::Database::unreachable();
+        let config = DatabaseConfig {
+            url: "http://127.0.0.1:1".to_string(),
+            database: "cortex".to_string(),
+            user: None,
+            password: None,
+            insert_batch_rows: 10,
+            insert_flush_ms: 60_000,
+        };
+        let writer = TransactionWriter::spawn(db.client(), &config).unwrap();
+
+        let mut committed = writer
+            .write(TransactionRow {
+                signature: "sig-1".to_string(),
+                wallet: "wallet".to_string(),
+                protocol: "jupiter".to_string(),
+                tx_type: "swap".to_string(),
+                token_in: "in".to_string(),
+                token_out: "out".to_string(),
+                amount_in: "1".to_string(),
+                amount_out: "2".to_string(),
+                usd_value: "3".to_string(),
+                block_time: 0,
+                slot: 1,
+            })
+            .await
+            .unwrap();
+
+        // The commit fails, but the row is held for the retry rather than acknowledged
+        assert!(writer.flush().await.is_err());
+        assert!(tokio::time::timeout(Duration::from_millis(50), &mut committed).await.is_err());
+
+        // Giving up at shutdown is reported to the writer
+        writer.shutdown().await;
+        assert!(committed.await.is_err());
+    }
+}
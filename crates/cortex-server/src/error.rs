@@ -1,5 +1,5 @@
 use axum::{
-    http::StatusCode,
+    http::{header, StatusCode},
     response::{IntoResponse, Response},
     Json,
 };
@@ -10,9 +10,34 @@ pub enum AppError {
     #[error("Wallet not found: {0}")]
     WalletNotFound(String),
 
+    #[error("Webhook not found: {0}")]
+    WebhookNotFound(String),
+
+    #[error("Alert rule not found: {0}")]
+    AlertRuleNotFound(String),
+
+    #[error("API key not found: {0}")]
+    ApiKeyNotFound(String),
+
+    #[error("Wallet group not found: {0}")]
+    GroupNotFound(String),
+
     #[error("Invalid wallet address: {0}")]
     InvalidWallet(String),
 
+    #[error("Unauthorized: {0}")]
+    Unauthorized(String),
+
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+
+    #[error("Subscription quota exceeded: {0}")]
+    QuotaExceeded(String),
+
+    /// Seconds until the key may try again
+    #[error("Rate limit exceeded, retry in {0}s")]
+    RateLimited(u64),
+
     #[error("Database error: {0}")]
     Database(#[from] clickhouse::error::Error),
 
@@ -29,10 +54,11 @@ pub enum AppError {
     Internal(String),
 }
 
-#[derive(Serialize)]
-struct ErrorResponse {
-    error: String,
-    code: String,
+/// Body of every error response
+#[derive(Serialize, utoipa::ToSchema)]
+pub struct ErrorResponse {
+    pub error: String,
+    pub code: String,
 }
 
 impl IntoResponse for AppError {
@@ -42,6 +68,38 @@ impl IntoResponse for AppError {
                 tracing::info!(wallet = %wallet, error_code = "WALLET_NOT_FOUND", "Wallet not found");
                 (StatusCode::NOT_FOUND, "WALLET_NOT_FOUND", "info")
             }
+            AppError::WebhookNotFound(id) => {
+                tracing::info!(webhook_id = %id, error_code = "WEBHOOK_NOT_FOUND", "Webhook not found");
+                (StatusCode::NOT_FOUND, "WEBHOOK_NOT_FOUND", "info")
+            }
+            AppError::AlertRuleNotFound(id) => {
+                tracing::info!(rule_id = %id, error_code = "ALERT_RULE_NOT_FOUND", "Alert rule not found");
+                (StatusCode::NOT_FOUND, "ALERT_RULE_NOT_FOUND", "info")
+            }
+            AppError::ApiKeyNotFound(id) => {
+                tracing::info!(key_id = %id, error_code = "API_KEY_NOT_FOUND", "API key not found");
+                (StatusCode::NOT_FOUND, "API_KEY_NOT_FOUND", "info")
+            }
+            AppError::GroupNotFound(id) => {
+                tracing::info!(group_id = %id, error_code = "GROUP_NOT_FOUND", "Wallet group not found");
+                (StatusCode::NOT_FOUND, "GROUP_NOT_FOUND", "info")
+            }
+            AppError::Unauthorized(msg) => {
+                tracing::warn!(message = %msg, error_code = "UNAUTHORIZED", "Request without a valid API key");
+                (StatusCode::UNAUTHORIZED, "UNAUTHORIZED", "warn")
+            }
+            AppError::Forbidden(msg) => {
+                tracing::warn!(message = %msg, error_code = "FORBIDDEN", "API key lacks the required scope");
+                (StatusCode::FORBIDDEN, "FORBIDDEN", "warn")
+            }
+            AppError::QuotaExceeded(msg) => {
+                tracing::warn!(message = %msg, error_code = "QUOTA_EXCEEDED", "Subscription quota exceeded");
+                (StatusCode::FORBIDDEN, "QUOTA_EXCEEDED", "warn")
+            }
+            AppError::RateLimited(retry_after) => {
+                tracing::warn!(retry_after_secs = %retry_after, error_code = "RATE_LIMITED", "Rate limit exceeded");
+                (StatusCode::TOO_MANY_REQUESTS, "RATE_LIMITED", "warn")
+            }
             AppError::InvalidWallet(wallet) => {
                 tracing::warn!(wallet = %wallet, error_code = "INVALID_WALLET", "Invalid wallet address");
                 (StatusCode::BAD_REQUEST, "INVALID_WALLET", "warn")
@@ -81,7 +139,12 @@ impl IntoResponse for AppError {
             code: code.to_string(),
         });
 
-        (status, body).into_response()
+        match self {
+            AppError::RateLimited(retry_after) => {
+                (status, [(header::RETRY_AFTER, retry_after.to_string())], body).into_response()
+            }
+            _ => (status, body).into_response(),
+        }
     }
 }
 
@@ -0,0 +1,220 @@
+//! Wallet conviction from indexed positions and prediction market holdings
+
+use std::str::FromStr;
+
+use async_graphql::SimpleObject;
+use chrono::{DateTime, Utc};
+use cortex_core::{
+    calculate_conviction, ConvictionConfidence, DeFiPosition, MarketStatus, PositionType, PredictionMarketBet, SignalType, Wallet,
+    WalletClassification,
+};
+
+use crate::db::models::{PositionRow, PredictionPositionRow, WalletSummaryRow};
+use crate::tokens;
+
+/// How closely a wallet's prediction market bets line up with its DeFi positions
+#[derive(Debug, SimpleObject)]
+pub struct Conviction {
+    /// 0 to 1; higher means bets and positions agree more
+    pub score: f64,
+    /// low, medium or high, depending on how much data there was
+    pub confidence: String,
+    pub interpretation: String,
+    pub signals: Vec<ConvictionSignal>,
+}
+
+/// One bet matched against the positions in the same asset
+#[derive(Debug, SimpleObject)]
+pub struct ConvictionSignal {
+    /// bullish_alignment, bearish_alignment or contradiction
+    pub signal_type: String,
+    pub strength: f64,
+    pub defi_context: String,
+    pub prediction_context: String,
+    pub description: String,
+}
+
+fn parse_f64(s: &str) -> f64 {
+    f64::from_str(s).unwrap_or_default()
+}
+
+fn datetime(millis: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(millis).unwrap_or_default()
+}
+
+fn defi_position(row: &PositionRow) -> DeFiPosition {
+    let position_type = match row.position_type.as_str() {
+        "lending_supply" => PositionType::Lending,
+        "lending_borrow" => PositionType::Borrowing,
+        "lp" => PositionType::LiquidityPool,
+        _ => PositionType::Other,
+    };
+    let entry_price = parse_f64(&row.entry_price);
+    DeFiPosition {
+        protocol: row.protocol.clone(),
+        position_type,
+        token: row.token.clone(),
+        // Liquid-staked SOL counts as SOL exposure
+        token_symbol: tokens::symbol(tokens::underlying_asset(&row.token)).unwrap_or(&row.token).to_string(),
+        amount: parse_f64(&row.amount),
+        usd_value: parse_f64(&row.usd_value),
+        entry_price: (entry_price > 0.0).then_some(entry_price),
+        current_price: parse_f64(&row.current_price),
+        unrealized_pnl: parse_f64(&row.unrealized_pnl),
+        opened_at: Utc::now(),
+        updated_at: Utc::now(),
+        metadata: None,
+    }
+}
+
+fn prediction_bet(row: &PredictionPositionRow) -> PredictionMarketBet {
+    let shares = parse_f64(&row.shares);
+    let current_price = parse_f64(&row.current_price);
+    let amount_usd = parse_f64(&row.cost_usd);
+    let market_status = match row.status.as_str() {
+        "closed" => MarketStatus::Closed,
+        "resolved" => MarketStatus::Resolved,
+        _ => MarketStatus::Open,
+    };
+    PredictionMarketBet {
+        platform: row.platform.clone(),
+        market_slug: row.slug.clone(),
+        market_title: row.title.clone(),
+        outcome: row.outcome.clone(),
+        amount_usd,
+        entry_price: parse_f64(&row.avg_price),
+        current_price,
+        shares,
+        unrealized_pnl: shares * current_price - amount_usd,
+        category: row.category.clone(),
+        resolution_date: (row.end_date > 0).then(|| datetime(row.end_date)),
+        placed_at: datetime(row.first_trade_at),
+        market_status,
+    }
+}
+
+/// Score a wallet with `cortex_core::calculate_conviction`. Without both positions and
+/// bets the score is 0 with low confidence, and the interpretation says why.
+pub fn wallet_conviction(
+    address: &str,
+    summary: Option<&WalletSummaryRow>,
+    positions: &[PositionRow],
+    predictions: &[PredictionPositionRow],
+) -> Conviction {
+    let wallet = Wallet {
+        address: address.to_string(),
+        total_value_usd: summary.map(|s| parse_f64(&s.total_value_usd)).unwrap_or_default(),
+        defi_positions: positions.iter().map(defi_position).collect(),
+        prediction_bets: predictions.iter().map(prediction_bet).collect(),
+        classification: summary.map(|s| WalletClassification::from_str_lossy(&s.classification)),
+        risk_score: summary.map(|s| s.risk_score).unwrap_or_default(),
+        last_activity: summary.map(|s| s.last_activity_datetime()).unwrap_or_default(),
+        protocols: summary.map(|s| s.protocols.clone()).unwrap_or_default(),
+    };
+
+    match calculate_conviction(&wallet) {
+        Ok(conviction) => Conviction {
+            score: conviction.conviction_score,
+            confidence: confidence_name(&conviction.confidence).to_string(),
+            interpretation: conviction.interpretation,
+            signals: conviction
+                .signals
+                .into_iter()
+                .map(|signal| ConvictionSignal {
+                    signal_type: signal_type_name(&signal.signal_type).to_string(),
+                    strength: signal.strength,
+                    defi_context: signal.defi_context,
+                    prediction_context: signal.prediction_context,
+                    description: signal.description,
+                })
+                .collect(),
+        },
+        Err(e) => Conviction {
+            score: 0.0,
+            confidence: confidence_name(&ConvictionConfidence::Low).to_string(),
+            interpretation: format!("Unable to calculate conviction: {}", e),
+            signals: Vec::new(),
+        },
+    }
+}
+
+fn confidence_name(confidence: &ConvictionConfidence) -> &'static str {
+    match confidence {
+        ConvictionConfidence::High => "high",
+        ConvictionConfidence::Medium => "medium",
+        ConvictionConfidence::Low => "low",
+    }
+}
+
+fn signal_type_name(signal_type: &SignalType) -> &'static str {
+    match signal_type {
+        SignalType::BullishAlignment => "bullish_alignment",
+        SignalType::BearishAlignment => "bearish_alignment",
+        SignalType::Contradiction => "contradiction",
+        SignalType::FrontRunning => "front_running",
+        SignalType::HighConviction => "high_conviction",
+        SignalType::TrackRecord => "track_record",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn position(token: &str, usd_value: &str, unrealized_pnl: &str) -> PositionRow {
+        PositionRow {
+            wallet: "wallet".to_string(),
+            protocol: "kamino".to_string(),
+            position_type: "lending_supply".to_string(),
+            token: token.to_string(),
+            pool: String::new(),
+            amount: "10".to_string(),
+            entry_price: "150".to_string(),
+            current_price: "160".to_string(),
+            usd_value: usd_value.to_string(),
+            unrealized_pnl: unrealized_pnl.to_string(),
+            apy: "0".to_string(),
+        }
+    }
+
+    fn bet(title: &str, outcome: &str) -> PredictionPositionRow {
+        PredictionPositionRow {
+            trader: "wallet".to_string(),
+            slug: "sol-above-200".to_string(),
+            platform: "polymarket".to_string(),
+            title: title.to_string(),
+            category: "crypto".to_string(),
+            status: "active".to_string(),
+            outcome: outcome.to_string(),
+            shares: "1000".to_string(),
+            cost_usd: "400".to_string(),
+            avg_price: "0.4".to_string(),
+            current_price: "0.55".to_string(),
+            first_trade_at: 1_700_000_000_000,
+            end_date: 0,
+        }
+    }
+
+    #[test]
+    fn test_staked_sol_backs_a_sol_bet() {
+        let positions = [position(tokens::MSOL_MINT, "1600", "100")];
+        let predictions = [bet("Will Solana reach $200 by June?", "Yes")];
+
+        let conviction = wallet_conviction("wallet", None, &positions, &predictions);
+        assert_eq!(conviction.signals.len(), 1);
+        assert_eq!(conviction.signals[0].signal_type, "bullish_alignment");
+        assert!(conviction.score > 0.7);
+
+        let predictions = [bet("Will Solana reach $200 by June?", "No")];
+        let conviction = wallet_conviction("wallet", None, &positions, &predictions);
+        assert_eq!(conviction.signals[0].signal_type, "contradiction");
+    }
+
+    #[test]
+    fn test_no_data_scores_zero() {
+        let conviction = wallet_conviction("wallet", None, &[], &[]);
+        assert_eq!(conviction.score, 0.0);
+        assert_eq!(conviction.confidence, "low");
+        assert!(conviction.interpretation.starts_with("Unable to calculate conviction"));
+    }
+}
@@ -0,0 +1,136 @@
+//! Per-request dataloaders. Every wallet or market a query touches is collected and
+//! fetched with one store call per field, rather than one per wallet or market.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_graphql::dataloader::{DataLoader, Loader};
+
+use crate::db::models::{MarketRow, MarketVolumeRow, PnlByProtocolRow, PositionRow, PredictionPositionRow, WalletSummaryRow};
+use crate::db::store::CortexStore;
+use crate::error::AppError;
+use crate::types::TimeWindow;
+
+/// Loader errors are shared by every field waiting on the batch
+pub type LoadError = Arc<AppError>;
+
+/// The dataloaders for one request, and the store for fields that aren't batched
+pub struct Loaders {
+    pub store: Arc<dyn CortexStore>,
+    pub summaries: DataLoader<SummaryLoader>,
+    pub positions: DataLoader<PositionLoader>,
+    pub pnl: DataLoader<PnlLoader>,
+    pub predictions: DataLoader<PredictionLoader>,
+    pub markets: DataLoader<MarketLoader>,
+    pub volumes: DataLoader<VolumeLoader>,
+}
+
+impl Loaders {
+    pub fn new(store: Arc<dyn CortexStore>) -> Self {
+        Self {
+            summaries: DataLoader::new(SummaryLoader(store.clone()), tokio::spawn),
+            positions: DataLoader::new(PositionLoader(store.clone()), tokio::spawn),
+            pnl: DataLoader::new(PnlLoader(store.clone()), tokio::spawn),
+            predictions: DataLoader::new(PredictionLoader(store.clone()), tokio::spawn),
+            markets: DataLoader::new(MarketLoader(store.clone()), tokio::spawn),
+            volumes: DataLoader::new(VolumeLoader(store.clone()), tokio::spawn),
+            store,
+        }
+    }
+}
+
+/// Group rows under their key, with an empty list for every key that has none
+fn group_by<V>(keys: &[String], rows: Vec<V>, key: impl Fn(&V) -> &str) -> HashMap<String, Vec<V>> {
+    let mut groups: HashMap<String, Vec<V>> = keys.iter().map(|k| (k.clone(), Vec::new())).collect();
+    for row in rows {
+        if let Some(group) = groups.get_mut(key(&row)) {
+            group.push(row);
+        }
+    }
+    groups
+}
+
+pub struct SummaryLoader(Arc<dyn CortexStore>);
+
+impl Loader<String> for SummaryLoader {
+    type Value = WalletSummaryRow;
+    type Error = LoadError;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        let summaries = self.0.get_wallet_summaries(keys).await?;
+        Ok(summaries.into_iter().map(|s| (s.wallet.clone(), s)).collect())
+    }
+}
+
+pub struct PositionLoader(Arc<dyn CortexStore>);
+
+impl Loader<String> for PositionLoader {
+    type Value = Vec<PositionRow>;
+    type Error = LoadError;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        let positions = self.0.get_positions_for_wallets(keys).await?;
+        Ok(group_by(keys, positions, |p| &p.wallet))
+    }
+}
+
+/// PnL by protocol, keyed by wallet and window. One query per distinct window.
+pub struct PnlLoader(Arc<dyn CortexStore>);
+
+impl Loader<(String, TimeWindow)> for PnlLoader {
+    type Value = Vec<PnlByProtocolRow>;
+    type Error = LoadError;
+
+    async fn load(&self, keys: &[(String, TimeWindow)]) -> Result<HashMap<(String, TimeWindow), Self::Value>, Self::Error> {
+        let mut by_window: HashMap<TimeWindow, Vec<String>> = HashMap::new();
+        for (wallet, window) in keys {
+            by_window.entry(*window).or_default().push(wallet.clone());
+        }
+
+        let mut loaded = HashMap::new();
+        for (window, wallets) in by_window {
+            let rows = self.0.get_pnl_by_protocol_for_wallets(&wallets, window).await?;
+            for (wallet, rows) in group_by(&wallets, rows, |r| &r.wallet) {
+                loaded.insert((wallet, window), rows.into_iter().map(Into::into).collect());
+            }
+        }
+        Ok(loaded)
+    }
+}
+
+/// Prediction market holdings, keyed by trader address
+pub struct PredictionLoader(Arc<dyn CortexStore>);
+
+impl Loader<String> for PredictionLoader {
+    type Value = Vec<PredictionPositionRow>;
+    type Error = LoadError;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        let positions = self.0.get_prediction_positions(keys).await?;
+        Ok(group_by(keys, positions, |p| &p.trader))
+    }
+}
+
+pub struct MarketLoader(Arc<dyn CortexStore>);
+
+impl Loader<String> for MarketLoader {
+    type Value = MarketRow;
+    type Error = LoadError;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        let markets = self.0.get_markets(keys).await?;
+        Ok(markets.into_iter().map(|m| (m.slug.clone(), m)).collect())
+    }
+}
+
+pub struct VolumeLoader(Arc<dyn CortexStore>);
+
+impl Loader<String> for VolumeLoader {
+    type Value = MarketVolumeRow;
+    type Error = LoadError;
+
+    async fn load(&self, keys: &[String]) -> Result<HashMap<String, Self::Value>, Self::Error> {
+        let volumes = self.0.get_market_volumes(keys).await?;
+        Ok(volumes.into_iter().map(|v| (v.slug.clone(), v)).collect())
+    }
+}
@@ -0,0 +1,210 @@
+//! The `Market` type: a prediction market with its price trend, volume and anomalies
+
+use async_graphql::{Context, Object, Result, SimpleObject};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+use super::loaders::Loaders;
+use crate::db::models::{MarketAnomalyRow, MarketCandleRow, MarketRow, MarketVolumeRow, PredictionPositionRow};
+use crate::error::AppError;
+use crate::types::CandleInterval;
+
+fn parse_decimal(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap_or_default()
+}
+
+fn datetime(millis: i64) -> DateTime<Utc> {
+    DateTime::from_timestamp_millis(millis).unwrap_or_default()
+}
+
+pub struct Market(pub MarketRow);
+
+/// Trades in one interval: opening, highest, lowest and closing price, and volume
+#[derive(Debug, SimpleObject)]
+pub struct Candle {
+    pub start: DateTime<Utc>,
+    pub open: Decimal,
+    pub high: Decimal,
+    pub low: Decimal,
+    pub close: Decimal,
+    pub volume_usd: Decimal,
+    pub trade_count: u32,
+}
+
+impl From<MarketCandleRow> for Candle {
+    fn from(row: MarketCandleRow) -> Self {
+        Self {
+            start: datetime(row.interval_start),
+            open: parse_decimal(&row.open),
+            high: parse_decimal(&row.high),
+            low: parse_decimal(&row.low),
+            close: parse_decimal(&row.close),
+            volume_usd: parse_decimal(&row.volume_usd),
+            trade_count: row.trade_count,
+        }
+    }
+}
+
+/// Trading over the last day and week, and the latest order book
+#[derive(Debug, SimpleObject)]
+pub struct MarketVolume {
+    pub volume_24h: Decimal,
+    pub volume_7d: Decimal,
+    pub trades_24h: u32,
+    pub unique_traders_24h: u32,
+    pub avg_trade_size: Decimal,
+    pub bid_depth_usd: Decimal,
+    pub ask_depth_usd: Decimal,
+    pub spread: Decimal,
+}
+
+impl From<MarketVolumeRow> for MarketVolume {
+    fn from(row: MarketVolumeRow) -> Self {
+        Self {
+            volume_24h: parse_decimal(&row.volume_24h),
+            volume_7d: parse_decimal(&row.volume_7d),
+            trades_24h: row.trades_24h,
+            unique_traders_24h: row.unique_traders_24h,
+            avg_trade_size: parse_decimal(&row.avg_trade_size),
+            bid_depth_usd: parse_decimal(&row.bid_depth_usd),
+            ask_depth_usd: parse_decimal(&row.ask_depth_usd),
+            spread: parse_decimal(&row.spread),
+        }
+    }
+}
+
+/// An outcome price far from its trailing one-hour mean
+#[derive(Debug, SimpleObject)]
+pub struct Anomaly {
+    pub outcome_token: String,
+    pub timestamp: DateTime<Utc>,
+    pub price: Decimal,
+    pub mean_price: Decimal,
+    pub std_dev: Decimal,
+    /// Standard deviations from the mean; negative below it
+    pub z_score: f64,
+}
+
+impl From<MarketAnomalyRow> for Anomaly {
+    fn from(row: MarketAnomalyRow) -> Self {
+        Self {
+            outcome_token: row.outcome_token,
+            timestamp: datetime(row.timestamp),
+            price: parse_decimal(&row.price),
+            mean_price: parse_decimal(&row.mean_price),
+            std_dev: parse_decimal(&row.std_dev),
+            z_score: row.z_score,
+        }
+    }
+}
+
+/// A prediction market on Polymarket, Kalshi or another platform
+#[Object]
+impl Market {
+    async fn slug(&self) -> &str {
+        &self.0.slug
+    }
+
+    async fn platform(&self) -> &str {
+        &self.0.platform
+    }
+
+    async fn title(&self) -> &str {
+        &self.0.title
+    }
+
+    async fn description(&self) -> &str {
+        &self.0.description
+    }
+
+    async fn category(&self) -> &str {
+        &self.0.category
+    }
+
+    /// active, closed or resolved
+    async fn status(&self) -> &str {
+        &self.0.status
+    }
+
+    async fn outcomes(&self) -> &[String] {
+        &self.0.outcome_labels
+    }
+
+    async fn resolved_outcome(&self) -> Option<&str> {
+        Some(self.0.resolved_outcome.as_str()).filter(|o| !o.is_empty())
+    }
+
+    async fn end_date(&self) -> Option<DateTime<Utc>> {
+        (self.0.end_date > 0).then(|| datetime(self.0.end_date))
+    }
+
+    /// Price candles, oldest first: 48 intervals of 5m, 15m, 1h or 4h, or 30 days of 1d
+    async fn trend(&self, ctx: &Context<'_>, #[graphql(default = "1h")] interval: String) -> Result<Vec<Candle>> {
+        let interval = CandleInterval::from_str(&interval).ok_or_else(|| AppError::InvalidParam(format!("Invalid interval: {}", interval)))?;
+        let store = &ctx.data_unchecked::<Loaders>().store;
+        let candles = store.get_market_candles(&self.0.slug, interval).await?;
+        Ok(candles.into_iter().map(Into::into).collect())
+    }
+
+    /// Null when the market has had no trades in the last week
+    async fn volume(&self, ctx: &Context<'_>) -> Result<Option<MarketVolume>> {
+        let volume = ctx.data_unchecked::<Loaders>().volumes.load_one(self.0.slug.clone()).await?;
+        Ok(volume.map(Into::into))
+    }
+
+    /// Prices in the last 24 hours more than `minZScore` standard deviations from
+    /// their trailing one-hour mean, newest first
+    async fn anomalies(&self, ctx: &Context<'_>, #[graphql(default = 3.0)] min_z_score: f64) -> Result<Vec<Anomaly>> {
+        if !min_z_score.is_finite() || min_z_score <= 0.0 {
+            return Err(AppError::InvalidParam(format!("Invalid minZScore: {}", min_z_score)).into());
+        }
+        let store = &ctx.data_unchecked::<Loaders>().store;
+        let anomalies = store.get_market_anomalies(&self.0.slug, min_z_score).await?;
+        Ok(anomalies.into_iter().map(Into::into).collect())
+    }
+}
+
+/// A net holding of one market outcome
+pub struct PredictionPosition(pub PredictionPositionRow);
+
+#[Object]
+impl PredictionPosition {
+    async fn trader(&self) -> &str {
+        &self.0.trader
+    }
+
+    async fn outcome(&self) -> &str {
+        &self.0.outcome
+    }
+
+    async fn shares(&self) -> Decimal {
+        parse_decimal(&self.0.shares)
+    }
+
+    /// USD paid for buys less USD received for sells
+    async fn cost_usd(&self) -> Decimal {
+        parse_decimal(&self.0.cost_usd)
+    }
+
+    async fn avg_price(&self) -> Decimal {
+        parse_decimal(&self.0.avg_price)
+    }
+
+    async fn current_price(&self) -> Decimal {
+        parse_decimal(&self.0.current_price)
+    }
+
+    async fn value_usd(&self) -> Decimal {
+        parse_decimal(&self.0.shares) * parse_decimal(&self.0.current_price)
+    }
+
+    async fn first_trade_at(&self) -> DateTime<Utc> {
+        datetime(self.0.first_trade_at)
+    }
+
+    async fn market(&self, ctx: &Context<'_>) -> Result<Option<Market>> {
+        let market = ctx.data_unchecked::<Loaders>().markets.load_one(self.0.slug.clone()).await?;
+        Ok(market.map(Market))
+    }
+}
@@ -0,0 +1,77 @@
+//! GraphQL over wallets and prediction markets, served beside the REST routes.
+//!
+//! One query can fetch a wallet's summary, positions, PnL, transactions and conviction
+//! together with the markets it bets on. The dataloaders in [`Loaders`] batch the
+//! wallets and markets a query touches into one store call per field.
+
+mod conviction;
+mod loaders;
+mod market;
+mod wallet;
+
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Result, Schema};
+
+use crate::error::AppError;
+use crate::types::validate_solana_address;
+
+pub use loaders::Loaders;
+use market::Market;
+use wallet::Wallet;
+
+pub type CortexSchema = Schema<Query, EmptyMutation, EmptySubscription>;
+
+/// Most wallets or markets one list field may ask for
+const MAX_BATCH: usize = 100;
+/// Deepest nesting a query may use; `wallets { predictions { market { volume { ... } } } }` is 5
+const MAX_DEPTH: usize = 8;
+/// Most fields a query may select
+const MAX_COMPLEXITY: usize = 500;
+
+pub fn schema() -> CortexSchema {
+    Schema::build(Query, EmptyMutation, EmptySubscription)
+        .limit_depth(MAX_DEPTH)
+        .limit_complexity(MAX_COMPLEXITY)
+        .finish()
+}
+
+fn check_batch(field: &str, len: usize) -> Result<()> {
+    if len == 0 || len > MAX_BATCH {
+        return Err(AppError::InvalidParam(format!("{} must list between 1 and {} entries", field, MAX_BATCH)).into());
+    }
+    Ok(())
+}
+
+fn wallet(address: String) -> Result<Wallet> {
+    if !validate_solana_address(&address) {
+        return Err(AppError::InvalidWallet(address).into());
+    }
+    Ok(Wallet { address })
+}
+
+pub struct Query;
+
+#[Object]
+impl Query {
+    async fn wallet(&self, address: String) -> Result<Wallet> {
+        wallet(address)
+    }
+
+    /// Up to 100 wallets, in the order given
+    async fn wallets(&self, addresses: Vec<String>) -> Result<Vec<Wallet>> {
+        check_batch("addresses", addresses.len())?;
+        addresses.into_iter().map(wallet).collect()
+    }
+
+    /// Null for an unknown slug
+    async fn market(&self, ctx: &Context<'_>, slug: String) -> Result<Option<Market>> {
+        let market = ctx.data_unchecked::<Loaders>().markets.load_one(slug).await?;
+        Ok(market.map(Market))
+    }
+
+    /// Up to 100 markets in the order given, leaving out unknown slugs
+    async fn markets(&self, ctx: &Context<'_>, slugs: Vec<String>) -> Result<Vec<Market>> {
+        check_batch("slugs", slugs.len())?;
+        let mut found = ctx.data_unchecked::<Loaders>().markets.load_many(slugs.iter().cloned()).await?;
+        Ok(slugs.iter().filter_map(|slug| found.remove(slug)).map(Market).collect())
+    }
+}
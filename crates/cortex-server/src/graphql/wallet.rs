@@ -0,0 +1,155 @@
+//! The `Wallet` type: an indexed wallet and everything known about it
+
+use async_graphql::{Context, Object, Result, SimpleObject};
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use std::str::FromStr;
+
+use super::conviction::{wallet_conviction, Conviction};
+use super::loaders::Loaders;
+use super::market::PredictionPosition;
+use crate::api::dto::{Position, ProtocolPnl, RiskSummary, TransactionEntry, UserSummaryResponse, MAX_TRANSACTION_PAGE};
+use crate::db::models::{PredictionPositionRow, TransactionFilter, WalletSummaryRow};
+use crate::error::AppError;
+use crate::types::{Protocol, TimeWindow, TransactionSort, TransactionType};
+
+fn parse_decimal(s: &str) -> Decimal {
+    Decimal::from_str(s).unwrap_or_default()
+}
+
+pub struct Wallet {
+    pub address: String,
+}
+
+/// Realized PnL within a window
+#[derive(Debug, SimpleObject)]
+pub struct Pnl {
+    pub window: String,
+    pub total_realized: Decimal,
+    pub total_unrealized: Decimal,
+    pub by_protocol: Vec<ProtocolPnl>,
+}
+
+impl Wallet {
+    async fn summary(&self, ctx: &Context<'_>) -> Result<Option<WalletSummaryRow>> {
+        Ok(ctx.data_unchecked::<Loaders>().summaries.load_one(self.address.clone()).await?)
+    }
+
+    /// Holdings of the wallet and, when given, of its linked EVM address
+    async fn holdings(&self, ctx: &Context<'_>, evm_address: Option<String>) -> Result<Vec<PredictionPositionRow>> {
+        let traders = std::iter::once(self.address.clone()).chain(evm_address);
+        let holdings = ctx.data_unchecked::<Loaders>().predictions.load_many(traders).await?;
+        Ok(holdings.into_values().flatten().collect())
+    }
+}
+
+/// A Solana wallet. Summary fields are zero and lists empty until it has been indexed.
+#[Object]
+impl Wallet {
+    async fn address(&self) -> &str {
+        &self.address
+    }
+
+    /// Whether the wallet has a stored summary
+    async fn indexed(&self, ctx: &Context<'_>) -> Result<bool> {
+        Ok(self.summary(ctx).await?.is_some())
+    }
+
+    async fn total_value_usd(&self, ctx: &Context<'_>) -> Result<Decimal> {
+        Ok(self.summary(ctx).await?.map(|s| parse_decimal(&s.total_value_usd)).unwrap_or_default())
+    }
+
+    async fn last_activity(&self, ctx: &Context<'_>) -> Result<Option<DateTime<Utc>>> {
+        Ok(self.summary(ctx).await?.map(|s| s.last_activity_datetime()))
+    }
+
+    async fn protocols(&self, ctx: &Context<'_>) -> Result<Vec<String>> {
+        Ok(self.summary(ctx).await?.map(|s| s.protocols).unwrap_or_default())
+    }
+
+    /// Behavioural class such as whale, trader or bot
+    async fn classification(&self, ctx: &Context<'_>) -> Result<String> {
+        Ok(self.summary(ctx).await?.map(|s| s.classification).filter(|c| !c.is_empty()).unwrap_or_else(|| "unknown".to_string()))
+    }
+
+    async fn risk(&self, ctx: &Context<'_>) -> Result<RiskSummary> {
+        Ok(match self.summary(ctx).await? {
+            Some(summary) => UserSummaryResponse::from(summary).risk,
+            None => RiskSummary::empty(),
+        })
+    }
+
+    /// Realized PnL by protocol over `window`: 24h, 7d, 30d or all
+    async fn pnl(&self, ctx: &Context<'_>, #[graphql(default = "7d")] window: String) -> Result<Pnl> {
+        let time_window = TimeWindow::from_str(&window).ok_or_else(|| AppError::InvalidParam(format!("Invalid window: {}", window)))?;
+        let rows = ctx
+            .data_unchecked::<Loaders>()
+            .pnl
+            .load_one((self.address.clone(), time_window))
+            .await?
+            .unwrap_or_default();
+        let by_protocol: Vec<ProtocolPnl> = rows.into_iter().map(Into::into).collect();
+        Ok(Pnl {
+            window,
+            total_realized: by_protocol.iter().map(|p| p.realized).sum(),
+            total_unrealized: by_protocol.iter().map(|p| p.unrealized).sum(),
+            by_protocol,
+        })
+    }
+
+    /// Open positions, largest first
+    async fn positions(&self, ctx: &Context<'_>) -> Result<Vec<Position>> {
+        let positions = ctx.data_unchecked::<Loaders>().positions.load_one(self.address.clone()).await?;
+        Ok(positions.unwrap_or_default().into_iter().map(Into::into).collect())
+    }
+
+    /// Most recent transactions, optionally of one protocol or type. Use the REST
+    /// history endpoint to page further back.
+    async fn transactions(
+        &self,
+        ctx: &Context<'_>,
+        #[graphql(default = 20)] first: u32,
+        protocol: Option<String>,
+        tx_type: Option<String>,
+    ) -> Result<Vec<TransactionEntry>> {
+        if first == 0 || first > MAX_TRANSACTION_PAGE {
+            return Err(AppError::InvalidParam(format!("first must be between 1 and {}", MAX_TRANSACTION_PAGE)).into());
+        }
+        let protocol = protocol
+            .map(|p| Protocol::from_str(&p).map(|p| p.to_string()).ok_or(AppError::InvalidParam(format!("Invalid protocol: {}", p))))
+            .transpose()?;
+        let tx_type = tx_type
+            .map(|t| TransactionType::from_str(&t).map(|t| t.to_string()).ok_or(AppError::InvalidParam(format!("Invalid tx_type: {}", t))))
+            .transpose()?;
+
+        let filter = TransactionFilter {
+            protocol,
+            tx_type,
+            token: None,
+            from: None,
+            to: None,
+            min_usd_value: None,
+            sort: TransactionSort::Newest,
+            after: None,
+            limit: first,
+        };
+        let store = &ctx.data_unchecked::<Loaders>().store;
+        let rows = store.get_transaction_page(&self.address, &filter).await?;
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    /// Prediction market outcomes held, from trades taken by this address or by
+    /// `evmAddress`, the wallet's account on EVM-based markets
+    async fn predictions(&self, ctx: &Context<'_>, evm_address: Option<String>) -> Result<Vec<PredictionPosition>> {
+        Ok(self.holdings(ctx, evm_address).await?.into_iter().map(PredictionPosition).collect())
+    }
+
+    /// How the wallet's prediction market bets line up with its DeFi positions
+    async fn conviction(&self, ctx: &Context<'_>, evm_address: Option<String>) -> Result<Conviction> {
+        let loaders = ctx.data_unchecked::<Loaders>();
+        let summary = self.summary(ctx).await?;
+        let positions = loaders.positions.load_one(self.address.clone()).await?.unwrap_or_default();
+        let holdings = self.holdings(ctx, evm_address).await?;
+        Ok(wallet_conviction(&self.address, summary.as_ref(), &positions, &holdings))
+    }
+}
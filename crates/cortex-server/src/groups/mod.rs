@@ -0,0 +1,151 @@
+//! Named wallet groups.
+//!
+//! A group is a named, tagged set of wallets such as "funds", "team treasury" or "copy
+//! targets". Groups are kept in memory and mirrored to the store. Handlers use them to
+//! index every member at once, to sum the members as one portfolio, and to restrict the
+//! leaderboard and token flows to the members.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use chrono::Utc;
+
+use crate::db::models::WalletGroupRow;
+use crate::db::store::CortexStore;
+use crate::error::{AppError, AppResult};
+use crate::registry::{Record, Registry};
+
+#[derive(Clone)]
+pub struct WalletGroups {
+    groups: Registry<WalletGroupRow>,
+}
+
+impl WalletGroups {
+    pub fn new(store: Arc<dyn CortexStore>) -> Self {
+        Self {
+            groups: Registry::new(store),
+        }
+    }
+
+    /// Load stored groups. Returns how many there are.
+    pub async fn load(&self) -> AppResult<usize> {
+        let count = self.groups.load().await?;
+
+        println!("[GROUP] Loaded {} wallet group(s)", count);
+        tracing::info!(count = %count, "Loaded wallet groups");
+        Ok(count)
+    }
+
+    /// Store a new or changed group
+    pub async fn save(&self, row: WalletGroupRow) -> AppResult<()> {
+        self.groups.save(row.clone()).await?;
+        println!("[GROUP] Saved group {} ({}, {} wallets)", row.id, row.name, row.wallets.len());
+        tracing::info!(group_id = %row.id, name = %row.name, wallet_count = %row.wallets.len(), tags = ?row.tags, "Wallet group saved");
+        Ok(())
+    }
+
+    /// Every group, oldest first, optionally only those tagged `tag`
+    pub async fn groups(&self, tag: Option<&str>) -> Vec<WalletGroupRow> {
+        let mut groups = self.groups.list().await;
+        groups.retain(|g| tag.is_none_or(|tag| g.tags.iter().any(|t| t == tag)));
+        groups
+    }
+
+    pub async fn group(&self, id: &str) -> Option<WalletGroupRow> {
+        self.groups.get(id).await
+    }
+
+    /// A group's members, or `GroupNotFound`
+    pub async fn members(&self, id: &str) -> AppResult<Vec<String>> {
+        self.group(id)
+            .await
+            .map(|g| g.wallets)
+            .ok_or_else(|| AppError::GroupNotFound(id.to_string()))
+    }
+
+    /// Returns false if there was no such group. Members keep any running subscriptions.
+    pub async fn delete(&self, id: &str) -> AppResult<bool> {
+        if self.groups.delete(id).await?.is_none() {
+            return Ok(false);
+        }
+        println!("[GROUP] Deleted group {}", id);
+        tracing::info!(group_id = %id, "Wallet group deleted");
+        Ok(true)
+    }
+}
+
+#[async_trait]
+impl Record for WalletGroupRow {
+    fn key(&self) -> &str {
+        &self.id
+    }
+
+    fn created_at(&self) -> i64 {
+        self.created_at
+    }
+
+    fn mark_deleted(&mut self) {
+        self.deleted = 1;
+    }
+
+    async fn save(&self, store: &dyn CortexStore) -> AppResult<()> {
+        store.save_wallet_group(self).await
+    }
+
+    async fn load_all(store: &dyn CortexStore) -> AppResult<Vec<Self>> {
+        store.get_wallet_groups().await
+    }
+}
+
+/// A new group row with a random id
+pub fn new_group(name: String, wallets: Vec<String>, tags: Vec<String>) -> WalletGroupRow {
+    WalletGroupRow {
+        id: uuid::Uuid::new_v4().to_string(),
+        name,
+        wallets,
+        tags,
+        deleted: 0,
+        created_at: Utc::now().timestamp_millis(),
+    }
+}
+
+/// Tags trimmed, lowercased, deduplicated and sorted, dropping empty ones
+pub fn normalize_tags(tags: Vec<String>) -> Vec<String> {
+    let mut tags: Vec<String> = tags.into_iter().map(|t| t.trim().to_lowercase()).filter(|t| !t.is_empty()).collect();
+    tags.sort();
+    tags.dedup();
+    tags
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::store::MemoryStore;
+
+    const WALLET: &str = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
+
+    #[tokio::test]
+    async fn test_groups_survive_reload_until_deleted() {
+        let store = Arc::new(MemoryStore::new());
+        let groups = WalletGroups::new(store.clone());
+        let group = new_group("funds".to_string(), vec![WALLET.to_string()], vec!["fund".to_string()]);
+        groups.save(group.clone()).await.unwrap();
+        groups.save(new_group("team".to_string(), Vec::new(), Vec::new())).await.unwrap();
+
+        let reloaded = WalletGroups::new(store.clone());
+        assert_eq!(reloaded.load().await.unwrap(), 2);
+        assert_eq!(reloaded.groups(Some("fund")).await.len(), 1);
+        assert_eq!(reloaded.members(&group.id).await.unwrap(), vec![WALLET.to_string()]);
+
+        assert!(reloaded.delete(&group.id).await.unwrap());
+        assert!(!reloaded.delete(&group.id).await.unwrap());
+        assert!(matches!(reloaded.members(&group.id).await, Err(AppError::GroupNotFound(_))));
+        assert_eq!(WalletGroups::new(store).load().await.unwrap(), 1);
+    }
+
+    #[test]
+    fn test_normalize_tags() {
+        let tags = vec![" Copy Targets ".to_string(), "fund".to_string(), String::new(), "FUND".to_string()];
+        assert_eq!(normalize_tags(tags), vec!["copy targets".to_string(), "fund".to_string()]);
+    }
+}
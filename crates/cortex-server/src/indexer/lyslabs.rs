@@ -9,6 +9,7 @@ use tokio_util::sync::CancellationToken;
 
 use crate::config::LysLabsConfig;
 use crate::error::{AppError, AppResult};
+use crate::telemetry::{self, SkipReason};
 
 // ============================================================================
 // LYS Labs WebSocket Client
@@ -120,6 +121,7 @@ impl LysLabsClient {
                 match msg {
                     Ok(Message::Text(text)) => {
                         messages_received += 1;
+                        telemetry::message_received();
 
                         // Log first few raw messages for debugging
                         if messages_received <= 3 {
@@ -178,11 +180,14 @@ impl LysLabsClient {
                                         if tx.send(transaction).await.is_err() {
                                             break;
                                         }
+                                    } else {
+                                        telemetry::transaction_skipped(SkipReason::OtherWallet);
                                     }
                                 }
                             }
                             Err(e) => {
                                 parse_errors += 1;
+                                telemetry::transaction_skipped(SkipReason::MalformedMessage);
                                 if parse_errors <= 3 {
                                     println!("[WEBSOCKET] Parse error: {} - text: {}...",
                                         e, text.chars().take(100).collect::<String>());
@@ -335,6 +340,7 @@ impl LysLabsClient {
                                     match msg {
                                         Some(Ok(Message::Text(text))) => {
                                             messages_received += 1;
+                                            telemetry::message_received();
 
                                             if let Ok(ws_msg) = serde_json::from_str::<LysWebSocketMessage>(&text) {
                                                 let transactions = match ws_msg.msg_type.as_str() {
@@ -364,8 +370,12 @@ impl LysLabsClient {
                                                             tracing::warn!(wallet = %wallet, "Transaction channel closed");
                                                             return;
                                                         }
+                                                    } else {
+                                                        telemetry::transaction_skipped(SkipReason::OtherWallet);
                                                     }
                                                 }
+                                            } else {
+                                                telemetry::transaction_skipped(SkipReason::MalformedMessage);
                                             }
 
                                             // Log progress periodically
@@ -423,6 +433,7 @@ impl LysLabsClient {
                 }
 
                 // Exponential backoff
+                telemetry::stream_reconnected();
                 let delay = RECONNECT_DELAY_BASE_MS * (2_u64.pow(reconnect_attempts.min(6)));
                 println!("[LYSLABS] Reconnecting in {}ms for wallet: {}", delay, wallet);
                 tracing::info!(wallet = %wallet, delay_ms = %delay, "Reconnecting after delay");
@@ -3,25 +3,46 @@ pub mod lyslabs;
 pub mod parser;
 pub mod protocols;
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet, VecDeque};
 use std::sync::Arc;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 
-use tokio::sync::{mpsc, RwLock};
+use rust_decimal::Decimal;
+use tokio::sync::{broadcast, mpsc, RwLock};
 use tokio_util::sync::CancellationToken;
 
 use self::helius::{EnhancedTransaction, HeliusClient};
 use self::lyslabs::{LysLabsClient, LysTransaction};
 use self::parser::parse_transaction;
+use crate::alerts::Alert;
 use crate::config::{HeliusConfig, LysLabsConfig};
-use crate::db::models::WalletSummaryRow;
-use crate::db::{queries, Database};
+use crate::db::models::{
+    ClosedTradeRow, PositionSignalRow, TransactionFlagRow, TransactionRow, WalletLinkRow, WalletSummaryRow,
+};
+use crate::db::store::CortexStore;
+use crate::db::writer::Committed;
 use crate::error::AppResult;
 use crate::metrics;
+use crate::metrics::signals::PositionSignal;
+use crate::telemetry;
+use crate::types::TimeWindow;
 
 /// Maximum historical transactions to fetch from Helius
 const MAX_HISTORICAL_TRANSACTIONS: usize = 1000;
 
+/// Position signals buffered for real-time subscribers before slow ones start lagging
+const SIGNAL_CHANNEL_CAPACITY: usize = 1024;
+
+/// Wallet updates buffered for push subscribers before slow ones start lagging
+const UPDATE_CHANNEL_CAPACITY: usize = 1024;
+
+/// Transactions this old at subscription start are history and don't emit signals
+const SIGNAL_GRACE_MS: i64 = 60_000;
+
+/// Shortest time between summary recomputations of a streamed wallet, so a backfill of
+/// many transactions doesn't recompute after every batch
+const SUMMARY_REFRESH_INTERVAL: Duration = Duration::from_secs(5);
+
 /// Info about an active wallet subscription
 #[derive(Clone)]
 pub struct WalletSubscription {
@@ -50,6 +71,27 @@ impl WalletSubscription {
     }
 }
 
+/// A change to a wallet's indexed data, pushed to real-time subscribers
+#[derive(Debug, Clone)]
+pub enum WalletUpdate {
+    /// A streamed transaction was stored
+    Transaction(Box<TransactionRow>),
+    /// The wallet's summary was recomputed and stored
+    Summary(Box<WalletSummaryRow>),
+    /// An alert rule started or stopped holding for the wallet
+    Alert(Box<Alert>),
+}
+
+impl WalletUpdate {
+    pub fn wallet(&self) -> &str {
+        match self {
+            WalletUpdate::Transaction(row) => &row.wallet,
+            WalletUpdate::Summary(row) => &row.wallet,
+            WalletUpdate::Alert(alert) => &alert.wallet,
+        }
+    }
+}
+
 /// Subscription status for API responses
 #[derive(Clone, serde::Serialize)]
 pub struct SubscriptionStatus {
@@ -63,22 +105,48 @@ pub struct SubscriptionStatus {
 pub struct Indexer {
     lyslabs: LysLabsClient,
     helius: HeliusClient,
-    db: Database,
+    /// Everything the indexer reads and writes
+    store: Arc<dyn CortexStore>,
     /// Active wallet subscriptions
     subscriptions: Arc<RwLock<HashMap<String, WalletSubscription>>>,
+    /// Position changes of subscribed wallets, for real-time feed subscribers
+    signals: broadcast::Sender<PositionSignal>,
+    /// Newly indexed transactions and recomputed summaries, for push subscribers
+    updates: broadcast::Sender<WalletUpdate>,
 }
 
 impl Indexer {
-    pub fn new(lyslabs_config: &LysLabsConfig, helius_config: &HeliusConfig, db: Database) -> Self {
+    pub fn new(
+        lyslabs_config: &LysLabsConfig,
+        helius_config: &HeliusConfig,
+        store: Arc<dyn CortexStore>,
+    ) -> Self {
         tracing::debug!("Initializing Indexer with LYS Labs and Helius clients");
         Self {
             lyslabs: LysLabsClient::new(lyslabs_config),
             helius: HeliusClient::new(helius_config),
-            db,
+            store,
             subscriptions: Arc::new(RwLock::new(HashMap::new())),
+            signals: broadcast::channel(SIGNAL_CHANNEL_CAPACITY).0,
+            updates: broadcast::channel(UPDATE_CHANNEL_CAPACITY).0,
         }
     }
 
+    /// Receive position signals from every subscribed wallet as they are detected.
+    pub fn subscribe_signals(&self) -> broadcast::Receiver<PositionSignal> {
+        self.signals.subscribe()
+    }
+
+    /// Receive every wallet's newly indexed transactions and recomputed summaries.
+    pub fn subscribe_updates(&self) -> broadcast::Receiver<WalletUpdate> {
+        self.updates.subscribe()
+    }
+
+    /// Push an update to real-time subscribers alongside the indexer's own
+    pub fn publish_update(&self, update: WalletUpdate) {
+        let _ = self.updates.send(update);
+    }
+
     /// Start continuous indexing for a wallet.
     /// First fetches historical data from Helius, then starts LYS Labs real-time stream.
     /// Returns true if subscription was started, false if already subscribed.
@@ -108,12 +176,16 @@ impl Indexer {
         subs.insert(wallet.to_string(), subscription);
         drop(subs); // Release lock before spawning
 
+        // Remembered so the subscription resumes after a restart
+        if let Err(e) = self.store.save_subscription(wallet).await {
+            tracing::warn!(wallet = %wallet, error = %e, "Failed to persist subscription");
+        }
+
         // Spawn transaction processor first so it's ready to receive
-        let db = self.db.clone();
+        let indexer = self.clone();
         let wallet_owned = wallet.to_string();
-        let tx_counter_clone = tx_counter.clone();
         tokio::spawn(async move {
-            Self::process_transaction_stream(wallet_owned, tx_receiver, db, tx_counter_clone).await;
+            indexer.process_transaction_stream(wallet_owned, tx_receiver, tx_counter).await;
         });
 
         // Fetch historical transactions from Helius and send to processor
@@ -227,6 +299,22 @@ impl Indexer {
             (String::new(), String::new(), String::new())
         };
 
+        // Plain SOL transfers carry no token transfer; fall back to the native one
+        let native = helius_tx
+            .native_transfers
+            .as_ref()
+            .and_then(|transfers| transfers.first())
+            .filter(|_| event_type == "TRANSFER" && source.is_empty() && destination.is_empty());
+        let (source, destination, mint, ui_amount) = match native {
+            Some(transfer) => (
+                transfer.from_user_account.clone().unwrap_or_default(),
+                transfer.to_user_account.clone().unwrap_or_default(),
+                crate::tokens::WSOL_MINT.to_string(),
+                transfer.amount as f64 / 1e9,
+            ),
+            None => (source, destination, mint, 0.0),
+        };
+
         // Collect accounts involved
         let mut accounts = vec![wallet.to_string(), helius_tx.fee_payer.clone()];
         if let Some(transfers) = &helius_tx.token_transfers {
@@ -239,6 +327,10 @@ impl Indexer {
                 }
             }
         }
+        // Native transfer recipients expose Jito tips
+        if let Some(transfers) = &helius_tx.native_transfers {
+            accounts.extend(transfers.iter().filter_map(|t| t.to_user_account.clone()));
+        }
 
         LysTransaction {
             tx_signature: helius_tx.signature.clone(),
@@ -255,7 +347,7 @@ impl Indexer {
             token_in,
             token_out,
             accounts,
-            ui_amount: 0.0,
+            ui_amount,
             amount: String::new(),
         }
     }
@@ -269,6 +361,10 @@ impl Indexer {
             println!("[INDEXER] Stopping subscription for wallet: {}", wallet);
             tracing::info!(wallet = %wallet, "Stopping wallet subscription");
             subscription.cancel();
+            drop(subs);
+            if let Err(e) = self.store.remove_subscription(wallet).await {
+                tracing::warn!(wallet = %wallet, error = %e, "Failed to persist subscription removal");
+            }
             true
         } else {
             println!("[INDEXER] No active subscription for wallet: {}", wallet);
@@ -277,6 +373,37 @@ impl Indexer {
         }
     }
 
+    /// Restart the subscriptions that were running when the server last stopped.
+    /// Returns how many were resumed.
+    pub async fn resume_subscriptions(&self) -> AppResult<usize> {
+        let wallets = self.store.subscribed_wallets().await?;
+        let mut resumed = 0;
+        for wallet in &wallets {
+            match self.start_subscription(wallet).await {
+                Ok(true) => resumed += 1,
+                Ok(false) => {}
+                Err(e) => {
+                    println!("[INDEXER] Failed to resume subscription for {}: {}", wallet, e);
+                    tracing::error!(wallet = %wallet, error = %e, "Failed to resume subscription");
+                }
+            }
+        }
+        println!("[INDEXER] Resumed {} of {} subscriptions", resumed, wallets.len());
+        tracing::info!(resumed = %resumed, stored = %wallets.len(), "Subscriptions resumed");
+        Ok(resumed)
+    }
+
+    /// Stop every subscription and write out any buffered transactions.
+    pub async fn shutdown(&self) {
+        let subs = std::mem::take(&mut *self.subscriptions.write().await);
+        println!("[INDEXER] Shutting down ({} subscriptions)...", subs.len());
+        tracing::info!(subscriptions = %subs.len(), "Shutting down indexer");
+        for subscription in subs.values() {
+            subscription.cancel();
+        }
+        self.store.close().await;
+    }
+
     /// Check if a wallet has an active subscription.
     pub async fn is_subscribed(&self, wallet: &str) -> bool {
         let subs = self.subscriptions.read().await;
@@ -301,76 +428,226 @@ impl Indexer {
         result
     }
 
-    /// Process incoming transactions from the stream
+    /// Process incoming transactions from the stream. Once transactions are committed,
+    /// the wallet's summary is recomputed from its stored history and published, at most
+    /// once per `SUMMARY_REFRESH_INTERVAL`, and once more when the stream ends.
     async fn process_transaction_stream(
+        &self,
         wallet: String,
         mut rx: mpsc::Receiver<LysTransaction>,
-        db: Database,
         tx_counter: Arc<RwLock<u64>>,
     ) {
         println!("[INDEXER] Transaction processor started for wallet: {}", wallet);
         tracing::info!(wallet = %wallet, "Transaction processor started");
+        let store = self.store.as_ref();
+
+        // Holdings from stored history, so live trades are classified against them
+        let history = match store.get_wallet_transactions(&wallet, TimeWindow::All).await {
+            Ok(rows) => rows,
+            Err(e) => {
+                tracing::warn!(wallet = %wallet, error = %e, "Failed to load stored history");
+                Vec::new()
+            }
+        };
+        let mut book = Self::load_position_book(store, &wallet, &history).await;
+        // Signatures already stored or queued. A resumed subscription refetches the same
+        // history and the live stream can overlap it, and `transactions` doesn't deduplicate.
+        let mut seen: HashSet<String> = history.into_iter().map(|row| row.signature).collect();
+        let mut prices = HashMap::new();
+        let live_from = chrono::Utc::now().timestamp_millis() - SIGNAL_GRACE_MS;
 
-        while let Some(lys_tx) = rx.recv().await {
-            // Parse the transaction
-            if let Some(parsed) = parse_transaction(&lys_tx, &wallet) {
-                let row = parsed.to_row();
-
-                // Insert into database
-                let query = r#"
-                    INSERT INTO transactions (
-                        signature, wallet, protocol, tx_type, token_in, token_out,
-                        amount_in, amount_out, usd_value, block_time, slot
-                    ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, fromUnixTimestamp64Milli(?), ?)
-                "#;
-
-                match db
-                    .client()
-                    .query(query)
-                    .bind(&row.signature)
-                    .bind(&row.wallet)
-                    .bind(&row.protocol)
-                    .bind(&row.tx_type)
-                    .bind(&row.token_in)
-                    .bind(&row.token_out)
-                    .bind(&row.amount_in)
-                    .bind(&row.amount_out)
-                    .bind(&row.usd_value)
-                    .bind(row.block_time)
-                    .bind(row.slot)
-                    .execute()
-                    .await
-                {
-                    Ok(_) => {
-                        let mut count = tx_counter.write().await;
-                        *count += 1;
-                        println!("[INDEXER] Stored tx {} for wallet {} (total: {})",
-                            &row.signature[..16], &wallet[..8], *count);
-                        tracing::debug!(
-                            wallet = %wallet,
-                            signature = %row.signature,
-                            protocol = %row.protocol,
-                            tx_type = %row.tx_type,
-                            "Transaction stored"
-                        );
+        // Stored transactions waiting for their batch to commit, oldest first. Batches
+        // commit in order, so each is announced once the one before it has been.
+        let mut uncommitted: VecDeque<(Committed, TransactionRow)> = VecDeque::new();
+        // When the summary is next recomputed, set by the first commit after the last one
+        let mut summary_due: Option<tokio::time::Instant> = None;
+        // Earliest block time committed since the last recomputation
+        let mut changed_from: Option<i64> = None;
+
+        loop {
+            tokio::select! {
+                received = rx.recv() => {
+                    let Some(lys_tx) = received else { break };
+                    if !seen.insert(lys_tx.tx_signature.clone()) {
+                        continue;
                     }
-                    Err(e) => {
-                        println!("[INDEXER] Failed to store tx: {}", e);
-                        tracing::error!(
-                            wallet = %wallet,
-                            signature = %row.signature,
-                            error = %e,
-                            "Failed to store transaction"
-                        );
+                    let lag_secs = (chrono::Utc::now().timestamp() - lys_tx.block_time) as f64;
+                    telemetry::subscription_progress(&wallet, lag_secs, rx.len());
+                    Self::record_stream_signals(store, &lys_tx, &wallet).await;
+
+                    // Parse the transaction
+                    let Some(mut parsed) = parse_transaction(&lys_tx, &wallet) else { continue };
+                    Self::price_transaction(store, &mut parsed, &mut prices).await;
+                    let position_signals = book.apply(&parsed);
+                    if parsed.block_time >= live_from {
+                        for signal in position_signals {
+                            Self::publish_signal(store, &self.signals, signal).await;
+                        }
+                    }
+
+                    let row = parsed.to_row();
+                    let signature = row.signature.clone();
+
+                    // Queue for storage; waits while the insert buffer is full, which in
+                    // turn holds back the stream channel
+                    match store.write_transaction(row.clone()).await {
+                        Ok(committed) => {
+                            let mut count = tx_counter.write().await;
+                            *count += 1;
+                            println!("[INDEXER] Queued tx {} for wallet {} (total: {})",
+                                &signature[..16], &wallet[..8], *count);
+                            tracing::debug!(
+                                wallet = %wallet,
+                                signature = %signature,
+                                protocol = %parsed.protocol,
+                                tx_type = %parsed.tx_type,
+                                "Transaction queued for insert"
+                            );
+                            uncommitted.push_back((committed, row));
+                        }
+                        Err(e) => {
+                            println!("[INDEXER] Failed to store tx: {}", e);
+                            tracing::error!(
+                                wallet = %wallet,
+                                signature = %signature,
+                                error = %e,
+                                "Failed to store transaction"
+                            );
+                        }
+                    }
+                }
+                (result, row) = next_committed(&mut uncommitted) => {
+                    let block_time = row.block_time;
+                    if Self::announce_transaction(&self.updates, result, row) {
+                        summary_due.get_or_insert_with(|| tokio::time::Instant::now() + SUMMARY_REFRESH_INTERVAL);
+                        changed_from = Some(changed_from.map_or(block_time, |earliest| earliest.min(block_time)));
+                    }
+                }
+                _ = sleep_until_due(summary_due) => {
+                    summary_due = None;
+                    if let Some(changed_from) = changed_from.take() {
+                        self.refresh_summary(&wallet, changed_from).await;
                     }
                 }
             }
         }
 
+        // Announce what was queued before the stream ended once it is stored
+        while let Some((committed, row)) = uncommitted.pop_front() {
+            let block_time = row.block_time;
+            if Self::announce_transaction(&self.updates, committed.await, row) {
+                changed_from = Some(changed_from.map_or(block_time, |earliest| earliest.min(block_time)));
+            }
+        }
+        if let Some(changed_from) = changed_from {
+            self.refresh_summary(&wallet, changed_from).await;
+        }
+
+        telemetry::subscription_ended(&wallet);
         println!("[INDEXER] Transaction processor ended for wallet: {}", wallet);
         tracing::info!(wallet = %wallet, "Transaction processor ended");
     }
 
+    /// Recompute a wallet's summary from its stored transactions, store it and publish
+    /// it. `changed_from` is the earliest block time (ms) stored since the last refresh.
+    /// Failures are logged, not propagated.
+    async fn refresh_summary(&self, wallet: &str, changed_from: i64) {
+        let transactions: Vec<parser::ParsedTransaction> = match self.store.get_wallet_transactions(wallet, TimeWindow::All).await {
+            Ok(rows) => rows.iter().filter_map(parser::ParsedTransaction::from_row).collect(),
+            Err(e) => {
+                tracing::error!(wallet = %wallet, error = %e, "Failed to load history for summary refresh");
+                return;
+            }
+        };
+        println!("[INDEXER] Refreshing summary for {} ({} transactions)", &wallet[..8.min(wallet.len())], transactions.len());
+        if let Err(e) = self.compute_wallet_summary(wallet, &transactions, changed_from).await {
+            println!("[INDEXER] Failed to refresh summary for {}: {}", wallet, e);
+            tracing::error!(wallet = %wallet, error = %e, "Failed to refresh wallet summary");
+        }
+    }
+
+    /// Tell subscribers about a streamed transaction once it has been committed.
+    /// Returns whether it was.
+    fn announce_transaction(updates: &broadcast::Sender<WalletUpdate>, committed: AppResult<()>, row: TransactionRow) -> bool {
+        match committed {
+            // No receivers just means nobody is listening right now
+            Ok(()) => {
+                let _ = updates.send(WalletUpdate::Transaction(Box::new(row)));
+                true
+            }
+            Err(e) => {
+                println!("[INDEXER] Failed to commit tx {}: {}", row.signature, e);
+                tracing::error!(
+                    wallet = %row.wallet,
+                    signature = %row.signature,
+                    error = %e,
+                    "Transaction was never committed"
+                );
+                false
+            }
+        }
+    }
+
+    /// Build a wallet's position book from its stored transactions and latest prices
+    async fn load_position_book(
+        store: &dyn CortexStore,
+        wallet: &str,
+        history: &[TransactionRow],
+    ) -> metrics::signals::PositionBook {
+        let mut book = metrics::signals::PositionBook::default();
+        for tx in history.iter().filter_map(parser::ParsedTransaction::from_row) {
+            book.apply(&tx);
+        }
+
+        match store.get_token_prices(&book.tokens()).await {
+            Ok(prices) => {
+                for row in prices {
+                    book.set_price(&row.token, row.price_usd.parse().unwrap_or_default());
+                }
+            }
+            Err(e) => {
+                tracing::warn!(wallet = %wallet, error = %e, "Failed to load prices for position signals");
+            }
+        }
+
+        book
+    }
+
+    /// Store a position signal and push it to real-time subscribers.
+    async fn publish_signal(store: &dyn CortexStore, signals: &broadcast::Sender<PositionSignal>, signal: PositionSignal) {
+        println!("[SIGNAL] {} {} {} {} (${})",
+            &signal.wallet[..8.min(signal.wallet.len())], signal.kind, signal.amount, signal.token, signal.usd_value);
+        tracing::info!(
+            wallet = %signal.wallet,
+            signature = %signal.signature,
+            token = %signal.token,
+            kind = %signal.kind,
+            usd_value = %signal.usd_value,
+            portfolio_weight = %signal.portfolio_weight,
+            "Position signal detected"
+        );
+
+        let row = PositionSignalRow {
+            wallet: signal.wallet.clone(),
+            signature: signal.signature.clone(),
+            token: signal.token.clone(),
+            kind: signal.kind.to_string(),
+            amount: signal.amount.to_string(),
+            price: signal.price.round_dp(8).to_string(),
+            usd_value: signal.usd_value.to_string(),
+            position_size: signal.position_size.to_string(),
+            portfolio_weight: signal.portfolio_weight.to_string(),
+            slot: signal.slot,
+            block_time: signal.block_time,
+        };
+        if let Err(e) = store.insert_position_signal(&row).await {
+            tracing::warn!(wallet = %signal.wallet, signature = %signal.signature, error = %e, "Failed to store position signal");
+        }
+
+        // No receivers just means nobody is listening right now
+        let _ = signals.send(signal);
+    }
+
     /// Legacy: Index a wallet with a one-time snapshot (original behavior).
     /// Kept for backward compatibility.
     pub async fn index_wallet_snapshot(&self, wallet: &str) -> AppResult<()> {
@@ -409,8 +686,11 @@ impl Indexer {
         let parse_start = Instant::now();
         let mut all_transactions = Vec::new();
         let mut parse_failures = 0;
+        let mut prices = HashMap::new();
         for tx in &transactions {
-            if let Some(parsed) = parse_transaction(tx, wallet) {
+            Self::record_stream_signals(self.store.as_ref(), tx, wallet).await;
+            if let Some(mut parsed) = parse_transaction(tx, wallet) {
+                Self::price_transaction(self.store.as_ref(), &mut parsed, &mut prices).await;
                 all_transactions.push(parsed);
             } else {
                 parse_failures += 1;
@@ -434,8 +714,7 @@ impl Indexer {
         let insert_start = Instant::now();
         let mut insert_errors = 0;
         for (i, tx) in all_transactions.iter().enumerate() {
-            let row = tx.to_row();
-            if let Err(e) = self.insert_transaction(&row).await {
+            if let Err(e) = self.store.write_transaction(tx.to_row()).await {
                 tracing::warn!(
                     wallet = %wallet,
                     signature = %tx.signature,
@@ -449,6 +728,8 @@ impl Indexer {
                 tracing::debug!(wallet = %wallet, progress = %i + 1, total = %all_transactions.len(), "Transaction insert progress");
             }
         }
+        // Stored before the summary, so rollup-backed reads see the whole history
+        self.store.flush_transactions().await?;
         println!("[INDEXER] Inserted {} transactions ({} errors, {}ms)",
             all_transactions.len() - insert_errors, insert_errors, insert_start.elapsed().as_millis());
         tracing::info!(
@@ -463,7 +744,8 @@ impl Indexer {
         println!("[INDEXER] Computing wallet metrics...");
         tracing::debug!(wallet = %wallet, "Computing wallet summary metrics");
         let metrics_start = Instant::now();
-        self.compute_wallet_summary(wallet, &all_transactions).await?;
+        // Every trade in the snapshot is (re)written
+        self.compute_wallet_summary(wallet, &all_transactions, i64::MIN).await?;
         println!("[INDEXER] Metrics computed ({}ms)", metrics_start.elapsed().as_millis());
         tracing::debug!(
             wallet = %wallet,
@@ -483,58 +765,208 @@ impl Indexer {
         Ok(())
     }
 
-    async fn insert_transaction(&self, tx: &crate::db::models::TransactionRow) -> AppResult<()> {
-        tracing::trace!(
-            signature = %tx.signature,
-            wallet = %tx.wallet,
-            protocol = %tx.protocol,
-            tx_type = %tx.tx_type,
-            "Inserting transaction into database"
-        );
+    /// Record the USD price a swap against a stablecoin implies for the other token, which
+    /// feeds current prices and the price history behind VaR. Any other transaction without
+    /// a USD value is valued at the latest known price of its token, from `prices` or else
+    /// the store, and left at zero when there is none. Failures are logged, not propagated.
+    async fn price_transaction(
+        store: &dyn CortexStore,
+        tx: &mut parser::ParsedTransaction,
+        prices: &mut HashMap<String, Option<Decimal>>,
+    ) {
+        if let Some((token, price)) = tx.implied_price() {
+            let price = price.round_dp(8);
+            if let Err(e) = store.record_token_price(token, price, tx.block_time).await {
+                tracing::warn!(token = %token, signature = %tx.signature, error = %e, "Failed to record implied price");
+            }
+            prices.insert(token.to_string(), Some(price));
+            return;
+        }
+        if !tx.usd_value.is_zero() {
+            return;
+        }
 
-        let query = r#"
-            INSERT INTO transactions (
-                signature, wallet, protocol, tx_type, token_in, token_out,
-                amount_in, amount_out, usd_value, block_time, slot
-            ) VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, fromUnixTimestamp64Milli(?), ?)
-        "#;
-
-        self.db
-            .client()
-            .query(query)
-            .bind(&tx.signature)
-            .bind(&tx.wallet)
-            .bind(&tx.protocol)
-            .bind(&tx.tx_type)
-            .bind(&tx.token_in)
-            .bind(&tx.token_out)
-            .bind(&tx.amount_in)
-            .bind(&tx.amount_out)
-            .bind(&tx.usd_value)
-            .bind(tx.block_time)
-            .bind(tx.slot)
-            .execute()
-            .await?;
+        for (token, amount) in [(tx.token_in.clone(), tx.amount_in), (tx.token_out.clone(), tx.amount_out)] {
+            if token.is_empty() || amount.is_zero() {
+                continue;
+            }
+            if crate::tokens::is_stablecoin(&token) {
+                tx.usd_value = amount.round_dp(2);
+                return;
+            }
+            if !prices.contains_key(&token) {
+                let stored = match store.get_token_prices(std::slice::from_ref(&token)).await {
+                    Ok(rows) => rows.first().and_then(|row| row.price_usd.parse().ok()),
+                    Err(e) => {
+                        tracing::warn!(token = %token, error = %e, "Failed to load price for valuation");
+                        None
+                    }
+                };
+                prices.insert(token.clone(), stored);
+            }
+            if let Some(price) = prices[&token] {
+                tx.usd_value = (amount * price).round_dp(2);
+                return;
+            }
+        }
+    }
+
+    /// Record signals only visible on the raw stream: Jito tips paid by the wallet, links
+    /// to other wallets (for clustering) and pool creations (for snipe detection).
+    /// Failures are logged, not propagated.
+    async fn record_stream_signals(store: &dyn CortexStore, tx: &LysTransaction, wallet: &str) {
+        if let Some(tip_account) = metrics::mev::jito_tip_account(&tx.accounts) {
+            let flag = TransactionFlagRow {
+                signature: tx.tx_signature.clone(),
+                wallet: wallet.to_string(),
+                flag: metrics::mev::MevFlag::JitoTip.to_string(),
+                counterparty: tip_account.to_string(),
+                estimated_loss_usd: "0".to_string(),
+                slot: tx.slot,
+                block_time: tx.block_time * 1000,
+            };
+            if let Err(e) = store.insert_transaction_flag(&flag).await {
+                tracing::warn!(wallet = %wallet, signature = %tx.tx_signature, error = %e, "Failed to store Jito tip flag");
+            }
+        }
 
-        tracing::trace!(signature = %tx.signature, "Transaction inserted successfully");
+        for link in Self::wallet_links(tx, wallet) {
+            if let Err(e) = store.insert_wallet_link(&link).await {
+                tracing::warn!(wallet = %wallet, signature = %tx.tx_signature, error = %e, "Failed to store wallet link");
+            }
+        }
 
-        Ok(())
+        if metrics::mev::is_pool_creation(&tx.event_type) && !tx.mint.is_empty() {
+            tracing::debug!(mint = %tx.mint, pool = %tx.pool, slot = %tx.slot, "Pool creation observed");
+            if let Err(e) =
+                store.record_token_launch(&tx.mint, &tx.pool, tx.slot, tx.block_time * 1000).await
+            {
+                tracing::warn!(mint = %tx.mint, error = %e, "Failed to record token launch");
+            }
+        }
+    }
+
+    /// Clustering evidence in a raw transaction: transfers to or from the wallet, and
+    /// another account paying the fee for a transaction that spends the wallet's funds.
+    fn wallet_links(tx: &LysTransaction, wallet: &str) -> Vec<WalletLinkRow> {
+        let mut links = Vec::new();
+        let block_time = tx.block_time * 1000;
+
+        let involves_wallet = tx.source == wallet || tx.destination == wallet;
+        if tx.event_type.eq_ignore_ascii_case("TRANSFER")
+            && involves_wallet
+            && !tx.source.is_empty()
+            && !tx.destination.is_empty()
+            && tx.source != tx.destination
+        {
+            links.push(WalletLinkRow {
+                source: tx.source.clone(),
+                target: tx.destination.clone(),
+                link_type: metrics::cluster::LinkKind::Transfer.to_string(),
+                mint: tx.mint.clone(),
+                amount: format!("{:.9}", tx.ui_amount),
+                signature: tx.tx_signature.clone(),
+                block_time,
+            });
+        }
+
+        // Whoever sends tokens to the wallet pays their own fee, so only count it when
+        // the wallet's own funds move
+        let spends_wallet = tx.source == wallet || tx.token_in.as_ref().is_some_and(|t| t.owner == wallet);
+        if spends_wallet && !tx.fee_payer.is_empty() && tx.fee_payer != wallet {
+            links.push(WalletLinkRow {
+                source: tx.fee_payer.clone(),
+                target: wallet.to_string(),
+                link_type: metrics::cluster::LinkKind::FeePayer.to_string(),
+                mint: String::new(),
+                amount: "0".to_string(),
+                signature: tx.tx_signature.clone(),
+                block_time,
+            });
+        }
+
+        links
     }
 
     async fn compute_wallet_summary(
         &self,
         wallet: &str,
         transactions: &[parser::ParsedTransaction],
+        changed_from: i64,
     ) -> AppResult<()> {
         tracing::debug!(wallet = %wallet, tx_count = %transactions.len(), "Computing wallet summary");
 
+        // MEV exposure and bot patterns
+        let mev = self.detect_mev(wallet, transactions).await?;
+        let mut summary = self.build_summary(wallet, transactions, &mev).await?;
+        // Realized windows as readers see them, so subscribers, alerts and webhooks get
+        // the same numbers as the API
+        let activity = self.store.get_wallet_activity(&[wallet.to_string()]).await?;
+        summary.apply_activity(&activity);
+
+        tracing::debug!(wallet = %wallet, "Upserting wallet summary to database");
+        self.store.upsert_wallet_summary(&summary).await?;
+        tracing::info!(
+            wallet = %wallet,
+            total_value_usd = %summary.total_value_usd,
+            protocol_count = %summary.protocol_count,
+            risk_score = %summary.risk_score,
+            "Wallet summary stored"
+        );
+        let _ = self.updates.send(WalletUpdate::Summary(Box::new(summary)));
+
+        self.store_closed_trades(wallet, transactions, changed_from).await?;
+
+        Ok(())
+    }
+
+    /// Store the wallet's closed trades so performance can be ranked across wallets.
+    /// Only trades closed at or after `changed_from`, the earliest transaction added
+    /// since the last run, are written: an earlier trade's average cost can't have
+    /// changed, while a later one's can, even if it was stored before (backfilled
+    /// history). Rewrites replace the stored row through the table's replacing key.
+    async fn store_closed_trades(
+        &self,
+        wallet: &str,
+        transactions: &[parser::ParsedTransaction],
+        changed_from: i64,
+    ) -> AppResult<()> {
+        let mut history = transactions.to_vec();
+        history.sort_by_key(|t| t.block_time);
+
+        let rows: Vec<ClosedTradeRow> = metrics::pnl::closed_trades(&history)
+            .into_iter()
+            .filter(|trade| trade.closed_at >= changed_from)
+            .map(|trade| ClosedTradeRow {
+                wallet: wallet.to_string(),
+                signature: trade.signature,
+                protocol: trade.protocol.to_string(),
+                token: trade.token,
+                cost_basis: trade.cost_basis.round_dp(2).to_string(),
+                pnl: trade.pnl.round_dp(2).to_string(),
+                block_time: trade.closed_at,
+            })
+            .collect();
+        self.store.insert_closed_trades(&rows).await?;
+        tracing::debug!(wallet = %wallet, trade_count = %rows.len(), "Closed trades stored");
+
+        Ok(())
+    }
+
+    /// Compute the summary row for a wallet or entity from its transactions. Reads
+    /// price history but writes nothing.
+    async fn build_summary(
+        &self,
+        wallet: &str,
+        transactions: &[parser::ParsedTransaction],
+        mev: &metrics::mev::MevSummary,
+    ) -> AppResult<WalletSummaryRow> {
         // Compute PnL metrics
         tracing::trace!(wallet = %wallet, "Computing PnL metrics");
         let pnl = metrics::compute_pnl(transactions);
         tracing::debug!(
             wallet = %wallet,
             total_value = %pnl.total_value,
-            realized_24h = %pnl.realized_24h,
             unrealized = %pnl.unrealized,
             "PnL metrics computed"
         );
@@ -546,9 +978,60 @@ impl Indexer {
             wallet = %wallet,
             risk_score = %risk.score,
             position_count = %risk.position_count,
+            asset_count = %risk.asset_count,
+            asset_exposure = ?risk.asset_exposure,
+            leverage = %risk.leverage,
+            liquidation_distance = %risk.liquidation_distance,
             "Risk metrics computed"
         );
 
+        // Value-at-risk from historical price moves of the current exposure
+        let exposure_tokens: Vec<String> = risk.net_exposure.keys().cloned().collect();
+        let closes: Vec<metrics::volatility::DailyClose> = self
+            .store
+            .get_token_daily_closes(&exposure_tokens, metrics::volatility::VAR_LOOKBACK_DAYS)
+            .await?
+        .into_iter()
+        .filter_map(|row| {
+            Some(metrics::volatility::DailyClose {
+                price: row.price_usd.parse().ok()?,
+                token: row.token,
+                day: row.day,
+            })
+        })
+        .collect();
+        let var = metrics::compute_var(&risk.net_exposure, &closes);
+        tracing::debug!(
+            wallet = %wallet,
+            var_95 = %var.var_95,
+            var_99 = %var.var_99,
+            coverage = %var.coverage,
+            scenario_days = %var.scenario_days,
+            token_volatility = ?var.token_volatility,
+            "Value-at-risk computed"
+        );
+
+        tracing::debug!(
+            wallet = %wallet,
+            mev_loss_usd = %mev.mev_loss_usd,
+            sandwiched = %mev.sandwiched_count,
+            round_trips = %mev.round_trip_count,
+            jito_tips = %mev.jito_tip_count,
+            snipes = %mev.snipe_count,
+            "MEV patterns detected"
+        );
+
+        // Behavioural classification
+        let features = metrics::extract_features(transactions, pnl.total_value, mev);
+        let classification = cortex_core::classify_wallet(&features);
+        tracing::debug!(
+            wallet = %wallet,
+            classification = %classification.classification.as_str(),
+            confidence = %classification.confidence,
+            features = ?classification.features,
+            "Wallet classified"
+        );
+
         // Gather protocol list
         let mut protocols: Vec<String> = transactions
             .iter()
@@ -569,9 +1052,10 @@ impl Indexer {
         let summary = WalletSummaryRow {
             wallet: wallet.to_string(),
             total_value_usd: pnl.total_value.to_string(),
-            realized_pnl_24h: pnl.realized_24h.to_string(),
-            realized_pnl_7d: pnl.realized_7d.to_string(),
-            realized_pnl_30d: pnl.realized_30d.to_string(),
+            // Realized windows are served live from the wallet_protocol_daily rollup
+            realized_pnl_24h: "0".to_string(),
+            realized_pnl_7d: "0".to_string(),
+            realized_pnl_30d: "0".to_string(),
             unrealized_pnl: pnl.unrealized.to_string(),
             largest_position_pct: risk.largest_position_pct.to_string(),
             protocol_count: protocols.len() as u8,
@@ -579,18 +1063,615 @@ impl Indexer {
             risk_score: risk.score,
             last_activity,
             protocols,
+            leverage: risk.leverage.round_dp(4).to_string(),
+            liquidation_distance: risk.liquidation_distance.round_dp(4).to_string(),
+            stablecoin_pct: risk.stablecoin_pct.round_dp(4).to_string(),
+            memecoin_pct: risk.memecoin_pct.round_dp(4).to_string(),
+            concentration_risk: risk.components.concentration,
+            leverage_risk: risk.components.leverage,
+            liquidation_risk: risk.components.liquidation,
+            asset_risk: risk.components.asset,
+            contract_risk: risk.components.contract,
+            var_95: var.var_95.to_string(),
+            var_99: var.var_99.to_string(),
+            expected_shortfall_95: var.expected_shortfall_95.to_string(),
+            expected_shortfall_99: var.expected_shortfall_99.to_string(),
+            portfolio_volatility: format!("{:.4}", var.portfolio_volatility),
+            var_coverage: var.coverage.round_dp(4).to_string(),
+            classification: classification.classification.as_str().to_string(),
+            classification_confidence: format!("{:.4}", classification.confidence),
+            classification_features: serde_json::to_string(&classification.features)
+                .unwrap_or_else(|_| "{}".to_string()),
+            mev_loss_usd: mev.mev_loss_usd.to_string(),
+            sandwiched_count: mev.sandwiched_count,
+            round_trip_count: mev.round_trip_count,
+            jito_tip_count: mev.jito_tip_count,
+            snipe_count: mev.snipe_count,
+            protocol_ltv: serde_json::to_string(
+                &risk
+                    .protocol_ltv
+                    .iter()
+                    .map(|(protocol, ltv)| (protocol.to_string(), ltv.round_dp(4).to_string()))
+                    .collect::<std::collections::BTreeMap<_, _>>(),
+            )
+            .unwrap_or_else(|_| "{}".to_string()),
+            asset_exposure: serde_json::to_string(
+                &risk
+                    .asset_exposure
+                    .iter()
+                    .map(|(asset, usd)| (asset.clone(), usd.round_dp(2).to_string()))
+                    .collect::<std::collections::BTreeMap<_, _>>(),
+            )
+            .unwrap_or_else(|_| "{}".to_string()),
         };
 
-        tracing::debug!(wallet = %wallet, "Upserting wallet summary to database");
-        queries::upsert_wallet_summary(self.db.client(), &summary).await?;
-        tracing::info!(
-            wallet = %wallet,
-            total_value_usd = %summary.total_value_usd,
-            protocol_count = %summary.protocol_count,
-            risk_score = %summary.risk_score,
-            "Wallet summary stored"
+        Ok(summary)
+    }
+
+    /// Flag sandwiched swaps, same-slot round trips and snipes, store the flags, and
+    /// total them together with the Jito tips recorded from the stream.
+    async fn detect_mev(
+        &self,
+        wallet: &str,
+        transactions: &[parser::ParsedTransaction],
+    ) -> AppResult<metrics::mev::MevSummary> {
+        let mut slots: Vec<u64> = transactions.iter().map(|t| t.slot).collect();
+        slots.sort_unstable();
+        slots.dedup();
+        let slot_swaps: Vec<parser::ParsedTransaction> = self.store.get_slot_swaps(wallet, &slots)
+            .await?
+            .iter()
+            .filter_map(parser::ParsedTransaction::from_row)
+            .collect();
+
+        let mut mints: Vec<String> = transactions.iter().map(|t| t.token_out.clone()).collect();
+        mints.sort_unstable();
+        mints.dedup();
+        let launches: HashMap<String, u64> = self.store.get_token_launches(&mints)
+            .await?
+            .into_iter()
+            .map(|launch| (launch.mint, launch.slot))
+            .collect();
+
+        let flags = [
+            metrics::mev::detect_sandwiches(transactions, &slot_swaps),
+            metrics::mev::detect_round_trips(transactions),
+            metrics::mev::detect_snipes(transactions, &launches),
+        ]
+        .concat();
+
+        // Detection reruns over the full history; only store what is new
+        let mut signatures: Vec<String> = flags.iter().map(|f| f.signature.clone()).collect();
+        signatures.sort_unstable();
+        signatures.dedup();
+        let stored: HashSet<(String, String)> = self.store.get_transaction_flags(wallet, &signatures)
+            .await?
+            .into_iter()
+            .map(|row| (row.signature, row.flag))
+            .collect();
+        let rows: Vec<TransactionFlagRow> = flags
+            .iter()
+            .map(|flag| TransactionFlagRow {
+                signature: flag.signature.clone(),
+                wallet: wallet.to_string(),
+                flag: flag.flag.to_string(),
+                counterparty: flag.counterparty.clone(),
+                estimated_loss_usd: flag.estimated_loss.to_string(),
+                slot: flag.slot,
+                block_time: flag.block_time,
+            })
+            .filter(|row| !stored.contains(&(row.signature.clone(), row.flag.clone())))
+            .collect();
+        self.store.insert_transaction_flags(&rows).await?;
+
+        let mut summary = metrics::mev::MevSummary::from_flags(&flags);
+        summary.jito_tip_count = self.store.count_wallet_flags(wallet, &metrics::mev::MevFlag::JitoTip.to_string()).await? as u32;
+        Ok(summary)
+    }
+
+    /// Resolve the entity a wallet belongs to by expanding outward over stored links,
+    /// a few hops at most, until the cluster stops growing or reaches
+    /// `MAX_CLUSTER_SIZE` members.
+    pub async fn resolve_cluster(&self, wallet: &str) -> AppResult<metrics::cluster::Cluster> {
+        const MAX_HOPS: usize = 4;
+
+        let mut links: Vec<metrics::cluster::WalletLink> = Vec::new();
+        let mut seen_links: HashSet<(String, String, String, String)> = HashSet::new();
+        let mut fan_out: HashMap<String, u64> = HashMap::new();
+        let mut expanded: HashSet<String> = HashSet::new();
+        let mut cluster = metrics::cluster::cluster_of(wallet, &links, &fan_out);
+
+        for hop in 0..MAX_HOPS {
+            let frontier: Vec<String> = cluster
+                .members
+                .iter()
+                .filter(|member| !expanded.contains(*member))
+                .cloned()
+                .collect();
+            if frontier.is_empty() || cluster.members.len() >= metrics::cluster::MAX_CLUSTER_SIZE {
+                break;
+            }
+
+            for row in self.store.get_wallet_links(&frontier).await? {
+                let Some(kind) = metrics::cluster::LinkKind::from_str(&row.link_type) else {
+                    continue;
+                };
+                let key = (row.source.clone(), row.target.clone(), row.link_type.clone(), row.signature.clone());
+                if seen_links.insert(key) {
+                    links.push(metrics::cluster::WalletLink {
+                        source: row.source,
+                        target: row.target,
+                        kind,
+                        block_time: row.block_time,
+                    });
+                }
+            }
+            expanded.extend(frontier);
+
+            // Fan-out of every new funder and fee payer, to skip exchanges and relayers
+            let mut sources: Vec<String> = links
+                .iter()
+                .map(|link| link.source.clone())
+                .filter(|source| !fan_out.contains_key(source))
+                .collect();
+            sources.sort_unstable();
+            sources.dedup();
+            for source in &sources {
+                fan_out.insert(source.clone(), 0);
+            }
+            for row in self.store.get_link_fan_out(&sources).await? {
+                fan_out.insert(row.source, row.targets);
+            }
+
+            cluster = metrics::cluster::cluster_of(wallet, &links, &fan_out);
+            tracing::debug!(
+                wallet = %wallet,
+                hop = %hop,
+                link_count = %links.len(),
+                member_count = %cluster.members.len(),
+                "Cluster expanded"
+            );
+        }
+
+        Ok(cluster)
+    }
+
+    /// Summary of a whole entity: every member's stored transactions analysed as one
+    /// portfolio, with MEV totals taken from the members' own summaries.
+    pub async fn entity_summary(&self, cluster: &metrics::cluster::Cluster) -> AppResult<WalletSummaryRow> {
+        let mut transactions: Vec<parser::ParsedTransaction> = Vec::new();
+        let mut mev = metrics::mev::MevSummary::default();
+
+        for member in &cluster.members {
+            transactions.extend(
+                self.store
+                    .get_wallet_transactions(member, TimeWindow::All)
+                    .await?
+                    .iter()
+                    .filter_map(parser::ParsedTransaction::from_row),
+            );
+            if let Some(row) = self.store.get_wallet_summary(member).await? {
+                mev.mev_loss_usd += row.mev_loss_usd.parse::<Decimal>().unwrap_or_default();
+                mev.sandwiched_count += row.sandwiched_count;
+                mev.round_trip_count += row.round_trip_count;
+                mev.jito_tip_count += row.jito_tip_count;
+                mev.snipe_count += row.snipe_count;
+            }
+        }
+        transactions.sort_by_key(|t| t.block_time);
+
+        tracing::debug!(
+            cluster_id = %cluster.id,
+            member_count = %cluster.members.len(),
+            tx_count = %transactions.len(),
+            "Computing entity summary"
         );
+        let mut summary = self.build_summary(&cluster.id, &transactions, &mev).await?;
+        let activity = self.store.get_wallet_activity(&cluster.members).await?;
+        summary.apply_activity(&activity);
 
-        Ok(())
+        Ok(summary)
+    }
+}
+
+/// Wait for the oldest queued transaction to be committed and take it off the queue.
+/// Never resolves while the queue is empty.
+async fn next_committed(uncommitted: &mut VecDeque<(Committed, TransactionRow)>) -> (AppResult<()>, TransactionRow) {
+    let Some((committed, _)) = uncommitted.front_mut() else {
+        return std::future::pending().await;
+    };
+    let result = committed.await;
+    let (_, row) = uncommitted.pop_front().expect("front was just awaited");
+    (result, row)
+}
+
+/// Wait until `due`, or forever when nothing is due.
+async fn sleep_until_due(due: Option<tokio::time::Instant>) {
+    match due {
+        Some(due) => tokio::time::sleep_until(due).await,
+        None => std::future::pending().await,
+    }
+}
+
+#[cfg(test)]
+impl Indexer {
+    /// An indexer over `store` whose external endpoints are unreachable, so nothing
+    /// leaves the machine.
+    pub fn offline(store: Arc<dyn CortexStore>) -> Self {
+        let lyslabs = LysLabsConfig {
+            api_key: String::new(),
+            ws_url: "ws://127.0.0.1:1".to_string(),
+        };
+        let helius = HeliusConfig { api_key: String::new() };
+        Self::new(&lyslabs, &helius, store)
+    }
+
+    /// Run `transactions` through the stream processor as a subscription would, returning
+    /// once they are stored and the summary is published.
+    pub async fn ingest(&self, wallet: &str, transactions: Vec<LysTransaction>) {
+        let (sender, receiver) = mpsc::channel(transactions.len().max(1));
+        for tx in transactions {
+            sender.send(tx).await.unwrap();
+        }
+        drop(sender);
+        self.process_transaction_stream(wallet.to_string(), receiver, Arc::new(RwLock::new(0))).await;
+    }
+}
+
+/// A Jupiter swap by `wallet` as it arrives from the stream
+#[cfg(test)]
+pub fn test_swap(wallet: &str, signature: &str, slot: u64, block_time: i64, token_in: (&str, f64), token_out: (&str, f64)) -> LysTransaction {
+    let token = |(mint, ui_amount): (&str, f64)| lyslabs::LysTokenAmount {
+        mint: mint.to_string(),
+        ui_amount,
+        owner: wallet.to_string(),
+        ..Default::default()
+    };
+    LysTransaction {
+        tx_signature: signature.to_string(),
+        slot,
+        block_time,
+        decoder_type: "JUPITER".to_string(),
+        event_type: "SWAP".to_string(),
+        mint: String::new(),
+        source: wallet.to_string(),
+        destination: String::new(),
+        fee_payer: wallet.to_string(),
+        program_id: String::new(),
+        pool: String::new(),
+        token_in: Some(token(token_in)),
+        token_out: Some(token(token_out)),
+        accounts: vec![wallet.to_string()],
+        ui_amount: 0.0,
+        amount: String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::store::MemoryStore;
+
+    const WALLET: &str = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
+
+    fn swap(signature: &str, block_time: i64) -> LysTransaction {
+        swap_between(signature, 1, block_time, (crate::tokens::USDC_MINT, 100.0), (crate::tokens::WSOL_MINT, 1.0))
+    }
+
+    fn swap_between(signature: &str, slot: u64, block_time: i64, token_in: (&str, f64), token_out: (&str, f64)) -> LysTransaction {
+        test_swap(WALLET, signature, slot, block_time, token_in, token_out)
+    }
+
+    #[tokio::test]
+    async fn test_stream_transactions_reach_store() {
+        let store = Arc::new(MemoryStore::new());
+        let indexer = Indexer::offline(store.clone());
+        let (sender, receiver) = mpsc::channel(8);
+        let counter = Arc::new(RwLock::new(0));
+        let mut updates = indexer.subscribe_updates();
+
+        sender.send(swap("sig-one-0000000000000000", 1_700_000_000)).await.unwrap();
+        sender.send(swap("sig-two-0000000000000000", 1_700_000_060)).await.unwrap();
+        drop(sender);
+        indexer.process_transaction_stream(WALLET.to_string(), receiver, counter.clone()).await;
+
+        assert_eq!(*counter.read().await, 2);
+        let stored = store.get_wallet_transactions(WALLET, TimeWindow::All).await.unwrap();
+        let signatures: Vec<&str> = stored.iter().map(|t| t.signature.as_str()).collect();
+        assert_eq!(signatures, vec!["sig-one-0000000000000000", "sig-two-0000000000000000"]);
+        assert_eq!(stored[0].protocol, "jupiter");
+        assert_eq!(stored[0].tx_type, "swap");
+
+        // Each stored transaction is pushed to subscribers, then the summary they change
+        for signature in signatures {
+            match updates.try_recv().unwrap() {
+                WalletUpdate::Transaction(row) => assert_eq!(row.signature, signature),
+                other => panic!("expected a transaction update, got {other:?}"),
+            }
+        }
+        match updates.try_recv().unwrap() {
+            WalletUpdate::Summary(summary) => assert_eq!(summary.protocols, vec!["jupiter"]),
+            other => panic!("expected a summary update, got {other:?}"),
+        }
+        assert!(updates.try_recv().is_err());
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_live_stream_publishes_summaries() {
+        let store = Arc::new(MemoryStore::new());
+        let indexer = Indexer::offline(store.clone());
+        let mut updates = indexer.subscribe_updates();
+        let (sender, receiver) = mpsc::channel(8);
+        let processor = {
+            let indexer = indexer.clone();
+            tokio::spawn(async move {
+                indexer.process_transaction_stream(WALLET.to_string(), receiver, Arc::new(RwLock::new(0))).await
+            })
+        };
+        let now = chrono::Utc::now().timestamp();
+        let next_summary = async |updates: &mut broadcast::Receiver<WalletUpdate>| loop {
+            if let WalletUpdate::Summary(summary) = updates.recv().await.unwrap() {
+                return summary;
+            }
+        };
+
+        // A buy and a sell arriving together are summarized once
+        sender
+            .send(swap_between("sig-buy-000000000000000", 7, now - 60, (crate::tokens::USDC_MINT, 100.0), (crate::tokens::WSOL_MINT, 1.0)))
+            .await
+            .unwrap();
+        sender
+            .send(swap_between("sig-sell-00000000000000", 9, now - 30, (crate::tokens::WSOL_MINT, 1.0), (crate::tokens::USDC_MINT, 110.0)))
+            .await
+            .unwrap();
+        let summary = next_summary(&mut updates).await;
+        assert_eq!(summary.wallet, WALLET);
+        assert!(!summary.classification.is_empty());
+        assert_eq!(store.get_wallet_summary(WALLET).await.unwrap().unwrap().last_activity, (now - 30) * 1000);
+        assert_eq!(store.closed_trades(WALLET).await.len(), 1);
+        assert!(updates.try_recv().is_err());
+
+        // A later transaction brings a fresh summary while the stream stays open
+        sender
+            .send(swap_between("sig-buy-2-0000000000000", 11, now, (crate::tokens::USDC_MINT, 50.0), (crate::tokens::WSOL_MINT, 0.5)))
+            .await
+            .unwrap();
+        let summary = next_summary(&mut updates).await;
+        assert_eq!(summary.last_activity, now * 1000);
+
+        drop(sender);
+        processor.await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_subscriptions_are_persisted() {
+        let store = Arc::new(MemoryStore::new());
+        let indexer = Indexer::offline(store.clone());
+
+        assert!(indexer.start_subscription(WALLET).await.unwrap());
+        assert_eq!(store.subscribed_wallets().await.unwrap(), vec![WALLET]);
+
+        // A fresh indexer over the same store picks the subscription back up
+        let restarted = Indexer::offline(store.clone());
+        assert_eq!(restarted.resume_subscriptions().await.unwrap(), 1);
+        assert!(restarted.is_subscribed(WALLET).await);
+
+        assert!(restarted.stop_subscription(WALLET).await);
+        assert!(store.subscribed_wallets().await.unwrap().is_empty());
+        indexer.shutdown().await;
+    }
+
+    #[tokio::test]
+    async fn test_refetched_history_is_not_stored_twice() {
+        let store = Arc::new(MemoryStore::new());
+        let now = chrono::Utc::now().timestamp();
+        let buy = swap_between("sig-buy-000000000000000", 7, now - 60, (crate::tokens::USDC_MINT, 100.0), (crate::tokens::WSOL_MINT, 1.0));
+        let sell = swap_between("sig-sell-00000000000000", 9, now - 30, (crate::tokens::WSOL_MINT, 1.0), (crate::tokens::USDC_MINT, 110.0));
+        Indexer::offline(store.clone()).ingest(WALLET, vec![buy.clone()]).await;
+
+        // After a restart the history comes again, and the live stream repeats the sell
+        let restarted = Indexer::offline(store.clone());
+        restarted.ingest(WALLET, vec![buy, sell.clone(), sell]).await;
+
+        let stored = store.get_wallet_transactions(WALLET, TimeWindow::All).await.unwrap();
+        let mut signatures: Vec<&str> = stored.iter().map(|row| row.signature.as_str()).collect();
+        signatures.sort();
+        assert_eq!(signatures, vec!["sig-buy-000000000000000", "sig-sell-00000000000000"]);
+    }
+
+    #[tokio::test]
+    async fn test_swaps_against_stablecoins_price_var() {
+        let store = Arc::new(MemoryStore::new());
+        let indexer = Indexer::offline(store.clone());
+        let mut updates = indexer.subscribe_updates();
+        let now = chrono::Utc::now().timestamp();
+        let day = 86_400;
+
+        // One SOL bought a day for three days, at 100, 90 and 99 USDC, then deposited
+        let mut history: Vec<LysTransaction> = [(now - 2 * day, 100.0), (now - day, 90.0), (now - 60, 99.0)]
+            .iter()
+            .enumerate()
+            .map(|(i, (block_time, usdc))| {
+                swap_between(&format!("sig-buy-{}-0000000000000", i), i as u64, *block_time, (crate::tokens::USDC_MINT, *usdc), (crate::tokens::WSOL_MINT, 1.0))
+            })
+            .collect();
+        let mut deposit = swap_between("sig-deposit-00000000000", 3, now, ("", 0.0), ("", 0.0));
+        (deposit.decoder_type, deposit.event_type, deposit.mint, deposit.ui_amount) =
+            ("KAMINO".to_string(), "DEPOSIT".to_string(), crate::tokens::WSOL_MINT.to_string(), 3.0);
+        (deposit.token_in, deposit.token_out) = (None, None);
+        history.push(deposit);
+        indexer.ingest(WALLET, history).await;
+
+        let prices = store.get_token_prices(&[crate::tokens::WSOL_MINT.to_string()]).await.unwrap();
+        assert_eq!(prices[0].price_usd, "99");
+        let stored = store.get_wallet_transactions(WALLET, TimeWindow::All).await.unwrap();
+        let deposit = stored.iter().find(|row| row.tx_type == "deposit").unwrap();
+        assert_eq!(deposit.usd_value, "297");
+
+        let summary = loop {
+            if let WalletUpdate::Summary(summary) = updates.recv().await.unwrap() {
+                break summary;
+            }
+        };
+        assert_eq!(summary.var_coverage, "1");
+        assert!(summary.var_95.parse::<Decimal>().unwrap() > Decimal::ZERO);
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_summary_against_store() {
+        let store = Arc::new(MemoryStore::new());
+        let indexer = Indexer::offline(store.clone());
+        let mut updates = indexer.subscribe_updates();
+        let now = chrono::Utc::now().timestamp();
+
+        // Bought and sold SOL in one slot: a closed trade and a same-slot round trip
+        let transactions: Vec<parser::ParsedTransaction> = [
+            swap_between("sig-buy", 7, now - 60, (crate::tokens::USDC_MINT, 100.0), (crate::tokens::WSOL_MINT, 1.0)),
+            swap_between("sig-sell", 7, now - 30, (crate::tokens::WSOL_MINT, 1.0), (crate::tokens::USDC_MINT, 110.0)),
+        ]
+        .iter()
+        .filter_map(|tx| parse_transaction(tx, WALLET))
+        .collect();
+        assert_eq!(transactions.len(), 2);
+        for tx in &transactions {
+            store.write_transaction(tx.to_row()).await.unwrap();
+        }
+        // A Jito tip recorded earlier from the stream
+        store
+            .insert_transaction_flag(&TransactionFlagRow {
+                signature: "sig-tip".to_string(),
+                wallet: WALLET.to_string(),
+                flag: metrics::mev::MevFlag::JitoTip.to_string(),
+                counterparty: String::new(),
+                estimated_loss_usd: "0".to_string(),
+                slot: 6,
+                block_time: (now - 90) * 1000,
+            })
+            .await
+            .unwrap();
+
+        indexer.compute_wallet_summary(WALLET, &transactions, i64::MIN).await.unwrap();
+
+        let summary = store.get_wallet_summary(WALLET).await.unwrap().expect("summary stored");
+        assert_eq!(summary.round_trip_count, 2);
+        assert_eq!(summary.jito_tip_count, 1);
+        assert_eq!(summary.protocols, vec!["jupiter"]);
+
+        let flags = store.transaction_flags(WALLET).await;
+        let round_trips: Vec<&str> = flags
+            .iter()
+            .filter(|f| f.flag == metrics::mev::MevFlag::SameSlotRoundTrip.to_string())
+            .map(|f| f.signature.as_str())
+            .collect();
+        assert_eq!(round_trips, vec!["sig-buy", "sig-sell"]);
+
+        let trades = store.closed_trades(WALLET).await;
+        assert_eq!(trades.len(), 1);
+        assert_eq!(trades[0].signature, "sig-sell");
+        assert_eq!(trades[0].token, crate::tokens::WSOL_MINT);
+
+        match updates.try_recv().unwrap() {
+            WalletUpdate::Summary(published) => assert_eq!(published.round_trip_count, 2),
+            other => panic!("expected a summary update, got {other:?}"),
+        }
+
+        // Flags already stored are not written again on the next refresh
+        let mut stored = flags.iter().find(|f| f.signature == "sig-buy").unwrap().clone();
+        stored.counterparty = "stored-earlier".to_string();
+        store.insert_transaction_flag(&stored).await.unwrap();
+        indexer.compute_wallet_summary(WALLET, &transactions, i64::MIN).await.unwrap();
+        let flags = store.transaction_flags(WALLET).await;
+        let buy = flags.iter().find(|f| f.signature == "sig-buy").unwrap();
+        assert_eq!(buy.counterparty, "stored-earlier");
+    }
+
+    #[tokio::test]
+    async fn test_closed_trades_are_rewritten_from_the_earliest_change() {
+        let store = Arc::new(MemoryStore::new());
+        let indexer = Indexer::offline(store.clone());
+        let now = chrono::Utc::now().timestamp();
+        let swap = |signature: &str, block_time: i64, token_in: (&str, f64), token_out: (&str, f64), usd_value: Decimal| {
+            let mut tx = parse_transaction(&swap_between(signature, 7, block_time, token_in, token_out), WALLET).unwrap();
+            tx.usd_value = usd_value;
+            tx
+        };
+        let buy = |signature: &str, block_time: i64, usdc: f64, sol: f64| {
+            swap(signature, block_time, (crate::tokens::USDC_MINT, usdc), (crate::tokens::WSOL_MINT, sol), Decimal::try_from(usdc).unwrap())
+        };
+        let sell = |signature: &str, block_time: i64| {
+            swap(signature, block_time, (crate::tokens::WSOL_MINT, 1.0), (crate::tokens::USDC_MINT, 110.0), Decimal::from(110))
+        };
+        let pnls = || async { store.closed_trades(WALLET).await.into_iter().map(|t| t.pnl).collect::<Vec<_>>() };
+
+        let mut history = vec![buy("sig-buy", now - 90, 300.0, 3.0), sell("sig-sell-1", now - 60), sell("sig-sell-2", now - 45)];
+        indexer.store_closed_trades(WALLET, &history, i64::MIN).await.unwrap();
+        assert_eq!(pnls().await, vec!["10", "10"]);
+
+        // Backfilled history older than the stored trades changes their average cost
+        let earlier = buy("sig-buy-earlier", now - 120, 50.0, 1.0);
+        let changed_from = earlier.block_time;
+        history.push(earlier);
+        indexer.store_closed_trades(WALLET, &history, changed_from).await.unwrap();
+        assert_eq!(pnls().await, vec!["22.50", "22.50"]);
+
+        // Trades closed before the earliest change are left as stored
+        let mut trades = store.closed_trades(WALLET).await;
+        trades[0].pnl = "999".to_string();
+        store.insert_closed_trades(&trades[..1]).await.unwrap();
+        let later = sell("sig-sell-3", now - 30);
+        let changed_from = later.block_time;
+        history.push(later);
+        indexer.store_closed_trades(WALLET, &history, changed_from).await.unwrap();
+
+        let trades = store.closed_trades(WALLET).await;
+        let signatures: Vec<&str> = trades.iter().map(|t| t.signature.as_str()).collect();
+        assert_eq!(signatures, vec!["sig-sell-1", "sig-sell-2", "sig-sell-3"]);
+        assert_eq!(trades[0].pnl, "999");
+    }
+
+    #[tokio::test]
+    async fn test_snapshot_summary_carries_realized_windows() {
+        let store = Arc::new(MemoryStore::new());
+        let indexer = Indexer::offline(store.clone());
+        let mut updates = indexer.subscribe_updates();
+        let hours_ago = |hours: i64| (chrono::Utc::now() - chrono::Duration::hours(hours)).timestamp_millis();
+
+        let rows = [
+            ("sig-recent", "swap", "40", hours_ago(2)),
+            ("sig-week", "swap", "25", hours_ago(24 * 3)),
+            ("sig-month", "withdraw", "100", hours_ago(24 * 20)),
+        ]
+        .map(|(signature, tx_type, usd_value, block_time)| TransactionRow {
+            signature: signature.to_string(),
+            wallet: WALLET.to_string(),
+            protocol: "jupiter".to_string(),
+            tx_type: tx_type.to_string(),
+            token_in: crate::tokens::WSOL_MINT.to_string(),
+            token_out: crate::tokens::USDC_MINT.to_string(),
+            amount_in: "1".to_string(),
+            amount_out: usd_value.to_string(),
+            usd_value: usd_value.to_string(),
+            block_time,
+            slot: 1,
+        });
+        for row in &rows {
+            store.write_transaction(row.clone()).await.unwrap();
+        }
+        let transactions: Vec<parser::ParsedTransaction> = rows.iter().filter_map(parser::ParsedTransaction::from_row).collect();
+
+        indexer.compute_wallet_summary(WALLET, &transactions, i64::MIN).await.unwrap();
+
+        let published = match updates.try_recv().unwrap() {
+            WalletUpdate::Summary(summary) => summary,
+            other => panic!("expected a summary update, got {other:?}"),
+        };
+        assert_eq!(published.realized_pnl_24h, "40");
+        assert_eq!(published.realized_pnl_7d, "65");
+        assert_eq!(published.realized_pnl_30d, "165");
+
+        // What was published is what the API serves
+        let stored = store.get_wallet_summary(WALLET).await.unwrap().unwrap();
+        assert_eq!(stored.realized_pnl_7d, published.realized_pnl_7d);
+        assert_eq!(stored.last_activity, rows[0].block_time);
     }
 }
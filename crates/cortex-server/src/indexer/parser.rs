@@ -1,8 +1,11 @@
 use rust_decimal::Decimal;
+use std::str::FromStr;
 use tracing;
 
 use super::lyslabs::LysTransaction;
 use crate::db::models::TransactionRow;
+use crate::telemetry::{self, SkipReason};
+use crate::tokens::is_stablecoin;
 use crate::types::{Protocol, TransactionType};
 
 #[derive(Clone)]
@@ -36,6 +39,40 @@ impl ParsedTransaction {
             slot: self.slot,
         }
     }
+
+    /// Rebuild a parsed transaction from a stored row.
+    /// Returns `None` if the protocol or transaction type is not recognized.
+    pub fn from_row(row: &TransactionRow) -> Option<Self> {
+        Some(Self {
+            signature: row.signature.clone(),
+            wallet: row.wallet.clone(),
+            protocol: Protocol::from_str(&row.protocol)?,
+            tx_type: TransactionType::from_str(&row.tx_type)?,
+            token_in: row.token_in.clone(),
+            token_out: row.token_out.clone(),
+            amount_in: Decimal::from_str(&row.amount_in).unwrap_or_default(),
+            amount_out: Decimal::from_str(&row.amount_out).unwrap_or_default(),
+            usd_value: Decimal::from_str(&row.usd_value).unwrap_or_default(),
+            block_time: row.block_time,
+            slot: row.slot,
+        })
+    }
+
+    /// USD price of the other side of a swap against a stablecoin, as `(mint, price)`
+    pub fn implied_price(&self) -> Option<(&str, Decimal)> {
+        if self.tx_type != TransactionType::Swap {
+            return None;
+        }
+        let (token, amount) = match (is_stablecoin(&self.token_in), is_stablecoin(&self.token_out)) {
+            (true, false) => (&self.token_out, self.amount_out),
+            (false, true) => (&self.token_in, self.amount_in),
+            _ => return None,
+        };
+        if token.is_empty() || amount.is_zero() || self.usd_value.is_zero() {
+            return None;
+        }
+        Some((token, self.usd_value / amount))
+    }
 }
 
 /// Parse a LYS Labs transaction into a ParsedTransaction
@@ -60,6 +97,7 @@ pub fn parse_transaction(tx: &LysTransaction, wallet: &str) -> Option<ParsedTran
                 program_id = %tx.program_id,
                 "Could not identify protocol, skipping transaction"
             );
+            telemetry::transaction_skipped(SkipReason::UnknownProtocol);
             return None;
         }
     };
@@ -72,6 +110,7 @@ pub fn parse_transaction(tx: &LysTransaction, wallet: &str) -> Option<ParsedTran
         }
         "TRANSFER" => {
             tracing::trace!(signature = %tx.tx_signature, "Skipping TRANSFER transaction");
+            telemetry::transaction_skipped(SkipReason::Transfer);
             None // We don't track simple transfers
         }
         "DEPOSIT" | "SUPPLY" => {
@@ -105,12 +144,14 @@ pub fn parse_transaction(tx: &LysTransaction, wallet: &str) -> Option<ParsedTran
                     event_type = %tx.event_type,
                     "Unknown event type and not a swap decoder, skipping"
                 );
+                telemetry::transaction_skipped(SkipReason::UnsupportedEvent);
                 None
             }
         }
     };
 
     if let Some(ref parsed) = result {
+        telemetry::transaction_parsed(&parsed.protocol.to_string());
         tracing::debug!(
             signature = %parsed.signature,
             protocol = ?parsed.protocol,
@@ -223,9 +264,19 @@ fn parse_swap(tx: &LysTransaction, wallet: &str, protocol: Protocol) -> Option<P
 
     // Skip if we couldn't identify tokens
     if token_in.is_empty() && token_out.is_empty() {
+        telemetry::transaction_skipped(SkipReason::NoTokens);
         return None;
     }
 
+    // Valued at the stablecoin side, when there is one
+    let usd_value = if is_stablecoin(&token_in) {
+        amount_in
+    } else if is_stablecoin(&token_out) {
+        amount_out
+    } else {
+        Decimal::ZERO
+    };
+
     Some(ParsedTransaction {
         signature: tx.tx_signature.clone(),
         wallet: wallet.to_string(),
@@ -235,7 +286,7 @@ fn parse_swap(tx: &LysTransaction, wallet: &str, protocol: Protocol) -> Option<P
         token_out,
         amount_in,
         amount_out,
-        usd_value: Decimal::ZERO, // Will be computed later with price data
+        usd_value,
         block_time: tx.block_time * 1000, // Convert to milliseconds
         slot: tx.slot,
     })
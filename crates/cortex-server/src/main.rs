@@ -1,30 +1,52 @@
+mod alerts;
 mod api;
+mod auth;
 mod config;
 mod db;
 mod error;
+mod graphql;
+mod groups;
 mod indexer;
 mod metrics;
+mod registry;
+mod telemetry;
+mod tokens;
 mod types;
+mod webhooks;
 
 use std::net::SocketAddr;
 use std::sync::Arc;
 
 use axum::Router;
-use tower_http::cors::{Any, CorsLayer};
+use tokio_util::sync::CancellationToken;
+use tower_http::cors::{AllowHeaders, Any, CorsLayer};
 use tower_http::trace::TraceLayer;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 
+use crate::alerts::AlertEngine;
+use crate::auth::ApiKeys;
 use crate::config::AppConfig;
 use crate::db::Database;
+use crate::db::store::{ClickHouseStore, CortexStore};
+use crate::db::writer::TransactionWriter;
+use crate::groups::WalletGroups;
 use crate::indexer::Indexer;
+use crate::webhooks::WebhookDispatcher;
 
 pub use crate::error::{AppError, AppResult};
 
 #[derive(Clone)]
 pub struct AppState {
     pub db: Database,
+    pub store: Arc<dyn CortexStore>,
     pub indexer: Indexer,
+    pub webhooks: WebhookDispatcher,
+    pub alerts: AlertEngine,
+    pub api_keys: ApiKeys,
+    pub groups: WalletGroups,
     pub config: Arc<AppConfig>,
+    /// Cancelled when the server starts shutting down, ending open streams
+    pub shutdown: CancellationToken,
 }
 
 #[tokio::main]
@@ -82,11 +104,22 @@ async fn main() -> anyhow::Result<()> {
     println!("[DB] Initializing ClickHouse connection...");
     let db = Database::new(&config.database);
 
+    // `cortex migrate [status]` and `cortex tax-lots` run once and exit
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return run_command(&config, &db, &args).await;
+    }
+
     // Check database connection
     match db.health_check().await {
         Ok(_) => {
             println!("[DB] ClickHouse connected successfully");
             tracing::info!("Connected to ClickHouse");
+
+            // Bring the schema up to date, refusing to start against an incompatible one
+            db.migrate()
+                .await
+                .map_err(|e| anyhow::anyhow!("Schema migration failed: {}", e))?;
         }
         Err(e) => {
             println!("[DB] WARNING: ClickHouse not available - {}", e);
@@ -96,29 +129,88 @@ async fn main() -> anyhow::Result<()> {
 
     // Initialize indexer with both LYS Labs (real-time) and Helius (historical)
     println!("[INDEXER] Initializing hybrid indexer (LYS Labs + Helius)...");
-    let indexer = Indexer::new(&config.lyslabs, &config.helius, db.clone());
+    let writer = TransactionWriter::spawn(db.client(), &config.database)
+        .map_err(|e| anyhow::anyhow!("Failed to start transaction writer: {}", e))?;
+    let store: Arc<dyn CortexStore> = Arc::new(ClickHouseStore::new(db.clone(), writer));
+    let indexer = Indexer::new(&config.lyslabs, &config.helius, store.clone());
     println!("[INDEXER] Indexer ready (Helius for historical, LYS Labs for real-time)");
 
+    // Deliver indexer updates to registered webhooks
+    let webhooks = WebhookDispatcher::new(store.clone(), &config.webhooks);
+    if let Err(e) = webhooks.load().await {
+        println!("[WEBHOOK] WARNING: Could not load webhooks - {}", e);
+        tracing::warn!(error = %e, "Could not load webhooks");
+    }
+    webhooks.spawn(indexer.subscribe_updates());
+
+    // Named wallet groups
+    let groups = WalletGroups::new(store.clone());
+    if let Err(e) = groups.load().await {
+        println!("[GROUP] WARNING: Could not load wallet groups - {}", e);
+        tracing::warn!(error = %e, "Could not load wallet groups");
+    }
+
+    // Evaluate alert rules whenever a summary is recomputed
+    let alerts = AlertEngine::new(store.clone(), indexer.clone(), groups.clone());
+    if let Err(e) = alerts.load().await {
+        println!("[ALERT] WARNING: Could not load alert rules - {}", e);
+        tracing::warn!(error = %e, "Could not load alert rules");
+    }
+    alerts.spawn(indexer.subscribe_updates());
+
+    // Resumed once webhooks and alerts are listening, so they see the first updates
+    if let Err(e) = indexer.resume_subscriptions().await {
+        println!("[INDEXER] WARNING: Could not restore subscriptions - {}", e);
+        tracing::warn!(error = %e, "Could not restore subscriptions");
+    }
+
+    // API keys guarding every route but /health
+    let api_keys = ApiKeys::new(store.clone(), &config.auth);
+    if let Err(e) = api_keys.load().await {
+        println!("[AUTH] WARNING: Could not load API keys - {}", e);
+        tracing::warn!(error = %e, "Could not load API keys");
+    }
+
+    if !config.auth.enabled {
+        println!("[AUTH] WARNING: Authentication disabled; every route is open");
+        tracing::warn!("Authentication disabled");
+    }
+
     // Create app state
+    let shutdown = CancellationToken::new();
     let state = AppState {
         db,
-        indexer,
+        store,
+        indexer: indexer.clone(),
+        webhooks,
+        alerts,
+        api_keys,
+        groups,
         config: Arc::new(config.clone()),
+        shutdown: shutdown.clone(),
     };
 
     // Build router
     println!("[ROUTER] Setting up API routes...");
     let app = Router::new()
-        .merge(api::create_router())
+        .merge(api::create_router(state))
         .layer(
             CorsLayer::new()
                 .allow_origin(Any)
                 .allow_methods(Any)
-                .allow_headers(Any),
+                // Mirrored rather than `*`, which browsers don't apply to Authorization
+                .allow_headers(AllowHeaders::mirror_request()),
         )
-        .layer(TraceLayer::new_for_http())
-        .with_state(state);
-    println!("[ROUTER] Routes configured: /health, /api/v1/user/{{wallet}}/*, /api/v1/index");
+        .layer(TraceLayer::new_for_http().make_span_with(|request: &axum::http::Request<_>| {
+            // As the default span, but without `?api_key=` values
+            tracing::debug_span!(
+                "request",
+                method = %request.method(),
+                uri = %auth::redacted_uri(request.uri()),
+                version = ?request.version(),
+            )
+        }));
+    println!("[ROUTER] Routes configured: /health, /api/v1/user/{{wallet}}/*, /api/v1/index, /api/v1/groups, /api/v1/alerts, /api/v1/webhooks, /metrics");
 
     // Start server
     let addr: SocketAddr = config.server_addr().parse()?;
@@ -132,7 +224,78 @@ async fn main() -> anyhow::Result<()> {
 
     tracing::info!("Listening on {}", addr);
 
-    axum::serve(listener, app).await?;
+    axum::serve(listener, app)
+        .with_graceful_shutdown(async move {
+            tokio::signal::ctrl_c().await.ok();
+            println!("[SERVER] Shutdown requested, draining...");
+            tracing::info!("Shutdown requested");
+            // Streams would otherwise hold their connections open forever
+            shutdown.cancel();
+        })
+        .await?;
+
+    // Buffered transactions are written before the process exits
+    indexer.shutdown().await;
+
+    Ok(())
+}
+
+const USAGE: &str = "cortex [migrate [status] | tax-lots <wallet> [--year YYYY] [--method fifo|lifo|hifo|average_cost] [--format json|csv]]";
+
+/// Run a one-off CLI command instead of the server
+async fn run_command(config: &AppConfig, db: &Database, args: &[String]) -> anyhow::Result<()> {
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["migrate"] => {
+            let applied = db.migrate().await?;
+            println!("[MIGRATE] {} migration(s) applied", applied.len());
+        }
+        ["migrate", "status"] => {
+            let applied = db.applied_migrations().await?;
+            for migration in db::migrations::MIGRATIONS {
+                let state = match applied.iter().find(|a| a.version == migration.version) {
+                    Some(a) if a.checksum == migration.checksum() => "applied",
+                    Some(_) => "CHANGED",
+                    None => "pending",
+                };
+                println!("{:>4}  {:<32} {}", migration.version, migration.name, state);
+            }
+            for unknown in applied.iter().filter(|a| db::migrations::MIGRATIONS.iter().all(|m| m.version != a.version)) {
+                println!("{:>4}  {:<32} UNKNOWN (newer build)", unknown.version, unknown.name);
+            }
+        }
+        ["tax-lots", wallet, options @ ..] => {
+            if !types::validate_solana_address(wallet) {
+                anyhow::bail!("Invalid wallet address: {}", wallet);
+            }
+            let mut year = None;
+            let mut method = types::LotMethod::Fifo;
+            let mut csv = false;
+            for option in options.chunks(2) {
+                match option {
+                    ["--year", value] => year = Some(value.parse().map_err(|_| anyhow::anyhow!("Invalid year: {}", value))?),
+                    ["--method", value] => {
+                        method = types::LotMethod::from_str(value).ok_or_else(|| anyhow::anyhow!("Invalid method: {}", value))?
+                    }
+                    ["--format", "json"] => csv = false,
+                    ["--format", "csv"] => csv = true,
+                    _ => anyhow::bail!("Unexpected argument: {} (usage: {})", option.join(" "), USAGE),
+                }
+            }
+            // Defaults to the previous calendar year, like the HTTP endpoint
+            let year = year.unwrap_or_else(|| chrono::Datelike::year(&chrono::Utc::now()) - 1);
 
+            let writer = TransactionWriter::spawn(db.client(), &config.database)
+                .map_err(|e| anyhow::anyhow!("Failed to start transaction writer: {}", e))?;
+            let store = ClickHouseStore::new(db.clone(), writer);
+            let report = api::handlers::tax_lot_report(&store, wallet, year, method).await?;
+            if csv {
+                print!("{}", report.to_csv());
+            } else {
+                println!("{}", serde_json::to_string_pretty(&report)?);
+            }
+        }
+        _ => anyhow::bail!("Unknown command: {} (usage: {})", args.join(" "), USAGE),
+    }
     Ok(())
 }
@@ -0,0 +1,117 @@
+use std::collections::HashSet;
+
+use chrono::Utc;
+use cortex_core::{interval_stats, WalletFeatures};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use super::mev::MevSummary;
+use crate::indexer::parser::ParsedTransaction;
+use crate::types::TransactionType;
+
+const MILLIS_PER_DAY: f64 = 86_400_000.0;
+
+/// Extract the behavioural features used by `cortex_core::classify_wallet`.
+///
+/// `transactions` should be the wallet's full history; order does not matter.
+pub fn extract_features(
+    transactions: &[ParsedTransaction],
+    portfolio_value: Decimal,
+    mev: &MevSummary,
+) -> WalletFeatures {
+    let mut timestamps: Vec<i64> = transactions.iter().map(|t| t.block_time).collect();
+    timestamps.sort_unstable();
+
+    let (Some(&first), Some(&last)) = (timestamps.first(), timestamps.last()) else {
+        return WalletFeatures {
+            portfolio_value_usd: portfolio_value.to_f64().unwrap_or(0.0),
+            ..WalletFeatures::default()
+        };
+    };
+
+    let swaps: Vec<&ParsedTransaction> = transactions
+        .iter()
+        .filter(|t| t.tx_type == TransactionType::Swap)
+        .collect();
+    let trade_count = swaps.len() as u64;
+
+    // At least one day, so a burst of activity doesn't divide by zero
+    let active_days = ((last - first) as f64 / MILLIS_PER_DAY).max(1.0);
+
+    let counterparties: HashSet<&str> = swaps
+        .iter()
+        .flat_map(|t| [t.token_in.as_str(), t.token_out.as_str()])
+        .filter(|token| !token.is_empty())
+        .collect();
+    let protocols: HashSet<_> = transactions.iter().map(|t| t.protocol).collect();
+
+    let passive = transactions
+        .iter()
+        .filter(|t| !matches!(t.tx_type, TransactionType::Swap))
+        .count();
+
+    let (median_interval_secs, interval_regularity) = interval_stats(&timestamps);
+
+    WalletFeatures {
+        portfolio_value_usd: portfolio_value.to_f64().unwrap_or(0.0),
+        trade_count,
+        trades_per_day: trade_count as f64 / active_days,
+        median_interval_secs,
+        interval_regularity,
+        counterparty_count: counterparties.len(),
+        account_age_days: (Utc::now().timestamp_millis() - first) as f64 / MILLIS_PER_DAY,
+        protocol_count: protocols.len(),
+        passive_share: passive as f64 / transactions.len() as f64,
+        same_slot_round_trips: u64::from(mev.round_trip_count),
+        jito_tip_count: u64::from(mev.jito_tip_count),
+        snipe_count: u64::from(mev.snipe_count),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Protocol;
+    use rust_decimal_macros::dec;
+
+    fn make_tx(tx_type: TransactionType, token_in: &str, token_out: &str, block_time: i64) -> ParsedTransaction {
+        ParsedTransaction {
+            signature: "test".to_string(),
+            wallet: "wallet".to_string(),
+            protocol: Protocol::Jupiter,
+            tx_type,
+            token_in: token_in.to_string(),
+            token_out: token_out.to_string(),
+            amount_in: Decimal::ZERO,
+            amount_out: Decimal::ZERO,
+            usd_value: Decimal::ZERO,
+            block_time,
+            slot: 0,
+        }
+    }
+
+    #[test]
+    fn test_empty_history() {
+        let features = extract_features(&[], dec!(100), &MevSummary::default());
+        assert_eq!(features.trade_count, 0);
+        assert_eq!(features.portfolio_value_usd, 100.0);
+    }
+
+    #[test]
+    fn test_extract_features() {
+        let day = MILLIS_PER_DAY as i64;
+        let txs = vec![
+            make_tx(TransactionType::Swap, "USDC", "SOL", 0),
+            make_tx(TransactionType::Swap, "SOL", "BONK", day),
+            make_tx(TransactionType::Deposit, "SOL", "", 2 * day),
+            make_tx(TransactionType::Swap, "BONK", "USDC", 4 * day),
+        ];
+
+        let features = extract_features(&txs, dec!(1000), &MevSummary::default());
+        assert_eq!(features.trade_count, 3);
+        assert_eq!(features.trades_per_day, 0.75);
+        assert_eq!(features.counterparty_count, 3);
+        assert_eq!(features.passive_share, 0.25);
+        assert_eq!(features.median_interval_secs, Some(86_400.0));
+    }
+}
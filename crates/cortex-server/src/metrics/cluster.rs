@@ -0,0 +1,287 @@
+use std::collections::{BTreeSet, HashMap, VecDeque};
+use std::fmt;
+
+/// Transfers between two wallets (either direction) needed to link them
+pub const MIN_REPEATED_TRANSFERS: usize = 3;
+
+/// A funder or fee payer linked to more wallets than this is treated as a service
+/// (exchange hot wallet, relayer, faucet) and doesn't join wallets together
+pub const MAX_SHARED_FAN_OUT: u64 = 20;
+
+/// A cluster never grows past this many members
+pub const MAX_CLUSTER_SIZE: usize = 100;
+
+/// How a raw link between two wallets was observed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkKind {
+    /// `source` sent funds to `target`
+    Transfer,
+    /// `source` paid the fee for a transaction moving `target`'s funds
+    FeePayer,
+}
+
+impl fmt::Display for LinkKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkKind::Transfer => write!(f, "transfer"),
+            LinkKind::FeePayer => write!(f, "fee_payer"),
+        }
+    }
+}
+
+impl LinkKind {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "transfer" => Some(LinkKind::Transfer),
+            "fee_payer" => Some(LinkKind::FeePayer),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct WalletLink {
+    pub source: String,
+    pub target: String,
+    pub kind: LinkKind,
+    pub block_time: i64,
+}
+
+/// Heuristic that joined two wallets
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LinkReason {
+    /// The first wallet to send funds to the other; wallets sharing a first funder
+    /// end up in the same cluster through it
+    FirstFunder,
+    /// At least `MIN_REPEATED_TRANSFERS` direct transfers between the two
+    RepeatedTransfers,
+    /// One paid fees for transactions spending the other's funds
+    SharedFeePayer,
+}
+
+impl fmt::Display for LinkReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LinkReason::FirstFunder => write!(f, "first_funder"),
+            LinkReason::RepeatedTransfers => write!(f, "repeated_transfers"),
+            LinkReason::SharedFeePayer => write!(f, "shared_fee_payer"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterEdge {
+    pub from: String,
+    pub to: String,
+    pub reason: LinkReason,
+    /// Transactions backing the edge
+    pub evidence_count: usize,
+}
+
+/// Addresses believed to belong to one entity
+#[derive(Debug, Clone)]
+pub struct Cluster {
+    /// Lexicographically smallest member, so the id is stable for a given membership
+    pub id: String,
+    /// Sorted member addresses
+    pub members: Vec<String>,
+    pub edges: Vec<ClusterEdge>,
+}
+
+/// Derive the clustering edges supported by a set of raw links.
+///
+/// `fan_out` maps an address to the number of distinct wallets it has linked to; any
+/// address above `MAX_SHARED_FAN_OUT` is ignored as a funder or fee payer, and
+/// addresses missing from the map are assumed to be personal wallets.
+pub fn cluster_edges(links: &[WalletLink], fan_out: &HashMap<String, u64>) -> Vec<ClusterEdge> {
+    let is_service = |address: &str| fan_out.get(address).is_some_and(|n| *n > MAX_SHARED_FAN_OUT);
+    let mut edges = Vec::new();
+
+    // Earliest incoming transfer per wallet
+    let mut first_funding: HashMap<&str, &WalletLink> = HashMap::new();
+    for link in links.iter().filter(|l| l.kind == LinkKind::Transfer) {
+        first_funding
+            .entry(link.target.as_str())
+            .and_modify(|first| {
+                if link.block_time < first.block_time {
+                    *first = link;
+                }
+            })
+            .or_insert(link);
+    }
+    for (target, link) in first_funding {
+        if !is_service(&link.source) {
+            edges.push(ClusterEdge {
+                from: link.source.clone(),
+                to: target.to_string(),
+                reason: LinkReason::FirstFunder,
+                evidence_count: 1,
+            });
+        }
+    }
+
+    // Direct transfers between a pair, in either direction
+    let mut pair_transfers: HashMap<(&str, &str), usize> = HashMap::new();
+    let mut fee_payments: HashMap<(&str, &str), usize> = HashMap::new();
+    for link in links {
+        match link.kind {
+            LinkKind::Transfer => {
+                let pair = if link.source < link.target {
+                    (link.source.as_str(), link.target.as_str())
+                } else {
+                    (link.target.as_str(), link.source.as_str())
+                };
+                *pair_transfers.entry(pair).or_default() += 1;
+            }
+            LinkKind::FeePayer => {
+                *fee_payments.entry((link.source.as_str(), link.target.as_str())).or_default() += 1;
+            }
+        }
+    }
+    for ((a, b), count) in pair_transfers {
+        if count >= MIN_REPEATED_TRANSFERS && !is_service(a) && !is_service(b) {
+            edges.push(ClusterEdge {
+                from: a.to_string(),
+                to: b.to_string(),
+                reason: LinkReason::RepeatedTransfers,
+                evidence_count: count,
+            });
+        }
+    }
+    for ((payer, wallet), count) in fee_payments {
+        if !is_service(payer) {
+            edges.push(ClusterEdge {
+                from: payer.to_string(),
+                to: wallet.to_string(),
+                reason: LinkReason::SharedFeePayer,
+                evidence_count: count,
+            });
+        }
+    }
+
+    edges.retain(|e| e.from != e.to);
+    edges.sort_by(|a, b| (&a.from, &a.to, a.reason as u8).cmp(&(&b.from, &b.to, b.reason as u8)));
+    edges
+}
+
+/// The cluster containing `wallet`: the addresses reachable from it over the edges
+/// `cluster_edges` derives from `links`, nearest first, up to `MAX_CLUSTER_SIZE`.
+pub fn cluster_of(wallet: &str, links: &[WalletLink], fan_out: &HashMap<String, u64>) -> Cluster {
+    let edges = cluster_edges(links, fan_out);
+
+    let mut adjacency: HashMap<&str, Vec<&str>> = HashMap::new();
+    for edge in &edges {
+        adjacency.entry(edge.from.as_str()).or_default().push(edge.to.as_str());
+        adjacency.entry(edge.to.as_str()).or_default().push(edge.from.as_str());
+    }
+
+    let mut members: BTreeSet<&str> = BTreeSet::from([wallet]);
+    let mut queue = VecDeque::from([wallet]);
+    'expand: while let Some(address) = queue.pop_front() {
+        for &next in adjacency.get(address).into_iter().flatten() {
+            if members.len() >= MAX_CLUSTER_SIZE {
+                break 'expand;
+            }
+            if members.insert(next) {
+                queue.push_back(next);
+            }
+        }
+    }
+
+    let members: Vec<String> = members.into_iter().map(String::from).collect();
+    let edges: Vec<ClusterEdge> = edges
+        .into_iter()
+        .filter(|e| members.binary_search(&e.from).is_ok() && members.binary_search(&e.to).is_ok())
+        .collect();
+
+    Cluster {
+        id: members[0].clone(),
+        members,
+        edges,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn link(source: &str, target: &str, kind: LinkKind, block_time: i64) -> WalletLink {
+        WalletLink {
+            source: source.to_string(),
+            target: target.to_string(),
+            kind,
+            block_time,
+        }
+    }
+
+    #[test]
+    fn test_common_first_funder_joins_siblings() {
+        let links = vec![
+            link("whale", "alt1", LinkKind::Transfer, 1),
+            link("whale", "alt2", LinkKind::Transfer, 2),
+            // A later deposit doesn't make the sender a first funder
+            link("stranger", "alt1", LinkKind::Transfer, 5),
+        ];
+
+        let cluster = cluster_of("alt2", &links, &HashMap::new());
+        assert_eq!(cluster.members, vec!["alt1", "alt2", "whale"]);
+        assert_eq!(cluster.id, "alt1");
+        assert!(cluster.edges.iter().all(|e| e.reason == LinkReason::FirstFunder));
+    }
+
+    #[test]
+    fn test_services_do_not_join_wallets() {
+        let links = vec![
+            link("exchange", "alice", LinkKind::Transfer, 1),
+            link("exchange", "bob", LinkKind::Transfer, 2),
+            link("relayer", "alice", LinkKind::FeePayer, 3),
+            link("relayer", "carol", LinkKind::FeePayer, 4),
+        ];
+        let fan_out = HashMap::from([("exchange".to_string(), 5_000), ("relayer".to_string(), 900)]);
+
+        let cluster = cluster_of("alice", &links, &fan_out);
+        assert_eq!(cluster.members, vec!["alice"]);
+    }
+
+    #[test]
+    fn test_repeated_transfers_and_fee_payer() {
+        let links = vec![
+            link("stranger", "main", LinkKind::Transfer, 0),
+            link("main", "hot", LinkKind::Transfer, 10),
+            link("hot", "main", LinkKind::Transfer, 11),
+            link("main", "hot", LinkKind::Transfer, 12),
+            link("main", "cold", LinkKind::FeePayer, 13),
+        ];
+        // Funded first by a stranger, so only repeated transfers link main and hot
+        let fan_out = HashMap::from([("stranger".to_string(), 50)]);
+
+        let cluster = cluster_of("hot", &links, &fan_out);
+        assert_eq!(cluster.members, vec!["cold", "hot", "main"]);
+        let repeated = cluster
+            .edges
+            .iter()
+            .find(|e| e.reason == LinkReason::RepeatedTransfers)
+            .unwrap();
+        assert_eq!(repeated.evidence_count, 3);
+    }
+
+    #[test]
+    fn test_cluster_size_is_capped() {
+        // A chain of first fundings, longer than a cluster may be
+        let chain: Vec<String> = (0..MAX_CLUSTER_SIZE + 50).map(|i| format!("w{:03}", i)).collect();
+        let links: Vec<WalletLink> = chain
+            .windows(2)
+            .enumerate()
+            .map(|(i, pair)| link(&pair[0], &pair[1], LinkKind::Transfer, i as i64))
+            .collect();
+
+        let cluster = cluster_of("w000", &links, &HashMap::new());
+        assert_eq!(cluster.members.len(), MAX_CLUSTER_SIZE);
+        // The nearest addresses are kept, and every edge stays inside the cluster
+        assert_eq!(cluster.members, chain[..MAX_CLUSTER_SIZE].to_vec());
+        assert!(cluster
+            .edges
+            .iter()
+            .all(|e| cluster.members.contains(&e.from) && cluster.members.contains(&e.to)));
+    }
+}
@@ -0,0 +1,158 @@
+use rust_decimal::Decimal;
+
+/// Wallets with the highest realized PnL over the last 30 days that count as top performers
+pub const TOP_PERFORMER_COUNT: u32 = 100;
+
+/// Classification treated as smart money regardless of trading record
+const WHALE_CLASSIFICATION: &str = "whale";
+
+/// One wallet's swaps into and out of a token over a window
+#[derive(Debug, Clone)]
+pub struct WalletFlow {
+    pub wallet: String,
+    /// Tokens received from swaps
+    pub bought: Decimal,
+    /// Tokens given up in swaps
+    pub sold: Decimal,
+    pub buy_count: u64,
+    pub sell_count: u64,
+    /// Behavioural class from the wallet summary, empty when not yet classified
+    pub classification: String,
+    /// Ranked among the top wallets by realized PnL
+    pub top_performer: bool,
+}
+
+impl WalletFlow {
+    pub fn net(&self) -> Decimal {
+        self.bought - self.sold
+    }
+
+    pub fn is_smart_money(&self) -> bool {
+        self.top_performer || self.classification == WHALE_CLASSIFICATION
+    }
+}
+
+/// Part of the flow coming from whales and top performers
+#[derive(Debug, Clone, Default)]
+pub struct SmartMoneyFlow {
+    pub wallet_count: u64,
+    pub buy_volume: Decimal,
+    pub sell_volume: Decimal,
+    /// Share of all buy volume, 0 when nothing was bought
+    pub buy_share: Decimal,
+    /// Share of all sell volume, 0 when nothing was sold
+    pub sell_share: Decimal,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct TokenFlows {
+    pub buy_volume: Decimal,
+    pub sell_volume: Decimal,
+    pub unique_buyers: u64,
+    pub unique_sellers: u64,
+    pub smart_money: SmartMoneyFlow,
+    /// Largest net buyers, biggest first
+    pub top_accumulators: Vec<WalletFlow>,
+    /// Largest net sellers, biggest first
+    pub top_distributors: Vec<WalletFlow>,
+}
+
+impl TokenFlows {
+    pub fn net_volume(&self) -> Decimal {
+        self.buy_volume - self.sell_volume
+    }
+}
+
+/// Aggregate per-wallet flows in a token, keeping the `top` largest net buyers and sellers.
+pub fn aggregate_flows(flows: Vec<WalletFlow>, top: usize) -> TokenFlows {
+    let mut result = TokenFlows::default();
+
+    for flow in &flows {
+        result.buy_volume += flow.bought;
+        result.sell_volume += flow.sold;
+        if flow.buy_count > 0 {
+            result.unique_buyers += 1;
+        }
+        if flow.sell_count > 0 {
+            result.unique_sellers += 1;
+        }
+        if flow.is_smart_money() {
+            result.smart_money.wallet_count += 1;
+            result.smart_money.buy_volume += flow.bought;
+            result.smart_money.sell_volume += flow.sold;
+        }
+    }
+
+    if result.buy_volume > Decimal::ZERO {
+        result.smart_money.buy_share = (result.smart_money.buy_volume / result.buy_volume).round_dp(4);
+    }
+    if result.sell_volume > Decimal::ZERO {
+        result.smart_money.sell_share = (result.smart_money.sell_volume / result.sell_volume).round_dp(4);
+    }
+
+    let (mut accumulators, mut distributors): (Vec<WalletFlow>, Vec<WalletFlow>) = flows
+        .into_iter()
+        .filter(|f| !f.net().is_zero())
+        .partition(|f| f.net() > Decimal::ZERO);
+    accumulators.sort_by(|a, b| b.net().cmp(&a.net()).then_with(|| a.wallet.cmp(&b.wallet)));
+    distributors.sort_by(|a, b| a.net().cmp(&b.net()).then_with(|| a.wallet.cmp(&b.wallet)));
+    accumulators.truncate(top);
+    distributors.truncate(top);
+    result.top_accumulators = accumulators;
+    result.top_distributors = distributors;
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn flow(wallet: &str, bought: Decimal, sold: Decimal, classification: &str, top_performer: bool) -> WalletFlow {
+        WalletFlow {
+            wallet: wallet.to_string(),
+            bought,
+            sold,
+            buy_count: u64::from(bought > Decimal::ZERO),
+            sell_count: u64::from(sold > Decimal::ZERO),
+            classification: classification.to_string(),
+            top_performer,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_flows() {
+        let flows = vec![
+            flow("whale", dec!(5000), dec!(0), "whale", false),
+            flow("pro", dec!(2000), dec!(500), "trader", true),
+            flow("retail", dec!(1000), dec!(1000), "retail", false),
+            flow("dumper", dec!(0), dec!(2500), "retail", false),
+        ];
+
+        let result = aggregate_flows(flows, 2);
+        assert_eq!(result.buy_volume, dec!(8000));
+        assert_eq!(result.sell_volume, dec!(4000));
+        assert_eq!(result.net_volume(), dec!(4000));
+        assert_eq!(result.unique_buyers, 3);
+        assert_eq!(result.unique_sellers, 3);
+
+        assert_eq!(result.smart_money.wallet_count, 2);
+        assert_eq!(result.smart_money.buy_share, dec!(0.875));
+        assert_eq!(result.smart_money.sell_share, dec!(0.125));
+
+        // Flat wallets are neither accumulating nor distributing
+        let accumulators: Vec<&str> = result.top_accumulators.iter().map(|f| f.wallet.as_str()).collect();
+        assert_eq!(accumulators, vec!["whale", "pro"]);
+        assert_eq!(result.top_distributors.len(), 1);
+        assert_eq!(result.top_distributors[0].net(), dec!(-2500));
+    }
+
+    #[test]
+    fn test_no_flows() {
+        let result = aggregate_flows(Vec::new(), 10);
+        assert_eq!(result.unique_buyers, 0);
+        assert!(result.smart_money.buy_share.is_zero());
+        assert!(result.top_accumulators.is_empty());
+    }
+}
@@ -0,0 +1,300 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+use crate::indexer::parser::ParsedTransaction;
+use crate::types::{Protocol, TransactionType};
+
+/// Jito block-engine tip accounts; a transfer to any of them means the transaction
+/// was submitted as part of a bundle.
+pub const JITO_TIP_ACCOUNTS: &[&str] = &[
+    "96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5",
+    "HFqU5x63VTqvQss8hp11i4wVV8bD44PvwucfZ2bU7gRe",
+    "Cw8CFyM9FkoMi7K7Crf6HNQqf4uEMzpKw6QNghXLvLkY",
+    "ADaUMid9yfUytqMBgopwjb2DTLSokTSzL1zt6iGPaS49",
+    "DfXygSm4jCyNCybVYYK6DwvWqjKee8pbDmJGcLWNDXjh",
+    "ADuUkR4vqLUMWXxW9gh6D6L8pMSawimctcNZ5pGwDcEt",
+    "DttWaMuVvTiduZRnguLF7jNxTgiMBZ1hyAumKUiL2KRL",
+    "3AVi9Tg9Uo68tJfuvoKvqKNWKkC5wPdSSdeBnizKZ6jT",
+];
+
+/// A buy this many slots (~400ms each) after a pool was created counts as a snipe
+pub const SNIPE_MAX_SLOTS: u64 = 2;
+
+/// Behaviour flagged on a single transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum MevFlag {
+    /// Front- and back-run in the same slot by another signer
+    Sandwiched,
+    /// Bought and sold the same pair within one slot
+    SameSlotRoundTrip,
+    /// Paid a Jito bundle tip
+    JitoTip,
+    /// Bought a token within `SNIPE_MAX_SLOTS` of its pool being created
+    Snipe,
+}
+
+impl fmt::Display for MevFlag {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MevFlag::Sandwiched => write!(f, "sandwiched"),
+            MevFlag::SameSlotRoundTrip => write!(f, "same_slot_round_trip"),
+            MevFlag::JitoTip => write!(f, "jito_tip"),
+            MevFlag::Snipe => write!(f, "snipe"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TransactionFlag {
+    pub signature: String,
+    pub flag: MevFlag,
+    /// Attacker for sandwiches, tip account for Jito tips, mint for snipes
+    pub counterparty: String,
+    /// USD lost to the attacker (sandwiches only)
+    pub estimated_loss: Decimal,
+    pub slot: u64,
+    pub block_time: i64,
+}
+
+/// Per-wallet totals of the flags above
+#[derive(Debug, Clone, Default)]
+pub struct MevSummary {
+    pub mev_loss_usd: Decimal,
+    pub sandwiched_count: u32,
+    pub round_trip_count: u32,
+    pub jito_tip_count: u32,
+    pub snipe_count: u32,
+}
+
+impl MevSummary {
+    pub fn from_flags(flags: &[TransactionFlag]) -> Self {
+        let mut summary = Self::default();
+        for flag in flags {
+            match flag.flag {
+                MevFlag::Sandwiched => {
+                    summary.sandwiched_count += 1;
+                    summary.mev_loss_usd += flag.estimated_loss;
+                }
+                MevFlag::SameSlotRoundTrip => summary.round_trip_count += 1,
+                MevFlag::JitoTip => summary.jito_tip_count += 1,
+                MevFlag::Snipe => summary.snipe_count += 1,
+            }
+        }
+        summary
+    }
+}
+
+/// Whether any of a transaction's accounts is a Jito tip account
+pub fn jito_tip_account(accounts: &[String]) -> Option<&str> {
+    accounts
+        .iter()
+        .map(String::as_str)
+        .find(|account| JITO_TIP_ACCOUNTS.contains(account))
+}
+
+/// Whether a stream event type announces a new pool or token launch
+pub fn is_pool_creation(event_type: &str) -> bool {
+    matches!(
+        event_type.to_uppercase().as_str(),
+        "CREATE_POOL" | "INITIALIZE_POOL" | "CREATE"
+    )
+}
+
+/// Find the wallet's swaps that were sandwiched by another signer.
+///
+/// Only indexed transactions are visible and intra-slot ordering isn't stored, so a
+/// sandwich is an attacker who swapped the victim's pair on the same protocol in both
+/// directions within the victim's slot, with one leg matching the victim's direction.
+/// The attacker's round-trip profit in the victim's input token is the estimated loss,
+/// split evenly when several victims share one attack.
+pub fn detect_sandwiches(wallet_swaps: &[ParsedTransaction], slot_swaps: &[ParsedTransaction]) -> Vec<TransactionFlag> {
+    // (slot, protocol) -> attacker -> swaps
+    let mut by_slot: HashMap<(u64, Protocol), HashMap<&str, Vec<&ParsedTransaction>>> = HashMap::new();
+    for swap in slot_swaps.iter().filter(|s| s.tx_type == TransactionType::Swap) {
+        by_slot
+            .entry((swap.slot, swap.protocol))
+            .or_default()
+            .entry(swap.wallet.as_str())
+            .or_default()
+            .push(swap);
+    }
+
+    let mut candidates: Vec<(&ParsedTransaction, &str, Decimal)> = Vec::new();
+    for victim in wallet_swaps.iter().filter(|s| s.tx_type == TransactionType::Swap) {
+        let Some(signers) = by_slot.get(&(victim.slot, victim.protocol)) else {
+            continue;
+        };
+        for (attacker, swaps) in signers {
+            if *attacker == victim.wallet {
+                continue;
+            }
+            let front = swaps
+                .iter()
+                .find(|s| s.token_in == victim.token_in && s.token_out == victim.token_out);
+            let back = swaps
+                .iter()
+                .find(|s| s.token_in == victim.token_out && s.token_out == victim.token_in);
+            if let (Some(front), Some(back)) = (front, back) {
+                // Profit in the victim's input token, priced at the victim's fill
+                let profit = (back.amount_out - front.amount_in).max(Decimal::ZERO);
+                let price = if victim.amount_in.is_zero() {
+                    Decimal::ZERO
+                } else {
+                    victim.usd_value / victim.amount_in
+                };
+                candidates.push((victim, *attacker, profit * price));
+            }
+        }
+    }
+
+    let mut victims_per_attack: HashMap<(u64, &str), u32> = HashMap::new();
+    for (victim, attacker, _) in &candidates {
+        *victims_per_attack.entry((victim.slot, *attacker)).or_default() += 1;
+    }
+
+    candidates
+        .into_iter()
+        .map(|(victim, attacker, loss)| {
+            let shared = victims_per_attack.get(&(victim.slot, attacker)).copied().unwrap_or(1);
+            TransactionFlag {
+                signature: victim.signature.clone(),
+                flag: MevFlag::Sandwiched,
+                counterparty: attacker.to_string(),
+                estimated_loss: (loss / Decimal::from(shared)).round_dp(2),
+                slot: victim.slot,
+                block_time: victim.block_time,
+            }
+        })
+        .collect()
+}
+
+/// Flag swaps where the wallet bought and sold the same pair within one slot,
+/// the signature of atomic arbitrage.
+pub fn detect_round_trips(wallet_swaps: &[ParsedTransaction]) -> Vec<TransactionFlag> {
+    let mut by_slot: HashMap<u64, Vec<&ParsedTransaction>> = HashMap::new();
+    for swap in wallet_swaps.iter().filter(|s| s.tx_type == TransactionType::Swap) {
+        by_slot.entry(swap.slot).or_default().push(swap);
+    }
+
+    let mut flagged: HashSet<&str> = HashSet::new();
+    let mut flags = Vec::new();
+    for swaps in by_slot.values() {
+        for swap in swaps {
+            let reversed = swaps
+                .iter()
+                .any(|other| other.token_in == swap.token_out && other.token_out == swap.token_in);
+            if reversed && flagged.insert(swap.signature.as_str()) {
+                flags.push(TransactionFlag {
+                    signature: swap.signature.clone(),
+                    flag: MevFlag::SameSlotRoundTrip,
+                    counterparty: String::new(),
+                    estimated_loss: Decimal::ZERO,
+                    slot: swap.slot,
+                    block_time: swap.block_time,
+                });
+            }
+        }
+    }
+    flags
+}
+
+/// Flag buys that landed within `SNIPE_MAX_SLOTS` of the token's pool creation.
+/// `launches` maps mint to the slot it was first seen being created.
+pub fn detect_snipes(wallet_swaps: &[ParsedTransaction], launches: &HashMap<String, u64>) -> Vec<TransactionFlag> {
+    wallet_swaps
+        .iter()
+        .filter(|s| s.tx_type == TransactionType::Swap)
+        .filter_map(|swap| {
+            let launch_slot = launches.get(&swap.token_out)?;
+            let delay = swap.slot.checked_sub(*launch_slot)?;
+            (delay <= SNIPE_MAX_SLOTS).then(|| TransactionFlag {
+                signature: swap.signature.clone(),
+                flag: MevFlag::Snipe,
+                counterparty: swap.token_out.clone(),
+                estimated_loss: Decimal::ZERO,
+                slot: swap.slot,
+                block_time: swap.block_time,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[allow(clippy::too_many_arguments)]
+    fn make_swap(
+        signature: &str,
+        wallet: &str,
+        slot: u64,
+        token_in: &str,
+        amount_in: Decimal,
+        token_out: &str,
+        amount_out: Decimal,
+        usd_value: Decimal,
+    ) -> ParsedTransaction {
+        ParsedTransaction {
+            signature: signature.to_string(),
+            wallet: wallet.to_string(),
+            protocol: Protocol::Raydium,
+            tx_type: TransactionType::Swap,
+            token_in: token_in.to_string(),
+            token_out: token_out.to_string(),
+            amount_in,
+            amount_out,
+            usd_value,
+            block_time: 0,
+            slot,
+        }
+    }
+
+    #[test]
+    fn test_detect_sandwich() {
+        let victim = vec![make_swap("v", "victim", 10, "SOL", dec!(10), "BONK", dec!(900), dec!(1500))];
+        let others = vec![
+            make_swap("f", "attacker", 10, "SOL", dec!(50), "BONK", dec!(5000), Decimal::ZERO),
+            make_swap("b", "attacker", 10, "BONK", dec!(5000), "SOL", dec!(50.4), Decimal::ZERO),
+            // Unrelated signer trading the pair one way only
+            make_swap("x", "other", 10, "SOL", dec!(1), "BONK", dec!(90), Decimal::ZERO),
+        ];
+
+        let flags = detect_sandwiches(&victim, &others);
+        assert_eq!(flags.len(), 1);
+        assert_eq!(flags[0].counterparty, "attacker");
+        // 0.4 SOL profit at the victim's $150 fill
+        assert_eq!(flags[0].estimated_loss, dec!(60));
+    }
+
+    #[test]
+    fn test_no_sandwich_across_slots() {
+        let victim = vec![make_swap("v", "victim", 10, "SOL", dec!(10), "BONK", dec!(900), dec!(1500))];
+        let others = vec![
+            make_swap("f", "attacker", 9, "SOL", dec!(50), "BONK", dec!(5000), Decimal::ZERO),
+            make_swap("b", "attacker", 11, "BONK", dec!(5000), "SOL", dec!(51), Decimal::ZERO),
+        ];
+        assert!(detect_sandwiches(&victim, &others).is_empty());
+    }
+
+    #[test]
+    fn test_round_trips_and_snipes() {
+        let swaps = vec![
+            make_swap("a", "bot", 5, "SOL", dec!(1), "WIF", dec!(10), dec!(150)),
+            make_swap("b", "bot", 5, "WIF", dec!(10), "SOL", dec!(1.01), dec!(151)),
+            make_swap("c", "bot", 8, "SOL", dec!(1), "NEWpump", dec!(1000), dec!(150)),
+        ];
+        assert_eq!(detect_round_trips(&swaps).len(), 2);
+
+        let launches = HashMap::from([("NEWpump".to_string(), 7)]);
+        let snipes = detect_snipes(&swaps, &launches);
+        assert_eq!(snipes.len(), 1);
+        assert_eq!(snipes[0].signature, "c");
+
+        let summary = MevSummary::from_flags(&[detect_round_trips(&swaps), snipes].concat());
+        assert_eq!(summary.round_trip_count, 2);
+        assert_eq!(summary.snipe_count, 1);
+    }
+}
@@ -1,5 +1,15 @@
+pub mod behaviour;
+pub mod cluster;
+pub mod flows;
+pub mod mev;
+pub mod performance;
 pub mod pnl;
 pub mod risk;
+pub mod signals;
+pub mod volatility;
 
+pub use behaviour::extract_features;
+pub use performance::compute_performance;
 pub use pnl::compute_pnl;
 pub use risk::compute_risk;
+pub use volatility::compute_var;
@@ -0,0 +1,207 @@
+use std::collections::BTreeMap;
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use super::pnl::{closed_trades, ClosedTrade};
+use crate::indexer::parser::ParsedTransaction;
+use crate::types::TimeWindow;
+
+const MILLIS_PER_DAY: i64 = 86_400_000;
+const TRADING_DAYS_PER_YEAR: f64 = 365.0;
+
+#[derive(Debug, Clone, Default)]
+pub struct PerformanceMetrics {
+    pub trade_count: u64,
+    pub win_rate: Decimal,
+    pub avg_win: Decimal,
+    pub avg_loss: Decimal,
+    /// Gross profit over gross loss; `None` when there were no losing trades
+    pub profit_factor: Option<Decimal>,
+    pub realized_pnl: Decimal,
+    /// Annualized, on daily returns (PnL over cost basis sold that day)
+    pub sharpe_ratio: Option<f64>,
+    pub sortino_ratio: Option<f64>,
+    /// Largest peak-to-trough drop of cumulative realized PnL, in USD
+    pub max_drawdown: Decimal,
+    /// Time from the drawdown's peak until it was recovered (or the last trade)
+    pub max_drawdown_duration_days: f64,
+}
+
+/// Compute risk-adjusted trading performance for trades closed within `window`.
+///
+/// `transactions` should be the wallet's full history sorted by `block_time`, so that
+/// positions opened before the window still have a cost basis.
+pub fn compute_performance(transactions: &[ParsedTransaction], window: TimeWindow) -> PerformanceMetrics {
+    let cutoff = window.cutoff_millis();
+    let trades: Vec<ClosedTrade> = closed_trades(transactions)
+        .into_iter()
+        .filter(|t| cutoff.is_none_or(|c| t.closed_at >= c))
+        .collect();
+
+    if trades.is_empty() {
+        return PerformanceMetrics::default();
+    }
+
+    let wins: Vec<Decimal> = trades.iter().map(|t| t.pnl).filter(|p| *p > Decimal::ZERO).collect();
+    let losses: Vec<Decimal> = trades.iter().map(|t| t.pnl).filter(|p| *p < Decimal::ZERO).collect();
+    let gross_profit: Decimal = wins.iter().copied().sum();
+    let gross_loss: Decimal = losses.iter().copied().sum();
+    let trade_count = trades.len() as u64;
+
+    let (max_drawdown, max_drawdown_duration_days) = max_drawdown(&trades);
+    let daily_returns = daily_returns(&trades);
+
+    PerformanceMetrics {
+        trade_count,
+        win_rate: Decimal::from(wins.len() as u64) / Decimal::from(trade_count),
+        avg_win: mean(&wins),
+        avg_loss: mean(&losses),
+        profit_factor: if gross_loss.is_zero() {
+            None
+        } else {
+            Some(gross_profit / gross_loss.abs())
+        },
+        realized_pnl: gross_profit + gross_loss,
+        sharpe_ratio: sharpe_ratio(&daily_returns),
+        sortino_ratio: sortino_ratio(&daily_returns),
+        max_drawdown,
+        max_drawdown_duration_days,
+    }
+}
+
+fn mean(values: &[Decimal]) -> Decimal {
+    if values.is_empty() {
+        return Decimal::ZERO;
+    }
+    values.iter().copied().sum::<Decimal>() / Decimal::from(values.len() as u64)
+}
+
+/// Daily return series from the first to the last trading day, with idle days as zero.
+fn daily_returns(trades: &[ClosedTrade]) -> Vec<f64> {
+    let mut by_day: BTreeMap<i64, (Decimal, Decimal)> = BTreeMap::new();
+    for trade in trades {
+        let entry = by_day.entry(trade.closed_at.div_euclid(MILLIS_PER_DAY)).or_default();
+        entry.0 += trade.pnl;
+        entry.1 += trade.cost_basis;
+    }
+
+    let (Some(first), Some(last)) = (by_day.keys().next().copied(), by_day.keys().last().copied()) else {
+        return Vec::new();
+    };
+
+    (first..=last)
+        .map(|day| match by_day.get(&day) {
+            Some((pnl, cost)) if !cost.is_zero() => (pnl / cost).to_f64().unwrap_or(0.0),
+            _ => 0.0,
+        })
+        .collect()
+}
+
+fn sharpe_ratio(returns: &[f64]) -> Option<f64> {
+    if returns.len() < 2 {
+        return None;
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    let std_dev = variance.sqrt();
+    if std_dev == 0.0 {
+        return None;
+    }
+    Some(mean / std_dev * TRADING_DAYS_PER_YEAR.sqrt())
+}
+
+fn sortino_ratio(returns: &[f64]) -> Option<f64> {
+    if returns.len() < 2 {
+        return None;
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let downside = (returns.iter().map(|r| r.min(0.0).powi(2)).sum::<f64>() / n).sqrt();
+    if downside == 0.0 {
+        return None;
+    }
+    Some(mean / downside * TRADING_DAYS_PER_YEAR.sqrt())
+}
+
+/// Deepest drawdown of the cumulative realized PnL curve and how long it lasted.
+fn max_drawdown(trades: &[ClosedTrade]) -> (Decimal, f64) {
+    let mut equity = Decimal::ZERO;
+    let mut peak = Decimal::ZERO;
+    let mut peak_at = trades.first().map(|t| t.closed_at).unwrap_or_default();
+    let mut max_drawdown = Decimal::ZERO;
+    let mut max_duration_ms: i64 = 0;
+    // Peak time of the deepest drawdown while it is still unrecovered
+    let mut open_drawdown_since: Option<i64> = None;
+
+    for trade in trades {
+        equity += trade.pnl;
+        if equity >= peak {
+            if let Some(since) = open_drawdown_since.take() {
+                max_duration_ms = trade.closed_at - since;
+            }
+            peak = equity;
+            peak_at = trade.closed_at;
+        } else if peak - equity > max_drawdown {
+            max_drawdown = peak - equity;
+            open_drawdown_since = Some(peak_at);
+        }
+    }
+
+    if let (Some(since), Some(last)) = (open_drawdown_since, trades.last()) {
+        max_duration_ms = last.closed_at - since;
+    }
+
+    (max_drawdown, max_duration_ms as f64 / MILLIS_PER_DAY as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Protocol;
+    use rust_decimal_macros::dec;
+
+    fn make_trade(pnl: Decimal, cost_basis: Decimal, day: i64) -> ClosedTrade {
+        ClosedTrade {
+            signature: format!("sig{day}"),
+            token: "BONK".to_string(),
+            protocol: Protocol::Jupiter,
+            cost_basis,
+            pnl,
+            closed_at: day * MILLIS_PER_DAY,
+        }
+    }
+
+    #[test]
+    fn test_no_trades() {
+        let perf = compute_performance(&[], TimeWindow::All);
+        assert_eq!(perf.trade_count, 0);
+        assert!(perf.sharpe_ratio.is_none());
+    }
+
+    #[test]
+    fn test_max_drawdown_recovered() {
+        let trades = vec![
+            make_trade(dec!(100), dec!(100), 0),
+            make_trade(dec!(-60), dec!(100), 1),
+            make_trade(dec!(-20), dec!(100), 2),
+            make_trade(dec!(90), dec!(100), 4),
+        ];
+        let (drawdown, days) = max_drawdown(&trades);
+        assert_eq!(drawdown, dec!(80));
+        assert_eq!(days, 4.0);
+    }
+
+    #[test]
+    fn test_daily_returns_fill_idle_days() {
+        let trades = vec![make_trade(dec!(10), dec!(100), 0), make_trade(dec!(-5), dec!(50), 2)];
+        assert_eq!(daily_returns(&trades), vec![0.1, 0.0, -0.1]);
+    }
+
+    #[test]
+    fn test_sortino_ignores_upside_volatility() {
+        assert!(sortino_ratio(&[0.1, 0.2, 0.3]).is_none());
+        assert!(sharpe_ratio(&[0.1, 0.2, 0.3]).unwrap() > 0.0);
+    }
+}
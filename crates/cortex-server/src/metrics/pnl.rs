@@ -1,56 +1,28 @@
 use std::collections::HashMap;
 
-use chrono::{Duration, Utc};
+use chrono::{DateTime, Months};
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
 use crate::indexer::parser::ParsedTransaction;
-use crate::types::TransactionType;
+use crate::tokens::is_stablecoin;
+use crate::types::{LotMethod, Protocol, TransactionType};
 
 #[derive(Debug, Clone, Default)]
 pub struct PnlMetrics {
     pub total_value: Decimal,
-    pub realized_24h: Decimal,
-    pub realized_7d: Decimal,
-    pub realized_30d: Decimal,
     pub unrealized: Decimal,
 }
 
+/// Current value and unrealized PnL from open positions. Realized PnL windows are
+/// aggregated in ClickHouse (`wallet_protocol_daily`), not here.
 pub fn compute_pnl(transactions: &[ParsedTransaction]) -> PnlMetrics {
-    let now = Utc::now().timestamp_millis();
-    let day_ago = now - Duration::days(1).num_milliseconds();
-    let week_ago = now - Duration::days(7).num_milliseconds();
-    let month_ago = now - Duration::days(30).num_milliseconds();
-
     let mut metrics = PnlMetrics::default();
 
     // Track token positions for unrealized PnL
     let mut token_positions: HashMap<String, TokenPosition> = HashMap::new();
 
     for tx in transactions {
-        let is_realized = matches!(
-            tx.tx_type,
-            TransactionType::Swap
-                | TransactionType::Withdraw
-                | TransactionType::RemoveLiquidity
-        );
-
-        if is_realized {
-            // For realized trades, we calculate PnL as output - input (simplified)
-            let pnl = tx.usd_value; // USD value represents the trade value
-
-            // Add to time-windowed realized PnL
-            if tx.block_time >= day_ago {
-                metrics.realized_24h += pnl;
-            }
-            if tx.block_time >= week_ago {
-                metrics.realized_7d += pnl;
-            }
-            if tx.block_time >= month_ago {
-                metrics.realized_30d += pnl;
-            }
-        }
-
         // Track positions for unrealized PnL
         match tx.tx_type {
             TransactionType::Deposit | TransactionType::AddLiquidity => {
@@ -104,6 +76,188 @@ struct TokenPosition {
     cost_basis: Decimal,
 }
 
+/// A swap that disposed of a token the wallet had previously acquired.
+#[derive(Debug, Clone)]
+pub struct ClosedTrade {
+    pub signature: String,
+    /// Token sold
+    pub token: String,
+    pub protocol: Protocol,
+    pub cost_basis: Decimal,
+    pub pnl: Decimal,
+    pub closed_at: i64,
+}
+
+/// Match each swap's input against the average cost of that token acquired earlier
+/// in `transactions` (which must be sorted by `block_time`).
+///
+/// Stablecoin disposals are treated as moving cash, not closing a trade. When a swap
+/// sells more than the indexed history accounts for, only the covered portion counts.
+pub fn closed_trades(transactions: &[ParsedTransaction]) -> Vec<ClosedTrade> {
+    let mut trades: Vec<(usize, ClosedTrade)> = Vec::new();
+
+    // A swap that spans several lots is still a single trade
+    for (tx_index, disposal) in match_lots(transactions, LotMethod::AverageCost) {
+        match trades.last_mut() {
+            Some((index, trade)) if *index == tx_index => {
+                trade.cost_basis += disposal.cost_basis;
+                trade.pnl += disposal.gain;
+            }
+            _ => trades.push((
+                tx_index,
+                ClosedTrade {
+                    protocol: transactions[tx_index].protocol,
+                    signature: disposal.signature,
+                    token: disposal.token,
+                    cost_basis: disposal.cost_basis,
+                    pnl: disposal.gain,
+                    closed_at: disposal.disposed_at,
+                },
+            )),
+        }
+    }
+
+    trades.into_iter().map(|(_, trade)| trade).collect()
+}
+
+/// Part of a swap's input matched against one acquisition lot.
+#[derive(Debug, Clone)]
+pub struct Disposal {
+    pub token: String,
+    pub amount: Decimal,
+    pub acquired_at: i64,
+    pub disposed_at: i64,
+    pub proceeds: Decimal,
+    pub cost_basis: Decimal,
+    pub gain: Decimal,
+    pub signature: String,
+}
+
+impl Disposal {
+    /// Held for more than one year (calendar), the usual long-term threshold
+    pub fn is_long_term(&self) -> bool {
+        let (Some(acquired), Some(disposed)) = (
+            DateTime::from_timestamp_millis(self.acquired_at),
+            DateTime::from_timestamp_millis(self.disposed_at),
+        ) else {
+            return false;
+        };
+        acquired
+            .checked_add_months(Months::new(12))
+            .is_some_and(|anniversary| disposed > anniversary)
+    }
+}
+
+/// Per-lot disposals from swaps, with cost basis assigned by `method`.
+///
+/// Follows the same rules as [`closed_trades`]: `transactions` sorted by `block_time`,
+/// stablecoin disposals skipped, and uncovered amounts left out.
+pub fn disposals(transactions: &[ParsedTransaction], method: LotMethod) -> Vec<Disposal> {
+    match_lots(transactions, method)
+        .into_iter()
+        .map(|(_, disposal)| disposal)
+        .collect()
+}
+
+#[derive(Debug, Clone)]
+struct Lot {
+    amount: Decimal,
+    cost_basis: Decimal,
+    acquired_at: i64,
+}
+
+/// Disposals paired with the index of the transaction that produced them
+fn match_lots(transactions: &[ParsedTransaction], method: LotMethod) -> Vec<(usize, Disposal)> {
+    let mut holdings: HashMap<String, Vec<Lot>> = HashMap::new();
+    let mut disposals = Vec::new();
+
+    for (tx_index, tx) in transactions.iter().enumerate() {
+        if tx.tx_type != TransactionType::Swap {
+            continue;
+        }
+
+        if !tx.token_in.is_empty() && !tx.amount_in.is_zero() {
+            if let Some(lots) = holdings.get_mut(&tx.token_in) {
+                let mut remaining = tx.amount_in;
+                while remaining > Decimal::ZERO {
+                    let Some(index) = next_lot(lots, method) else {
+                        break;
+                    };
+                    let lot = &mut lots[index];
+                    let matched = remaining.min(lot.amount);
+                    let cost_basis = lot.cost_basis * matched / lot.amount;
+
+                    if !is_stablecoin(&tx.token_in) {
+                        let proceeds = tx.usd_value * matched / tx.amount_in;
+                        disposals.push((
+                            tx_index,
+                            Disposal {
+                                token: tx.token_in.clone(),
+                                amount: matched,
+                                acquired_at: lot.acquired_at,
+                                disposed_at: tx.block_time,
+                                proceeds,
+                                cost_basis,
+                                gain: proceeds - cost_basis,
+                                signature: tx.signature.clone(),
+                            },
+                        ));
+                    }
+
+                    lot.cost_basis -= cost_basis;
+                    lot.amount -= matched;
+                    remaining -= matched;
+                    if lot.amount.is_zero() {
+                        lots.remove(index);
+                    }
+                }
+            }
+        }
+
+        if !tx.token_out.is_empty() && !tx.amount_out.is_zero() {
+            let lots = holdings.entry(tx.token_out.clone()).or_default();
+            lots.push(Lot {
+                amount: tx.amount_out,
+                cost_basis: tx.usd_value,
+                acquired_at: tx.block_time,
+            });
+            if method == LotMethod::AverageCost {
+                pool_cost_basis(lots);
+            }
+        }
+    }
+
+    disposals
+}
+
+/// Index of the lot a disposal draws from next. Lots are kept in acquisition order.
+fn next_lot(lots: &[Lot], method: LotMethod) -> Option<usize> {
+    if lots.is_empty() {
+        return None;
+    }
+    match method {
+        LotMethod::Fifo | LotMethod::AverageCost => Some(0),
+        LotMethod::Lifo => Some(lots.len() - 1),
+        LotMethod::Hifo => lots
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| (a.cost_basis / a.amount).cmp(&(b.cost_basis / b.amount)))
+            .map(|(index, _)| index),
+    }
+}
+
+/// Give every lot the pool's average unit cost, keeping the total basis unchanged.
+fn pool_cost_basis(lots: &mut [Lot]) {
+    let amount: Decimal = lots.iter().map(|l| l.amount).sum();
+    let cost_basis: Decimal = lots.iter().map(|l| l.cost_basis).sum();
+    if amount.is_zero() {
+        return;
+    }
+    for lot in lots {
+        lot.cost_basis = cost_basis * lot.amount / amount;
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -135,6 +289,86 @@ mod tests {
     fn test_empty_transactions() {
         let pnl = compute_pnl(&[]);
         assert_eq!(pnl.total_value, Decimal::ZERO);
-        assert_eq!(pnl.realized_24h, Decimal::ZERO);
+        assert_eq!(pnl.unrealized, Decimal::ZERO);
+    }
+
+    fn make_swap(
+        token_in: &str,
+        amount_in: Decimal,
+        token_out: &str,
+        amount_out: Decimal,
+        usd_value: Decimal,
+        timestamp: i64,
+    ) -> ParsedTransaction {
+        ParsedTransaction {
+            token_out: token_out.to_string(),
+            amount_out,
+            ..make_tx(TransactionType::Swap, token_in, amount_in, usd_value, timestamp)
+        }
+    }
+
+    #[test]
+    fn test_closed_trades_average_cost() {
+        let usdc = crate::tokens::USDC_MINT;
+        let txs = vec![
+            make_swap(usdc, dec!(100), "BONK", dec!(10), dec!(100), 1),
+            make_swap(usdc, dec!(300), "BONK", dec!(10), dec!(300), 2),
+            // Sell half the bag at $25 each: basis is the $20 average
+            make_swap("BONK", dec!(10), usdc, dec!(250), dec!(250), 3),
+        ];
+
+        let trades = closed_trades(&txs);
+        assert_eq!(trades.len(), 1, "Stablecoin buys should not count as trades");
+        assert_eq!(trades[0].cost_basis, dec!(200));
+        assert_eq!(trades[0].pnl, dec!(50));
+    }
+
+    #[test]
+    fn test_closed_trades_ignores_unknown_basis() {
+        let txs = vec![make_swap("BONK", dec!(10), crate::tokens::USDC_MINT, dec!(50), dec!(50), 1)];
+        assert!(closed_trades(&txs).is_empty());
+    }
+
+    #[test]
+    fn test_disposals_lot_methods() {
+        let usdc = crate::tokens::USDC_MINT;
+        let txs = vec![
+            make_swap(usdc, dec!(100), "BONK", dec!(10), dec!(100), 1),
+            make_swap(usdc, dec!(300), "BONK", dec!(10), dec!(300), 2),
+            make_swap("BONK", dec!(15), usdc, dec!(375), dec!(375), 3),
+        ];
+
+        let fifo = disposals(&txs, LotMethod::Fifo);
+        assert_eq!(fifo.len(), 2, "A sale spanning two lots produces two disposals");
+        assert_eq!(fifo[0].cost_basis, dec!(100));
+        assert_eq!(fifo[1].cost_basis, dec!(150));
+        assert_eq!(fifo[1].acquired_at, 2);
+
+        let lifo = disposals(&txs, LotMethod::Lifo);
+        assert_eq!(lifo[0].cost_basis, dec!(300));
+        assert_eq!(lifo[1].cost_basis, dec!(50));
+
+        let average: Decimal = disposals(&txs, LotMethod::AverageCost).iter().map(|d| d.cost_basis).sum();
+        assert_eq!(average, dec!(300));
+        assert_eq!(closed_trades(&txs).len(), 1);
+    }
+
+    #[test]
+    fn test_disposal_holding_period() {
+        let acquired = DateTime::parse_from_rfc3339("2024-03-01T00:00:00Z").unwrap().timestamp_millis();
+        let mut disposal = Disposal {
+            token: "BONK".to_string(),
+            amount: dec!(1),
+            acquired_at: acquired,
+            disposed_at: DateTime::parse_from_rfc3339("2025-03-01T00:00:00Z").unwrap().timestamp_millis(),
+            proceeds: dec!(2),
+            cost_basis: dec!(1),
+            gain: dec!(1),
+            signature: "test".to_string(),
+        };
+        assert!(!disposal.is_long_term(), "Exactly one year is still short-term");
+
+        disposal.disposed_at += 1;
+        assert!(disposal.is_long_term());
     }
 }
@@ -1,17 +1,58 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeSet, HashMap, HashSet};
 
+use rust_decimal::prelude::ToPrimitive;
 use rust_decimal::Decimal;
 use rust_decimal_macros::dec;
 
 use crate::indexer::parser::ParsedTransaction;
-use crate::types::{Protocol, TransactionType};
+use crate::tokens::{classify_token, underlying_asset, TokenClass, LP_TOKEN};
+use crate::types::{ContractRiskTier, Protocol, TransactionType};
 
 #[derive(Debug, Clone, Default)]
 pub struct RiskMetrics {
     pub score: u8,
+    /// Share of position value in the largest underlying asset
     pub largest_position_pct: Decimal,
     pub position_count: u16,
+    /// Distinct underlying assets across open positions
+    pub asset_count: u16,
     pub protocol_concentration: Decimal,
+    /// Borrowed value over lending collateral value
+    pub leverage: Decimal,
+    /// Fraction collateral can fall before liquidation (1 when nothing is borrowed)
+    pub liquidation_distance: Decimal,
+    /// Borrowed over supplied value on each lending protocol the wallet has used
+    pub protocol_ltv: HashMap<Protocol, Decimal>,
+    pub stablecoin_pct: Decimal,
+    pub memecoin_pct: Decimal,
+    pub components: RiskComponents,
+    /// USD exposure per token: open positions minus debt in that token, with liquidity
+    /// positions split across their pool's tokens
+    pub net_exposure: HashMap<String, Decimal>,
+    /// USD exposure per underlying asset, net of debt
+    pub asset_exposure: HashMap<String, Decimal>,
+}
+
+/// Per-dimension risk scores (0-100 each) that are blended into `RiskMetrics::score`
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RiskComponents {
+    pub concentration: u8,
+    pub leverage: u8,
+    pub liquidation: u8,
+    pub asset: u8,
+    pub contract: u8,
+}
+
+impl RiskComponents {
+    /// Weighted blend of the components into a single 0-100 score
+    pub fn blended_score(&self) -> u8 {
+        let weighted = u32::from(self.concentration) * 35
+            + u32::from(self.leverage) * 15
+            + u32::from(self.liquidation) * 20
+            + u32::from(self.asset) * 20
+            + u32::from(self.contract) * 10;
+        (weighted / 100).min(100) as u8
+    }
 }
 
 pub fn compute_risk(transactions: &[ParsedTransaction]) -> RiskMetrics {
@@ -19,22 +60,51 @@ pub fn compute_risk(transactions: &[ParsedTransaction]) -> RiskMetrics {
         return RiskMetrics::default();
     }
 
-    // Track positions by token/protocol
+    // Track positions by token/protocol, with lending supply and debt kept separately
     let mut positions: HashMap<(String, Protocol), Decimal> = HashMap::new();
+    let mut collateral: HashMap<String, Decimal> = HashMap::new();
+    let mut debt: HashMap<String, Decimal> = HashMap::new();
+    // Supplied and borrowed value per lending protocol
+    let mut lending: HashMap<Protocol, (Decimal, Decimal)> = HashMap::new();
     let mut protocols: HashSet<Protocol> = HashSet::new();
+    // Positions that are legs of a liquidity pool
+    let mut liquidity: HashSet<(String, Protocol)> = HashSet::new();
 
     for tx in transactions {
         protocols.insert(tx.protocol);
 
         match tx.tx_type {
-            TransactionType::Deposit | TransactionType::AddLiquidity | TransactionType::Borrow => {
-                let key = (tx.token_in.clone(), tx.protocol);
+            TransactionType::Deposit | TransactionType::AddLiquidity => {
+                let key = (position_token(tx).to_string(), tx.protocol);
+                if tx.tx_type == TransactionType::AddLiquidity {
+                    liquidity.insert(key.clone());
+                }
                 *positions.entry(key).or_default() += tx.usd_value;
+                if tx.tx_type == TransactionType::Deposit {
+                    *collateral.entry(position_token(tx).to_string()).or_default() += tx.usd_value;
+                    lending.entry(tx.protocol).or_default().0 += tx.usd_value;
+                }
             }
-            TransactionType::Withdraw | TransactionType::RemoveLiquidity | TransactionType::Repay => {
-                let key = (tx.token_out.clone(), tx.protocol);
+            TransactionType::Withdraw | TransactionType::RemoveLiquidity => {
+                let key = (position_token(tx).to_string(), tx.protocol);
                 let pos = positions.entry(key).or_default();
                 *pos = (*pos - tx.usd_value).max(Decimal::ZERO);
+                if tx.tx_type == TransactionType::Withdraw {
+                    let supplied = collateral.entry(position_token(tx).to_string()).or_default();
+                    *supplied = (*supplied - tx.usd_value).max(Decimal::ZERO);
+                    let (supplied, _) = lending.entry(tx.protocol).or_default();
+                    *supplied = (*supplied - tx.usd_value).max(Decimal::ZERO);
+                }
+            }
+            TransactionType::Borrow => {
+                *debt.entry(position_token(tx).to_string()).or_default() += tx.usd_value;
+                lending.entry(tx.protocol).or_default().1 += tx.usd_value;
+            }
+            TransactionType::Repay => {
+                let owed = debt.entry(position_token(tx).to_string()).or_default();
+                *owed = (*owed - tx.usd_value).max(Decimal::ZERO);
+                let (_, owed) = lending.entry(tx.protocol).or_default();
+                *owed = (*owed - tx.usd_value).max(Decimal::ZERO);
             }
             TransactionType::Swap => {
                 // Swaps don't directly create positions, but indicate activity
@@ -46,10 +116,39 @@ pub fn compute_risk(transactions: &[ParsedTransaction]) -> RiskMetrics {
     let total_value: Decimal = positions.values().copied().sum();
     let position_count = positions.len() as u16;
 
-    // Largest position percentage
-    let largest_position = positions.values().copied().max().unwrap_or_default();
+    // Exposure per token. A pool holds its tokens in equal value, so a liquidity position
+    // is exposed to each by its share of the pool: half each for a pair. Legs are recorded
+    // without their pool, so the open legs on one protocol are taken as one pool.
+    let mut token_exposure: HashMap<&str, Decimal> = HashMap::new();
+    let mut pools: HashMap<Protocol, (Decimal, BTreeSet<&str>)> = HashMap::new();
+    for ((token, protocol), value) in &positions {
+        if liquidity.contains(&(token.clone(), *protocol)) {
+            let (pool_value, legs) = pools.entry(*protocol).or_default();
+            *pool_value += value;
+            if *value > Decimal::ZERO {
+                legs.insert(token);
+            }
+        } else {
+            *token_exposure.entry(token).or_default() += value;
+        }
+    }
+    for (pool_value, legs) in pools.values().filter(|(_, legs)| !legs.is_empty()) {
+        let share = pool_value / Decimal::from(legs.len());
+        for leg in legs {
+            *token_exposure.entry(leg).or_default() += share;
+        }
+    }
+
+    // Largest position percentage, measured on underlying assets so that wrapped and
+    // staked variants of the same asset don't look diversified
+    let mut long_exposure: HashMap<&str, Decimal> = HashMap::new();
+    for (token, value) in &token_exposure {
+        *long_exposure.entry(underlying_asset(token)).or_default() += value;
+    }
+    let asset_count = long_exposure.values().filter(|v| **v > Decimal::ZERO).count() as u16;
+    let largest_position = long_exposure.values().copied().max().unwrap_or_default();
     let largest_position_pct = if total_value > Decimal::ZERO {
-        largest_position / total_value
+        (largest_position / total_value).min(dec!(1))
     } else {
         Decimal::ZERO
     };
@@ -67,43 +166,190 @@ pub fn compute_risk(transactions: &[ParsedTransaction]) -> RiskMetrics {
         Decimal::ZERO
     };
 
-    // Calculate risk score (0-100)
-    let score = calculate_risk_score(
-        largest_position_pct,
-        protocol_concentration,
-        protocols.len(),
-        position_count,
-    );
+    // Leverage and distance to liquidation
+    let collateral_value: Decimal = collateral.values().copied().sum();
+    let debt_value: Decimal = debt.values().copied().sum();
+    let leverage = if collateral_value > Decimal::ZERO {
+        debt_value / collateral_value
+    } else if debt_value > Decimal::ZERO {
+        // Debt with no tracked collateral: treat as fully levered
+        dec!(1)
+    } else {
+        Decimal::ZERO
+    };
+    let protocol_ltv = lending
+        .into_iter()
+        .filter_map(|(protocol, (supplied, owed))| {
+            let ltv = if supplied > Decimal::ZERO {
+                owed / supplied
+            } else if owed > Decimal::ZERO {
+                dec!(1)
+            } else {
+                return None;
+            };
+            Some((protocol, ltv))
+        })
+        .collect();
+    let liquidation_ltv = weighted_liquidation_ltv(&collateral);
+    let liquidation_distance = if debt_value.is_zero() {
+        dec!(1)
+    } else {
+        (dec!(1) - leverage / liquidation_ltv).clamp(Decimal::ZERO, dec!(1))
+    };
+
+    // Asset mix by volatility class
+    let mut class_values: HashMap<TokenClass, Decimal> = HashMap::new();
+    for ((token, _), value) in &positions {
+        *class_values.entry(classify_token(token)).or_default() += value;
+    }
+    let share = |class: TokenClass| {
+        if total_value > Decimal::ZERO {
+            class_values.get(&class).copied().unwrap_or_default() / total_value
+        } else {
+            Decimal::ZERO
+        }
+    };
+    let stablecoin_pct = share(TokenClass::Stablecoin);
+    let memecoin_pct = share(TokenClass::Memecoin);
+
+    // Smart-contract exposure, weighted by value held in each protocol
+    let contract_exposure = if total_value > Decimal::ZERO {
+        protocol_values
+            .iter()
+            .map(|(protocol, value)| contract_tier_weight(protocol.risk_tier()) * value)
+            .sum::<Decimal>()
+            / total_value
+    } else {
+        // Activity but no open positions: fall back to the riskiest protocol touched
+        protocols
+            .iter()
+            .map(|p| contract_tier_weight(p.risk_tier()))
+            .max()
+            .unwrap_or_default()
+    };
+
+    let components = RiskComponents {
+        concentration: calculate_risk_score(
+            largest_position_pct,
+            protocol_concentration,
+            protocols.len(),
+            asset_count,
+        ),
+        leverage: to_score(leverage * dec!(100)),
+        liquidation: liquidation_score(debt_value, liquidation_distance),
+        asset: to_score(
+            (memecoin_pct + (share(TokenClass::Other) + share(TokenClass::Major)) / dec!(2)) * dec!(100),
+        ),
+        contract: to_score(contract_exposure * dec!(100)),
+    };
+
+    let mut net_exposure: HashMap<String, Decimal> = token_exposure
+        .iter()
+        .map(|(token, value)| (token.to_string(), *value))
+        .collect();
+    for (token, owed) in &debt {
+        *net_exposure.entry(token.clone()).or_default() -= owed;
+    }
+    net_exposure.retain(|_, value| !value.is_zero());
+
+    let mut asset_exposure: HashMap<String, Decimal> = long_exposure
+        .into_iter()
+        .map(|(asset, value)| (asset.to_string(), value))
+        .collect();
+    for (token, owed) in &debt {
+        *asset_exposure.entry(underlying_asset(token).to_string()).or_default() -= owed;
+    }
+    asset_exposure.retain(|_, value| !value.is_zero());
 
     RiskMetrics {
-        score,
+        score: components.blended_score(),
         largest_position_pct,
         position_count,
+        asset_count,
         protocol_concentration,
+        leverage,
+        liquidation_distance,
+        protocol_ltv,
+        stablecoin_pct,
+        memecoin_pct,
+        components,
+        net_exposure,
+        asset_exposure,
+    }
+}
+
+/// Position events carry the mint in `token_in` or `token_out` depending on the parser,
+/// with the other side empty or the LP placeholder
+fn position_token(tx: &ParsedTransaction) -> &str {
+    if tx.token_in.is_empty() || tx.token_in == LP_TOKEN {
+        &tx.token_out
+    } else {
+        &tx.token_in
+    }
+}
+
+/// Loan-to-value at which collateral of a given class gets liquidated
+fn liquidation_ltv(class: TokenClass) -> Decimal {
+    match class {
+        TokenClass::Stablecoin => dec!(0.90),
+        TokenClass::Major => dec!(0.85),
+        TokenClass::Other => dec!(0.70),
+        TokenClass::Memecoin => dec!(0.50),
+    }
+}
+
+fn weighted_liquidation_ltv(collateral: &HashMap<String, Decimal>) -> Decimal {
+    let total: Decimal = collateral.values().copied().sum();
+    if total.is_zero() {
+        return liquidation_ltv(TokenClass::Other);
+    }
+    collateral
+        .iter()
+        .map(|(token, value)| liquidation_ltv(classify_token(token)) * value)
+        .sum::<Decimal>()
+        / total
+}
+
+fn contract_tier_weight(tier: ContractRiskTier) -> Decimal {
+    match tier {
+        ContractRiskTier::Established => dec!(0.2),
+        ContractRiskTier::Moderate => dec!(0.5),
+        ContractRiskTier::Experimental => dec!(1.0),
     }
 }
 
+fn liquidation_score(debt_value: Decimal, distance: Decimal) -> u8 {
+    if debt_value.is_zero() {
+        return 0;
+    }
+    match distance {
+        d if d < dec!(0.05) => 100,
+        d if d < dec!(0.15) => 75,
+        d if d < dec!(0.30) => 50,
+        d if d < dec!(0.50) => 25,
+        _ => 10,
+    }
+}
+
+fn to_score(value: Decimal) -> u8 {
+    value.clamp(Decimal::ZERO, dec!(100)).to_u8().unwrap_or(0)
+}
+
 fn calculate_risk_score(
     largest_position_pct: Decimal,
     protocol_concentration: Decimal,
     protocol_count: usize,
-    position_count: u16,
+    asset_count: u16,
 ) -> u8 {
     let mut score: u8 = 0;
 
     // Concentration risk (0-40 points)
     // Higher concentration = higher risk
-    let concentration_score = (largest_position_pct * dec!(40))
-        .to_string()
-        .parse::<f64>()
-        .unwrap_or(0.0) as u8;
+    let concentration_score = to_score(largest_position_pct * dec!(40));
     score += concentration_score.min(40);
 
     // Protocol concentration risk (0-30 points)
-    let protocol_risk = (protocol_concentration * dec!(30))
-        .to_string()
-        .parse::<f64>()
-        .unwrap_or(0.0) as u8;
+    let protocol_risk = to_score(protocol_concentration * dec!(30));
     score += protocol_risk.min(30);
 
     // Diversification bonus (reduces risk)
@@ -116,15 +362,15 @@ fn calculate_risk_score(
     };
     score += diversification_bonus;
 
-    // Position count factor
-    // Too few or too many positions can be risky
-    let position_factor = match position_count {
+    // Asset count factor
+    // Too few or too many assets can be risky
+    let asset_factor = match asset_count {
         0 => 10,
         1..=3 => 5,
         4..=10 => 0,
-        _ => 5, // Many positions = complexity risk
+        _ => 5, // Many assets = complexity risk
     };
-    score += position_factor;
+    score += asset_factor;
 
     score.min(100)
 }
@@ -132,6 +378,28 @@ fn calculate_risk_score(
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::tokens::{JITOSOL_MINT, MSOL_MINT, USDC_MINT, WSOL_MINT};
+
+    fn make_tx(
+        tx_type: TransactionType,
+        protocol: Protocol,
+        token: &str,
+        usd_value: Decimal,
+    ) -> ParsedTransaction {
+        ParsedTransaction {
+            signature: "test".to_string(),
+            wallet: "wallet".to_string(),
+            protocol,
+            tx_type,
+            token_in: token.to_string(),
+            token_out: String::new(),
+            amount_in: Decimal::ZERO,
+            amount_out: Decimal::ZERO,
+            usd_value,
+            block_time: 0,
+            slot: 0,
+        }
+    }
 
     #[test]
     fn test_empty_transactions() {
@@ -147,7 +415,7 @@ mod tests {
             dec!(0.8),  // 80% in one position
             dec!(1.0),  // 100% in one protocol
             1,          // 1 protocol
-            1,          // 1 position
+            1,          // 1 asset
         );
         assert!(risk > 50, "High concentration should result in high risk score");
     }
@@ -158,8 +426,78 @@ mod tests {
             dec!(0.2),  // 20% max position
             dec!(0.33), // 33% per protocol
             3,          // 3 protocols
-            6,          // 6 positions
+            6,          // 6 assets
         );
         assert!(risk < 30, "Diversified portfolio should have lower risk");
     }
+
+    #[test]
+    fn test_borrowing_raises_risk() {
+        let spot = compute_risk(&[make_tx(TransactionType::Deposit, Protocol::Kamino, WSOL_MINT, dec!(1000))]);
+        let levered = compute_risk(&[
+            make_tx(TransactionType::Deposit, Protocol::Kamino, WSOL_MINT, dec!(1000)),
+            make_tx(TransactionType::Borrow, Protocol::Kamino, USDC_MINT, dec!(800)),
+        ]);
+
+        assert_eq!(spot.leverage, Decimal::ZERO);
+        assert_eq!(spot.liquidation_distance, dec!(1));
+        assert_eq!(levered.leverage, dec!(0.8));
+        assert!(levered.liquidation_distance < dec!(0.1));
+        assert_eq!(levered.position_count, 1, "Debt should not count as a position");
+        assert_eq!(levered.protocol_ltv.get(&Protocol::Kamino), Some(&dec!(0.8)));
+        assert!(spot.protocol_ltv.get(&Protocol::Kamino).unwrap().is_zero());
+        assert!(levered.score > spot.score);
+    }
+
+    #[test]
+    fn test_asset_mix() {
+        let risk = compute_risk(&[
+            make_tx(TransactionType::Deposit, Protocol::Kamino, USDC_MINT, dec!(750)),
+            make_tx(TransactionType::AddLiquidity, Protocol::Raydium, "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgpump", dec!(250)),
+        ]);
+        assert_eq!(risk.stablecoin_pct, dec!(0.75));
+        assert_eq!(risk.memecoin_pct, dec!(0.25));
+    }
+
+    #[test]
+    fn test_sol_derivatives_roll_up() {
+        let risk = compute_risk(&[
+            make_tx(TransactionType::Deposit, Protocol::Kamino, MSOL_MINT, dec!(300)),
+            make_tx(TransactionType::Deposit, Protocol::Kamino, JITOSOL_MINT, dec!(300)),
+            make_tx(TransactionType::AddLiquidity, Protocol::Raydium, WSOL_MINT, dec!(200)),
+            make_tx(TransactionType::AddLiquidity, Protocol::Raydium, USDC_MINT, dec!(200)),
+        ]);
+
+        assert_eq!(risk.position_count, 4);
+        assert_eq!(risk.asset_count, 2);
+        assert_eq!(risk.largest_position_pct, dec!(0.8));
+        assert_eq!(risk.asset_exposure.get(WSOL_MINT), Some(&dec!(800)));
+    }
+
+    #[test]
+    fn test_liquidity_splits_across_pool_legs() {
+        let risk = compute_risk(&[
+            make_tx(TransactionType::AddLiquidity, Protocol::Raydium, WSOL_MINT, dec!(300)),
+            make_tx(TransactionType::AddLiquidity, Protocol::Raydium, USDC_MINT, dec!(100)),
+            make_tx(TransactionType::Deposit, Protocol::Kamino, USDC_MINT, dec!(100)),
+        ]);
+
+        // Half of the 400 pool position in each leg
+        assert_eq!(risk.asset_exposure.get(WSOL_MINT), Some(&dec!(200)));
+        assert_eq!(risk.asset_exposure.get(USDC_MINT), Some(&dec!(300)));
+        assert_eq!(risk.net_exposure.get(WSOL_MINT), Some(&dec!(200)));
+        assert_eq!(risk.largest_position_pct, dec!(0.6));
+    }
+
+    #[test]
+    fn test_remove_liquidity_uses_mint() {
+        let mut remove = make_tx(TransactionType::RemoveLiquidity, Protocol::Raydium, LP_TOKEN, dec!(200));
+        remove.token_out = WSOL_MINT.to_string();
+        let risk = compute_risk(&[
+            make_tx(TransactionType::AddLiquidity, Protocol::Raydium, WSOL_MINT, dec!(200)),
+            remove,
+        ]);
+
+        assert!(risk.asset_exposure.is_empty());
+    }
 }
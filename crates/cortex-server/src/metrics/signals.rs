@@ -0,0 +1,304 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
+
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+
+use crate::indexer::parser::ParsedTransaction;
+use crate::tokens::is_stablecoin;
+use crate::types::TransactionType;
+
+/// A sell leaving less than this share of the prior holding counts as a full exit
+const CLOSE_THRESHOLD: Decimal = dec!(0.001);
+
+/// Slots behind the newest swap for which signatures are remembered, about ten minutes
+const SEEN_SLOTS: u64 = 1_500;
+
+/// What a trade did to a wallet's position in a token
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SignalKind {
+    Opened,
+    Added,
+    Reduced,
+    Closed,
+}
+
+impl fmt::Display for SignalKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SignalKind::Opened => write!(f, "opened"),
+            SignalKind::Added => write!(f, "added"),
+            SignalKind::Reduced => write!(f, "reduced"),
+            SignalKind::Closed => write!(f, "closed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PositionSignal {
+    pub signature: String,
+    pub wallet: String,
+    pub token: String,
+    pub kind: SignalKind,
+    /// Tokens bought or sold
+    pub amount: Decimal,
+    /// USD per token at the fill, zero when unknown
+    pub price: Decimal,
+    /// `amount` at `price`
+    pub usd_value: Decimal,
+    /// Holding after the trade
+    pub position_size: Decimal,
+    /// Share of the wallet's priced holdings the position makes up after the trade
+    pub portfolio_weight: Decimal,
+    pub block_time: i64,
+    pub slot: u64,
+}
+
+/// Running token holdings of one wallet, built from its swaps.
+///
+/// Transactions are expected roughly in slot order, as history is loaded and the stream
+/// delivers them. Each signature counts once: signatures are remembered for the last
+/// `SEEN_SLOTS` slots, and swaps older than that are taken as already applied. Only
+/// swaps change holdings: lending and LP moves keep the wallet's exposure, and
+/// stablecoins are tracked for portfolio weight but never produce signals.
+#[derive(Debug, Default)]
+pub struct PositionBook {
+    holdings: HashMap<String, Decimal>,
+    prices: HashMap<String, Decimal>,
+    /// Recently applied signatures by slot
+    seen: BTreeMap<u64, HashSet<String>>,
+    newest_slot: u64,
+}
+
+impl PositionBook {
+    /// Tokens currently held
+    pub fn tokens(&self) -> Vec<String> {
+        self.holdings
+            .iter()
+            .filter(|(_, amount)| **amount > Decimal::ZERO)
+            .map(|(token, _)| token.clone())
+            .collect()
+    }
+
+    /// Record a known USD price, e.g. from the price table
+    pub fn set_price(&mut self, token: &str, price: Decimal) {
+        if price > Decimal::ZERO {
+            self.prices.insert(token.to_string(), price);
+        }
+    }
+
+    fn price(&self, token: &str) -> Option<Decimal> {
+        if is_stablecoin(token) {
+            return Some(Decimal::ONE);
+        }
+        self.prices.get(token).copied()
+    }
+
+    /// USD value of all priced holdings
+    pub fn portfolio_value(&self) -> Decimal {
+        self.holdings
+            .iter()
+            .filter(|(_, amount)| **amount > Decimal::ZERO)
+            .filter_map(|(token, amount)| self.price(token).map(|price| price * amount))
+            .sum()
+    }
+
+    /// Apply a transaction and return the position changes it caused.
+    pub fn apply(&mut self, tx: &ParsedTransaction) -> Vec<PositionSignal> {
+        if tx.tx_type != TransactionType::Swap || !self.first_sighting(tx) {
+            return Vec::new();
+        }
+
+        self.update_fill_prices(tx);
+
+        let mut changes = Vec::new();
+        if !tx.token_in.is_empty() && tx.amount_in > Decimal::ZERO {
+            changes.push(self.change_holding(&tx.token_in, -tx.amount_in));
+        }
+        if !tx.token_out.is_empty() && tx.amount_out > Decimal::ZERO {
+            changes.push(self.change_holding(&tx.token_out, tx.amount_out));
+        }
+
+        let portfolio_value = self.portfolio_value();
+        changes
+            .into_iter()
+            .flatten()
+            .filter(|(token, ..)| !is_stablecoin(token))
+            .map(|(token, kind, amount, position_size)| {
+                let price = self.price(&token).unwrap_or_default();
+                let portfolio_weight = if portfolio_value > Decimal::ZERO {
+                    (position_size * price / portfolio_value).round_dp(6)
+                } else {
+                    Decimal::ZERO
+                };
+                PositionSignal {
+                    signature: tx.signature.clone(),
+                    wallet: tx.wallet.clone(),
+                    token,
+                    kind,
+                    amount,
+                    price,
+                    usd_value: (amount * price).round_dp(2),
+                    position_size,
+                    portfolio_weight,
+                    block_time: tx.block_time,
+                    slot: tx.slot,
+                }
+            })
+            .collect()
+    }
+
+    /// Remember the transaction's signature, returning false if it was already applied
+    /// or is too old to tell. Forgets signatures that fall out of the window.
+    fn first_sighting(&mut self, tx: &ParsedTransaction) -> bool {
+        if tx.slot < self.newest_slot.saturating_sub(SEEN_SLOTS) {
+            return false;
+        }
+        if !self.seen.entry(tx.slot).or_default().insert(tx.signature.clone()) {
+            return false;
+        }
+        if tx.slot > self.newest_slot {
+            self.newest_slot = tx.slot;
+            self.seen = self.seen.split_off(&tx.slot.saturating_sub(SEEN_SLOTS));
+        }
+        true
+    }
+
+    /// Price each leg of a swap off the other when one side's price is known
+    fn update_fill_prices(&mut self, tx: &ParsedTransaction) {
+        if tx.amount_in <= Decimal::ZERO || tx.amount_out <= Decimal::ZERO {
+            return;
+        }
+        match (self.price(&tx.token_in), self.price(&tx.token_out)) {
+            (Some(price_in), _) if is_stablecoin(&tx.token_in) || !is_stablecoin(&tx.token_out) => {
+                let price_out = price_in * tx.amount_in / tx.amount_out;
+                self.set_price(&tx.token_out, price_out);
+            }
+            (_, Some(price_out)) => {
+                let price_in = price_out * tx.amount_out / tx.amount_in;
+                self.set_price(&tx.token_in, price_in);
+            }
+            _ => {}
+        }
+    }
+
+    /// Change a holding and classify the move. Sells of a token with no recorded
+    /// holding are ignored, since the buy predates the indexed history.
+    fn change_holding(&mut self, token: &str, delta: Decimal) -> Option<(String, SignalKind, Decimal, Decimal)> {
+        let before = self.holdings.get(token).copied().unwrap_or_default().max(Decimal::ZERO);
+
+        if delta > Decimal::ZERO {
+            let after = before + delta;
+            self.holdings.insert(token.to_string(), after);
+            let kind = if before.is_zero() { SignalKind::Opened } else { SignalKind::Added };
+            return Some((token.to_string(), kind, delta, after));
+        }
+
+        if before.is_zero() {
+            return None;
+        }
+        let sold = (-delta).min(before);
+        let mut after = before - sold;
+        let kind = if after <= before * CLOSE_THRESHOLD {
+            after = Decimal::ZERO;
+            SignalKind::Closed
+        } else {
+            SignalKind::Reduced
+        };
+        self.holdings.insert(token.to_string(), after);
+        Some((token.to_string(), kind, sold, after))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tokens::USDC_MINT;
+    use crate::types::Protocol;
+
+    fn make_swap(signature: &str, token_in: &str, amount_in: Decimal, token_out: &str, amount_out: Decimal) -> ParsedTransaction {
+        ParsedTransaction {
+            signature: signature.to_string(),
+            wallet: "wallet".to_string(),
+            protocol: Protocol::Jupiter,
+            tx_type: TransactionType::Swap,
+            token_in: token_in.to_string(),
+            token_out: token_out.to_string(),
+            amount_in,
+            amount_out,
+            usd_value: Decimal::ZERO,
+            block_time: 0,
+            slot: 0,
+        }
+    }
+
+    #[test]
+    fn test_position_lifecycle() {
+        let mut book = PositionBook::default();
+
+        let opened = book.apply(&make_swap("a", USDC_MINT, dec!(1000), "BONK", dec!(50000)));
+        assert_eq!(opened.len(), 1);
+        assert_eq!(opened[0].kind, SignalKind::Opened);
+        assert_eq!(opened[0].price, dec!(0.02));
+        assert_eq!(opened[0].usd_value, dec!(1000));
+        assert_eq!(opened[0].portfolio_weight, dec!(1));
+
+        let added = book.apply(&make_swap("b", USDC_MINT, dec!(500), "BONK", dec!(25000)));
+        assert_eq!(added[0].kind, SignalKind::Added);
+        assert_eq!(added[0].position_size, dec!(75000));
+
+        let reduced = book.apply(&make_swap("c", "BONK", dec!(25000), USDC_MINT, dec!(750)));
+        assert_eq!(reduced[0].kind, SignalKind::Reduced);
+        assert_eq!(reduced[0].price, dec!(0.03));
+        // 50k BONK at $0.03 against $750 USDC
+        assert_eq!(reduced[0].portfolio_weight, dec!(0.666667));
+
+        let closed = book.apply(&make_swap("d", "BONK", dec!(50000), USDC_MINT, dec!(1500)));
+        assert_eq!(closed[0].kind, SignalKind::Closed);
+        assert_eq!(closed[0].position_size, Decimal::ZERO);
+    }
+
+    #[test]
+    fn test_duplicates_and_unknown_history() {
+        let mut book = PositionBook::default();
+        let buy = make_swap("a", USDC_MINT, dec!(100), "WIF", dec!(50));
+        assert_eq!(book.apply(&buy).len(), 1);
+        assert!(book.apply(&buy).is_empty());
+
+        // Selling a token bought before the indexed history yields no signal
+        let sell = make_swap("b", "POPCAT", dec!(10), USDC_MINT, dec!(5));
+        assert!(book.apply(&sell).is_empty());
+    }
+
+    #[test]
+    fn test_seen_signatures_are_bounded() {
+        let mut book = PositionBook::default();
+        let swap_at = |signature: &str, slot: u64| ParsedTransaction {
+            slot,
+            ..make_swap(signature, USDC_MINT, dec!(1), "WIF", dec!(1))
+        };
+
+        for slot in 0..5_000 {
+            book.apply(&swap_at(&format!("sig-{slot}"), slot));
+        }
+        assert_eq!(book.seen.values().map(HashSet::len).sum::<usize>(), SEEN_SLOTS as usize + 1);
+
+        // Replays within the window are recognized, anything older is taken as applied
+        assert!(book.apply(&swap_at("sig-4000", 4_000)).is_empty());
+        assert!(book.apply(&swap_at("sig-late", 100)).is_empty());
+        assert_eq!(book.apply(&swap_at("sig-new", 4_999)).len(), 1);
+    }
+
+    #[test]
+    fn test_token_to_token_swap() {
+        let mut book = PositionBook::default();
+        book.apply(&make_swap("a", USDC_MINT, dec!(1500), "SOL", dec!(10)));
+        let signals = book.apply(&make_swap("b", "SOL", dec!(2), "JUP", dec!(300)));
+
+        assert_eq!(signals.len(), 2);
+        assert_eq!(signals[0].kind, SignalKind::Reduced);
+        assert_eq!(signals[1].kind, SignalKind::Opened);
+        // JUP priced off the SOL leg: 2 SOL * $150 / 300
+        assert_eq!(signals[1].price, dec!(1));
+    }
+}
@@ -0,0 +1,210 @@
+use std::collections::{BTreeMap, HashMap};
+
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::Decimal;
+
+use crate::tokens::is_stablecoin;
+
+/// Days of price history used for the historical simulation
+pub const VAR_LOOKBACK_DAYS: u32 = 90;
+
+const DAYS_PER_YEAR: f64 = 365.0;
+
+/// A token's closing price for one UTC day
+#[derive(Debug, Clone)]
+pub struct DailyClose {
+    pub token: String,
+    /// Day as `YYYY-MM-DD`
+    pub day: String,
+    pub price: Decimal,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct VarMetrics {
+    /// 1-day loss not exceeded on 95% of historical days, in USD
+    pub var_95: Decimal,
+    pub var_99: Decimal,
+    /// Average loss on days beyond the VaR threshold, in USD
+    pub expected_shortfall_95: Decimal,
+    pub expected_shortfall_99: Decimal,
+    /// Annualized volatility of the simulated portfolio returns
+    pub portfolio_volatility: f64,
+    /// Annualized realized volatility per token with enough history
+    pub token_volatility: HashMap<String, f64>,
+    /// Share of absolute exposure backed by price history (stablecoins count as covered)
+    pub coverage: Decimal,
+    pub scenario_days: usize,
+}
+
+/// Estimate 1-day VaR and expected shortfall by replaying historical daily returns
+/// against current USD exposure per token.
+///
+/// Stablecoins are assumed flat. Tokens without at least two consecutive closes are
+/// left out of the scenarios and reduce `coverage`.
+pub fn compute_var(exposure: &HashMap<String, Decimal>, closes: &[DailyClose]) -> VarMetrics {
+    let gross_exposure: Decimal = exposure.values().map(|v| v.abs()).sum();
+    if gross_exposure.is_zero() {
+        return VarMetrics::default();
+    }
+
+    let returns = daily_returns_by_token(closes);
+
+    let mut scenarios: BTreeMap<&str, f64> = BTreeMap::new();
+    let mut token_volatility = HashMap::new();
+    let mut covered = Decimal::ZERO;
+
+    for (token, value) in exposure {
+        if is_stablecoin(token) {
+            covered += value.abs();
+            continue;
+        }
+        let Some(token_returns) = returns.get(token.as_str()) else {
+            continue;
+        };
+        covered += value.abs();
+
+        let series: Vec<f64> = token_returns.values().copied().collect();
+        if let Some(vol) = annualized_volatility(&series) {
+            token_volatility.insert(token.clone(), vol);
+        }
+
+        let value = value.to_f64().unwrap_or(0.0);
+        for (day, ret) in token_returns {
+            *scenarios.entry(day).or_default() += value * ret;
+        }
+    }
+
+    let pnl: Vec<f64> = scenarios.into_values().collect();
+    let gross = gross_exposure.to_f64().unwrap_or(0.0);
+    let portfolio_returns: Vec<f64> = pnl.iter().map(|p| p / gross).collect();
+
+    let (var_95, es_95) = historical_var(&pnl, 95);
+    let (var_99, es_99) = historical_var(&pnl, 99);
+
+    VarMetrics {
+        var_95: to_usd(var_95),
+        var_99: to_usd(var_99),
+        expected_shortfall_95: to_usd(es_95),
+        expected_shortfall_99: to_usd(es_99),
+        portfolio_volatility: annualized_volatility(&portfolio_returns).unwrap_or(0.0),
+        token_volatility,
+        coverage: covered / gross_exposure,
+        scenario_days: pnl.len(),
+    }
+}
+
+/// Simple daily returns per token, keyed by the day the return was realized.
+/// Only consecutive calendar days produce a return.
+fn daily_returns_by_token(closes: &[DailyClose]) -> HashMap<&str, BTreeMap<&str, f64>> {
+    let mut prices: HashMap<&str, BTreeMap<&str, f64>> = HashMap::new();
+    for close in closes {
+        if let Some(price) = close.price.to_f64().filter(|p| *p > 0.0) {
+            prices.entry(close.token.as_str()).or_default().insert(close.day.as_str(), price);
+        }
+    }
+
+    prices
+        .into_iter()
+        .filter_map(|(token, series)| {
+            let returns: BTreeMap<&str, f64> = series
+                .iter()
+                .zip(series.iter().skip(1))
+                .filter(|((prev_day, _), (day, _))| are_consecutive(prev_day, day))
+                .map(|((_, prev), (day, price))| (*day, price / prev - 1.0))
+                .collect();
+            (!returns.is_empty()).then_some((token, returns))
+        })
+        .collect()
+}
+
+fn are_consecutive(prev_day: &str, day: &str) -> bool {
+    match (
+        chrono::NaiveDate::parse_from_str(prev_day, "%Y-%m-%d"),
+        chrono::NaiveDate::parse_from_str(day, "%Y-%m-%d"),
+    ) {
+        (Ok(prev), Ok(next)) => (next - prev).num_days() == 1,
+        _ => false,
+    }
+}
+
+fn annualized_volatility(returns: &[f64]) -> Option<f64> {
+    if returns.len() < 2 {
+        return None;
+    }
+    let n = returns.len() as f64;
+    let mean = returns.iter().sum::<f64>() / n;
+    let variance = returns.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (n - 1.0);
+    Some(variance.sqrt() * DAYS_PER_YEAR.sqrt())
+}
+
+/// Historical VaR and expected shortfall at `confidence_pct`, both as positive losses.
+fn historical_var(pnl: &[f64], confidence_pct: usize) -> (f64, f64) {
+    if pnl.is_empty() {
+        return (0.0, 0.0);
+    }
+    let mut sorted = pnl.to_vec();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+
+    // Number of worst days in the tail, at least one
+    let tail = ((100 - confidence_pct) * sorted.len()).div_ceil(100).max(1);
+    let var = -sorted[tail - 1];
+    let shortfall = -sorted[..tail].iter().sum::<f64>() / tail as f64;
+
+    (var.max(0.0), shortfall.max(0.0))
+}
+
+fn to_usd(value: f64) -> Decimal {
+    Decimal::from_f64(value).unwrap_or_default().round_dp(2)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn close(token: &str, day: &str, price: Decimal) -> DailyClose {
+        DailyClose {
+            token: token.to_string(),
+            day: day.to_string(),
+            price,
+        }
+    }
+
+    #[test]
+    fn test_historical_var_tail() {
+        let pnl: Vec<f64> = (1..=100).map(|i| i as f64 - 50.0).collect();
+        let (var, es) = historical_var(&pnl, 95);
+        // Five worst days are -49..-45
+        assert_eq!(var, 45.0);
+        assert_eq!(es, 47.0);
+    }
+
+    #[test]
+    fn test_compute_var_single_token() {
+        let exposure = HashMap::from([("SOL".to_string(), dec!(1000))]);
+        let closes = vec![
+            close("SOL", "2026-01-01", dec!(100)),
+            close("SOL", "2026-01-02", dec!(90)),
+            close("SOL", "2026-01-03", dec!(99)),
+            // Gap: no return for 2026-01-05
+            close("SOL", "2026-01-05", dec!(50)),
+        ];
+
+        let var = compute_var(&exposure, &closes);
+        assert_eq!(var.scenario_days, 2);
+        assert_eq!(var.var_99, dec!(100));
+        assert_eq!(var.coverage, dec!(1));
+        assert!(var.token_volatility.contains_key("SOL"));
+    }
+
+    #[test]
+    fn test_stablecoins_are_flat_and_covered() {
+        let exposure = HashMap::from([
+            (crate::tokens::USDC_MINT.to_string(), dec!(500)),
+            ("UNPRICED".to_string(), dec!(500)),
+        ]);
+        let var = compute_var(&exposure, &[]);
+        assert_eq!(var.var_95, Decimal::ZERO);
+        assert_eq!(var.coverage, dec!(0.5));
+    }
+}
@@ -0,0 +1,166 @@
+//! In-memory registries of stored records.
+//!
+//! Webhooks, alert rules, API keys and wallet groups are few, read on every request or
+//! update, and changed rarely. Each is kept in a [`Registry`]: a map loaded from the store
+//! at startup and written through on every change. Deletion is soft: the record is stored
+//! once more marked deleted, and dropped from memory.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use tokio::sync::RwLock;
+
+use crate::db::store::CortexStore;
+use crate::error::AppResult;
+
+/// A record a [`Registry`] can hold
+#[async_trait]
+pub trait Record: Clone + Send + Sync + 'static {
+    /// Unique key the registry finds the record by
+    fn key(&self) -> &str;
+    /// Creation time in milliseconds, for listing oldest first
+    fn created_at(&self) -> i64;
+    /// Mark the record deleted before it is stored for the last time
+    fn mark_deleted(&mut self);
+
+    /// Store the current state of the record
+    async fn save(&self, store: &dyn CortexStore) -> AppResult<()>;
+    /// Every stored record that hasn't been deleted
+    async fn load_all(store: &dyn CortexStore) -> AppResult<Vec<Self>>;
+}
+
+pub struct Registry<R> {
+    store: Arc<dyn CortexStore>,
+    /// Live records by key, mirroring the store
+    records: Arc<RwLock<HashMap<String, R>>>,
+}
+
+impl<R> Clone for Registry<R> {
+    fn clone(&self) -> Self {
+        Self {
+            store: self.store.clone(),
+            records: self.records.clone(),
+        }
+    }
+}
+
+impl<R: Record> Registry<R> {
+    pub fn new(store: Arc<dyn CortexStore>) -> Self {
+        Self {
+            store,
+            records: Arc::new(RwLock::new(HashMap::new())),
+        }
+    }
+
+    /// Replace the registry's contents with the stored records. Returns how many there are.
+    pub async fn load(&self) -> AppResult<usize> {
+        let stored = R::load_all(self.store.as_ref()).await?;
+        let count = stored.len();
+        *self.records.write().await = stored.into_iter().map(|r| (r.key().to_string(), r)).collect();
+        Ok(count)
+    }
+
+    /// Store a new or changed record, then keep it
+    pub async fn save(&self, record: R) -> AppResult<()> {
+        record.save(self.store.as_ref()).await?;
+        self.records.write().await.insert(record.key().to_string(), record);
+        Ok(())
+    }
+
+    /// Every record, oldest first
+    pub async fn list(&self) -> Vec<R> {
+        let mut records: Vec<R> = self.records.read().await.values().cloned().collect();
+        records.sort_by(|a, b| (a.created_at(), a.key()).cmp(&(b.created_at(), b.key())));
+        records
+    }
+
+    pub async fn get(&self, key: &str) -> Option<R> {
+        self.records.read().await.get(key).cloned()
+    }
+
+    /// The first record matching `predicate`, in no particular order
+    pub async fn find(&self, predicate: impl Fn(&R) -> bool) -> Option<R> {
+        self.records.read().await.values().find(|r| predicate(r)).cloned()
+    }
+
+    /// Change a record in place and store it, unless `change` returns false. Returns
+    /// the changed record, or `None` if it was unchanged or there was no such record.
+    /// The change is kept even if storing it fails.
+    pub async fn update(&self, key: &str, change: impl FnOnce(&mut R) -> bool) -> AppResult<Option<R>> {
+        let updated = {
+            let mut records = self.records.write().await;
+            let Some(record) = records.get_mut(key) else {
+                return Ok(None);
+            };
+            if !change(record) {
+                return Ok(None);
+            }
+            record.clone()
+        };
+        updated.save(self.store.as_ref()).await?;
+        Ok(Some(updated))
+    }
+
+    /// Store the record marked deleted and drop it. Returns the deleted record, or
+    /// `None` if there was no such record.
+    pub async fn delete(&self, key: &str) -> AppResult<Option<R>> {
+        let Some(mut record) = self.get(key).await else {
+            return Ok(None);
+        };
+        record.mark_deleted();
+        record.save(self.store.as_ref()).await?;
+        self.records.write().await.remove(key);
+        Ok(Some(record))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::models::WebhookRow;
+    use crate::db::store::MemoryStore;
+    use crate::webhooks::{generate_secret, new_webhook, DEFAULT_MIN_USD_VALUE, DEFAULT_RISK_THRESHOLD};
+
+    fn webhook(id: &str, created_at: i64) -> WebhookRow {
+        let mut webhook = new_webhook(
+            "http://127.0.0.1:1/".to_string(),
+            generate_secret(),
+            None,
+            &[],
+            DEFAULT_MIN_USD_VALUE,
+            DEFAULT_RISK_THRESHOLD,
+        );
+        webhook.id = id.to_string();
+        webhook.created_at = created_at;
+        webhook
+    }
+
+    #[tokio::test]
+    async fn test_registry_mirrors_store() {
+        let store = Arc::new(MemoryStore::new());
+        let registry: Registry<WebhookRow> = Registry::new(store.clone());
+        registry.save(webhook("b", 2)).await.unwrap();
+        registry.save(webhook("a", 2)).await.unwrap();
+        registry.save(webhook("c", 1)).await.unwrap();
+
+        let keys = |records: Vec<WebhookRow>| records.into_iter().map(|r| r.id).collect::<Vec<_>>();
+        assert_eq!(keys(registry.list().await), vec!["c", "a", "b"]);
+
+        let moved = registry.update("a", |w| {
+            w.url = "http://127.0.0.1:2/".to_string();
+            true
+        });
+        assert_eq!(moved.await.unwrap().unwrap().url, "http://127.0.0.1:2/");
+        assert!(registry.update("a", |_| false).await.unwrap().is_none());
+        assert!(registry.update("missing", |_| true).await.unwrap().is_none());
+
+        assert_eq!(registry.delete("b").await.unwrap().unwrap().deleted, 1);
+        assert!(registry.delete("b").await.unwrap().is_none());
+
+        let reloaded: Registry<WebhookRow> = Registry::new(store);
+        assert_eq!(reloaded.load().await.unwrap(), 2);
+        assert_eq!(reloaded.get("a").await.unwrap().url, "http://127.0.0.1:2/");
+        assert!(reloaded.find(|w| w.created_at == 1).await.is_some());
+    }
+}
@@ -0,0 +1,258 @@
+//! Prometheus metrics, served at `/metrics`.
+//!
+//! One registry per process. The API records request latency per route through
+//! [`track_requests`], `db::queries` the duration of each ClickHouse query by name, and
+//! the indexer what happens to streamed messages from receipt to insert, along with each
+//! subscription's lag behind the chain and the depth of its transaction channel.
+
+use std::sync::LazyLock;
+use std::time::{Duration, Instant};
+
+use axum::extract::{MatchedPath, Request};
+use axum::middleware::Next;
+use axum::response::Response;
+use prometheus::{
+    Encoder, GaugeVec, HistogramOpts, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, Opts, Registry, TextEncoder,
+};
+
+/// Content type of [`render`]'s output
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+static REGISTRY: LazyLock<Registry> = LazyLock::new(|| Registry::new_custom(Some("cortex".to_string()), None).unwrap());
+
+/// Register a metric on first use
+fn register<M: prometheus::core::Collector + Clone + 'static>(metric: M) -> M {
+    REGISTRY.register(Box::new(metric.clone())).unwrap();
+    metric
+}
+
+static HTTP_REQUEST_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(
+        HistogramVec::new(
+            HistogramOpts::new("http_request_duration_seconds", "REST API request latency by route"),
+            &["method", "route", "status"],
+        )
+        .unwrap(),
+    )
+});
+
+static QUERY_DURATION: LazyLock<HistogramVec> = LazyLock::new(|| {
+    register(
+        HistogramVec::new(
+            HistogramOpts::new("clickhouse_query_duration_seconds", "Duration of successful ClickHouse queries by name"),
+            &["query"],
+        )
+        .unwrap(),
+    )
+});
+
+static MESSAGES_RECEIVED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register(IntCounter::new("indexer_messages_received_total", "LYS Labs WebSocket messages received by wallet streams").unwrap())
+});
+
+static TRANSACTIONS_PARSED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new("indexer_transactions_parsed_total", "Streamed transactions parsed into DeFi transactions"),
+            &["protocol"],
+        )
+        .unwrap(),
+    )
+});
+
+static TRANSACTIONS_SKIPPED: LazyLock<IntCounterVec> = LazyLock::new(|| {
+    register(
+        IntCounterVec::new(
+            Opts::new("indexer_skipped_total", "Streamed messages and transactions dropped before storage"),
+            &["reason"],
+        )
+        .unwrap(),
+    )
+});
+
+static TRANSACTIONS_INSERTED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register(IntCounter::new("indexer_transactions_inserted_total", "Transaction rows committed to ClickHouse").unwrap())
+});
+
+static INSERT_FAILURES: LazyLock<IntCounter> = LazyLock::new(|| {
+    register(IntCounter::new("indexer_insert_failures_total", "Failed transaction batch commits, retried on the next tick").unwrap())
+});
+
+static INSERT_BACKLOG: LazyLock<IntGauge> = LazyLock::new(|| {
+    register(IntGauge::new("indexer_insert_backlog", "Transactions written but not yet committed").unwrap())
+});
+
+static WEBHOOK_EVENTS_DROPPED: LazyLock<IntCounter> = LazyLock::new(|| {
+    register(IntCounter::new("webhook_events_dropped_total", "Webhook events dropped because the webhook's queue was full").unwrap())
+});
+
+static STREAM_RECONNECTS: LazyLock<IntCounter> = LazyLock::new(|| {
+    register(IntCounter::new("indexer_stream_reconnects_total", "LYS Labs WebSocket reconnect attempts").unwrap())
+});
+
+static SUBSCRIPTION_LAG: LazyLock<GaugeVec> = LazyLock::new(|| {
+    register(
+        GaugeVec::new(
+            Opts::new("subscription_lag_seconds", "Age of the last transaction a subscription processed"),
+            &["wallet"],
+        )
+        .unwrap(),
+    )
+});
+
+static SUBSCRIPTION_CHANNEL_DEPTH: LazyLock<IntGaugeVec> = LazyLock::new(|| {
+    register(
+        IntGaugeVec::new(
+            Opts::new("subscription_channel_depth", "Transactions waiting in a subscription's channel"),
+            &["wallet"],
+        )
+        .unwrap(),
+    )
+});
+
+/// Why the indexer dropped a message or transaction
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The WebSocket message wasn't valid JSON of the expected shape
+    MalformedMessage,
+    /// The transaction doesn't involve the subscribed wallet
+    OtherWallet,
+    /// Neither the decoder nor the program id names a supported protocol
+    UnknownProtocol,
+    /// Plain transfers aren't tracked
+    Transfer,
+    /// An event type with no parser
+    UnsupportedEvent,
+    /// A swap without a recognizable token on either side
+    NoTokens,
+}
+
+impl SkipReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SkipReason::MalformedMessage => "malformed_message",
+            SkipReason::OtherWallet => "other_wallet",
+            SkipReason::UnknownProtocol => "unknown_protocol",
+            SkipReason::Transfer => "transfer",
+            SkipReason::UnsupportedEvent => "unsupported_event",
+            SkipReason::NoTokens => "no_tokens",
+        }
+    }
+}
+
+/// Record a ClickHouse query's duration under its name in `db::queries`
+pub fn observe_query(query: &str, elapsed: Duration) {
+    QUERY_DURATION.with_label_values(&[query]).observe(elapsed.as_secs_f64());
+}
+
+pub fn message_received() {
+    MESSAGES_RECEIVED.inc();
+}
+
+pub fn transaction_parsed(protocol: &str) {
+    TRANSACTIONS_PARSED.with_label_values(&[protocol]).inc();
+}
+
+pub fn transaction_skipped(reason: SkipReason) {
+    TRANSACTIONS_SKIPPED.with_label_values(&[reason.as_str()]).inc();
+}
+
+pub fn transactions_inserted(rows: u64) {
+    TRANSACTIONS_INSERTED.inc_by(rows);
+}
+
+pub fn insert_failed() {
+    INSERT_FAILURES.inc();
+}
+
+pub fn insert_backlog(rows: usize) {
+    INSERT_BACKLOG.set(i64::try_from(rows).unwrap_or(i64::MAX));
+}
+
+pub fn webhook_event_dropped() {
+    WEBHOOK_EVENTS_DROPPED.inc();
+}
+
+pub fn stream_reconnected() {
+    STREAM_RECONNECTS.inc();
+}
+
+/// Record how far behind the chain a subscription is and how much is queued behind it
+pub fn subscription_progress(wallet: &str, lag_secs: f64, channel_depth: usize) {
+    SUBSCRIPTION_LAG.with_label_values(&[wallet]).set(lag_secs.max(0.0));
+    SUBSCRIPTION_CHANNEL_DEPTH
+        .with_label_values(&[wallet])
+        .set(i64::try_from(channel_depth).unwrap_or(i64::MAX));
+}
+
+/// Drop a stopped subscription's series
+pub fn subscription_ended(wallet: &str) {
+    let _ = SUBSCRIPTION_LAG.remove_label_values(&[wallet]);
+    let _ = SUBSCRIPTION_CHANNEL_DEPTH.remove_label_values(&[wallet]);
+}
+
+/// Middleware timing every request under its route template, so `/api/v1/user/{wallet}/pnl`
+/// is one series rather than one per wallet
+pub async fn track_requests(request: Request, next: Next) -> Response {
+    let method = request.method().to_string();
+    let route = request
+        .extensions()
+        .get::<MatchedPath>()
+        .map(|path| path.as_str().to_string())
+        .unwrap_or_else(|| "unmatched".to_string());
+    let start = Instant::now();
+
+    let response = next.run(request).await;
+
+    HTTP_REQUEST_DURATION
+        .with_label_values(&[method.as_str(), route.as_str(), response.status().as_str()])
+        .observe(start.elapsed().as_secs_f64());
+    response
+}
+
+/// Every metric in the Prometheus text format
+pub fn render() -> String {
+    // Touch each metric so it is exported, at zero, before anything happens
+    LazyLock::force(&HTTP_REQUEST_DURATION);
+    LazyLock::force(&QUERY_DURATION);
+    LazyLock::force(&MESSAGES_RECEIVED);
+    LazyLock::force(&TRANSACTIONS_PARSED);
+    LazyLock::force(&TRANSACTIONS_SKIPPED);
+    LazyLock::force(&TRANSACTIONS_INSERTED);
+    LazyLock::force(&INSERT_FAILURES);
+    LazyLock::force(&INSERT_BACKLOG);
+    LazyLock::force(&WEBHOOK_EVENTS_DROPPED);
+    LazyLock::force(&STREAM_RECONNECTS);
+    LazyLock::force(&SUBSCRIPTION_LAG);
+    LazyLock::force(&SUBSCRIPTION_CHANNEL_DEPTH);
+
+    let mut buffer = Vec::new();
+    if let Err(e) = TextEncoder::new().encode(&REGISTRY.gather(), &mut buffer) {
+        tracing::error!(error = %e, "Failed to encode metrics");
+    }
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_exports_recorded_metrics() {
+        observe_query("test_render_query", Duration::from_millis(12));
+        transaction_skipped(SkipReason::NoTokens);
+        transactions_inserted(3);
+        subscription_progress("test-render-wallet", 4.5, 7);
+
+        let text = render();
+        assert!(text.contains("cortex_clickhouse_query_duration_seconds_count{query=\"test_render_query\"} 1"));
+        assert!(text.contains("cortex_indexer_skipped_total{reason=\"no_tokens\"}"));
+        assert!(text.contains("cortex_subscription_lag_seconds{wallet=\"test-render-wallet\"} 4.5"));
+        assert!(text.contains("cortex_subscription_channel_depth{wallet=\"test-render-wallet\"} 7"));
+        // Counters without labels are exported before anything is recorded
+        assert!(text.contains("cortex_indexer_stream_reconnects_total"));
+
+        subscription_ended("test-render-wallet");
+        assert!(!render().contains("test-render-wallet"));
+    }
+}
@@ -0,0 +1,99 @@
+//! Well-known Solana token mints used by the metrics.
+
+/// USDC
+pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
+/// USDT
+pub const USDT_MINT: &str = "Es9vMFrzaCERmJfrF4H2FYD4KCoNkY11McCe8BenwNYB";
+/// PayPal USD
+pub const PYUSD_MINT: &str = "2b1kV6DkPAnxd5ixfnxCpjxmKwqjjaYmCZfHsFu24GXo";
+/// Wrapped SOL
+pub const WSOL_MINT: &str = "So11111111111111111111111111111111111111112";
+/// Marinade staked SOL
+pub const MSOL_MINT: &str = "mSoLzYCxHdYgdzU16g5QSh3i5K3z3KZK7ytfqcJm7So";
+/// Jito staked SOL
+pub const JITOSOL_MINT: &str = "J1toso1uCk3RLmjorhTtrVwY9HJ7X8V9yYac6Y7kGCPn";
+/// BlazeStake staked SOL
+pub const BSOL_MINT: &str = "bSo13r4TkiE4KumL71LsHTPpL2euBYLFx6h9HP3piy1";
+/// Wrapped BTC (Portal)
+pub const WBTC_MINT: &str = "3NZ9JMVBmGAqocybic2c7LQCJScmgsAZ6vQqTDzcqmJh";
+/// Wrapped ETH (Portal)
+pub const WETH_MINT: &str = "7vfCXTUXx5WJV5JADk17DUJ4ksgau7utNKj4b963voxs";
+
+const STABLECOIN_MINTS: &[&str] = &[USDC_MINT, USDT_MINT, PYUSD_MINT];
+
+const MAJOR_MINTS: &[&str] = &[
+    WSOL_MINT,
+    MSOL_MINT,
+    JITOSOL_MINT,
+    BSOL_MINT,
+    WBTC_MINT,
+    WETH_MINT,
+];
+
+const MEMECOIN_MINTS: &[&str] = &[
+    "DezXAZ8z7PnrnRJjz3wXBoRgixCa6xjnB7YaB1pPB263", // BONK
+    "EKpQGSJtjMFqKZ9KQanSqYXRcF8fBopzLHYxdM65zcjm", // WIF
+    "7GCihgDB8fe6KNjn2MYtkzZcRjQy3t9GHdC8uHYmW2hr", // POPCAT
+    "MEW1gQWJ3nEXg2qgERiKu7FAFj79PHvQVREQUzScPP5",  // MEW
+];
+
+/// Mints that are claims on SOL and move with it one-for-one
+const SOL_DERIVATIVE_MINTS: &[&str] = &[WSOL_MINT, MSOL_MINT, JITOSOL_MINT, BSOL_MINT];
+
+/// Placeholder parsers use for the pool-share side of liquidity events
+pub const LP_TOKEN: &str = "LP";
+
+/// Broad volatility class of a token
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TokenClass {
+    Stablecoin,
+    /// SOL, liquid staking tokens and bridged BTC/ETH
+    Major,
+    Memecoin,
+    Other,
+}
+
+/// Whether a mint is a USD stablecoin (treated as cash rather than a position).
+pub fn is_stablecoin(mint: &str) -> bool {
+    STABLECOIN_MINTS.contains(&mint)
+}
+
+pub fn classify_token(mint: &str) -> TokenClass {
+    if is_stablecoin(mint) {
+        TokenClass::Stablecoin
+    } else if MAJOR_MINTS.contains(&mint) {
+        TokenClass::Major
+    } else if MEMECOIN_MINTS.contains(&mint) || mint.ends_with("pump") {
+        // Pump.fun vanity mints all end in "pump"
+        TokenClass::Memecoin
+    } else {
+        TokenClass::Other
+    }
+}
+
+/// Asset a token gives economic exposure to. Wrapped and liquid-staked SOL roll up to
+/// `WSOL_MINT`; every other mint is its own underlying.
+pub fn underlying_asset(mint: &str) -> &str {
+    if SOL_DERIVATIVE_MINTS.contains(&mint) {
+        WSOL_MINT
+    } else {
+        mint
+    }
+}
+
+/// Ticker of a well-known mint, or `None` for anything else
+pub fn symbol(mint: &str) -> Option<&'static str> {
+    let symbol = match mint {
+        USDC_MINT => "USDC",
+        USDT_MINT => "USDT",
+        PYUSD_MINT => "PYUSD",
+        WSOL_MINT => "SOL",
+        MSOL_MINT => "mSOL",
+        JITOSOL_MINT => "JitoSOL",
+        BSOL_MINT => "bSOL",
+        WBTC_MINT => "WBTC",
+        WETH_MINT => "WETH",
+        _ => return None,
+    };
+    Some(symbol)
+}
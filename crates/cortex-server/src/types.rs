@@ -1,3 +1,4 @@
+use chrono::{Duration, Utc};
 use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::fmt;
@@ -40,6 +41,30 @@ impl Protocol {
     }
 }
 
+/// Smart-contract risk tier of a protocol, based on track record and audits
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ContractRiskTier {
+    /// Long-running, audited, high-TVL programs
+    Established,
+    /// Audited but younger or with a shorter track record
+    Moderate,
+    /// Unaudited or launchpad-style programs
+    Experimental,
+}
+
+impl Protocol {
+    pub fn risk_tier(&self) -> ContractRiskTier {
+        match self {
+            Protocol::Jupiter | Protocol::Raydium | Protocol::Orca | Protocol::Kamino => {
+                ContractRiskTier::Established
+            }
+            Protocol::Meteora => ContractRiskTier::Moderate,
+            Protocol::PumpFun => ContractRiskTier::Experimental,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TransactionType {
@@ -66,6 +91,21 @@ impl fmt::Display for TransactionType {
     }
 }
 
+impl TransactionType {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "swap" => Some(TransactionType::Swap),
+            "deposit" => Some(TransactionType::Deposit),
+            "withdraw" => Some(TransactionType::Withdraw),
+            "borrow" => Some(TransactionType::Borrow),
+            "repay" => Some(TransactionType::Repay),
+            "add_liquidity" => Some(TransactionType::AddLiquidity),
+            "remove_liquidity" => Some(TransactionType::RemoveLiquidity),
+            _ => None,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum PositionType {
@@ -84,7 +124,7 @@ impl fmt::Display for PositionType {
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TimeWindow {
     #[serde(rename = "24h")]
@@ -116,6 +156,297 @@ impl TimeWindow {
             TimeWindow::All => None,
         }
     }
+
+    /// Start of the window in unix milliseconds, or `None` for all time.
+    pub fn cutoff_millis(&self) -> Option<i64> {
+        self.to_days()
+            .map(|days| (Utc::now() - Duration::days(days)).timestamp_millis())
+    }
+}
+
+/// How disposals are matched against previously acquired tax lots
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LotMethod {
+    /// First in, first out
+    Fifo,
+    /// Last in, first out
+    Lifo,
+    /// Highest unit cost first
+    Hifo,
+    /// Pooled average cost, lots still consumed oldest first for holding periods
+    AverageCost,
+}
+
+impl fmt::Display for LotMethod {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LotMethod::Fifo => write!(f, "fifo"),
+            LotMethod::Lifo => write!(f, "lifo"),
+            LotMethod::Hifo => write!(f, "hifo"),
+            LotMethod::AverageCost => write!(f, "average_cost"),
+        }
+    }
+}
+
+impl LotMethod {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "fifo" => Some(LotMethod::Fifo),
+            "lifo" => Some(LotMethod::Lifo),
+            "hifo" => Some(LotMethod::Hifo),
+            "average_cost" | "average" | "avg" => Some(LotMethod::AverageCost),
+            _ => None,
+        }
+    }
+}
+
+/// Whether a request covers one address or the entity (wallet cluster) it belongs to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scope {
+    Wallet,
+    Entity,
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Scope::Wallet => write!(f, "wallet"),
+            Scope::Entity => write!(f, "entity"),
+        }
+    }
+}
+
+impl Scope {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "wallet" => Some(Scope::Wallet),
+            "entity" | "cluster" => Some(Scope::Entity),
+            _ => None,
+        }
+    }
+}
+
+/// What the smart-money leaderboard ranks wallets by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LeaderboardMetric {
+    RealizedPnl,
+    /// Realized PnL over the cost basis of everything sold
+    Roi,
+    WinRate,
+    /// Annualized Sharpe ratio of daily returns
+    Sharpe,
+}
+
+impl fmt::Display for LeaderboardMetric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LeaderboardMetric::RealizedPnl => write!(f, "realized_pnl"),
+            LeaderboardMetric::Roi => write!(f, "roi"),
+            LeaderboardMetric::WinRate => write!(f, "win_rate"),
+            LeaderboardMetric::Sharpe => write!(f, "sharpe"),
+        }
+    }
+}
+
+impl LeaderboardMetric {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "realized_pnl" | "pnl" => Some(LeaderboardMetric::RealizedPnl),
+            "roi" => Some(LeaderboardMetric::Roi),
+            "win_rate" => Some(LeaderboardMetric::WinRate),
+            "sharpe" | "sharpe_ratio" | "risk_adjusted" => Some(LeaderboardMetric::Sharpe),
+            _ => None,
+        }
+    }
+}
+
+/// Order of a wallet's transaction history
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TransactionSort {
+    Newest,
+    Oldest,
+    /// Highest USD value first
+    Largest,
+}
+
+impl fmt::Display for TransactionSort {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TransactionSort::Newest => write!(f, "newest"),
+            TransactionSort::Oldest => write!(f, "oldest"),
+            TransactionSort::Largest => write!(f, "largest"),
+        }
+    }
+}
+
+impl TransactionSort {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "newest" | "desc" => Some(TransactionSort::Newest),
+            "oldest" | "asc" => Some(TransactionSort::Oldest),
+            "largest" | "usd_value" => Some(TransactionSort::Largest),
+            _ => None,
+        }
+    }
+}
+
+/// What a webhook can be notified about
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    /// Any newly indexed transaction
+    Transaction,
+    /// A transaction worth at least the webhook's `min_usd_value`
+    LargeTrade,
+    /// A wallet's risk score rising to or above the webhook's `risk_threshold`
+    RiskThreshold,
+    /// An alert rule starting or stopping to hold for a wallet
+    Alert,
+}
+
+impl fmt::Display for WebhookEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WebhookEvent::Transaction => write!(f, "transaction"),
+            WebhookEvent::LargeTrade => write!(f, "large_trade"),
+            WebhookEvent::RiskThreshold => write!(f, "risk_threshold"),
+            WebhookEvent::Alert => write!(f, "alert"),
+        }
+    }
+}
+
+impl WebhookEvent {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "transaction" => Some(WebhookEvent::Transaction),
+            "large_trade" => Some(WebhookEvent::LargeTrade),
+            "risk_threshold" | "risk" => Some(WebhookEvent::RiskThreshold),
+            "alert" => Some(WebhookEvent::Alert),
+            _ => None,
+        }
+    }
+}
+
+/// What an API key may do
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiScope {
+    /// Query wallets, streams, signals and alerts
+    Read,
+    /// Start and stop continuous wallet indexing
+    Index,
+    /// Manage API keys, webhooks and alert rules; allows everything
+    Admin,
+}
+
+impl fmt::Display for ApiScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApiScope::Read => write!(f, "read"),
+            ApiScope::Index => write!(f, "index"),
+            ApiScope::Admin => write!(f, "admin"),
+        }
+    }
+}
+
+impl ApiScope {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "read" => Some(ApiScope::Read),
+            "index" => Some(ApiScope::Index),
+            "admin" => Some(ApiScope::Admin),
+            _ => None,
+        }
+    }
+}
+
+/// Whether an alert rule currently holds for a wallet
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, utoipa::ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertState {
+    Firing,
+    Resolved,
+}
+
+impl fmt::Display for AlertState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AlertState::Firing => write!(f, "firing"),
+            AlertState::Resolved => write!(f, "resolved"),
+        }
+    }
+}
+
+impl AlertState {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "firing" => Some(AlertState::Firing),
+            "resolved" => Some(AlertState::Resolved),
+            _ => None,
+        }
+    }
+}
+
+/// Bucket width of a prediction market's price candles
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum CandleInterval {
+    #[serde(rename = "5m")]
+    FiveMinutes,
+    #[serde(rename = "15m")]
+    FifteenMinutes,
+    #[serde(rename = "1h")]
+    Hour,
+    #[serde(rename = "4h")]
+    FourHours,
+    #[serde(rename = "1d")]
+    Day,
+}
+
+impl fmt::Display for CandleInterval {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CandleInterval::FiveMinutes => write!(f, "5m"),
+            CandleInterval::FifteenMinutes => write!(f, "15m"),
+            CandleInterval::Hour => write!(f, "1h"),
+            CandleInterval::FourHours => write!(f, "4h"),
+            CandleInterval::Day => write!(f, "1d"),
+        }
+    }
+}
+
+impl CandleInterval {
+    pub fn from_str(s: &str) -> Option<Self> {
+        match s.to_lowercase().as_str() {
+            "5m" => Some(CandleInterval::FiveMinutes),
+            "15m" => Some(CandleInterval::FifteenMinutes),
+            "1h" => Some(CandleInterval::Hour),
+            "4h" => Some(CandleInterval::FourHours),
+            "1d" | "24h" => Some(CandleInterval::Day),
+            _ => None,
+        }
+    }
+
+    pub fn to_minutes(self) -> i64 {
+        match self {
+            CandleInterval::FiveMinutes => 5,
+            CandleInterval::FifteenMinutes => 15,
+            CandleInterval::Hour => 60,
+            CandleInterval::FourHours => 240,
+            CandleInterval::Day => 1440,
+        }
+    }
+
+    /// How far back candles go: 48 buckets at minute widths, 30 days of daily ones
+    pub fn lookback_minutes(&self) -> i64 {
+        match self {
+            CandleInterval::Day => 30 * 1440,
+            interval => 48 * interval.to_minutes(),
+        }
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
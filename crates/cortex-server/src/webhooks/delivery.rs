@@ -0,0 +1,107 @@
+//! Sending one signed webhook request
+
+use std::time::{Duration, Instant};
+
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+
+/// Longest wait between retries, however many attempts have failed
+const MAX_BACKOFF_MS: u64 = 5 * 60 * 1000;
+
+/// Error text kept per failed attempt
+const MAX_ERROR_LEN: usize = 500;
+
+/// What happened to one attempt
+#[derive(Debug)]
+pub(super) struct Attempt {
+    /// 0 when no response was received
+    pub status_code: u16,
+    pub error: String,
+    pub duration: Duration,
+}
+
+impl Attempt {
+    pub fn succeeded(&self) -> bool {
+        (200..300).contains(&self.status_code)
+    }
+}
+
+/// Signature sent in `X-Cortex-Signature`: hex HMAC-SHA256 of `"{timestamp}.{body}"`
+/// keyed with the webhook's secret.
+pub fn sign(secret: &str, timestamp: i64, body: &str) -> String {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes()).expect("HMAC accepts keys of any length");
+    mac.update(timestamp.to_string().as_bytes());
+    mac.update(b".");
+    mac.update(body.as_bytes());
+    let digest: String = mac.finalize().into_bytes().iter().map(|b| format!("{:02x}", b)).collect();
+    format!("sha256={}", digest)
+}
+
+/// Wait before retrying after `attempt` (1-based) failed
+pub(super) fn backoff(retry_base_ms: u64, attempt: u32) -> Duration {
+    let factor = 1u64.checked_shl(attempt.saturating_sub(1)).unwrap_or(u64::MAX);
+    Duration::from_millis(retry_base_ms.saturating_mul(factor).min(MAX_BACKOFF_MS))
+}
+
+/// POST `body` to `url`, signed with `secret`
+pub(super) async fn send(
+    http: &reqwest::Client,
+    url: &str,
+    secret: &str,
+    event_type: &str,
+    delivery_id: &str,
+    body: &str,
+) -> Attempt {
+    let start = Instant::now();
+    let timestamp = chrono::Utc::now().timestamp();
+
+    let result = http
+        .post(url)
+        .header("content-type", "application/json")
+        .header("x-cortex-event", event_type)
+        .header("x-cortex-delivery", delivery_id)
+        .header("x-cortex-timestamp", timestamp.to_string())
+        .header("x-cortex-signature", sign(secret, timestamp, body))
+        .body(body.to_string())
+        .send()
+        .await;
+
+    let (status_code, error) = match result {
+        Ok(response) if response.status().is_success() => (response.status().as_u16(), String::new()),
+        Ok(response) => {
+            let status = response.status();
+            let text = response.text().await.unwrap_or_default();
+            (status.as_u16(), format!("HTTP {}: {}", status, text))
+        }
+        Err(e) => (0, e.to_string()),
+    };
+
+    Attempt {
+        status_code,
+        error: error.chars().take(MAX_ERROR_LEN).collect(),
+        duration: start.elapsed(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backoff_doubles_up_to_cap() {
+        assert_eq!(backoff(1000, 1), Duration::from_millis(1000));
+        assert_eq!(backoff(1000, 2), Duration::from_millis(2000));
+        assert_eq!(backoff(1000, 4), Duration::from_millis(8000));
+        assert_eq!(backoff(1000, 40), Duration::from_millis(MAX_BACKOFF_MS));
+    }
+
+    #[test]
+    fn test_sign_matches_reference_hmac() {
+        // hmac.new(b"whsec_test", b'1700000000.{"ok":true}', hashlib.sha256).hexdigest()
+        assert_eq!(
+            sign("whsec_test", 1_700_000_000, r#"{"ok":true}"#),
+            "sha256=85876387ad9d6be57a04653bc0729da757049f58afb10ba6cac3bedaecf4fda3"
+        );
+        assert_ne!(sign("whsec_test", 1_700_000_001, r#"{"ok":true}"#), sign("whsec_test", 1_700_000_000, r#"{"ok":true}"#));
+    }
+}
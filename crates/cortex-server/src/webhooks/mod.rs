@@ -0,0 +1,641 @@
+//! Outbound webhooks.
+//!
+//! A webhook follows one wallet (or every wallet) and a set of [`WebhookEvent`]s. The
+//! dispatcher reads the indexer's update feed, turns matching updates into events and
+//! queues them per webhook. Each webhook's worker delivers its events in order, signed,
+//! retrying with exponential backoff. Every attempt is recorded, and a webhook whose
+//! deliveries keep failing is disabled until it is re-enabled.
+
+mod delivery;
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::Serialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::sync::mpsc::error::TrySendError;
+use tokio::sync::{broadcast, mpsc, RwLock};
+use tokio::task::JoinHandle;
+
+use crate::api::dto::{TransactionEntry, UserSummaryResponse};
+use crate::config::WebhookConfig;
+use crate::db::models::{WebhookDeliveryRow, WebhookRow};
+use crate::db::store::CortexStore;
+use crate::error::AppResult;
+use crate::indexer::WalletUpdate;
+use crate::registry::{Record, Registry};
+use crate::telemetry;
+use crate::types::WebhookEvent;
+
+/// Large-trade threshold when a webhook doesn't set one
+pub const DEFAULT_MIN_USD_VALUE: Decimal = dec!(10000);
+
+/// Risk score crossing reported when a webhook doesn't set one
+pub const DEFAULT_RISK_THRESHOLD: u8 = 70;
+
+/// The JSON body POSTed to a webhook
+#[derive(Debug, Clone, Serialize)]
+pub struct WebhookPayload {
+    /// Shared by every attempt at this event, also sent as `X-Cortex-Delivery`
+    pub id: String,
+    #[serde(rename = "type")]
+    pub event_type: WebhookEvent,
+    pub wallet: String,
+    pub created_at: DateTime<Utc>,
+    pub data: serde_json::Value,
+}
+
+impl WebhookPayload {
+    fn new(event_type: WebhookEvent, wallet: &str, data: serde_json::Value) -> Self {
+        Self {
+            id: uuid::Uuid::new_v4().to_string(),
+            event_type,
+            wallet: wallet.to_string(),
+            created_at: Utc::now(),
+            data,
+        }
+    }
+}
+
+/// Events waiting for one webhook's worker
+type Queue = mpsc::Sender<(WebhookRow, WebhookPayload)>;
+
+#[derive(Serialize)]
+struct RiskCrossing {
+    risk_score: u8,
+    /// Absent for the first summary seen since the server started
+    previous_risk_score: Option<u8>,
+    threshold: u8,
+    summary: UserSummaryResponse,
+}
+
+#[derive(Clone)]
+pub struct WebhookDispatcher {
+    store: Arc<dyn CortexStore>,
+    http: reqwest::Client,
+    config: WebhookConfig,
+    webhooks: Registry<WebhookRow>,
+    /// Latest risk score seen per wallet, to spot threshold crossings
+    risk_scores: Arc<RwLock<HashMap<String, u8>>>,
+    /// Events waiting for each webhook's worker, by webhook id
+    queues: Arc<Mutex<HashMap<String, Queue>>>,
+}
+
+impl WebhookDispatcher {
+    pub fn new(store: Arc<dyn CortexStore>, config: &WebhookConfig) -> Self {
+        let http = reqwest::Client::builder()
+            .timeout(Duration::from_millis(config.timeout_ms))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            webhooks: Registry::new(store.clone()),
+            store,
+            http,
+            config: config.clone(),
+            risk_scores: Arc::new(RwLock::new(HashMap::new())),
+            queues: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Load stored webhooks. Returns how many there are.
+    pub async fn load(&self) -> AppResult<usize> {
+        let count = self.webhooks.load().await?;
+        println!("[WEBHOOK] Loaded {} webhook(s)", count);
+        tracing::info!(count = %count, "Loaded webhooks");
+        Ok(count)
+    }
+
+    pub async fn create(&self, webhook: WebhookRow) -> AppResult<()> {
+        self.webhooks.save(webhook.clone()).await?;
+        println!("[WEBHOOK] Created {} -> {}", webhook.id, webhook.url);
+        tracing::info!(webhook_id = %webhook.id, url = %webhook.url, events = ?webhook.event_types, "Webhook created");
+        Ok(())
+    }
+
+    /// Every webhook, oldest first
+    pub async fn list(&self) -> Vec<WebhookRow> {
+        self.webhooks.list().await
+    }
+
+    pub async fn get(&self, id: &str) -> Option<WebhookRow> {
+        self.webhooks.get(id).await
+    }
+
+    /// Returns false if there was no such webhook
+    pub async fn delete(&self, id: &str) -> AppResult<bool> {
+        if self.webhooks.delete(id).await?.is_none() {
+            return Ok(false);
+        }
+        // Its worker stops once the events already queued are skipped
+        self.queues.lock().unwrap_or_else(|e| e.into_inner()).remove(id);
+        println!("[WEBHOOK] Deleted {}", id);
+        tracing::info!(webhook_id = %id, "Webhook deleted");
+        Ok(true)
+    }
+
+    /// Resume deliveries to a webhook, clearing its failure count
+    pub async fn enable(&self, id: &str) -> AppResult<Option<WebhookRow>> {
+        let enabled = self.webhooks.update(id, |webhook| {
+            webhook.active = 1;
+            webhook.consecutive_failures = 0;
+            true
+        });
+        let Some(webhook) = enabled.await? else {
+            return Ok(None);
+        };
+        println!("[WEBHOOK] Enabled {}", id);
+        tracing::info!(webhook_id = %id, "Webhook enabled");
+        Ok(Some(webhook))
+    }
+
+    pub async fn deliveries(&self, id: &str, limit: u32) -> AppResult<Vec<WebhookDeliveryRow>> {
+        self.store.get_webhook_deliveries(id, limit).await
+    }
+
+    /// Deliver events for `updates` until the indexer goes away
+    pub fn spawn(&self, mut updates: broadcast::Receiver<WalletUpdate>) -> JoinHandle<()> {
+        let dispatcher = self.clone();
+        tokio::spawn(async move {
+            loop {
+                match updates.recv().await {
+                    Ok(update) => {
+                        for (webhook, payload) in dispatcher.events_for(&update).await {
+                            dispatcher.enqueue(webhook, payload);
+                        }
+                    }
+                    Err(RecvError::Lagged(skipped)) => {
+                        println!("[WEBHOOK] Dispatcher lagging, {} update(s) dropped", skipped);
+                        tracing::warn!(skipped = %skipped, "Webhook dispatcher lagging, updates dropped");
+                    }
+                    Err(RecvError::Closed) => break,
+                }
+            }
+            tracing::info!("Webhook dispatcher stopped");
+        })
+    }
+
+    /// Queue an event behind the webhook's earlier ones, starting its worker if needed.
+    /// Drops the event if the queue is full.
+    fn enqueue(&self, webhook: WebhookRow, payload: WebhookPayload) {
+        let mut queues = self.queues.lock().unwrap_or_else(|e| e.into_inner());
+        let queue = queues.entry(webhook.id.clone()).or_insert_with(|| self.start_worker());
+        let event = match queue.try_send((webhook, payload)) {
+            Ok(()) => return,
+            Err(TrySendError::Full((webhook, payload))) => {
+                println!("[WEBHOOK] Queue for {} is full, dropping {} event", webhook.id, payload.event_type);
+                tracing::warn!(
+                    webhook_id = %webhook.id,
+                    delivery_id = %payload.id,
+                    event = %payload.event_type,
+                    queue_size = %self.config.queue_size,
+                    "Webhook queue full, event dropped"
+                );
+                telemetry::webhook_event_dropped();
+                return;
+            }
+            // The worker died; start another
+            Err(TrySendError::Closed(event)) => event,
+        };
+        let id = event.0.id.clone();
+        let queue = self.start_worker();
+        let _ = queue.try_send(event);
+        queues.insert(id, queue);
+    }
+
+    /// A worker delivering queued events one at a time, until its queue is dropped
+    fn start_worker(&self) -> Queue {
+        let (queue, mut events) = mpsc::channel(self.config.queue_size.max(1));
+        let dispatcher = self.clone();
+        tokio::spawn(async move {
+            while let Some((webhook, payload)) = events.recv().await {
+                dispatcher.clone().deliver(webhook, payload).await;
+            }
+        });
+        queue
+    }
+
+    /// Events `update` produces for each active webhook that follows it
+    async fn events_for(&self, update: &WalletUpdate) -> Vec<(WebhookRow, WebhookPayload)> {
+        let wallet = update.wallet();
+        // Tracked whether or not anyone listens, so a later webhook sees real crossings
+        let previous_risk = match update {
+            WalletUpdate::Summary(summary) => self.risk_scores.write().await.insert(wallet.to_string(), summary.risk_score),
+            WalletUpdate::Transaction(_) | WalletUpdate::Alert(_) => None,
+        };
+
+        let webhooks = self.webhooks.list().await;
+        let following = webhooks
+            .iter()
+            .filter(|w| w.active == 1 && (w.wallet.is_empty() || w.wallet == wallet));
+
+        let mut events = Vec::new();
+        for webhook in following {
+            let subscribed = |event: WebhookEvent| webhook.event_types.iter().any(|t| t == &event.to_string());
+            match update {
+                WalletUpdate::Transaction(row) => {
+                    let data = || serde_json::to_value(TransactionEntry::from((**row).clone())).unwrap_or_default();
+                    if subscribed(WebhookEvent::Transaction) {
+                        events.push((webhook.clone(), WebhookPayload::new(WebhookEvent::Transaction, wallet, data())));
+                    }
+                    let min_usd_value = webhook.min_usd_value.parse().unwrap_or(DEFAULT_MIN_USD_VALUE);
+                    let usd_value: Decimal = row.usd_value.parse().unwrap_or_default();
+                    if subscribed(WebhookEvent::LargeTrade) && usd_value >= min_usd_value {
+                        events.push((webhook.clone(), WebhookPayload::new(WebhookEvent::LargeTrade, wallet, data())));
+                    }
+                }
+                WalletUpdate::Summary(summary) => {
+                    let threshold = webhook.risk_threshold;
+                    let crossed =
+                        summary.risk_score >= threshold && previous_risk.is_none_or(|previous| previous < threshold);
+                    if subscribed(WebhookEvent::RiskThreshold) && crossed {
+                        let crossing = RiskCrossing {
+                            risk_score: summary.risk_score,
+                            previous_risk_score: previous_risk,
+                            threshold,
+                            summary: (**summary).clone().into(),
+                        };
+                        let data = serde_json::to_value(crossing).unwrap_or_default();
+                        events.push((webhook.clone(), WebhookPayload::new(WebhookEvent::RiskThreshold, wallet, data)));
+                    }
+                }
+                WalletUpdate::Alert(alert) => {
+                    if subscribed(WebhookEvent::Alert) {
+                        let data = serde_json::to_value(alert).unwrap_or_default();
+                        events.push((webhook.clone(), WebhookPayload::new(WebhookEvent::Alert, wallet, data)));
+                    }
+                }
+            }
+        }
+        events
+    }
+
+    /// Deliver one event, retrying until it is accepted or attempts run out
+    async fn deliver(self, webhook: WebhookRow, payload: WebhookPayload) {
+        let body = match serde_json::to_string(&payload) {
+            Ok(body) => body,
+            Err(e) => {
+                tracing::error!(webhook_id = %webhook.id, error = %e, "Failed to serialize webhook payload");
+                return;
+            }
+        };
+        let event_type = payload.event_type.to_string();
+
+        for attempt in 1..=self.config.max_attempts.max(1) {
+            // Deleted or disabled while queued or while a retry was pending
+            if self.get(&webhook.id).await.is_none_or(|w| w.active != 1) {
+                return;
+            }
+
+            let outcome = delivery::send(&self.http, &webhook.url, &webhook.secret, &event_type, &payload.id, &body).await;
+            let succeeded = outcome.succeeded();
+            let row = WebhookDeliveryRow {
+                webhook_id: webhook.id.clone(),
+                delivery_id: payload.id.clone(),
+                event_type: event_type.clone(),
+                attempt: attempt.min(u8::MAX as u32) as u8,
+                status_code: outcome.status_code,
+                error: outcome.error.clone(),
+                success: u8::from(succeeded),
+                duration_ms: outcome.duration.as_millis().min(u32::MAX as u128) as u32,
+                attempted_at: Utc::now().timestamp_millis(),
+            };
+            if let Err(e) = self.store.record_webhook_delivery(&row).await {
+                tracing::warn!(webhook_id = %webhook.id, error = %e, "Failed to record webhook delivery");
+            }
+
+            if succeeded {
+                println!("[WEBHOOK] Delivered {} to {} (attempt {})", event_type, webhook.id, attempt);
+                tracing::info!(webhook_id = %webhook.id, delivery_id = %payload.id, event = %event_type, attempt = %attempt, "Webhook delivered");
+                self.record_outcome(&webhook.id, true).await;
+                return;
+            }
+
+            println!("[WEBHOOK] Delivery of {} to {} failed (attempt {}): {}", event_type, webhook.id, attempt, outcome.error);
+            tracing::warn!(
+                webhook_id = %webhook.id,
+                delivery_id = %payload.id,
+                attempt = %attempt,
+                status_code = %outcome.status_code,
+                error = %outcome.error,
+                "Webhook delivery attempt failed"
+            );
+            if attempt < self.config.max_attempts {
+                tokio::time::sleep(delivery::backoff(self.config.retry_base_ms, attempt)).await;
+            }
+        }
+
+        self.record_outcome(&webhook.id, false).await;
+    }
+
+    /// Track consecutive failed deliveries, disabling the webhook when there are too many
+    async fn record_outcome(&self, id: &str, delivered: bool) {
+        let updated = self.webhooks.update(id, |webhook| {
+            if delivered {
+                if webhook.consecutive_failures == 0 {
+                    return false;
+                }
+                webhook.consecutive_failures = 0;
+            } else {
+                webhook.consecutive_failures += 1;
+                if webhook.active == 1 && webhook.consecutive_failures >= self.config.disable_after {
+                    webhook.active = 0;
+                    println!("[WEBHOOK] Disabled {} after {} failed deliveries", id, webhook.consecutive_failures);
+                    tracing::warn!(webhook_id = %id, failures = %webhook.consecutive_failures, "Webhook disabled after repeated failures");
+                }
+            }
+            true
+        });
+
+        if let Err(e) = updated.await {
+            tracing::warn!(webhook_id = %id, error = %e, "Failed to store webhook delivery state");
+        }
+    }
+}
+
+#[async_trait]
+impl Record for WebhookRow {
+    fn key(&self) -> &str {
+        &self.id
+    }
+
+    fn created_at(&self) -> i64 {
+        self.created_at
+    }
+
+    fn mark_deleted(&mut self) {
+        self.deleted = 1;
+    }
+
+    async fn save(&self, store: &dyn CortexStore) -> AppResult<()> {
+        store.save_webhook(self).await
+    }
+
+    async fn load_all(store: &dyn CortexStore) -> AppResult<Vec<Self>> {
+        store.get_webhooks().await
+    }
+}
+
+/// A new webhook row with a random id
+pub fn new_webhook(
+    url: String,
+    secret: String,
+    wallet: Option<String>,
+    events: &[WebhookEvent],
+    min_usd_value: Decimal,
+    risk_threshold: u8,
+) -> WebhookRow {
+    WebhookRow {
+        id: uuid::Uuid::new_v4().to_string(),
+        url,
+        secret,
+        wallet: wallet.unwrap_or_default(),
+        event_types: events.iter().map(|e| e.to_string()).collect(),
+        min_usd_value: min_usd_value.to_string(),
+        risk_threshold,
+        active: 1,
+        consecutive_failures: 0,
+        deleted: 0,
+        created_at: Utc::now().timestamp_millis(),
+    }
+}
+
+/// A random signing secret for webhooks created without one
+pub fn generate_secret() -> String {
+    format!("whsec_{}{}", uuid::Uuid::new_v4().simple(), uuid::Uuid::new_v4().simple())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::delivery::sign;
+    use super::*;
+    use crate::db::models::{TransactionRow, WalletSummaryRow};
+    use crate::db::store::MemoryStore;
+    use axum::http::{HeaderMap, StatusCode};
+    use axum::routing::post;
+    use std::sync::Mutex;
+
+    const WALLET: &str = "7xKXtg2CW87d97TXJSDpbD5jBkheTqA83TZRuJosgAsU";
+
+    type Received = Arc<Mutex<Vec<(HeaderMap, String)>>>;
+
+    /// A local endpoint answering with `statuses` in turn (the last one repeats),
+    /// recording every request it gets
+    async fn stand_in(statuses: Vec<u16>) -> (String, Received) {
+        let received: Received = Arc::default();
+        let log = received.clone();
+        let app = axum::Router::new().route(
+            "/hook",
+            post(move |headers: HeaderMap, body: String| {
+                let log = log.clone();
+                let statuses = statuses.clone();
+                async move {
+                    let mut log = log.lock().unwrap();
+                    let status = statuses[log.len().min(statuses.len() - 1)];
+                    log.push((headers, body));
+                    StatusCode::from_u16(status).unwrap()
+                }
+            }),
+        );
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, received)
+    }
+
+    fn config(max_attempts: u32, disable_after: u32) -> WebhookConfig {
+        WebhookConfig {
+            max_attempts,
+            retry_base_ms: 1,
+            disable_after,
+            timeout_ms: 2000,
+            queue_size: 16,
+        }
+    }
+
+    fn transaction(usd_value: &str) -> WalletUpdate {
+        WalletUpdate::Transaction(Box::new(TransactionRow {
+            signature: "sig-webhook".to_string(),
+            wallet: WALLET.to_string(),
+            protocol: "jupiter".to_string(),
+            tx_type: "swap".to_string(),
+            token_in: crate::tokens::USDC_MINT.to_string(),
+            token_out: crate::tokens::WSOL_MINT.to_string(),
+            amount_in: usd_value.to_string(),
+            amount_out: "1".to_string(),
+            usd_value: usd_value.to_string(),
+            block_time: 1_700_000_000_000,
+            slot: 1,
+        }))
+    }
+
+    fn summary(risk_score: u8) -> WalletUpdate {
+        WalletUpdate::Summary(Box::new(WalletSummaryRow {
+            wallet: WALLET.to_string(),
+            total_value_usd: "1000".to_string(),
+            protocol_count: 1,
+            position_count: 1,
+            risk_score,
+            protocols: vec!["jupiter".to_string()],
+            ..WalletSummaryRow::default()
+        }))
+    }
+
+    async fn wait_for(received: &Received, count: usize) {
+        for _ in 0..200 {
+            if received.lock().unwrap().len() >= count {
+                return;
+            }
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        panic!("expected {} webhook request(s), got {}", count, received.lock().unwrap().len());
+    }
+
+    #[tokio::test]
+    async fn test_signed_delivery_retries_until_accepted() {
+        let (url, received) = stand_in(vec![500, 503, 200]).await;
+        let store = Arc::new(MemoryStore::new());
+        let dispatcher = WebhookDispatcher::new(store.clone(), &config(5, 5));
+        let webhook = new_webhook(url, "whsec_test".to_string(), Some(WALLET.to_string()), &[WebhookEvent::Transaction], DEFAULT_MIN_USD_VALUE, DEFAULT_RISK_THRESHOLD);
+        dispatcher.create(webhook.clone()).await.unwrap();
+
+        let (updates, receiver) = broadcast::channel(8);
+        dispatcher.spawn(receiver);
+        updates.send(transaction("25")).unwrap();
+        wait_for(&received, 3).await;
+
+        let requests = received.lock().unwrap().clone();
+        let (headers, body) = &requests[2];
+        let header = |name: &str| headers[name].to_str().unwrap().to_string();
+        let timestamp: i64 = header("x-cortex-timestamp").parse().unwrap();
+        assert_eq!(header("x-cortex-signature"), sign("whsec_test", timestamp, body));
+        assert_eq!(header("x-cortex-event"), "transaction");
+        // Retries resend the same event
+        assert_eq!(requests[0].1, *body);
+
+        let payload: serde_json::Value = serde_json::from_str(body).unwrap();
+        assert_eq!(payload["type"], "transaction");
+        assert_eq!(payload["id"], header("x-cortex-delivery"));
+        assert_eq!(payload["data"]["signature"], "sig-webhook");
+
+        // Attempts are recorded once the last one finishes
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        let attempts = dispatcher.deliveries(&webhook.id, 10).await.unwrap();
+        let statuses: Vec<(u8, u16, u8)> = attempts.iter().map(|a| (a.attempt, a.status_code, a.success)).collect();
+        assert_eq!(statuses, vec![(3, 200, 1), (2, 503, 0), (1, 500, 0)]);
+        assert_eq!(dispatcher.get(&webhook.id).await.unwrap().consecutive_failures, 0);
+    }
+
+    #[tokio::test]
+    async fn test_events_are_delivered_in_order() {
+        let (url, received) = stand_in(vec![500, 200]).await;
+        let store = Arc::new(MemoryStore::new());
+        let dispatcher = WebhookDispatcher::new(store, &config(3, 5));
+        let webhook = new_webhook(url, generate_secret(), None, &[WebhookEvent::Transaction], DEFAULT_MIN_USD_VALUE, DEFAULT_RISK_THRESHOLD);
+        dispatcher.create(webhook).await.unwrap();
+
+        let (updates, receiver) = broadcast::channel(8);
+        dispatcher.spawn(receiver);
+        for signature in ["sig-first", "sig-second", "sig-third"] {
+            let mut update = transaction("25");
+            if let WalletUpdate::Transaction(row) = &mut update {
+                row.signature = signature.to_string();
+            }
+            updates.send(update).unwrap();
+        }
+        wait_for(&received, 4).await;
+
+        // The first event's retry goes out before the later events
+        let signatures: Vec<String> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, body)| serde_json::from_str::<serde_json::Value>(body).unwrap()["data"]["signature"].as_str().unwrap().to_string())
+            .collect();
+        assert_eq!(signatures, vec!["sig-first", "sig-first", "sig-second", "sig-third"]);
+    }
+
+    #[tokio::test]
+    async fn test_failing_webhook_is_disabled() {
+        let (url, received) = stand_in(vec![500]).await;
+        let store = Arc::new(MemoryStore::new());
+        let dispatcher = WebhookDispatcher::new(store.clone(), &config(2, 2));
+        let webhook = new_webhook(url, generate_secret(), None, &[WebhookEvent::Transaction], DEFAULT_MIN_USD_VALUE, DEFAULT_RISK_THRESHOLD);
+        dispatcher.create(webhook.clone()).await.unwrap();
+
+        for _ in 0..2 {
+            let events = dispatcher.events_for(&transaction("25")).await;
+            assert_eq!(events.len(), 1);
+            let (webhook, payload) = events.into_iter().next().unwrap();
+            dispatcher.clone().deliver(webhook, payload).await;
+        }
+
+        assert_eq!(received.lock().unwrap().len(), 4);
+        let disabled = dispatcher.get(&webhook.id).await.unwrap();
+        assert_eq!((disabled.active, disabled.consecutive_failures), (0, 2));
+        assert_eq!(store.get_webhooks().await.unwrap()[0].active, 0);
+        assert!(dispatcher.events_for(&transaction("25")).await.is_empty());
+
+        dispatcher.enable(&webhook.id).await.unwrap();
+        assert_eq!(dispatcher.events_for(&transaction("25")).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_updates_match_event_types() {
+        let dispatcher = WebhookDispatcher::new(Arc::new(MemoryStore::new()), &config(1, 1));
+        let large = new_webhook("http://127.0.0.1:1/".to_string(), generate_secret(), None, &[WebhookEvent::LargeTrade], dec!(1000), DEFAULT_RISK_THRESHOLD);
+        let risk = new_webhook("http://127.0.0.1:1/".to_string(), generate_secret(), Some(WALLET.to_string()), &[WebhookEvent::RiskThreshold], DEFAULT_MIN_USD_VALUE, 60);
+        let elsewhere = new_webhook("http://127.0.0.1:1/".to_string(), generate_secret(), Some("9WzDXwBbmkg8ZTbNMqUxvQRAyrZzDsGYdLVL9zYtAWWM".to_string()), &[WebhookEvent::Transaction, WebhookEvent::RiskThreshold], DEFAULT_MIN_USD_VALUE, 1);
+        for webhook in [&large, &risk, &elsewhere] {
+            dispatcher.create(webhook.clone()).await.unwrap();
+        }
+        let matched = |events: Vec<(WebhookRow, WebhookPayload)>| -> Vec<(String, WebhookEvent)> {
+            events.into_iter().map(|(w, p)| (w.id, p.event_type)).collect()
+        };
+
+        assert!(dispatcher.events_for(&transaction("999.99")).await.is_empty());
+        assert_eq!(matched(dispatcher.events_for(&transaction("1000")).await), vec![(large.id.clone(), WebhookEvent::LargeTrade)]);
+
+        // Only rising to or past the threshold counts
+        assert!(dispatcher.events_for(&summary(40)).await.is_empty());
+        let events = dispatcher.events_for(&summary(65)).await;
+        assert_eq!(events[0].1.data["previous_risk_score"], 40);
+        assert_eq!(matched(events), vec![(risk.id.clone(), WebhookEvent::RiskThreshold)]);
+        assert!(dispatcher.events_for(&summary(80)).await.is_empty());
+        assert!(dispatcher.events_for(&summary(10)).await.is_empty());
+        assert_eq!(dispatcher.events_for(&summary(60)).await.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_streamed_transactions_are_delivered() {
+        let (url, received) = stand_in(vec![200]).await;
+        let store = Arc::new(MemoryStore::new());
+        let indexer = crate::indexer::Indexer::offline(store.clone());
+        let dispatcher = WebhookDispatcher::new(store, &config(1, 1));
+        // A zero threshold is crossed by the wallet's first summary, whatever its score
+        let webhook = new_webhook(url, generate_secret(), Some(WALLET.to_string()), &[WebhookEvent::Transaction, WebhookEvent::RiskThreshold], DEFAULT_MIN_USD_VALUE, 0);
+        dispatcher.create(webhook).await.unwrap();
+        dispatcher.spawn(indexer.subscribe_updates());
+
+        let now = Utc::now().timestamp();
+        let swap = crate::indexer::test_swap(WALLET, "sig-live-00000000000000", 1, now, (crate::tokens::USDC_MINT, 100.0), (crate::tokens::WSOL_MINT, 1.0));
+        indexer.ingest(WALLET, vec![swap]).await;
+        wait_for(&received, 2).await;
+
+        let events: Vec<serde_json::Value> = received
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(_, body)| serde_json::from_str(body).unwrap())
+            .collect();
+        assert_eq!(events[0]["type"], "transaction");
+        assert_eq!(events[0]["data"]["signature"], "sig-live-00000000000000");
+        assert_eq!(events[1]["type"], "risk_threshold");
+        assert_eq!(events[1]["data"]["summary"]["protocols"], serde_json::json!(["jupiter"]));
+    }
+}
@@ -32,6 +32,9 @@ pub struct DefiConfig {
     /// Request timeout in seconds
     #[serde(default = "default_timeout")]
     pub timeout_seconds: u64,
+    /// API key sent as a bearer token to the Cortex DeFi API
+    #[serde(default = "default_api_key")]
+    pub api_key: Option<String>,
 }
 
 impl Default for DefiConfig {
@@ -39,6 +42,7 @@ impl Default for DefiConfig {
         Self {
             api_url: default_defi_api_url(),
             timeout_seconds: default_timeout(),
+            api_key: default_api_key(),
         }
     }
 }
@@ -51,6 +55,10 @@ fn default_timeout() -> u64 {
     30
 }
 
+fn default_api_key() -> Option<String> {
+    std::env::var("CORTEX_API_KEY").ok().filter(|key| !key.is_empty())
+}
+
 /// Prediction market configuration
 #[derive(Debug, Clone, Deserialize)]
 pub struct PredictionConfig {
@@ -9,7 +9,7 @@ use cortex_core::{
     MarketStatus, PositionType, PredictionMarketBet, Wallet, WalletClassification,
     WalletConvictionResponse,
 };
-use reqwest::Client;
+use reqwest::{Client, RequestBuilder};
 use serde_json::{json, Value};
 use std::sync::Arc;
 use std::time::Duration;
@@ -22,6 +22,7 @@ use crate::polymarket::PolymarketClient;
 pub struct DefiClient {
     client: Client,
     api_url: String,
+    api_key: Option<String>,
     polymarket: Arc<PolymarketClient>,
 }
 
@@ -36,6 +37,7 @@ impl DefiClient {
         Self {
             client,
             api_url: config.api_url.clone(),
+            api_key: config.api_key.clone(),
             polymarket: Arc::new(PolymarketClient::new()),
         }
     }
@@ -58,12 +60,60 @@ impl DefiClient {
         self.get(&url).await
     }
 
+    /// Get wallet trading performance (Sharpe, Sortino, drawdown, win rate)
+    pub async fn get_wallet_performance(&self, wallet: &str, window: &str) -> Result<Value> {
+        let url = format!("{}/api/v1/user/{}/performance?window={}", self.api_url, wallet, window);
+        self.get(&url).await
+    }
+
     /// Get wallet positions
     pub async fn get_wallet_positions(&self, wallet: &str) -> Result<Value> {
         let url = format!("{}/api/v1/user/{}/positions", self.api_url, wallet);
         self.get(&url).await
     }
 
+    /// Get the cluster of addresses believed to share an owner with the wallet
+    pub async fn get_wallet_cluster(&self, wallet: &str) -> Result<Value> {
+        let url = format!("{}/api/v1/user/{}/cluster", self.api_url, wallet);
+        self.get(&url).await
+    }
+
+    /// Get the copy-trading feed of position changes by subscribed wallets
+    pub async fn get_signals(&self, wallets: Option<&str>, limit: u64) -> Result<Value> {
+        let mut url = format!("{}/api/v1/signals?limit={}", self.api_url, limit);
+        if let Some(wallets) = wallets {
+            url.push_str(&format!("&wallet={}", wallets));
+        }
+        self.get(&url).await
+    }
+
+    /// Rank indexed wallets by closed-trade performance
+    pub async fn get_leaderboard(
+        &self,
+        window: &str,
+        metric: &str,
+        protocol: Option<&str>,
+        limit: u64,
+    ) -> Result<Value> {
+        let mut url = format!(
+            "{}/api/v1/leaderboard?window={}&metric={}&limit={}",
+            self.api_url, window, metric, limit
+        );
+        if let Some(protocol) = protocol {
+            url.push_str(&format!("&protocol={}", protocol));
+        }
+        self.get(&url).await
+    }
+
+    /// Net smart-money flow into a token
+    pub async fn get_token_flows(&self, mint: &str, window: &str, limit: u64) -> Result<Value> {
+        let url = format!(
+            "{}/api/v1/token/{}/flows?window={}&limit={}",
+            self.api_url, mint, window, limit
+        );
+        self.get(&url).await
+    }
+
     /// Start indexing a wallet
     pub async fn start_indexing(&self, wallet: &str) -> Result<Value> {
         let url = format!("{}/api/v1/index", self.api_url);
@@ -91,21 +141,27 @@ impl DefiClient {
     /// For cross-domain analysis, provide `evm_address` parameter to correlate
     /// a Solana wallet's DeFi activity with Polymarket positions.
     pub async fn get_wallet_conviction(&self, wallet_addr: &str) -> Result<WalletConvictionResponse> {
-        self.get_wallet_conviction_with_evm(wallet_addr, None).await
+        self.get_wallet_conviction_with_evm(wallet_addr, None, false).await
     }
 
-    /// Calculate wallet conviction with optional linked EVM address
+    /// Calculate wallet conviction with optional linked EVM address.
+    ///
+    /// With `entity`, DeFi data covers every address clustered with the wallet.
     pub async fn get_wallet_conviction_with_evm(
         &self,
         wallet_addr: &str,
         evm_address: Option<&str>,
+        entity: bool,
     ) -> Result<WalletConvictionResponse> {
         let addr_type = detect_address_type(wallet_addr);
+        let scope = if entity { "entity" } else { "wallet" };
 
         // Fetch DeFi data (for Solana addresses)
         let (summary, defi_positions) = if addr_type == AddressType::Solana {
-            let summary = self.get_wallet_summary(wallet_addr).await.unwrap_or(json!({}));
-            let positions_data = self.get_wallet_positions(wallet_addr).await.unwrap_or(json!({"positions": []}));
+            let summary_url = format!("{}/api/v1/user/{}/summary?scope={}", self.api_url, wallet_addr, scope);
+            let positions_url = format!("{}/api/v1/user/{}/positions?scope={}", self.api_url, wallet_addr, scope);
+            let summary = self.get(&summary_url).await.unwrap_or(json!({}));
+            let positions_data = self.get(&positions_url).await.unwrap_or(json!({"positions": []}));
             (summary, parse_defi_positions(&positions_data))
         } else {
             // EVM address - no DeFi data from Cortex (Solana-focused)
@@ -121,7 +177,12 @@ impl DefiClient {
             total_value_usd: summary["total_value_usd"].as_f64().unwrap_or(0.0),
             defi_positions,
             prediction_bets,
-            classification: Some(WalletClassification::Unknown),
+            classification: Some(
+                summary["classification"]["class"]
+                    .as_str()
+                    .map(WalletClassification::from_str_lossy)
+                    .unwrap_or(WalletClassification::Unknown),
+            ),
             risk_score: summary["risk_score"].as_u64().unwrap_or(50) as u8,
             last_activity: Utc::now(),
             protocols: summary["protocols"]
@@ -332,12 +393,20 @@ impl DefiClient {
 
     // HTTP helper methods
 
+    /// Attach the configured API key, when set, as a bearer token
+    fn authorized(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.api_key {
+            Some(key) => request.bearer_auth(key),
+            None => request,
+        }
+    }
+
     async fn get(&self, url: &str) -> Result<Value> {
         let response = self
-            .client
-            .get(url)
+            .authorized(self.client.get(url))
             .send()
             .await
+            .and_then(|r| r.error_for_status())
             .map_err(|e| CortexMcpError::Http(e.to_string()))?;
 
         response
@@ -348,11 +417,11 @@ impl DefiClient {
 
     async fn post(&self, url: &str, body: Value) -> Result<Value> {
         let response = self
-            .client
-            .post(url)
+            .authorized(self.client.post(url))
             .json(&body)
             .send()
             .await
+            .and_then(|r| r.error_for_status())
             .map_err(|e| CortexMcpError::Http(e.to_string()))?;
 
         response
@@ -363,10 +432,10 @@ impl DefiClient {
 
     async fn delete(&self, url: &str) -> Result<Value> {
         let response = self
-            .client
-            .delete(url)
+            .authorized(self.client.delete(url))
             .send()
             .await
+            .and_then(|r| r.error_for_status())
             .map_err(|e| CortexMcpError::Http(e.to_string()))?;
 
         response
@@ -79,13 +79,18 @@ impl CortexTools {
             // DeFi Tools
             Tool {
                 name: "cortex_get_wallet_summary".to_string(),
-                description: "Get a comprehensive summary of a Solana wallet including total value, PnL (profit/loss), risk metrics, and protocol exposure.".to_string(),
+                description: "Get a comprehensive summary of a Solana wallet including total value, PnL (profit/loss), risk metrics, protocol exposure, and trading performance (Sharpe, Sortino, max drawdown, win rate, profit factor).".to_string(),
                 input_schema: json!({
                     "type": "object",
                     "properties": {
                         "wallet": {
                             "type": "string",
                             "description": "Solana wallet address (base58 encoded)"
+                        },
+                        "window": {
+                            "type": "string",
+                            "enum": ["24h", "7d", "30d", "all"],
+                            "description": "Time window for performance statistics (default: 30d)"
                         }
                     },
                     "required": ["wallet"]
@@ -124,6 +129,89 @@ impl CortexTools {
                     "required": ["wallet"]
                 }),
             },
+            Tool {
+                name: "cortex_get_wallet_cluster".to_string(),
+                description: "Find other Solana addresses likely controlled by the same entity, linked by common first funder, repeated direct transfers or shared fee payers.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "wallet": {
+                            "type": "string",
+                            "description": "Solana wallet address (base58 encoded)"
+                        }
+                    },
+                    "required": ["wallet"]
+                }),
+            },
+            Tool {
+                name: "cortex_get_position_signals".to_string(),
+                description: "Get the latest copy-trading signals: positions opened, added to, reduced or closed by subscribed wallets, with size, fill price and portfolio weight.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "wallet": {
+                            "type": "string",
+                            "description": "Optional comma-separated Solana wallet addresses to filter by (default: all subscribed wallets)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Number of signals to return, newest first (1-200, default: 20)"
+                        }
+                    },
+                    "required": []
+                }),
+            },
+            Tool {
+                name: "cortex_get_leaderboard".to_string(),
+                description: "Rank indexed wallets (smart money) by realized PnL, ROI, win rate or Sharpe ratio of their closed trades over a time window.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "window": {
+                            "type": "string",
+                            "enum": ["24h", "7d", "30d", "all"],
+                            "description": "Time window for closed trades (default: 7d)"
+                        },
+                        "metric": {
+                            "type": "string",
+                            "enum": ["realized_pnl", "roi", "win_rate", "sharpe"],
+                            "description": "Ranking metric (default: realized_pnl)"
+                        },
+                        "protocol": {
+                            "type": "string",
+                            "description": "Optional protocol to restrict trades to (e.g. jupiter, raydium)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Number of wallets to return (1-100, default: 10)"
+                        }
+                    },
+                    "required": []
+                }),
+            },
+            Tool {
+                name: "cortex_get_token_flows".to_string(),
+                description: "Who is accumulating or distributing a token: net buy/sell volume, unique buyers and sellers, top accumulating and distributing wallets, and the share from whales and top performers.".to_string(),
+                input_schema: json!({
+                    "type": "object",
+                    "properties": {
+                        "mint": {
+                            "type": "string",
+                            "description": "Token mint address"
+                        },
+                        "window": {
+                            "type": "string",
+                            "enum": ["24h", "7d", "30d", "all"],
+                            "description": "Time window (default: 7d)"
+                        },
+                        "limit": {
+                            "type": "integer",
+                            "description": "Accumulators and distributors to list (1-50, default: 10)"
+                        }
+                    },
+                    "required": ["mint"]
+                }),
+            },
             Tool {
                 name: "cortex_start_indexing".to_string(),
                 description: "Start continuous indexing for a wallet. Fetches historical transactions and monitors real-time activity.".to_string(),
@@ -175,6 +263,10 @@ impl CortexTools {
                         "evm_address": {
                             "type": "string",
                             "description": "Optional linked EVM address (0x-prefixed) for Polymarket positions. Use when correlating Solana DeFi with Polymarket bets."
+                        },
+                        "entity": {
+                            "type": "boolean",
+                            "description": "Aggregate DeFi activity across all Solana addresses clustered with the wallet (default: false)"
                         }
                     },
                     "required": ["wallet"]
@@ -307,8 +399,13 @@ impl CortexTools {
             // DeFi tools
             "cortex_get_wallet_summary" => {
                 let wallet = args["wallet"].as_str().ok_or("Missing wallet parameter")?;
+                let window = args["window"].as_str().unwrap_or("30d");
                 validate_wallet(wallet).map_err(|e| e.to_string())?;
-                self.defi.get_wallet_summary(wallet).await.map_err(|e| e.to_string())
+                let mut summary = self.defi.get_wallet_summary(wallet).await.map_err(|e| e.to_string())?;
+                if let Ok(performance) = self.defi.get_wallet_performance(wallet, window).await {
+                    summary["performance"] = performance;
+                }
+                Ok(summary)
             }
 
             "cortex_get_wallet_pnl" => {
@@ -324,6 +421,42 @@ impl CortexTools {
                 self.defi.get_wallet_positions(wallet).await.map_err(|e| e.to_string())
             }
 
+            "cortex_get_wallet_cluster" => {
+                let wallet = args["wallet"].as_str().ok_or("Missing wallet parameter")?;
+                validate_wallet(wallet).map_err(|e| e.to_string())?;
+                self.defi.get_wallet_cluster(wallet).await.map_err(|e| e.to_string())
+            }
+
+            "cortex_get_position_signals" => {
+                let wallets = args["wallet"].as_str();
+                let limit = args["limit"].as_u64().unwrap_or(20);
+                if let Some(wallets) = wallets {
+                    for wallet in wallets.split(',').map(str::trim) {
+                        validate_wallet(wallet).map_err(|e| e.to_string())?;
+                    }
+                }
+                self.defi.get_signals(wallets, limit).await.map_err(|e| e.to_string())
+            }
+
+            "cortex_get_leaderboard" => {
+                let window = args["window"].as_str().unwrap_or("7d");
+                let metric = args["metric"].as_str().unwrap_or("realized_pnl");
+                let protocol = args["protocol"].as_str();
+                let limit = args["limit"].as_u64().unwrap_or(10);
+                self.defi
+                    .get_leaderboard(window, metric, protocol, limit)
+                    .await
+                    .map_err(|e| e.to_string())
+            }
+
+            "cortex_get_token_flows" => {
+                let mint = args["mint"].as_str().ok_or("Missing mint parameter")?;
+                validate_wallet(mint).map_err(|e| e.to_string())?;
+                let window = args["window"].as_str().unwrap_or("7d");
+                let limit = args["limit"].as_u64().unwrap_or(10);
+                self.defi.get_token_flows(mint, window, limit).await.map_err(|e| e.to_string())
+            }
+
             "cortex_start_indexing" => {
                 let wallet = args["wallet"].as_str().ok_or("Missing wallet parameter")?;
                 validate_wallet(wallet).map_err(|e| e.to_string())?;
@@ -344,6 +477,7 @@ impl CortexTools {
             "cortex_get_wallet_conviction" => {
                 let wallet = args["wallet"].as_str().ok_or("Missing wallet parameter")?;
                 let evm_address = args["evm_address"].as_str();
+                let entity = args["entity"].as_bool().unwrap_or(false);
                 
                 // Skip strict Solana validation if it's an EVM address
                 if !wallet.starts_with("0x") {
@@ -351,7 +485,7 @@ impl CortexTools {
                 }
                 
                 let response = self.defi
-                    .get_wallet_conviction_with_evm(wallet, evm_address)
+                    .get_wallet_conviction_with_evm(wallet, evm_address, entity)
                     .await
                     .map_err(|e| e.to_string())?;
                 serde_json::to_value(response).map_err(|e| e.to_string())
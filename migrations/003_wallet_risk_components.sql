@@ -0,0 +1,14 @@
+-- Risk score components for wallet summaries
+-- Run with: clickhouse-client --multiquery < migrations/003_wallet_risk_components.sql
+
+ALTER TABLE cortex.wallet_summaries
+    ADD COLUMN IF NOT EXISTS leverage Decimal32(4) DEFAULT 0,             -- Borrowed / lending collateral
+    ADD COLUMN IF NOT EXISTS liquidation_distance Decimal32(4) DEFAULT 1, -- Collateral drop until liquidation (1 = no debt)
+    ADD COLUMN IF NOT EXISTS stablecoin_pct Decimal32(4) DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS memecoin_pct Decimal32(4) DEFAULT 0,
+    -- Component scores (0-100) blended into risk_score
+    ADD COLUMN IF NOT EXISTS concentration_risk UInt8 DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS leverage_risk UInt8 DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS liquidation_risk UInt8 DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS asset_risk UInt8 DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS contract_risk UInt8 DEFAULT 0;
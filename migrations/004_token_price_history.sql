@@ -0,0 +1,24 @@
+-- Token price history and wallet value-at-risk
+-- Run with: clickhouse-client --multiquery < migrations/004_token_price_history.sql
+
+-- Every price update, kept for volatility and VaR
+CREATE TABLE IF NOT EXISTS cortex.token_price_history (
+    token String,
+    price_usd Decimal64(8),
+    timestamp DateTime64(3)
+) ENGINE = MergeTree()
+ORDER BY (token, timestamp)
+TTL toDateTime(timestamp) + INTERVAL 1 YEAR;
+
+-- token_prices only keeps the latest price per token; copy each insert into history
+CREATE MATERIALIZED VIEW IF NOT EXISTS cortex.token_price_history_mv
+TO cortex.token_price_history
+AS SELECT token, price_usd, updated_at AS timestamp
+FROM cortex.token_prices;
+
+ALTER TABLE cortex.wallet_summaries
+    ADD COLUMN IF NOT EXISTS var_95 Decimal64(2) DEFAULT 0,                -- 1-day historical VaR, USD
+    ADD COLUMN IF NOT EXISTS var_99 Decimal64(2) DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS expected_shortfall_95 Decimal64(2) DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS expected_shortfall_99 Decimal64(2) DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS portfolio_volatility Decimal32(4) DEFAULT 0;  -- Annualized
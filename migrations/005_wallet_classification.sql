@@ -0,0 +1,7 @@
+-- Behavioural wallet classification
+-- Run with: clickhouse-client --multiquery < migrations/005_wallet_classification.sql
+
+ALTER TABLE cortex.wallet_summaries
+    ADD COLUMN IF NOT EXISTS classification LowCardinality(String) DEFAULT 'unknown', -- whale, trader, bot, fund, exchange, retail, new
+    ADD COLUMN IF NOT EXISTS classification_confidence Decimal32(4) DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS classification_features String DEFAULT '{}';            -- JSON of the features used
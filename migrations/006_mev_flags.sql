@@ -0,0 +1,31 @@
+-- MEV and bot-pattern flags
+-- Run with: clickhouse-client --multiquery < migrations/006_mev_flags.sql
+
+-- One row per flagged behaviour on a transaction
+CREATE TABLE IF NOT EXISTS cortex.transaction_flags (
+    signature String,
+    wallet String,
+    flag LowCardinality(String),       -- sandwiched, same_slot_round_trip, jito_tip, snipe
+    counterparty String,               -- Attacker, tip account or sniped mint
+    estimated_loss_usd Decimal64(2),   -- USD lost to the attacker (sandwiched only)
+    slot UInt64,
+    block_time DateTime64(3),
+    detected_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplacingMergeTree(detected_at)
+ORDER BY (wallet, signature, flag);
+
+-- Pool and token creations seen on the stream, used to spot snipers
+CREATE TABLE IF NOT EXISTS cortex.token_launches (
+    mint String,
+    pool String,
+    slot UInt64,
+    block_time DateTime64(3)
+) ENGINE = MergeTree()
+ORDER BY (mint, slot);
+
+ALTER TABLE cortex.wallet_summaries
+    ADD COLUMN IF NOT EXISTS mev_loss_usd Decimal64(2) DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS sandwiched_count UInt32 DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS round_trip_count UInt32 DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS jito_tip_count UInt32 DEFAULT 0,
+    ADD COLUMN IF NOT EXISTS snipe_count UInt32 DEFAULT 0;
@@ -0,0 +1,19 @@
+-- Evidence linking wallets controlled by the same entity
+-- Run with: clickhouse-client --multiquery < migrations/007_wallet_links.sql
+
+-- One row per observed link between two wallets
+CREATE TABLE IF NOT EXISTS cortex.wallet_links (
+    source String,                     -- Sender of a transfer, or the paying fee payer
+    target String,                     -- Recipient of a transfer, or the wallet whose fee was paid
+    link_type LowCardinality(String),  -- transfer, fee_payer
+    mint String,                       -- Transferred mint (transfer only)
+    amount Decimal128(9),
+    signature String,
+    block_time DateTime64(3),
+    observed_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplacingMergeTree(observed_at)
+ORDER BY (source, target, link_type, signature);
+
+-- Lookups by recipient (first funders, fee-paid wallets)
+ALTER TABLE cortex.wallet_links
+    ADD INDEX IF NOT EXISTS idx_target target TYPE bloom_filter GRANULARITY 4;
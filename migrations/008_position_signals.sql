@@ -0,0 +1,18 @@
+-- Copy-trading signals: position changes of tracked wallets
+-- Run with: clickhouse-client --multiquery < migrations/008_position_signals.sql
+
+CREATE TABLE IF NOT EXISTS cortex.position_signals (
+    wallet String,
+    signature String,
+    token String,
+    kind LowCardinality(String),       -- opened, added, reduced, closed
+    amount Decimal128(9),              -- Tokens bought or sold
+    price Decimal64(8),                -- USD per token at the fill (0 when unknown)
+    usd_value Decimal64(2),
+    position_size Decimal128(9),       -- Holding after the trade
+    portfolio_weight Decimal64(6),     -- Share of priced holdings after the trade
+    slot UInt64,
+    block_time DateTime64(3),
+    emitted_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplacingMergeTree(emitted_at)
+ORDER BY (wallet, block_time, signature, token);
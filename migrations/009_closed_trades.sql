@@ -0,0 +1,17 @@
+-- Closed trades, the basis for cross-wallet performance rankings
+-- Run with: clickhouse-client --multiquery < migrations/009_closed_trades.sql
+
+-- One row per swap that sold a previously acquired token, matched at average cost.
+-- Each summary refresh rewrites the trades closed at or after the earliest transaction
+-- it added, so backfilled history corrects the cost basis of later trades.
+CREATE TABLE IF NOT EXISTS cortex.closed_trades (
+    wallet String,
+    signature String,
+    protocol LowCardinality(String),
+    token String,                      -- Token sold
+    cost_basis Decimal64(2),           -- USD cost of the amount sold
+    pnl Decimal64(2),                  -- Realized gain (negative for a loss)
+    block_time DateTime64(3),          -- When the trade closed
+    computed_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplacingMergeTree(computed_at)
+ORDER BY (wallet, signature);
@@ -0,0 +1,45 @@
+-- Incremental per-wallet, per-protocol daily rollup of transactions
+-- Run with: clickhouse-client --multiquery < migrations/010_wallet_protocol_daily.sql
+
+-- Summary PnL windows and per-protocol PnL read from here instead of rescanning
+-- every transaction of a wallet
+CREATE TABLE IF NOT EXISTS cortex.wallet_protocol_daily (
+    wallet String,
+    protocol LowCardinality(String),
+    day Date,
+    tx_count SimpleAggregateFunction(sum, UInt64),
+    realized_usd SimpleAggregateFunction(sum, Decimal128(2)),   -- Swaps, withdrawals, LP removals
+    deployed_usd SimpleAggregateFunction(sum, Decimal128(2)),   -- Deposits, borrows, LP adds
+    volume_usd SimpleAggregateFunction(sum, Decimal128(2)),     -- All transactions
+    last_activity SimpleAggregateFunction(max, DateTime64(3))
+) ENGINE = AggregatingMergeTree()
+ORDER BY (wallet, protocol, day);
+
+-- Backfill existing history once, before the view starts capturing new inserts
+INSERT INTO cortex.wallet_protocol_daily
+SELECT
+    wallet,
+    protocol,
+    toDate(block_time) AS day,
+    count() AS tx_count,
+    sumIf(usd_value, tx_type IN ('swap', 'withdraw', 'remove_liquidity')) AS realized_usd,
+    sumIf(usd_value, tx_type IN ('deposit', 'borrow', 'add_liquidity')) AS deployed_usd,
+    sum(usd_value) AS volume_usd,
+    max(block_time) AS last_activity
+FROM cortex.transactions
+WHERE (SELECT count() FROM cortex.wallet_protocol_daily) = 0
+GROUP BY wallet, protocol, day;
+
+CREATE MATERIALIZED VIEW IF NOT EXISTS cortex.wallet_protocol_daily_mv
+TO cortex.wallet_protocol_daily AS
+SELECT
+    wallet,
+    protocol,
+    toDate(block_time) AS day,
+    count() AS tx_count,
+    sumIf(usd_value, tx_type IN ('swap', 'withdraw', 'remove_liquidity')) AS realized_usd,
+    sumIf(usd_value, tx_type IN ('deposit', 'borrow', 'add_liquidity')) AS deployed_usd,
+    sum(usd_value) AS volume_usd,
+    max(block_time) AS last_activity
+FROM cortex.transactions
+GROUP BY wallet, protocol, day;
@@ -0,0 +1,10 @@
+-- Wallets under continuous indexing, resumed when the server restarts
+-- Run with: clickhouse-client --multiquery < migrations/011_wallet_subscriptions.sql
+
+-- Starting or stopping a subscription writes a row; the latest one per wallet wins.
+CREATE TABLE IF NOT EXISTS cortex.wallet_subscriptions (
+    wallet String,
+    active UInt8,
+    updated_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplacingMergeTree(updated_at)
+ORDER BY wallet;
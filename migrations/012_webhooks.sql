@@ -0,0 +1,34 @@
+-- Outbound webhook subscriptions and their delivery log
+-- Run with: clickhouse-client --multiquery < migrations/012_webhooks.sql
+
+-- Every change to a webhook writes the whole row; the latest one per id wins.
+CREATE TABLE IF NOT EXISTS cortex.webhooks (
+    id String,
+    url String,
+    secret String,                     -- HMAC key for the X-Cortex-Signature header
+    wallet String,                     -- Empty to receive every wallet's events
+    event_types Array(LowCardinality(String)),
+    min_usd_value Decimal64(2),        -- Smallest transaction that counts as a large trade
+    risk_threshold UInt8,              -- Risk score whose crossing is reported
+    active UInt8,                      -- Cleared after repeated failed deliveries
+    consecutive_failures UInt32,
+    deleted UInt8,
+    created_at DateTime64(3),
+    updated_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplacingMergeTree(updated_at)
+ORDER BY id;
+
+-- One row per delivery attempt, kept for 30 days
+CREATE TABLE IF NOT EXISTS cortex.webhook_deliveries (
+    webhook_id String,
+    delivery_id String,                -- Shared by every attempt at one event
+    event_type LowCardinality(String),
+    attempt UInt8,
+    status_code UInt16,                -- 0 when no response was received
+    error String,
+    success UInt8,
+    duration_ms UInt32,
+    attempted_at DateTime64(3)
+) ENGINE = MergeTree()
+ORDER BY (webhook_id, attempted_at)
+TTL toDateTime(attempted_at) + INTERVAL 30 DAY;
@@ -0,0 +1,27 @@
+-- Alert rules over wallet metrics and the current state of each alert
+-- Run with: clickhouse-client --multiquery < migrations/013_alerts.sql
+
+ALTER TABLE cortex.wallet_summaries
+    ADD COLUMN IF NOT EXISTS protocol_ltv String DEFAULT '{}';   -- JSON of lending protocol -> loan-to-value
+
+-- Every change to a rule writes the whole row; the latest one per id wins.
+CREATE TABLE IF NOT EXISTS cortex.alert_rules (
+    id String,
+    name String,
+    condition String,                  -- e.g. "risk_score > 70" or "kamino.ltv > 0.85"
+    wallets Array(String),             -- Empty to evaluate every wallet
+    deleted UInt8,
+    created_at DateTime64(3),
+    updated_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplacingMergeTree(updated_at)
+ORDER BY id;
+
+-- Latest state per rule and wallet, so a firing alert isn't raised again after a restart
+CREATE TABLE IF NOT EXISTS cortex.alert_states (
+    rule_id String,
+    wallet String,
+    state LowCardinality(String),      -- firing or resolved
+    value Decimal64(4),                -- Metric value at the transition
+    changed_at DateTime64(3)
+) ENGINE = ReplacingMergeTree(changed_at)
+ORDER BY (rule_id, wallet);
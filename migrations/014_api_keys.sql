@@ -0,0 +1,26 @@
+-- API keys for the REST API and the wallet subscriptions each key started
+-- Run with: clickhouse-client --multiquery < migrations/014_api_keys.sql
+
+-- Only the SHA-256 of a key is stored. Every change writes the whole row; the latest one per id wins.
+CREATE TABLE IF NOT EXISTS cortex.api_keys (
+    id String,
+    name String,
+    key_hash String,                   -- Hex SHA-256 of the key
+    key_prefix String,                 -- First characters of the key, to tell keys apart
+    scopes Array(LowCardinality(String)),  -- read, index and/or admin
+    rate_limit_per_minute UInt32,      -- 0 for unlimited
+    max_subscriptions UInt32,          -- 0 for unlimited
+    revoked UInt8,
+    created_at DateTime64(3),
+    updated_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplacingMergeTree(updated_at)
+ORDER BY id;
+
+-- Which key started a wallet subscription, for per-key quotas; the latest row per key and wallet wins
+CREATE TABLE IF NOT EXISTS cortex.api_key_subscriptions (
+    key_id String,
+    wallet String,
+    active UInt8,
+    updated_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplacingMergeTree(updated_at)
+ORDER BY (key_id, wallet);
@@ -0,0 +1,14 @@
+-- Named groups of wallets, such as funds, a team treasury or copy targets
+-- Run with: clickhouse-client --multiquery < migrations/015_wallet_groups.sql
+
+-- Every change to a group writes the whole row; the latest one per id wins.
+CREATE TABLE IF NOT EXISTS cortex.wallet_groups (
+    id String,
+    name String,
+    wallets Array(String),
+    tags Array(LowCardinality(String)),
+    deleted UInt8,
+    created_at DateTime64(3),
+    updated_at DateTime64(3) DEFAULT now64(3)
+) ENGINE = ReplacingMergeTree(updated_at)
+ORDER BY id;
@@ -0,0 +1,5 @@
+-- Alert rules scoped to a wallet group, whose members are looked up when the rule is evaluated
+-- Run with: clickhouse-client --multiquery < migrations/016_alert_rule_groups.sql
+
+ALTER TABLE cortex.alert_rules
+    ADD COLUMN IF NOT EXISTS group_id String DEFAULT '' AFTER wallets;  -- Empty when the rule isn't scoped to a group
@@ -0,0 +1,5 @@
+-- Share of a wallet's exposure its value-at-risk is based on
+-- Run with: clickhouse-client --multiquery < migrations/017_var_coverage.sql
+
+ALTER TABLE cortex.wallet_summaries
+    ADD COLUMN IF NOT EXISTS var_coverage Decimal32(4) DEFAULT 0 AFTER portfolio_volatility;  -- 0 when no exposure has price history
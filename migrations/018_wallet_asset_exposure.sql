@@ -0,0 +1,5 @@
+-- USD exposure per underlying asset, served with the wallet summary
+-- Run with: clickhouse-client --multiquery < migrations/018_wallet_asset_exposure.sql
+
+ALTER TABLE cortex.wallet_summaries
+    ADD COLUMN IF NOT EXISTS asset_exposure String DEFAULT '{}';  -- JSON of underlying asset mint -> USD, net of debt